@@ -704,6 +704,19 @@ async fn spawn_bench_workers(
     futures
 }
 
+/// Error type a failed execution is counted under: aborts the payload provokes by design are
+/// counted apart from other execution errors.
+pub(crate) fn execution_error_type(
+    payload: &dyn Payload,
+    effects: &ExecutionEffects,
+) -> &'static str {
+    if payload.is_expected_abort(effects) {
+        "expected_abort"
+    } else {
+        "execution"
+    }
+}
+
 async fn run_bench_worker(
     barrier: Arc<Barrier>,
     metrics: Arc<BenchMetrics>,
@@ -793,7 +806,7 @@ async fn run_bench_worker(
                         .num_error
                         .with_label_values(&[
                             &payload.to_string(),
-                            "execution",
+                            execution_error_type(payload.as_ref(), &effects),
                             &client_type.to_string(),
                         ])
                         .inc();
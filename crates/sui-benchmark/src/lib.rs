@@ -7,6 +7,7 @@ use anyhow::bail;
 use async_trait::async_trait;
 use embedded_reconfig_observer::EmbeddedReconfigObserver;
 use fullnode_reconfig_observer::FullNodeReconfigObserver;
+use move_core_types::language_storage::ModuleId;
 use prometheus::Registry;
 use rand::Rng;
 use sui_config::genesis::Genesis;
@@ -48,7 +49,7 @@ use sui_types::{
     base_types::{AuthorityName, SuiAddress},
     sui_system_state::SuiSystemStateTrait,
 };
-use sui_types::{digests::ChainIdentifier, gas::GasCostSummary};
+use sui_types::{digests::ChainIdentifier, event::Event, gas::GasCostSummary};
 use sui_types::{
     effects::{TransactionEffectsAPI, TransactionEvents},
     execution_status::ExecutionFailureStatus,
@@ -184,6 +185,31 @@ impl ExecutionEffects {
         }
     }
 
+    /// Module and abort code of a transaction that failed with a Move abort. Only available for
+    /// effects returned by validators.
+    pub fn move_abort(&self) -> Option<(ModuleId, u64)> {
+        match self {
+            ExecutionEffects::FinalizedTransactionEffects(effects, ..) => {
+                match effects.data().status() {
+                    sui_types::execution_status::ExecutionStatus::Failure {
+                        error: ExecutionFailureStatus::MoveAbort(location, code),
+                        ..
+                    } => Some((location.module.clone(), *code)),
+                    _ => None,
+                }
+            }
+            ExecutionEffects::SuiTransactionBlockEffects(_) => None,
+        }
+    }
+
+    /// Events emitted by the transaction. Only available for effects returned by validators.
+    pub fn events(&self) -> &[Event] {
+        match self {
+            ExecutionEffects::FinalizedTransactionEffects(_, events) => &events.data,
+            ExecutionEffects::SuiTransactionBlockEffects(_) => &[],
+        }
+    }
+
     pub fn status(&self) -> String {
         match self {
             ExecutionEffects::FinalizedTransactionEffects(effects, ..) => {
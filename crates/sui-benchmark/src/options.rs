@@ -6,6 +6,7 @@ use clap::*;
 use strum_macros::EnumString;
 
 use crate::drivers::Interval;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Parser)]
@@ -193,6 +194,13 @@ pub enum RunSpec {
         // relative weight of party transactions in the benchmark workload
         #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
         party: Vec<u32>,
+        // relative weight of trace transactions in the benchmark workload. The weight only
+        // splits the workers, as the target qps of the trace workload is given by the trace.
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
+        trace: Vec<u32>,
+        // relative weight of amm swap transactions in the benchmark workload
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
+        amm: Vec<u32>,
 
         // --- workload-specific options --- (TODO: use subcommands or similar)
         // 100 for max hotness i.e all requests target
@@ -226,6 +234,29 @@ pub enum RunSpec {
         // See `ExpectedFailureType` enum for `expected_failure_type`
         #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [0])]
         expected_failure_type: Vec<u32>,
+        // directory of checkpoint files (`<sequence_number>.chk`) whose transactions are replayed
+        // by the trace workload
+        #[clap(long)]
+        trace_checkpoint_dir: Option<PathBuf>,
+        // transaction rate of the trace workload, as a percentage of the transaction rate of the
+        // recorded checkpoints. E.g. 200 replays the trace at twice its recorded speed.
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [100])]
+        trace_speedup_percent: Vec<u32>,
+        // maximum number of stand-in shared objects of the trace workload. Shared objects of the
+        // trace beyond the most used ones share the stand-ins of the least used ones.
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [1000])]
+        trace_max_shared_objects: Vec<u64>,
+        // number of pools of the amm workload
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [16])]
+        amm_num_pools: Vec<u64>,
+        // exponent of the Zipfian distribution of swaps over amm pools, as a percentage. 0 spreads
+        // swaps evenly over the pools, larger values concentrate them on the first pools.
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [100])]
+        amm_zipf_exponent_percent: Vec<u32>,
+        // slippage tolerated by amm swaps, in basis points of the quoted output. Swaps whose
+        // output falls short of it abort, which happens as quotes go stale under contention.
+        #[clap(long, num_args(1..), value_delimiter = ',', default_values_t = [50])]
+        amm_slippage_bps: Vec<u32>,
 
         // --- generic options ---
         // Target qps
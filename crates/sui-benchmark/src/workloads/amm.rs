@@ -0,0 +1,610 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Swaps against the pools of a constant-product AMM. Pool popularity follows a Zipfian
+//! distribution, so that a few hot pools take most of the swaps, as on a DEX. Each transaction
+//! routes a coin split off its gas coin through one to three pools and merges the output back.
+//! The final hop is guarded by a slippage check against a quote derived from the swap events
+//! seen so far, so that swaps whose quote went stale abort like they would for a real trader.
+//! These aborts are reported as expected aborts, apart from other execution errors.
+
+use crate::drivers::Interval;
+use crate::system_state_observer::SystemStateObserver;
+use crate::workloads::benchmark_move_base_dir;
+use crate::workloads::payload::Payload;
+use crate::workloads::workload::{
+    ExpectedFailureType, Workload, WorkloadBuilder, MAX_GAS_FOR_TESTING,
+};
+use crate::workloads::{Gas, GasCoinConfig, WorkloadBuilderInfo, WorkloadParams};
+use crate::ProgrammableTransactionBuilder;
+use crate::{ExecutionEffects, ValidatorProxy};
+use async_trait::async_trait;
+use futures::future::join_all;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use sui_test_transaction_builder::TestTransactionBuilder;
+use sui_types::base_types::{ObjectID, SequenceNumber};
+use sui_types::crypto::get_key_pair;
+use sui_types::event::Event;
+use sui_types::gas_coin::MIST_PER_SUI;
+use sui_types::object::Owner;
+use sui_types::transaction::{Argument, Command, ObjectArg, Transaction};
+use tracing::{debug, error, info};
+
+/// Initial reserve of each side of a pool.
+const POOL_RESERVE: u64 = 100 * MIST_PER_SUI;
+/// Amount swapped by each transaction, which moves the price of a pool by roughly 0.2%.
+const SWAP_AMOUNT: u64 = MIST_PER_SUI / 10;
+/// Fee charged on the input of every swap, in basis points. Must match `amm::amm`.
+const FEE_BPS: u128 = 30;
+/// Abort code of the slippage check of `amm::swap`.
+const SLIPPAGE_ABORT_CODE: u64 = 1;
+/// Maximum number of pools a swap is routed through.
+const MAX_HOPS: usize = 3;
+
+/// Reserves of a pool, as of its `swaps`-th swap.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Reserves {
+    reserve_x: u64,
+    reserve_y: u64,
+    swaps: u64,
+}
+
+impl Reserves {
+    /// Output of swapping `amount_in` against these reserves, as computed by `amm::swap`.
+    fn amount_out(&self, amount_in: u64, x_to_y: bool) -> u64 {
+        let (reserve_in, reserve_out) = if x_to_y {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        };
+        let amount_in = amount_in as u128 * (10_000 - FEE_BPS);
+        (amount_in * reserve_out as u128 / (reserve_in as u128 * 10_000 + amount_in)) as u64
+    }
+}
+
+/// Minimum output accepted for a swap quoted to output `quoted_out`, given a slippage tolerance
+/// in basis points.
+fn min_amount_out(quoted_out: u64, slippage_bps: u64) -> u64 {
+    (quoted_out as u128 * (10_000 - slippage_bps) as u128 / 10_000) as u64
+}
+
+/// Zipfian popularity weights of `num_pools` pools, in decreasing order of popularity.
+fn zipf_weights(num_pools: usize, exponent: f64) -> Vec<f64> {
+    (1..=num_pools)
+        .map(|rank| 1.0 / (rank as f64).powf(exponent))
+        .collect()
+}
+
+/// Draws a route of distinct pools by popularity, of up to `MAX_HOPS` hops. Only the
+/// `num_routable_pools` pools of non-zero popularity can be drawn: with large exponents, the
+/// weights of the least popular pools underflow to zero.
+fn sample_route(
+    popularity: &WeightedIndex<f64>,
+    num_routable_pools: usize,
+    rng: &mut impl Rng,
+) -> Vec<usize> {
+    let num_hops = rng.gen_range(1..=MAX_HOPS.min(num_routable_pools));
+    let mut route = Vec::with_capacity(num_hops);
+    while route.len() < num_hops {
+        let pool = popularity.sample(rng);
+        if !route.contains(&pool) {
+            route.push(pool);
+        }
+    }
+    route
+}
+
+/// Mirrors `amm::SwapEvent`.
+#[derive(Debug, Deserialize)]
+struct SwapEvent {
+    pool: ObjectID,
+    reserves: Reserves,
+}
+
+/// Latest reserves seen for each pool. Shared by all payloads of the workload, like the price
+/// feed of a DEX frontend, and only as fresh as the swap events it has been fed.
+#[derive(Debug)]
+struct PoolQuotes {
+    package_id: ObjectID,
+    index: HashMap<ObjectID, usize>,
+    reserves: RwLock<Vec<Reserves>>,
+}
+
+impl PoolQuotes {
+    fn new(package_id: ObjectID, pools: &[(ObjectID, SequenceNumber)]) -> Self {
+        let initial = Reserves {
+            reserve_x: POOL_RESERVE,
+            reserve_y: POOL_RESERVE,
+            swaps: 0,
+        };
+        PoolQuotes {
+            package_id,
+            index: pools
+                .iter()
+                .enumerate()
+                .map(|(i, (id, _))| (*id, i))
+                .collect(),
+            reserves: RwLock::new(vec![initial; pools.len()]),
+        }
+    }
+
+    fn get(&self, pool: usize) -> Reserves {
+        self.reserves.read().unwrap()[pool]
+    }
+
+    fn update(&self, events: &[Event]) {
+        let mut reserves = self.reserves.write().unwrap();
+        for event in events {
+            if event.package_id != self.package_id || event.type_.name.as_str() != "SwapEvent" {
+                continue;
+            }
+            let Ok(swap) = bcs::from_bytes::<SwapEvent>(&event.contents) else {
+                error!("Failed to deserialize swap event {:?}", event);
+                continue;
+            };
+            if let Some(&i) = self.index.get(&swap.pool) {
+                // Events of concurrent transactions arrive out of order
+                if swap.reserves.swaps > reserves[i].swaps {
+                    reserves[i] = swap.reserves;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AmmTestPayload {
+    package_id: ObjectID,
+    pools: Arc<Vec<(ObjectID, SequenceNumber)>>,
+    /// Zipfian distribution over the indices of `pools`
+    popularity: WeightedIndex<f64>,
+    /// Number of pools with a non-zero popularity
+    num_routable_pools: usize,
+    quotes: Arc<PoolQuotes>,
+    slippage_bps: u64,
+    gas: Gas,
+    system_state_observer: Arc<SystemStateObserver>,
+}
+
+impl std::fmt::Display for AmmTestPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "amm")
+    }
+}
+
+impl Payload for AmmTestPayload {
+    fn make_new_payload(&mut self, effects: &ExecutionEffects) {
+        if effects.is_ok() {
+            self.quotes.update(effects.events());
+        } else if self.is_expected_abort(effects) {
+            debug!("Amm swap aborted on slippage check");
+        } else if !effects.is_cancelled() {
+            effects.print_gas_summary();
+            error!("Amm tx failed... Status: {:?}", effects.status());
+        }
+        self.gas.0 = effects.gas_object().0;
+    }
+
+    fn make_transaction(&mut self) -> Transaction {
+        let mut rng = rand::thread_rng();
+        let route = sample_route(&self.popularity, self.num_routable_pools, &mut rng);
+
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let amount = builder.pure(SWAP_AMOUNT).unwrap();
+        let mut coin = builder.command(Command::SplitCoins(Argument::GasCoin, vec![amount]));
+        let mut quoted_out = SWAP_AMOUNT;
+        for (hop, pool) in route.iter().enumerate() {
+            let x_to_y = rng.gen_bool(0.5);
+            quoted_out = self.quotes.get(*pool).amount_out(quoted_out, x_to_y);
+            // Only the output of the whole route is checked, like a DEX router does
+            let min_out = if hop + 1 == route.len() {
+                min_amount_out(quoted_out, self.slippage_bps)
+            } else {
+                0
+            };
+            let (id, initial_shared_version) = self.pools[*pool];
+            let args = vec![
+                builder
+                    .obj(ObjectArg::SharedObject {
+                        id,
+                        initial_shared_version,
+                        mutable: true,
+                    })
+                    .unwrap(),
+                coin,
+                builder.pure(x_to_y).unwrap(),
+                builder.pure(min_out).unwrap(),
+            ];
+            coin = builder.programmable_move_call(
+                self.package_id,
+                Identifier::new("amm").unwrap(),
+                Identifier::new("swap").unwrap(),
+                vec![],
+                args,
+            );
+        }
+        builder.command(Command::MergeCoins(Argument::GasCoin, vec![coin]));
+
+        let gas_price = self
+            .system_state_observer
+            .state
+            .borrow()
+            .reference_gas_price;
+        TestTransactionBuilder::new(self.gas.1, self.gas.0, gas_price)
+            .programmable(builder.finish())
+            .build_and_sign(self.gas.2.as_ref())
+    }
+
+    fn get_failure_type(&self) -> Option<ExpectedFailureType> {
+        None
+    }
+
+    fn is_expected_abort(&self, effects: &ExecutionEffects) -> bool {
+        matches!(
+            effects.move_abort(),
+            Some((module, code))
+                if *module.address() == AccountAddress::from(self.package_id)
+                    && module.name().as_str() == "amm"
+                    && code == SLIPPAGE_ABORT_CODE
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct AmmWorkloadBuilder {
+    num_pools: u64,
+    zipf_exponent: f64,
+    slippage_bps: u64,
+    num_payloads: u64,
+}
+
+impl AmmWorkloadBuilder {
+    pub fn from(
+        workload_weight: f32,
+        target_qps: u64,
+        num_workers: u64,
+        in_flight_ratio: u64,
+        amm_num_pools: u64,
+        amm_zipf_exponent_percent: u32,
+        amm_slippage_bps: u32,
+        duration: Interval,
+        group: u32,
+    ) -> Option<WorkloadBuilderInfo> {
+        let target_qps = (workload_weight * target_qps as f32).ceil() as u64;
+        let num_workers = (workload_weight * num_workers as f32).ceil() as u64;
+        let max_ops = target_qps * in_flight_ratio;
+        if max_ops == 0 || num_workers == 0 || amm_num_pools == 0 {
+            None
+        } else {
+            let workload_params = WorkloadParams {
+                target_qps,
+                num_workers,
+                max_ops,
+                duration,
+                group,
+            };
+            let workload_builder =
+                Box::<dyn WorkloadBuilder<dyn Payload>>::from(Box::new(AmmWorkloadBuilder {
+                    num_pools: amm_num_pools,
+                    zipf_exponent: amm_zipf_exponent_percent as f64 / 100.0,
+                    slippage_bps: amm_slippage_bps.min(10_000) as u64,
+                    num_payloads: max_ops,
+                }));
+            let builder_info = WorkloadBuilderInfo {
+                workload_params,
+                workload_builder,
+            };
+            Some(builder_info)
+        }
+    }
+}
+
+#[async_trait]
+impl WorkloadBuilder<dyn Payload> for AmmWorkloadBuilder {
+    async fn generate_coin_config_for_init(&self) -> Vec<GasCoinConfig> {
+        // Gas coin for publishing the package, and one for funding each pool
+        (0..=self.num_pools)
+            .map(|_| {
+                let (address, keypair) = get_key_pair();
+                GasCoinConfig {
+                    amount: MAX_GAS_FOR_TESTING,
+                    address,
+                    keypair: Arc::new(keypair),
+                }
+            })
+            .collect()
+    }
+
+    async fn generate_coin_config_for_payloads(&self) -> Vec<GasCoinConfig> {
+        (0..self.num_payloads)
+            .map(|_| {
+                let (address, keypair) = get_key_pair();
+                GasCoinConfig {
+                    amount: MAX_GAS_FOR_TESTING,
+                    address,
+                    keypair: Arc::new(keypair),
+                }
+            })
+            .collect()
+    }
+
+    async fn build(
+        &self,
+        init_gas: Vec<Gas>,
+        payload_gas: Vec<Gas>,
+    ) -> Box<dyn Workload<dyn Payload>> {
+        Box::<dyn Workload<dyn Payload>>::from(Box::new(AmmWorkload {
+            package_id: None,
+            pools: vec![],
+            zipf_exponent: self.zipf_exponent,
+            slippage_bps: self.slippage_bps,
+            init_gas,
+            payload_gas,
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct AmmWorkload {
+    /// ID of the `amm` package
+    package_id: Option<ObjectID>,
+    /// Pools in decreasing order of popularity
+    pools: Vec<(ObjectID, SequenceNumber)>,
+    zipf_exponent: f64,
+    slippage_bps: u64,
+    pub init_gas: Vec<Gas>,
+    pub payload_gas: Vec<Gas>,
+}
+
+#[async_trait]
+impl Workload<dyn Payload> for AmmWorkload {
+    async fn init(
+        &mut self,
+        proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) {
+        if self.package_id.is_some() {
+            return;
+        }
+        let gas_price = system_state_observer.state.borrow().reference_gas_price;
+        let (head, tail) = self
+            .init_gas
+            .split_first()
+            .expect("Not enough gas to initialize amm workload");
+
+        info!("Publishing amm package");
+        let mut path = benchmark_move_base_dir();
+        path.push("src/workloads/data/amm");
+        let transaction = TestTransactionBuilder::new(head.1, head.0, gas_price)
+            .publish(path)
+            .build_and_sign(head.2.as_ref());
+        let (_, execution_result) = proxy.execute_transaction_block(transaction).await;
+        let effects = execution_result.unwrap();
+        let package_id = effects
+            .created()
+            .iter()
+            .find(|(_, owner)| matches!(owner, Owner::Immutable))
+            .expect("Amm package must be created")
+            .0
+             .0;
+        self.package_id = Some(package_id);
+        info!("Amm package id {:?}", package_id);
+
+        info!("Creating {} amm pools", tail.len());
+        let futures = tail.iter().map(|(gas, sender, keypair)| {
+            let mut builder = ProgrammableTransactionBuilder::new();
+            let reserve = builder.pure(POOL_RESERVE).unwrap();
+            let x = builder.command(Command::SplitCoins(Argument::GasCoin, vec![reserve]));
+            let y = builder.command(Command::SplitCoins(Argument::GasCoin, vec![reserve]));
+            builder.programmable_move_call(
+                package_id,
+                Identifier::new("amm").unwrap(),
+                Identifier::new("create_pool").unwrap(),
+                vec![],
+                vec![x, y],
+            );
+            let transaction = TestTransactionBuilder::new(*sender, *gas, gas_price)
+                .programmable(builder.finish())
+                .build_and_sign(keypair.as_ref());
+            let proxy = proxy.clone();
+            async move {
+                let (_, execution_result) = proxy.execute_transaction_block(transaction).await;
+                let effects = execution_result.unwrap();
+                effects
+                    .created()
+                    .into_iter()
+                    .find_map(|(obj, owner)| match owner {
+                        Owner::Shared {
+                            initial_shared_version,
+                        } => Some((obj.0, initial_shared_version)),
+                        _ => None,
+                    })
+                    .expect("Amm pool must be shared")
+            }
+        });
+        self.pools = join_all(futures).await;
+    }
+
+    async fn make_test_payloads(
+        &self,
+        _proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) -> Vec<Box<dyn Payload>> {
+        let package_id = self.package_id.unwrap();
+        let pools = Arc::new(self.pools.clone());
+        let quotes = Arc::new(PoolQuotes::new(package_id, &self.pools));
+        let weights = zipf_weights(self.pools.len(), self.zipf_exponent);
+        let num_routable_pools = weights.iter().filter(|weight| **weight > 0.0).count();
+        let popularity = WeightedIndex::new(weights).expect("Amm workload needs at least one pool");
+        self.payload_gas
+            .iter()
+            .map(|gas| {
+                Box::<dyn Payload>::from(Box::new(AmmTestPayload {
+                    package_id,
+                    pools: pools.clone(),
+                    popularity: popularity.clone(),
+                    num_routable_pools,
+                    quotes: quotes.clone(),
+                    slippage_bps: self.slippage_bps,
+                    gas: gas.clone(),
+                    system_state_observer: system_state_observer.clone(),
+                }))
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "Amm"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{min_amount_out, sample_route, zipf_weights, AmmTestPayload, PoolQuotes, Reserves};
+    use crate::drivers::bench_driver::execution_error_type;
+    use crate::system_state_observer::{SystemState, SystemStateObserver};
+    use crate::workloads::payload::Payload;
+    use crate::ExecutionEffects;
+    use move_core_types::identifier::Identifier;
+    use move_core_types::language_storage::ModuleId;
+    use rand::distributions::WeightedIndex;
+    use std::sync::Arc;
+    use sui_test_transaction_builder::TestTransactionBuilder;
+    use sui_types::base_types::{random_object_ref, ObjectID};
+    use sui_types::crypto::{get_key_pair, AccountKeyPair};
+    use sui_types::effects::{TestEffectsBuilder, TransactionEvents};
+    use sui_types::execution_status::{ExecutionFailureStatus, ExecutionStatus, MoveLocation};
+    use sui_types::quorum_driver_types::{EffectsFinalityInfo, FinalizedEffects};
+
+    fn payload(package_id: ObjectID) -> AmmTestPayload {
+        let (address, keypair): (_, AccountKeyPair) = get_key_pair();
+        let (_, state) = tokio::sync::watch::channel(SystemState {
+            reference_gas_price: 1000,
+            protocol_config: None,
+        });
+        AmmTestPayload {
+            package_id,
+            pools: Arc::new(vec![]),
+            popularity: WeightedIndex::new(zipf_weights(1, 1.0)).unwrap(),
+            num_routable_pools: 1,
+            quotes: Arc::new(PoolQuotes::new(package_id, &[])),
+            slippage_bps: 50,
+            gas: (random_object_ref(), address, Arc::new(keypair)),
+            system_state_observer: Arc::new(SystemStateObserver {
+                state,
+                _sender: tokio::sync::oneshot::channel().0,
+            }),
+        }
+    }
+
+    /// Effects of a transaction that aborted with `code` in `module`.
+    fn aborted(module: ModuleId, code: u64) -> ExecutionEffects {
+        let (sender, keypair): (_, AccountKeyPair) = get_key_pair();
+        let transaction = TestTransactionBuilder::new(sender, random_object_ref(), 1000)
+            .transfer_sui(None, sender)
+            .build_and_sign(&keypair);
+        let effects = TestEffectsBuilder::new(transaction.data())
+            .with_status(ExecutionStatus::Failure {
+                error: ExecutionFailureStatus::MoveAbort(
+                    MoveLocation {
+                        module,
+                        function: 0,
+                        instruction: 0,
+                        function_name: None,
+                    },
+                    code,
+                ),
+                command: Some(1),
+            })
+            .build();
+        ExecutionEffects::FinalizedTransactionEffects(
+            FinalizedEffects {
+                effects,
+                finality_info: EffectsFinalityInfo::QuorumExecuted(0),
+            },
+            TransactionEvents::default(),
+        )
+    }
+
+    #[test]
+    fn test_zipf_weights() {
+        assert_eq!(zipf_weights(4, 1.0), vec![1.0, 0.5, 1.0 / 3.0, 0.25]);
+        assert_eq!(zipf_weights(3, 2.0), vec![1.0, 0.25, 1.0 / 9.0]);
+        // An exponent of zero makes all pools equally popular
+        assert_eq!(zipf_weights(3, 0.0), vec![1.0; 3]);
+        assert!(zipf_weights(0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_sample_route() {
+        let mut rng = rand::thread_rng();
+        let popularity = WeightedIndex::new(zipf_weights(5, 1.0)).unwrap();
+        for _ in 0..100 {
+            let mut route = sample_route(&popularity, 5, &mut rng);
+            let num_hops = route.len();
+            assert!((1..=3).contains(&num_hops));
+            // Pools are not repeated
+            route.sort();
+            route.dedup();
+            assert_eq!(route.len(), num_hops);
+        }
+
+        // With a large exponent, the weights of all but the most popular pool underflow to zero
+        let weights = zipf_weights(5, 2000.0);
+        assert_eq!(weights, vec![1.0, 0.0, 0.0, 0.0, 0.0]);
+        let popularity = WeightedIndex::new(weights).unwrap();
+        for _ in 0..100 {
+            assert_eq!(sample_route(&popularity, 1, &mut rng), vec![0]);
+        }
+    }
+
+    #[test]
+    fn test_quote() {
+        let reserves = Reserves {
+            reserve_x: 1_000_000,
+            reserve_y: 2_000_000,
+            swaps: 0,
+        };
+        // 1000 * 0.997 * 2_000_000 / (1_000_000 + 1000 * 0.997), rounded down
+        assert_eq!(reserves.amount_out(1000, true), 1992);
+        // 1000 * 0.997 * 1_000_000 / (2_000_000 + 1000 * 0.997), rounded down
+        assert_eq!(reserves.amount_out(1000, false), 498);
+
+        assert_eq!(min_amount_out(1992, 50), 1982);
+        assert_eq!(min_amount_out(1992, 0), 1992);
+        assert_eq!(min_amount_out(1992, 10_000), 0);
+        // Quotes too large to be multiplied in u64 don't overflow
+        assert_eq!(min_amount_out(u64::MAX, 100), 18_262_276_632_972_456_098);
+    }
+
+    #[test]
+    fn test_slippage_abort_is_expected() {
+        let package_id = ObjectID::random();
+        let payload = payload(package_id);
+        let amm =
+            |package: ObjectID| ModuleId::new(package.into(), Identifier::new("amm").unwrap());
+
+        let slippage = aborted(amm(package_id), 1);
+        assert!(payload.is_expected_abort(&slippage));
+        assert_eq!(execution_error_type(&payload, &slippage), "expected_abort");
+
+        // Other aborts, and aborts of other packages, are execution errors
+        for effects in [
+            aborted(amm(package_id), 2),
+            aborted(amm(ObjectID::random()), 1),
+            aborted(
+                ModuleId::new(package_id.into(), Identifier::new("other").unwrap()),
+                1,
+            ),
+        ] {
+            assert!(!payload.is_expected_abort(&effects));
+            assert_eq!(execution_error_type(&payload, &effects), "execution");
+        }
+    }
+}
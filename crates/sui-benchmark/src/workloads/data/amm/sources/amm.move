@@ -0,0 +1,79 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// A minimal constant-product AMM, swapped against by the amm workload. Both sides of a pool hold
+/// SUI, so that traders need nothing but their gas coins to swap.
+module amm::amm {
+    use sui::balance::Balance;
+    use sui::coin::{Self, Coin};
+    use sui::event;
+    use sui::sui::SUI;
+
+    /// The output of a swap is below the minimum the sender asked for.
+    const ESlippageExceeded: u64 = 1;
+
+    /// Fee charged on the input of every swap, in basis points.
+    const FEE_BPS: u128 = 30;
+
+    public struct Pool has key {
+        id: UID,
+        reserve_x: Balance<SUI>,
+        reserve_y: Balance<SUI>,
+        /// Number of swaps against the pool, which orders its swap events.
+        swaps: u64,
+    }
+
+    /// Emitted after every swap, with the reserves it left the pool with.
+    public struct SwapEvent has copy, drop {
+        pool: ID,
+        reserve_x: u64,
+        reserve_y: u64,
+        swaps: u64,
+    }
+
+    public fun create_pool(x: Coin<SUI>, y: Coin<SUI>, ctx: &mut TxContext) {
+        transfer::share_object(Pool {
+            id: object::new(ctx),
+            reserve_x: x.into_balance(),
+            reserve_y: y.into_balance(),
+            swaps: 0,
+        })
+    }
+
+    /// Swaps `coin_in` for the other side of the pool, aborting if that yields less than `min_out`.
+    public fun swap(
+        pool: &mut Pool,
+        coin_in: Coin<SUI>,
+        x_to_y: bool,
+        min_out: u64,
+        ctx: &mut TxContext,
+    ): Coin<SUI> {
+        let amount_in = coin_in.value();
+        let coin_out = if (x_to_y) {
+            let amount_out = amount_out(amount_in, pool.reserve_x.value(), pool.reserve_y.value());
+            assert!(amount_out >= min_out, ESlippageExceeded);
+            pool.reserve_x.join(coin_in.into_balance());
+            coin::take(&mut pool.reserve_y, amount_out, ctx)
+        } else {
+            let amount_out = amount_out(amount_in, pool.reserve_y.value(), pool.reserve_x.value());
+            assert!(amount_out >= min_out, ESlippageExceeded);
+            pool.reserve_y.join(coin_in.into_balance());
+            coin::take(&mut pool.reserve_x, amount_out, ctx)
+        };
+        pool.swaps = pool.swaps + 1;
+        event::emit(SwapEvent {
+            pool: object::id(pool),
+            reserve_x: pool.reserve_x.value(),
+            reserve_y: pool.reserve_y.value(),
+            swaps: pool.swaps,
+        });
+        coin_out
+    }
+
+    fun amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64): u64 {
+        let amount_in = (amount_in as u128) * (10_000 - FEE_BPS);
+        let amount_out = amount_in * (reserve_out as u128)
+            / ((reserve_in as u128) * 10_000 + amount_in);
+        amount_out as u64
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// Stand-ins for the objects and Move calls of recorded transactions, replayed by the trace
+/// workload.
+module trace::trace {
+    /// Stands in for a shared object of a recorded transaction
+    public struct SharedStandIn has key {
+        id: UID,
+        value: u64,
+    }
+
+    /// Stands in for an owned object of a recorded transaction
+    public struct OwnedStandIn has key, store {
+        id: UID,
+        value: u64,
+    }
+
+    public fun create_shared(ctx: &mut TxContext) {
+        transfer::share_object(SharedStandIn { id: object::new(ctx), value: 0 })
+    }
+
+    public fun touch_shared(obj: &mut SharedStandIn) {
+        obj.value = obj.value + 1;
+    }
+
+    public fun read_shared(obj: &SharedStandIn): u64 {
+        obj.value
+    }
+
+    public fun create_owned(ctx: &mut TxContext): OwnedStandIn {
+        OwnedStandIn { id: object::new(ctx), value: 0 }
+    }
+
+    public fun touch_owned(obj: &mut OwnedStandIn) {
+        obj.value = obj.value + 1;
+    }
+
+    /// Burns computation gas, in proportion to `iterations`.
+    public fun burn(mut iterations: u64): u64 {
+        let mut acc = 0;
+        while (iterations > 0) {
+            acc = acc + iterations;
+            iterations = iterations - 1;
+        };
+        acc
+    }
+}
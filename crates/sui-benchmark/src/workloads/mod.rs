@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod adversarial;
+pub mod amm;
 pub mod batch_payment;
 pub mod delegation;
 pub mod expected_failure;
@@ -12,6 +13,7 @@ pub mod randomness;
 pub mod shared_counter;
 pub mod shared_object_deletion;
 pub mod slow;
+pub mod trace;
 pub mod transfer_object;
 pub mod workload;
 pub mod workload_configuration;
@@ -15,4 +15,9 @@ pub trait Payload: Send + Sync + std::fmt::Debug + Display {
     fn get_failure_type(&self) -> Option<ExpectedFailureType> {
         None // Default implementation returns None
     }
+    /// Whether a failed execution is an abort the workload provokes by design (e.g. a slippage
+    /// check), which is counted apart from other execution errors.
+    fn is_expected_abort(&self, _effects: &ExecutionEffects) -> bool {
+        false
+    }
 }
@@ -0,0 +1,702 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays the shapes of recorded transactions. A window of checkpoint files is reduced to the
+//! shape of each user transaction in it: its owned and shared inputs, the mix of its PTB commands
+//! and its computation gas. Shared objects are ranked by how many transactions use them, and the
+//! most used ones are mapped to their own stand-in shared object, so that the generated load has
+//! the same hot spots as the recorded one. Payloads then issue equivalent transactions against
+//! the stand-in `trace` package, cycling through the recorded shapes.
+
+use crate::drivers::Interval;
+use crate::in_memory_wallet::InMemoryWallet;
+use crate::system_state_observer::SystemStateObserver;
+use crate::workloads::benchmark_move_base_dir;
+use crate::workloads::payload::Payload;
+use crate::workloads::workload::{
+    ExpectedFailureType, Workload, WorkloadBuilder, MAX_GAS_FOR_TESTING,
+};
+use crate::workloads::{Gas, GasCoinConfig, WorkloadBuilderInfo, WorkloadParams};
+use crate::ProgrammableTransactionBuilder;
+use crate::{ExecutionEffects, ValidatorProxy};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use futures::future::join_all;
+use move_core_types::identifier::Identifier;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use sui_storage::blob::Blob;
+use sui_test_transaction_builder::TestTransactionBuilder;
+use sui_types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress};
+use sui_types::crypto::get_key_pair;
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::full_checkpoint_content::CheckpointData;
+use sui_types::object::Owner;
+use sui_types::transaction::{
+    Argument, CallArg, Command, ObjectArg, Transaction, TransactionDataAPI, TransactionKind,
+};
+use tracing::{error, info};
+
+/// Rough number of computation gas units burnt by one iteration of `trace::burn`.
+const GAS_UNITS_PER_BURN_ITERATION: u64 = 10;
+/// Cap on the iterations of `trace::burn` per transaction, to stay within the gas budget.
+const MAX_BURN_ITERATIONS: u64 = 100_000;
+/// Number of owned stand-ins a payload keeps. Once reached, transfers of recorded transactions
+/// are replayed by transferring existing stand-ins back to the sender.
+const MAX_OWNED_STAND_INS: usize = 16;
+
+/// Counts of the PTB commands of a recorded transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandMix {
+    pub move_calls: usize,
+    pub transfer_objects: usize,
+    pub split_coins: usize,
+    pub merge_coins: usize,
+}
+
+/// The parts of a recorded transaction that determine how it loads the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionShape {
+    /// Number of owned object inputs, not counting gas
+    pub num_owned_inputs: usize,
+    /// Shared inputs, as the hotness rank of the stand-in they map to and whether they are used
+    /// mutably
+    pub shared_inputs: Vec<(usize, bool)>,
+    pub commands: CommandMix,
+    /// Computation gas units used by the transaction
+    pub computation_units: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub shapes: Vec<TransactionShape>,
+    /// Number of stand-in shared objects the shapes refer to
+    pub num_shared_objects: usize,
+    /// Time covered by the recorded checkpoints
+    pub duration_ms: u64,
+}
+
+/// A recorded user transaction, with the shared objects it uses before they are ranked.
+struct RecordedTransaction {
+    num_owned_inputs: usize,
+    shared_inputs: Vec<(ObjectID, bool)>,
+    commands: CommandMix,
+    computation_units: u64,
+}
+
+/// Accumulates the transactions of checkpoints one at a time, so that a window of checkpoints
+/// never needs to be held in memory at once.
+#[derive(Default)]
+struct TraceRecorder {
+    recorded: Vec<RecordedTransaction>,
+    /// Number of transactions using each shared object
+    usage: HashMap<ObjectID, usize>,
+    /// Lowest and highest checkpoint timestamps seen
+    timestamps_ms: Option<(u64, u64)>,
+}
+
+impl TraceRecorder {
+    fn record(&mut self, checkpoint: &CheckpointData) {
+        let timestamp_ms = checkpoint.checkpoint_summary.timestamp_ms;
+        self.timestamps_ms = Some(match self.timestamps_ms {
+            Some((lo, hi)) => (lo.min(timestamp_ms), hi.max(timestamp_ms)),
+            None => (timestamp_ms, timestamp_ms),
+        });
+        for tx in &checkpoint.transactions {
+            let data = tx.transaction.transaction_data();
+            let TransactionKind::ProgrammableTransaction(pt) = data.kind() else {
+                continue;
+            };
+            let mut num_owned_inputs = 0;
+            let mut shared_inputs = vec![];
+            for input in &pt.inputs {
+                match input {
+                    CallArg::Object(ObjectArg::ImmOrOwnedObject(_))
+                    | CallArg::Object(ObjectArg::Receiving(_)) => num_owned_inputs += 1,
+                    CallArg::Object(ObjectArg::SharedObject { id, mutable, .. }) => {
+                        *self.usage.entry(*id).or_default() += 1;
+                        shared_inputs.push((*id, *mutable));
+                    }
+                    _ => {}
+                }
+            }
+            let mut commands = CommandMix::default();
+            for command in &pt.commands {
+                match command {
+                    Command::MoveCall(_) => commands.move_calls += 1,
+                    Command::TransferObjects(..) => commands.transfer_objects += 1,
+                    Command::SplitCoins(..) => commands.split_coins += 1,
+                    Command::MergeCoins(..) => commands.merge_coins += 1,
+                    _ => {}
+                }
+            }
+            let computation_units =
+                tx.effects.gas_cost_summary().computation_cost / data.gas_price().max(1);
+            self.recorded.push(RecordedTransaction {
+                num_owned_inputs,
+                shared_inputs,
+                commands,
+                computation_units,
+            });
+        }
+    }
+
+    fn finish(self, max_shared_objects: usize) -> Trace {
+        // Rank shared objects by the number of transactions using them
+        let mut ranked: Vec<_> = self.usage.into_iter().collect();
+        ranked.sort_by(|(id_a, a), (id_b, b)| b.cmp(a).then(id_a.cmp(id_b)));
+        let max_shared_objects = max_shared_objects.max(1);
+        let num_shared_objects = ranked.len().min(max_shared_objects);
+        // Objects beyond the cap are folded onto the least used half of the stand-ins, so that
+        // the hottest ones are as hot as they were recorded.
+        let first_fold = max_shared_objects / 2;
+        let stand_ins: HashMap<ObjectID, usize> = ranked
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (id, _))| {
+                let stand_in = if rank < max_shared_objects {
+                    rank
+                } else {
+                    first_fold + (rank - max_shared_objects) % (max_shared_objects - first_fold)
+                };
+                (id, stand_in)
+            })
+            .collect();
+
+        let shapes = self
+            .recorded
+            .into_iter()
+            .map(|tx| {
+                // Objects folded onto the same stand-in are used once, mutably if any of them is
+                // used mutably.
+                let mut stand_in_inputs = BTreeMap::new();
+                for (id, mutable) in tx.shared_inputs {
+                    *stand_in_inputs.entry(stand_ins[&id]).or_default() |= mutable;
+                }
+                TransactionShape {
+                    num_owned_inputs: tx.num_owned_inputs,
+                    shared_inputs: stand_in_inputs.into_iter().collect(),
+                    commands: tx.commands,
+                    computation_units: tx.computation_units,
+                }
+            })
+            .collect();
+        let duration_ms = self.timestamps_ms.map_or(0, |(lo, hi)| hi - lo);
+        Trace {
+            shapes,
+            num_shared_objects,
+            duration_ms,
+        }
+    }
+}
+
+impl Trace {
+    /// Loads the checkpoint files (`<sequence_number>.chk`) in `dir`, reading one checkpoint at a
+    /// time. Shared objects beyond the `max_shared_objects` most used ones share the stand-ins
+    /// of the least used ones.
+    pub fn load(dir: &Path, max_shared_objects: usize) -> anyhow::Result<Self> {
+        let mut files = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "chk") {
+                let sequence_number: u64 = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())
+                    .with_context(|| format!("Invalid checkpoint file name {}", path.display()))?;
+                files.push((sequence_number, path));
+            }
+        }
+        if files.is_empty() {
+            return Err(anyhow!("No checkpoint files in {}", dir.display()));
+        }
+        files.sort();
+        let mut recorder = TraceRecorder::default();
+        for (_, path) in files {
+            recorder.record(&Blob::from_bytes::<CheckpointData>(&fs::read(path)?)?);
+        }
+        Ok(recorder.finish(max_shared_objects))
+    }
+
+    pub fn from_checkpoints(checkpoints: &[CheckpointData], max_shared_objects: usize) -> Self {
+        let mut recorder = TraceRecorder::default();
+        for checkpoint in checkpoints {
+            recorder.record(checkpoint);
+        }
+        recorder.finish(max_shared_objects)
+    }
+
+    /// Transactions per second in the recorded checkpoints.
+    pub fn recorded_tps(&self) -> f64 {
+        self.shapes.len() as f64 * 1000.0 / self.duration_ms.max(1) as f64
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceTestPayload {
+    package_id: ObjectID,
+    /// Stand-in shared objects, in the order of the hotness rank of the objects they stand in for
+    shared_objects: Arc<Vec<(ObjectID, SequenceNumber)>>,
+    shapes: Arc<Vec<TransactionShape>>,
+    /// Index of the next shape to replay
+    next_shape: usize,
+    /// Number of payloads, by which `next_shape` advances
+    stride: usize,
+    sender: SuiAddress,
+    state: InMemoryWallet,
+    system_state_observer: Arc<SystemStateObserver>,
+}
+
+impl std::fmt::Display for TraceTestPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "trace")
+    }
+}
+
+impl Payload for TraceTestPayload {
+    fn make_new_payload(&mut self, effects: &ExecutionEffects) {
+        if !effects.is_ok() && !effects.is_cancelled() {
+            effects.print_gas_summary();
+            error!("Trace tx failed... Status: {:?}", effects.status());
+        }
+        self.state.update(effects);
+        self.next_shape = (self.next_shape + self.stride) % self.shapes.len();
+    }
+
+    fn make_transaction(&mut self) -> Transaction {
+        let shape = self.shapes[self.next_shape].clone();
+        self.create_transaction(&shape)
+    }
+
+    fn get_failure_type(&self) -> Option<ExpectedFailureType> {
+        None
+    }
+}
+
+impl TraceTestPayload {
+    fn move_call(
+        &self,
+        builder: &mut ProgrammableTransactionBuilder,
+        function: &str,
+        args: Vec<Argument>,
+    ) -> Argument {
+        builder.programmable_move_call(
+            self.package_id,
+            Identifier::new("trace").unwrap(),
+            Identifier::new(function).unwrap(),
+            vec![],
+            args,
+        )
+    }
+
+    fn create_transaction(&self, shape: &TransactionShape) -> Transaction {
+        let account = self.state.account(&self.sender).unwrap();
+        let gas_price = self
+            .system_state_observer
+            .state
+            .borrow()
+            .reference_gas_price;
+        let owned: Vec<ObjectRef> = self
+            .state
+            .owned_objects(&self.sender)
+            .map(|objects| objects.copied().collect())
+            .unwrap_or_default();
+        let mut builder = ProgrammableTransactionBuilder::new();
+        let mut move_calls = 0;
+
+        for (rank, mutable) in &shape.shared_inputs {
+            let (id, initial_shared_version) = self.shared_objects[*rank];
+            let arg = builder
+                .obj(ObjectArg::SharedObject {
+                    id,
+                    initial_shared_version,
+                    mutable: *mutable,
+                })
+                .unwrap();
+            let function = if *mutable {
+                "touch_shared"
+            } else {
+                "read_shared"
+            };
+            self.move_call(&mut builder, function, vec![arg]);
+            move_calls += 1;
+        }
+
+        let owned_inputs: Vec<Argument> = owned
+            .iter()
+            .take(shape.num_owned_inputs)
+            .map(|obj| builder.obj(ObjectArg::ImmOrOwnedObject(*obj)).unwrap())
+            .collect();
+        for arg in &owned_inputs {
+            self.move_call(&mut builder, "touch_owned", vec![*arg]);
+            move_calls += 1;
+        }
+
+        // Transfers send owned stand-ins back to the sender. New ones are created while the
+        // payload holds too few of them, and later transactions use them as owned inputs.
+        let recipient = builder.pure(self.sender).unwrap();
+        for i in 0..shape.commands.transfer_objects {
+            let obj = if let Some(arg) = owned_inputs.get(i) {
+                *arg
+            } else if owned.len() >= MAX_OWNED_STAND_INS && i < owned.len() {
+                builder.obj(ObjectArg::ImmOrOwnedObject(owned[i])).unwrap()
+            } else {
+                move_calls += 1;
+                self.move_call(&mut builder, "create_owned", vec![])
+            };
+            builder.command(Command::TransferObjects(vec![obj], recipient));
+        }
+
+        // Coins split off the gas coin are merged back into it, so that the payload's owned
+        // objects are all stand-ins.
+        let merges = shape.commands.merge_coins;
+        let splits = shape.commands.split_coins.max(merges);
+        if splits > 0 {
+            let amount = builder.pure(1u64).unwrap();
+            let coins: Vec<Argument> = (0..splits)
+                .map(|_| builder.command(Command::SplitCoins(Argument::GasCoin, vec![amount])))
+                .collect();
+            let chunk_size = splits.div_ceil(merges.max(1));
+            for chunk in coins.chunks(chunk_size) {
+                builder.command(Command::MergeCoins(Argument::GasCoin, chunk.to_vec()));
+            }
+        }
+
+        // Remaining Move calls burn the computation gas of the recorded transaction
+        let burns = shape.commands.move_calls.saturating_sub(move_calls).max(1);
+        let iterations = (shape.computation_units / GAS_UNITS_PER_BURN_ITERATION)
+            .min(MAX_BURN_ITERATIONS)
+            / burns as u64;
+        if iterations > 0 {
+            for _ in 0..burns {
+                let arg = builder.pure(iterations).unwrap();
+                self.move_call(&mut builder, "burn", vec![arg]);
+            }
+        }
+
+        TestTransactionBuilder::new(self.sender, account.gas, gas_price)
+            .programmable(builder.finish())
+            .build_and_sign(account.key())
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceWorkloadBuilder {
+    trace: Arc<Trace>,
+    num_payloads: u64,
+}
+
+impl TraceWorkloadBuilder {
+    pub fn from(
+        workload_weight: f32,
+        num_workers: u64,
+        in_flight_ratio: u64,
+        trace_checkpoint_dir: Option<&Path>,
+        trace_speedup_percent: u32,
+        trace_max_shared_objects: u64,
+        duration: Interval,
+        group: u32,
+    ) -> Option<WorkloadBuilderInfo> {
+        if workload_weight == 0.0 {
+            return None;
+        }
+        let dir =
+            trace_checkpoint_dir.expect("--trace-checkpoint-dir is required for trace workload");
+        let trace = Trace::load(dir, trace_max_shared_objects as usize)
+            .unwrap_or_else(|e| panic!("Failed to load trace from {}: {e}", dir.display()));
+        // The target qps is given by the trace rather than the workload weight
+        let target_qps =
+            (trace.recorded_tps() * trace_speedup_percent as f64 / 100.0).ceil() as u64;
+        let num_workers = (workload_weight * num_workers as f32).ceil() as u64;
+        let max_ops = target_qps * in_flight_ratio;
+        info!(
+            "Trace of {} transactions over {}ms, replaying at {} tps",
+            trace.shapes.len(),
+            trace.duration_ms,
+            target_qps
+        );
+        if max_ops == 0 || num_workers == 0 || trace.shapes.is_empty() {
+            None
+        } else {
+            let workload_params = WorkloadParams {
+                target_qps,
+                num_workers,
+                max_ops,
+                duration,
+                group,
+            };
+            let workload_builder =
+                Box::<dyn WorkloadBuilder<dyn Payload>>::from(Box::new(TraceWorkloadBuilder {
+                    trace: Arc::new(trace),
+                    num_payloads: max_ops,
+                }));
+            let builder_info = WorkloadBuilderInfo {
+                workload_params,
+                workload_builder,
+            };
+            Some(builder_info)
+        }
+    }
+}
+
+#[async_trait]
+impl WorkloadBuilder<dyn Payload> for TraceWorkloadBuilder {
+    async fn generate_coin_config_for_init(&self) -> Vec<GasCoinConfig> {
+        // Gas coin for publishing the package, and one for each stand-in shared object
+        (0..=self.trace.num_shared_objects)
+            .map(|_| {
+                let (address, keypair) = get_key_pair();
+                GasCoinConfig {
+                    amount: MAX_GAS_FOR_TESTING,
+                    address,
+                    keypair: Arc::new(keypair),
+                }
+            })
+            .collect()
+    }
+
+    async fn generate_coin_config_for_payloads(&self) -> Vec<GasCoinConfig> {
+        (0..self.num_payloads)
+            .map(|_| {
+                let (address, keypair) = get_key_pair();
+                GasCoinConfig {
+                    amount: MAX_GAS_FOR_TESTING,
+                    address,
+                    keypair: Arc::new(keypair),
+                }
+            })
+            .collect()
+    }
+
+    async fn build(
+        &self,
+        init_gas: Vec<Gas>,
+        payload_gas: Vec<Gas>,
+    ) -> Box<dyn Workload<dyn Payload>> {
+        Box::<dyn Workload<dyn Payload>>::from(Box::new(TraceWorkload {
+            package_id: None,
+            shared_objects: vec![],
+            trace: self.trace.clone(),
+            init_gas,
+            payload_gas,
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceWorkload {
+    /// ID of the stand-in `trace` package
+    package_id: Option<ObjectID>,
+    shared_objects: Vec<(ObjectID, SequenceNumber)>,
+    trace: Arc<Trace>,
+    pub init_gas: Vec<Gas>,
+    pub payload_gas: Vec<Gas>,
+}
+
+#[async_trait]
+impl Workload<dyn Payload> for TraceWorkload {
+    async fn init(
+        &mut self,
+        proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) {
+        if self.package_id.is_some() {
+            return;
+        }
+        let gas_price = system_state_observer.state.borrow().reference_gas_price;
+        let (head, tail) = self
+            .init_gas
+            .split_first()
+            .expect("Not enough gas to initialize trace workload");
+
+        info!("Publishing trace package");
+        let mut path = benchmark_move_base_dir();
+        path.push("src/workloads/data/trace");
+        let transaction = TestTransactionBuilder::new(head.1, head.0, gas_price)
+            .publish(path)
+            .build_and_sign(head.2.as_ref());
+        let (_, execution_result) = proxy.execute_transaction_block(transaction).await;
+        let effects = execution_result.unwrap();
+        let package_id = effects
+            .created()
+            .iter()
+            .find(|(_, owner)| matches!(owner, Owner::Immutable))
+            .expect("Trace package must be created")
+            .0
+             .0;
+        self.package_id = Some(package_id);
+        info!("Trace package id {:?}", package_id);
+
+        info!(
+            "Creating {} stand-in shared objects",
+            self.trace.num_shared_objects
+        );
+        let futures = tail.iter().map(|(gas, sender, keypair)| {
+            let mut builder = ProgrammableTransactionBuilder::new();
+            builder.programmable_move_call(
+                package_id,
+                Identifier::new("trace").unwrap(),
+                Identifier::new("create_shared").unwrap(),
+                vec![],
+                vec![],
+            );
+            let transaction = TestTransactionBuilder::new(*sender, *gas, gas_price)
+                .programmable(builder.finish())
+                .build_and_sign(keypair.as_ref());
+            let proxy = proxy.clone();
+            async move {
+                let (_, execution_result) = proxy.execute_transaction_block(transaction).await;
+                let effects = execution_result.unwrap();
+                let (obj, owner) = effects.created()[0];
+                let Owner::Shared {
+                    initial_shared_version,
+                } = owner
+                else {
+                    panic!("Stand-in object must be shared");
+                };
+                (obj.0, initial_shared_version)
+            }
+        });
+        self.shared_objects = join_all(futures).await;
+    }
+
+    async fn make_test_payloads(
+        &self,
+        _proxy: Arc<dyn ValidatorProxy + Sync + Send>,
+        system_state_observer: Arc<SystemStateObserver>,
+    ) -> Vec<Box<dyn Payload>> {
+        let shared_objects = Arc::new(self.shared_objects.clone());
+        let shapes = Arc::new(self.trace.shapes.clone());
+        let stride = self.payload_gas.len();
+        self.payload_gas
+            .iter()
+            .enumerate()
+            .map(|(i, gas)| {
+                Box::<dyn Payload>::from(Box::new(TraceTestPayload {
+                    package_id: self.package_id.unwrap(),
+                    shared_objects: shared_objects.clone(),
+                    shapes: shapes.clone(),
+                    next_shape: i % shapes.len(),
+                    stride,
+                    sender: gas.1,
+                    state: InMemoryWallet::new(gas),
+                    system_state_observer: system_state_observer.clone(),
+                }))
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "Trace"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandMix, Trace, TransactionShape};
+    use sui_types::base_types::ObjectID;
+    use sui_types::full_checkpoint_content::CheckpointData;
+    use sui_types::object::Owner;
+    use sui_types::test_checkpoint_data_builder::TestCheckpointDataBuilder;
+
+    /// Two checkpoints, 2s apart, using shared objects 1 (by three transactions), 3 (by two) and
+    /// 2 (by one).
+    fn checkpoints() -> Vec<CheckpointData> {
+        let mut builder = TestCheckpointDataBuilder::new(0)
+            .with_timestamp_ms(1000)
+            .start_transaction(0)
+            .create_shared_object(1)
+            .create_shared_object(2)
+            .create_shared_object(3)
+            .create_owned_object(10)
+            .finish_transaction()
+            .start_transaction(0)
+            .change_object_owner(10, Owner::Immutable)
+            .finish_transaction();
+        let first = builder.build_checkpoint();
+
+        let mut builder = builder
+            .with_timestamp_ms(3000)
+            .start_transaction(0)
+            .read_frozen_object(10)
+            .mutate_shared_object(1)
+            .read_shared_object(2)
+            .add_move_call(ObjectID::ZERO, "m", "f")
+            .add_move_call(ObjectID::ZERO, "m", "g")
+            .finish_transaction()
+            .start_transaction(0)
+            .mutate_shared_object(1)
+            .finish_transaction()
+            .start_transaction(0)
+            .read_shared_object(1)
+            .read_shared_object(3)
+            .finish_transaction()
+            .start_transaction(0)
+            .read_shared_object(3)
+            .finish_transaction();
+        let second = builder.build_checkpoint();
+        vec![first, second]
+    }
+
+    fn shape(
+        num_owned_inputs: usize,
+        shared_inputs: Vec<(usize, bool)>,
+        move_calls: usize,
+    ) -> TransactionShape {
+        TransactionShape {
+            num_owned_inputs,
+            shared_inputs,
+            commands: CommandMix {
+                move_calls,
+                ..Default::default()
+            },
+            computation_units: 0,
+        }
+    }
+
+    #[test]
+    fn test_from_checkpoints() {
+        let trace = Trace::from_checkpoints(&checkpoints(), 10);
+        assert_eq!(trace.num_shared_objects, 3);
+        assert_eq!(trace.duration_ms, 2000);
+        assert_eq!(trace.recorded_tps(), 3.0);
+        // Shared objects are ranked 1, 3, 2 by the number of transactions using them
+        assert_eq!(
+            trace.shapes,
+            vec![
+                shape(0, vec![], 0),
+                shape(0, vec![], 0),
+                shape(1, vec![(0, true), (2, false)], 2),
+                shape(0, vec![(0, true)], 0),
+                shape(0, vec![(0, false), (1, false)], 0),
+                shape(0, vec![(1, false)], 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_checkpoints_caps_shared_objects() {
+        let trace = Trace::from_checkpoints(&checkpoints(), 2);
+        assert_eq!(trace.num_shared_objects, 2);
+        // The least used object 2 is folded onto the least used stand-in, that of object 3,
+        // rather than onto the hottest one
+        assert_eq!(
+            trace.shapes[2..],
+            [
+                shape(1, vec![(0, true), (1, false)], 2),
+                shape(0, vec![(0, true)], 0),
+                shape(0, vec![(0, false), (1, false)], 0),
+                shape(0, vec![(1, false)], 0),
+            ]
+        );
+
+        // A cap of zero still keeps one stand-in, that every shared object is folded onto. The
+        // first transaction using objects 1 and 2 then only uses it once, mutably.
+        let trace = Trace::from_checkpoints(&checkpoints(), 0);
+        assert_eq!(trace.num_shared_objects, 1);
+        assert_eq!(trace.shapes[2], shape(1, vec![(0, true)], 2));
+        assert_eq!(trace.shapes[4], shape(0, vec![(0, false)], 0));
+    }
+}
@@ -5,15 +5,18 @@ use crate::bank::BenchmarkBank;
 use crate::drivers::Interval;
 use crate::options::{Opts, RunSpec};
 use crate::system_state_observer::SystemStateObserver;
+use crate::workloads::amm::AmmWorkloadBuilder;
 use crate::workloads::batch_payment::BatchPaymentWorkloadBuilder;
 use crate::workloads::delegation::DelegationWorkloadBuilder;
 use crate::workloads::party::PartyWorkloadBuilder;
 use crate::workloads::shared_counter::SharedCounterWorkloadBuilder;
 use crate::workloads::slow::SlowWorkloadBuilder;
+use crate::workloads::trace::TraceWorkloadBuilder;
 use crate::workloads::transfer_object::TransferObjectWorkloadBuilder;
 use crate::workloads::{ExpectedFailureType, GroupID, WorkloadBuilderInfo, WorkloadInfo};
 use anyhow::Result;
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use tracing::info;
@@ -37,6 +40,8 @@ pub struct WorkloadWeights {
     pub randomized_transaction: u32,
     pub slow: u32,
     pub party: u32,
+    pub trace: u32,
+    pub amm: u32,
 }
 
 pub struct WorkloadConfig {
@@ -50,6 +55,12 @@ pub struct WorkloadConfig {
     pub shared_counter_hotness_factor: u32,
     pub num_shared_counters: Option<u64>,
     pub shared_counter_max_tip: u64,
+    pub trace_checkpoint_dir: Option<PathBuf>,
+    pub trace_speedup_percent: u32,
+    pub trace_max_shared_objects: u64,
+    pub amm_num_pools: u64,
+    pub amm_zipf_exponent_percent: u32,
+    pub amm_slippage_bps: u32,
     pub target_qps: u64,
     pub in_flight_ratio: u64,
     pub duration: Interval,
@@ -79,12 +90,20 @@ impl WorkloadConfiguration {
                 randomized_transaction,
                 slow,
                 party,
+                trace,
+                amm,
                 shared_counter_hotness_factor,
                 num_shared_counters,
                 shared_counter_max_tip,
                 batch_payment_size,
                 adversarial_cfg,
                 expected_failure_type,
+                trace_checkpoint_dir,
+                trace_speedup_percent,
+                trace_max_shared_objects,
+                amm_num_pools,
+                amm_zipf_exponent_percent,
+                amm_slippage_bps,
                 target_qps,
                 num_workers,
                 in_flight_ratio,
@@ -115,6 +134,8 @@ impl WorkloadConfiguration {
                             randomized_transaction: randomized_transaction[i],
                             slow: slow[i],
                             party: party[i],
+                            trace: trace[i],
+                            amm: amm[i],
                         },
                         adversarial_cfg: AdversarialPayloadCfg::from_str(&adversarial_cfg[i])
                             .unwrap(),
@@ -126,6 +147,12 @@ impl WorkloadConfiguration {
                         shared_counter_hotness_factor: shared_counter_hotness_factor[i],
                         num_shared_counters: num_shared_counters.as_ref().map(|n| n[i]),
                         shared_counter_max_tip: shared_counter_max_tip[i],
+                        trace_checkpoint_dir: trace_checkpoint_dir.clone(),
+                        trace_speedup_percent: trace_speedup_percent[i],
+                        trace_max_shared_objects: trace_max_shared_objects[i],
+                        amm_num_pools: amm_num_pools[i],
+                        amm_zipf_exponent_percent: amm_zipf_exponent_percent[i],
+                        amm_slippage_bps: amm_slippage_bps[i],
                         target_qps: target_qps[i],
                         in_flight_ratio: in_flight_ratio[i],
                         duration: duration[i],
@@ -200,6 +227,12 @@ impl WorkloadConfiguration {
             shared_counter_hotness_factor,
             num_shared_counters,
             shared_counter_max_tip,
+            trace_checkpoint_dir,
+            trace_speedup_percent,
+            trace_max_shared_objects,
+            amm_num_pools,
+            amm_zipf_exponent_percent,
+            amm_slippage_bps,
             target_qps,
             in_flight_ratio,
             duration,
@@ -223,7 +256,9 @@ impl WorkloadConfiguration {
             + weights.expected_failure
             + weights.randomized_transaction
             + weights.slow
-            + weights.party;
+            + weights.party
+            + weights.trace
+            + weights.amm;
         let reference_gas_price = system_state_observer.state.borrow().reference_gas_price;
         let mut workload_builders = vec![];
         let shared_workload = SharedCounterWorkloadBuilder::from(
@@ -339,6 +374,29 @@ impl WorkloadConfiguration {
             group,
         );
         workload_builders.push(party_workload);
+        let trace_workload = TraceWorkloadBuilder::from(
+            weights.trace as f32 / total_weight as f32,
+            num_workers,
+            in_flight_ratio,
+            trace_checkpoint_dir.as_deref(),
+            trace_speedup_percent,
+            trace_max_shared_objects,
+            duration,
+            group,
+        );
+        workload_builders.push(trace_workload);
+        let amm_workload = AmmWorkloadBuilder::from(
+            weights.amm as f32 / total_weight as f32,
+            target_qps,
+            num_workers,
+            in_flight_ratio,
+            amm_num_pools,
+            amm_zipf_exponent_percent,
+            amm_slippage_bps,
+            duration,
+            group,
+        );
+        workload_builders.push(amm_workload);
         workload_builders
     }
 }
@@ -637,6 +637,8 @@ mod test {
 
             // Always enable the randomized tx workload in this test.
             simulated_load_config.randomized_transaction_weight = 1;
+            // Swaps on a few hot pools, alongside the shared counters.
+            simulated_load_config.amm_weight = 5;
             info!("Simulated load config: {:?}", simulated_load_config);
         }
 
@@ -1103,6 +1105,7 @@ mod test {
         expected_failure_weight: u32,
         expected_failure_config: ExpectedFailurePayloadCfg,
         party_weight: u32,
+        amm_weight: u32,
     }
 
     impl Default for SimulatedLoadConfig {
@@ -1128,6 +1131,7 @@ mod test {
                 },
                 // TODO: Set this to 1 once party object is enabled in mainnet protocol config.
                 party_weight: 0,
+                amm_weight: 0,
             }
         }
     }
@@ -1234,6 +1238,8 @@ mod test {
             randomized_transaction: config.randomized_transaction_weight,
             slow: config.slow_weight,
             party: config.party_weight,
+            trace: 0,
+            amm: config.amm_weight,
         };
 
         let workload_config = WorkloadConfig {
@@ -1247,6 +1253,12 @@ mod test {
             shared_counter_hotness_factor: config.shared_counter_hotness_factor,
             num_shared_counters: config.num_shared_counters,
             shared_counter_max_tip,
+            trace_checkpoint_dir: None,
+            trace_speedup_percent: 100,
+            trace_max_shared_objects: 1000,
+            amm_num_pools: 16,
+            amm_zipf_exponent_percent: 100,
+            amm_slippage_bps: 50,
             target_qps,
             in_flight_ratio,
             duration,
@@ -221,6 +221,16 @@ pub struct NodeConfig {
     /// Fork recovery configuration for handling validator equivocation after forks
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fork_recovery: Option<ForkRecoveryConfig>,
+
+    /// If set, a background scrubber verifies the authority store against itself while the node
+    /// is running, to detect silent database corruption.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authority_store_scrubber_config: Option<AuthorityStoreScrubberConfig>,
+
+    /// Selects the storage engine of each of the node's databases. Databases not listed use the
+    /// default engine of the build.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_engine_config: Option<StorageEngineConfig>,
 }
 
 #[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
@@ -1088,6 +1098,22 @@ pub struct AuthorityStorePruningConfig {
     pub enable_compaction_filter: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub num_epochs_to_retain_for_indexes: Option<u64>,
+    /// objects whose versions are all retained indefinitely, regardless of `num_epochs_to_retain`.
+    /// Requires `enable_compaction_filter`, which enforces it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object_retention: Option<ObjectRetentionConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ObjectRetentionConfig {
+    /// all versions of these packages, and of objects whose type is defined in one of them, are retained
+    #[serde(default)]
+    pub packages: Vec<ObjectID>,
+    /// all versions of objects of these types are retained. A type given without type parameters,
+    /// e.g. `0x2::coin::Coin`, covers all of its instantiations
+    #[serde(default)]
+    pub object_types: Vec<String>,
 }
 
 fn default_num_latest_epoch_dbs_to_retain() -> usize {
@@ -1129,6 +1155,7 @@ impl Default for AuthorityStorePruningConfig {
             smooth: true,
             enable_compaction_filter: cfg!(test) || cfg!(msim),
             num_epochs_to_retain_for_indexes: None,
+            object_retention: None,
         }
     }
 }
@@ -1160,6 +1187,77 @@ impl AuthorityStorePruningConfig {
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthorityStoreScrubberConfig {
+    /// time to wait between scrubbing batches, which keeps the scrubber at low priority
+    #[serde(default = "default_scrub_interval_ms")]
+    pub scrub_interval_ms: u64,
+    /// number of entries of the objects table sampled per batch
+    #[serde(default = "default_scrub_objects_per_batch")]
+    pub objects_per_batch: usize,
+    /// number of executed checkpoints verified per batch
+    #[serde(default = "default_scrub_checkpoints_per_batch")]
+    pub checkpoints_per_batch: usize,
+    /// halts the node when corruption is found, instead of only reporting it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub halt_on_corruption: bool,
+}
+
+fn default_scrub_interval_ms() -> u64 {
+    1000
+}
+
+fn default_scrub_objects_per_batch() -> usize {
+    100
+}
+
+fn default_scrub_checkpoints_per_batch() -> usize {
+    1
+}
+
+impl Default for AuthorityStoreScrubberConfig {
+    fn default() -> Self {
+        Self {
+            scrub_interval_ms: default_scrub_interval_ms(),
+            objects_per_batch: default_scrub_objects_per_batch(),
+            checkpoints_per_batch: default_scrub_checkpoints_per_batch(),
+            halt_on_corruption: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum StorageEngine {
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+    /// Requires a build with TideHunter support, i.e. with `USE_TIDEHUNTER` set.
+    #[serde(rename = "tidehunter")]
+    TideHunter,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct StorageEngineConfig {
+    /// engine of the perpetual authority store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perpetual: Option<StorageEngine>,
+    /// engine of the per-epoch authority stores
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<StorageEngine>,
+    /// engine of the checkpoint store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<StorageEngine>,
+    /// engine of the JSON-RPC and gRPC index stores. They rely on RocksDB-specific table
+    /// options, so only `rocksdb` is supported for now and other engines are rejected.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_index_storage_engine"
+    )]
+    pub index: Option<StorageEngine>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct MetricsConfig {
@@ -1182,6 +1280,10 @@ pub struct DBCheckpointConfig {
     pub perform_index_db_checkpoints_at_epoch_end: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prune_and_compact_before_upload: Option<bool>,
+    /// If true, db checkpoints are uploaded in chunks addressed by their digest, so that files
+    /// shared between epochs are stored once and interrupted uploads resume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_addressed_upload: Option<bool>,
 }
 
 #[derive(Debug, Clone)]
@@ -1214,6 +1316,10 @@ pub struct StateSnapshotConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub object_store_config: Option<ObjectStoreConfig>,
     pub concurrency: usize,
+    /// Whether to also write a delta snapshot of each epoch against the previous one. This keeps
+    /// the db checkpoint of each epoch around until the snapshots of the next one are written.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub delta_snapshots: bool,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
@@ -1549,6 +1655,22 @@ fn read_credential_from_path_or_literal(value: &str) -> Result<String, std::io::
 }
 
 // Custom deserializer for remote store options that supports file paths or literal values
+fn deserialize_index_storage_engine<'de, D>(
+    deserializer: D,
+) -> Result<Option<StorageEngine>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    match Option::<StorageEngine>::deserialize(deserializer)? {
+        Some(StorageEngine::TideHunter) => Err(D::Error::custom(
+            "the index stores only support the rocksdb storage engine",
+        )),
+        engine => Ok(engine),
+    }
+}
+
 fn deserialize_remote_store_options<'de, D>(
     deserializer: D,
 ) -> Result<Vec<(String, String)>, D::Error>
@@ -1584,7 +1706,7 @@ mod tests {
     use sui_keys::keypair_file::{write_authority_keypair_to_file, write_keypair_to_file};
     use sui_types::crypto::{get_key_pair_from_rng, AuthorityKeyPair, NetworkKeyPair, SuiKeyPair};
 
-    use super::{Genesis, StateArchiveConfig};
+    use super::{Genesis, StateArchiveConfig, StorageEngine, StorageEngineConfig};
     use crate::NodeConfig;
 
     #[test]
@@ -1721,6 +1843,30 @@ remote-store-options:
         assert_eq!(config.remote_store_options[0].1, "literal_access_key");
         assert_eq!(config.remote_store_options[1].1, "literal_secret_key");
     }
+
+    #[test]
+    fn test_storage_engine_config() {
+        let yaml_config = r#"
+perpetual: tidehunter
+checkpoint: rocksdb
+"#;
+
+        let config: StorageEngineConfig = serde_yaml::from_str(yaml_config).unwrap();
+
+        assert_eq!(config.perpetual, Some(StorageEngine::TideHunter));
+        assert_eq!(config.epoch, None);
+        assert_eq!(config.checkpoint, Some(StorageEngine::RocksDb));
+        assert_eq!(config.index, None);
+
+        let config: StorageEngineConfig = serde_yaml::from_str("index: rocksdb").unwrap();
+        assert_eq!(config.index, Some(StorageEngine::RocksDb));
+
+        // The index stores only support RocksDB
+        let err = serde_yaml::from_str::<StorageEngineConfig>("index: tidehunter").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the index stores only support the rocksdb storage engine"));
+    }
 }
 
 // RunWithRange is used to specify the ending epoch/checkpoint to process.
@@ -165,6 +165,7 @@ use crate::authority::authority_store::{ExecutionLockReadGuard, ObjectLockStatus
 use crate::authority::authority_store_pruner::{
     AuthorityStorePruner, EPOCH_DURATION_MS_FOR_TESTING,
 };
+use crate::authority::authority_store_scrubber::AuthorityStoreScrubber;
 use crate::authority::epoch_start_configuration::EpochStartConfigTrait;
 use crate::authority::epoch_start_configuration::EpochStartConfiguration;
 use crate::checkpoints::CheckpointStore;
@@ -238,6 +239,7 @@ pub mod authority_per_epoch_store;
 pub mod authority_per_epoch_store_pruner;
 
 pub mod authority_store_pruner;
+pub mod authority_store_scrubber;
 pub mod authority_store_tables;
 pub mod authority_store_types;
 pub mod consensus_tx_status_cache;
@@ -1002,6 +1004,7 @@ pub struct AuthorityState {
     pub metrics: Arc<AuthorityMetrics>,
     _pruner: AuthorityStorePruner,
     _authority_per_epoch_pruner: AuthorityPerEpochStorePruner,
+    _scrubber: Option<AuthorityStoreScrubber>,
 
     /// Take db checkpoints of different dbs
     db_checkpoint_config: DBCheckpointConfig,
@@ -3481,6 +3484,17 @@ impl AuthorityState {
             prometheus_registry,
             pruner_db,
         );
+        let _scrubber = config
+            .authority_store_scrubber_config
+            .clone()
+            .map(|scrubber_config| {
+                AuthorityStoreScrubber::new(
+                    store.perpetual_tables.clone(),
+                    checkpoint_store.clone(),
+                    scrubber_config,
+                    prometheus_registry,
+                )
+            });
         let input_loader =
             TransactionInputLoader::new(execution_cache_trait_pointers.object_cache_reader.clone());
         let epoch = epoch_store.epoch();
@@ -3520,6 +3534,7 @@ impl AuthorityState {
             tx_execution_shutdown: Mutex::new(Some(tx_execution_shutdown)),
             metrics,
             _pruner,
+            _scrubber,
             _authority_per_epoch_pruner,
             db_checkpoint_config: db_checkpoint_config.clone(),
             config,
@@ -29,7 +29,7 @@ use parking_lot::RwLock;
 use parking_lot::{Mutex, RwLockReadGuard, RwLockWriteGuard};
 use prometheus::IntCounter;
 use serde::{Deserialize, Serialize};
-use sui_config::node::ExpensiveSafetyCheckConfig;
+use sui_config::node::{ExpensiveSafetyCheckConfig, StorageEngine};
 use sui_execution::{self, Executor};
 use sui_macros::fail_point;
 use sui_macros::fail_point_arg;
@@ -84,7 +84,7 @@ use typed_store::rocksdb::Options;
 use typed_store::DBMapUtils;
 use typed_store::Map;
 
-use super::authority_store_tables::ENV_VAR_LOCKS_BLOCK_CACHE_SIZE;
+use super::authority_store_tables::{resolve_storage_engine, ENV_VAR_LOCKS_BLOCK_CACHE_SIZE};
 use super::consensus_tx_status_cache::{ConsensusTxStatus, ConsensusTxStatusCache};
 use super::epoch_start_configuration::EpochStartConfigTrait;
 use super::execution_time_estimator::{ConsensusObservations, ExecutionTimeEstimator};
@@ -341,6 +341,7 @@ pub struct AuthorityPerEpochStore {
     // needed for re-opening epoch db.
     parent_path: PathBuf,
     db_options: Option<Options>,
+    storage_engine: Option<StorageEngine>,
 
     /// In-memory cache of the content from the reconfig_state db table.
     reconfig_state_mem: RwLock<ReconfigState>,
@@ -598,18 +599,30 @@ fn pending_consensus_transactions_table_default_config() -> DBOptions {
 }
 
 impl AuthorityEpochTables {
+    pub fn open(
+        epoch: EpochId,
+        parent_path: &Path,
+        db_options: Option<Options>,
+        storage_engine: Option<StorageEngine>,
+    ) -> Self {
+        match resolve_storage_engine(storage_engine) {
+            StorageEngine::RocksDb => Self::open_tables_read_write(
+                Self::path(epoch, parent_path),
+                MetricConf::new("epoch"),
+                db_options,
+                None,
+            ),
+            StorageEngine::TideHunter => Self::open_tidehunter(epoch, parent_path),
+        }
+    }
+
     #[cfg(not(tidehunter))]
-    pub fn open(epoch: EpochId, parent_path: &Path, db_options: Option<Options>) -> Self {
-        Self::open_tables_read_write(
-            Self::path(epoch, parent_path),
-            MetricConf::new("epoch"),
-            db_options,
-            None,
-        )
+    fn open_tidehunter(_: EpochId, _: &Path) -> Self {
+        panic!("AuthorityEpochTables can't use tidehunter in a build without it");
     }
 
     #[cfg(tidehunter)]
-    pub fn open(epoch: EpochId, parent_path: &Path, db_options: Option<Options>) -> Self {
+    fn open_tidehunter(epoch: EpochId, parent_path: &Path) -> Self {
         tracing::warn!("AuthorityEpochTables using tidehunter");
         use typed_store::tidehunter_util::{
             default_cells_per_mutex, KeyIndexing, KeySpaceConfig, KeyType, ThConfig,
@@ -843,7 +856,7 @@ impl AuthorityEpochTables {
                 ThConfig::new(8 + 4, MUTEXES, uniform_key),
             ),
         ];
-        Self::open_tables_read_write(
+        Self::open_tables_tidehunter(
             Self::path(epoch, parent_path),
             MetricConf::new("epoch"),
             configs.into_iter().collect(),
@@ -958,6 +971,7 @@ impl AuthorityPerEpochStore {
         committee: Arc<Committee>,
         parent_path: &Path,
         db_options: Option<Options>,
+        storage_engine: Option<StorageEngine>,
         metrics: Arc<EpochMetrics>,
         epoch_start_configuration: EpochStartConfiguration,
         backing_package_store: Arc<dyn BackingPackageStore + Send + Sync>,
@@ -975,7 +989,8 @@ impl AuthorityPerEpochStore {
             .current_voting_right
             .set(committee.weight(&name) as i64);
 
-        let tables = AuthorityEpochTables::open(epoch_id, parent_path, db_options.clone());
+        let tables =
+            AuthorityEpochTables::open(epoch_id, parent_path, db_options.clone(), storage_engine);
         let end_of_publish =
             StakeAggregator::from_iter(committee.clone(), tables.end_of_publish.safe_iter())?;
         let reconfig_state = tables
@@ -1141,6 +1156,7 @@ impl AuthorityPerEpochStore {
             )),
             parent_path: parent_path.to_path_buf(),
             db_options,
+            storage_engine,
             reconfig_state_mem: RwLock::new(reconfig_state),
             epoch_alive_notify,
             user_certs_closed_notify: NotifyOnce::new(),
@@ -1314,6 +1330,7 @@ impl AuthorityPerEpochStore {
             Arc::new(new_committee),
             &self.parent_path,
             self.db_options.clone(),
+            self.storage_engine,
             self.metrics.clone(),
             epoch_start_configuration,
             backing_package_store,
@@ -645,11 +645,30 @@ impl AuthorityStore {
         perpetual_db: &AuthorityPerpetualTables,
         live_objects: impl Iterator<Item = LiveObject>,
         expected_sha3_digest: &[u8; 32],
+    ) -> SuiResult<()> {
+        Self::bulk_insert_filtered_live_objects(
+            perpetual_db,
+            live_objects,
+            |_| true,
+            expected_sha3_digest,
+        )
+    }
+
+    /// Like `bulk_insert_live_objects`, but only inserts the objects matching `filter`. The
+    /// digest is still checked against all objects.
+    pub fn bulk_insert_filtered_live_objects(
+        perpetual_db: &AuthorityPerpetualTables,
+        live_objects: impl Iterator<Item = LiveObject>,
+        filter: impl Fn(&LiveObject) -> bool,
+        expected_sha3_digest: &[u8; 32],
     ) -> SuiResult<()> {
         let mut hasher = Sha3_256::default();
         let mut batch = perpetual_db.objects.batch();
         for object in live_objects {
             hasher.update(object.object_reference().2.inner());
+            if !filter(&object) {
+                continue;
+            }
             match object {
                 LiveObject::Normal(object) => {
                     let store_object_wrapper = get_store_object(object.clone());
@@ -692,6 +711,29 @@ impl AuthorityStore {
         Ok(())
     }
 
+    /// Removes the given live objects (e.g. those listed as removed by a delta snapshot) along
+    /// with their owned object markers. This assumes a store restored from a formal snapshot,
+    /// which holds a single version of each live object, so that no older version of a removed
+    /// object becomes live again.
+    pub fn bulk_remove_live_objects(
+        perpetual_db: &AuthorityPerpetualTables,
+        object_refs: impl Iterator<Item = ObjectRef>,
+    ) -> SuiResult<()> {
+        let mut batch = perpetual_db.objects.batch();
+        for object_ref in object_refs {
+            batch.delete_batch(
+                &perpetual_db.objects,
+                std::iter::once(ObjectKey::from(object_ref)),
+            )?;
+            batch.delete_batch(
+                &perpetual_db.live_owned_object_markers,
+                std::iter::once(object_ref),
+            )?;
+        }
+        batch.write()?;
+        Ok(())
+    }
+
     pub fn set_epoch_start_configuration(
         &self,
         epoch_start_configuration: &EpochStartConfiguration,
@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::authority_store_tables::{AuthorityPerpetualTables, AuthorityPrunerTables};
-use crate::authority::authority_store_types::{StoreObject, StoreObjectWrapper};
+use crate::authority::authority_store_types::{
+    StoreData, StoreObject, StoreObjectValue, StoreObjectWrapper,
+};
 use crate::checkpoints::{CheckpointStore, CheckpointWatermark};
 use crate::jsonrpc_index::IndexStore;
 use crate::rpc_index::RpcIndexStore;
 use anyhow::anyhow;
 use bincode::Options;
+use move_core_types::language_storage::StructTag;
 use mysten_metrics::{monitored_scope, spawn_monitored_task};
 use once_cell::sync::Lazy;
 use prometheus::{
@@ -15,12 +18,12 @@ use prometheus::{
     Registry,
 };
 use std::cmp::{max, min};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::{Mutex, Weak};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{sync::Arc, time::Duration};
-use sui_config::node::AuthorityStorePruningConfig;
-use sui_types::base_types::SequenceNumber;
+use sui_config::node::{AuthorityStorePruningConfig, ObjectRetentionConfig};
+use sui_types::base_types::{MoveObjectType, SequenceNumber};
 use sui_types::committee::EpochId;
 use sui_types::effects::TransactionEffects;
 use sui_types::effects::TransactionEffectsAPI;
@@ -30,6 +33,7 @@ use sui_types::messages_checkpoint::{
 };
 use sui_types::{
     base_types::{ObjectID, VersionNumber},
+    parse_sui_struct_tag,
     storage::ObjectKey,
 };
 use tokio::sync::oneshot::{self, Sender};
@@ -129,6 +133,63 @@ pub enum PruningMode {
     Checkpoints,
 }
 
+/// Objects whose versions are all retained, regardless of the number of epochs to retain. Built
+/// from [`ObjectRetentionConfig`], and enforced by [`ObjectsCompactionFilter`] and when pruning
+/// object tombstones.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectRetentionPolicy {
+    packages: HashSet<ObjectID>,
+    object_types: Vec<StructTag>,
+}
+
+impl ObjectRetentionPolicy {
+    pub fn new(config: Option<&ObjectRetentionConfig>) -> anyhow::Result<Self> {
+        let Some(config) = config else {
+            return Ok(Self::default());
+        };
+        let object_types = config
+            .object_types
+            .iter()
+            .map(|object_type| {
+                parse_sui_struct_tag(object_type)
+                    .map_err(|e| anyhow!("invalid retained object type {object_type}: {e}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            packages: config.packages.iter().copied().collect(),
+            object_types,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.object_types.is_empty()
+    }
+
+    fn retains(&self, object: &StoreObjectValue) -> bool {
+        match &object.data {
+            StoreData::Move(object) => self.retains_type(object.type_()),
+            StoreData::Coin(_) => self.retains_type(&MoveObjectType::gas_coin()),
+            StoreData::Package(package) => self.packages.contains(&package.id()),
+            StoreData::IndirectObjectDeprecated => false,
+        }
+    }
+
+    fn retains_type(&self, object_type: &MoveObjectType) -> bool {
+        self.packages
+            .contains(&ObjectID::from(object_type.address()))
+            || self.object_types.iter().any(|tag| {
+                // A type without type parameters covers all of its instantiations.
+                if tag.type_params.is_empty() {
+                    object_type.address() == tag.address
+                        && object_type.module() == tag.module.as_ident_str()
+                        && object_type.name() == tag.name.as_ident_str()
+                } else {
+                    object_type.is(tag)
+                }
+            })
+    }
+}
+
 impl AuthorityStorePruner {
     /// prunes old versions of objects based on transaction effects
     async fn prune_objects(
@@ -138,6 +199,7 @@ impl AuthorityStorePruner {
         checkpoint_number: CheckpointSequenceNumber,
         metrics: Arc<AuthorityStorePruningMetrics>,
         enable_pruning_tombstones: bool,
+        retention: &ObjectRetentionPolicy,
     ) -> anyhow::Result<()> {
         let _scope = monitored_scope("ObjectsLivePruner");
         let mut wb = perpetual_db.objects.batch();
@@ -199,15 +261,28 @@ impl AuthorityStorePruner {
         // performance, we set `ignore_range_deletions` on all read options, and using range delete to delete tombstones
         // may leak object (imagine a tombstone is compacted away, but earlier version is still not). Using point deletes
         // guarantees that all earlier versions are deleted in the database.
+        // Versions kept by the retention policy are not deleted, and neither are the tombstones
+        // following them, so that the retained versions are never read as the latest version of
+        // a deleted or wrapped object.
         if !object_tombstones_to_prune.is_empty() {
             let mut object_keys_to_delete = vec![];
             for ObjectKey(object_id, seq_number) in object_tombstones_to_prune {
+                let mut retained = false;
                 for result in perpetual_db.objects.safe_iter_with_bounds(
                     Some(ObjectKey(object_id, VersionNumber::MIN)),
                     Some(ObjectKey(object_id, seq_number.next())),
                 ) {
-                    let (object_key, _) = result?;
+                    let (object_key, object) = result?;
                     assert_eq!(object_key.0, object_id);
+                    match object.into_inner() {
+                        StoreObject::Value(object) if retention.retains(&object) => {
+                            retained = true;
+                            continue;
+                        }
+                        StoreObject::Value(_) => {}
+                        _ if retained => continue,
+                        _ => {}
+                    }
                     object_keys_to_delete.push(object_key);
                 }
             }
@@ -412,6 +487,12 @@ impl AuthorityStorePruner {
         metrics: Arc<AuthorityStorePruningMetrics>,
     ) -> anyhow::Result<()> {
         let _scope = monitored_scope("PruneForEligibleEpochs");
+        let retention = ObjectRetentionPolicy::new(config.object_retention.as_ref())?;
+        if mode == PruningMode::Objects && !retention.is_empty() && pruner_db.is_none() {
+            return Err(anyhow!(
+                "object retention requires the objects compaction filter to be enabled"
+            ));
+        }
 
         let mut checkpoint_number = starting_checkpoint_number;
         let current_epoch = checkpoint_store
@@ -472,6 +553,7 @@ impl AuthorityStorePruner {
                             checkpoint_number,
                             metrics.clone(),
                             !config.killswitch_tombstone_pruning,
+                            &retention,
                         )
                         .await?
                     }
@@ -504,6 +586,7 @@ impl AuthorityStorePruner {
                         checkpoint_number,
                         metrics.clone(),
                         !config.killswitch_tombstone_pruning,
+                        &retention,
                     )
                     .await?
                 }
@@ -630,7 +713,6 @@ impl AuthorityStorePruner {
     }
 
     #[allow(clippy::all)]
-    #[allow(dead_code)]
     fn th_pruning_config() -> AuthorityStorePruningConfig {
         let mut config = AuthorityStorePruningConfig::default();
         config.num_epochs_to_retain = u64::MAX;
@@ -655,8 +737,11 @@ impl AuthorityStorePruner {
             "Starting object pruning service with num_epochs_to_retain={}",
             config.num_epochs_to_retain
         );
-        #[cfg(tidehunter)]
-        let config = Self::th_pruning_config();
+        let config = if perpetual_db.objects.db.is_tidehunter() {
+            Self::th_pruning_config()
+        } else {
+            config
+        };
 
         let tick_duration =
             Duration::from_millis(Self::pruning_tick_duration_ms(epoch_duration_ms));
@@ -777,13 +862,19 @@ impl AuthorityStorePruner {
 #[derive(Clone)]
 pub struct ObjectsCompactionFilter {
     db: Weak<AuthorityPrunerTables>,
+    retention: Arc<ObjectRetentionPolicy>,
     metrics: Arc<ObjectCompactionMetrics>,
 }
 
 impl ObjectsCompactionFilter {
-    pub fn new(db: Arc<AuthorityPrunerTables>, registry: &Registry) -> Self {
+    pub fn new(
+        db: Arc<AuthorityPrunerTables>,
+        retention: ObjectRetentionPolicy,
+        registry: &Registry,
+    ) -> Self {
         Self {
             db: Arc::downgrade(&db),
+            retention: Arc::new(retention),
             metrics: ObjectCompactionMetrics::new(registry),
         }
     }
@@ -793,11 +884,15 @@ impl ObjectsCompactionFilter {
             .with_fixint_encoding()
             .deserialize(key)?;
         let object: StoreObjectWrapper = bcs::from_bytes(value)?;
-        if matches!(object.into_inner(), StoreObject::Value(_)) {
+        if let StoreObject::Value(object) = object.into_inner() {
             if let Some(db) = self.db.upgrade() {
                 match db.object_tombstones.get(&object_id)? {
                     Some(gc_version) => {
                         if version <= gc_version {
+                            if self.retention.retains(&object) {
+                                self.metrics.key_retained.inc();
+                                return Ok(Decision::Keep);
+                            }
                             self.metrics.key_removed.inc();
                             return Ok(Decision::Remove);
                         }
@@ -814,6 +909,7 @@ impl ObjectsCompactionFilter {
 struct ObjectCompactionMetrics {
     key_removed: IntCounter,
     key_kept: IntCounter,
+    key_retained: IntCounter,
     key_not_found: IntCounter,
 }
 
@@ -832,6 +928,12 @@ impl ObjectCompactionMetrics {
                 registry
             )
             .unwrap(),
+            key_retained: register_int_counter_with_registry!(
+                "objects_compaction_filter_key_retained",
+                "Compaction key kept by the object retention policy",
+                registry
+            )
+            .unwrap(),
             key_not_found: register_int_counter_with_registry!(
                 "objects_compaction_filter_key_not_found",
                 "Compaction key not found",
@@ -867,7 +969,8 @@ mod tests {
     use typed_store::rocks::{default_db_options, DBMap, MetricConf, ReadWriteOptions};
     use typed_store::Map;
 
-    use super::AuthorityStorePruner;
+    use super::{AuthorityStorePruner, ObjectRetentionPolicy};
+    use sui_config::node::ObjectRetentionConfig;
 
     fn get_keys_after_pruning(path: &Path) -> anyhow::Result<HashSet<ObjectKey>> {
         let perpetual_db_path = path.join(Path::new("perpetual"));
@@ -989,9 +1092,17 @@ mod tests {
                     ObjectDigest::MIN,
                 ));
             }
-            AuthorityStorePruner::prune_objects(vec![effects], &db, None, 0, metrics, true)
-                .await
-                .unwrap();
+            AuthorityStorePruner::prune_objects(
+                vec![effects],
+                &db,
+                None,
+                0,
+                metrics,
+                true,
+                &ObjectRetentionPolicy::default(),
+            )
+            .await
+            .unwrap();
             to_keep
         };
         tokio::time::sleep(Duration::from_secs(3)).await;
@@ -1024,6 +1135,62 @@ mod tests {
         assert_eq!(get_keys_after_pruning(&path).unwrap().len(), 0);
     }
 
+    // Tests that versions of deleted objects covered by the retention policy are kept, along with
+    // the tombstones of the objects.
+    #[tokio::test]
+    async fn test_pruning_tombstones_with_retention() {
+        for (object_type, num_retained) in [
+            ("0x2::coin::Coin", (3 + 1) * 100),
+            ("0x2::coin::Coin<0x2::foo::FOO>", 0),
+        ] {
+            let config = ObjectRetentionConfig {
+                packages: vec![],
+                object_types: vec![object_type.to_string()],
+            };
+            let retention = ObjectRetentionPolicy::new(Some(&config)).unwrap();
+            let path = tempfile::tempdir().unwrap().keep();
+            {
+                let db = Arc::new(AuthorityPerpetualTables::open(&path, None));
+                let (_, _, tombstones) = generate_test_data(db.clone(), 3, 0, 100).unwrap();
+                let mut effects = TransactionEffects::default();
+                for object in &tombstones {
+                    effects.unsafe_add_object_tombstone_for_testing((
+                        object.0,
+                        object.1,
+                        ObjectDigest::MIN,
+                    ));
+                }
+                AuthorityStorePruner::prune_objects(
+                    vec![effects],
+                    &db,
+                    None,
+                    0,
+                    AuthorityStorePruningMetrics::new_for_test(),
+                    true,
+                    &retention,
+                )
+                .await
+                .unwrap();
+
+                // Retained versions do not bring the deleted objects back
+                for ObjectKey(object_id, version) in tombstones {
+                    assert!(db.get_object_fallible(&object_id).unwrap().is_none());
+                    if num_retained > 0 {
+                        let (key, object) = db
+                            .get_latest_object_or_tombstone(object_id)
+                            .unwrap()
+                            .unwrap();
+                        assert_eq!(key, ObjectKey(object_id, version));
+                        assert!(matches!(object.into_inner(), StoreObject::Deleted));
+                    }
+                }
+            }
+            // The test objects are gas coins, which are kept along with their tombstones if
+            // retained.
+            assert_eq!(get_keys_after_pruning(&path).unwrap().len(), num_retained);
+        }
+    }
+
     #[cfg(not(target_env = "msvc"))]
     #[tokio::test]
     async fn test_db_size_after_compaction() -> Result<(), anyhow::Error> {
@@ -1084,6 +1251,7 @@ mod tests {
             0,
             metrics,
             true,
+            &ObjectRetentionPolicy::default(),
         )
         .await;
         info!("Total pruned keys = {:?}", total_pruned);
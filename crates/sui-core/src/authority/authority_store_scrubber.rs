@@ -0,0 +1,630 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use super::authority_store_tables::AuthorityPerpetualTables;
+use crate::checkpoints::CheckpointStore;
+use mysten_common::fatal;
+use mysten_metrics::monitored_scope;
+use prometheus::{
+    register_int_counter_vec_with_registry, register_int_counter_with_registry,
+    register_int_gauge_with_registry, IntCounter, IntCounterVec, IntGauge, Registry,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sui_config::node::AuthorityStoreScrubberConfig;
+use sui_types::base_types::{ObjectID, SequenceNumber};
+use sui_types::effects::TransactionEffectsAPI;
+use sui_types::error::SuiResult;
+use sui_types::message_envelope::Message;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use sui_types::storage::ObjectKey;
+use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+use typed_store::Map;
+
+/// Verifies the authority store against itself in the background of a running node, a small batch
+/// at a time, to detect silent database corruption:
+///
+/// - Sampled entries of the `objects` table must match the digest that the effects of their
+///   previous transaction recorded for them.
+/// - Executed checkpoints must have contents matching their summary, and the transactions and
+///   effects in the contents must match their digests.
+/// - The object versions written by those effects must be in the `objects` table, unless they
+///   may have been pruned.
+pub struct AuthorityStoreScrubber {
+    _cancel_handle: oneshot::Sender<()>,
+}
+
+pub struct AuthorityStoreScrubberMetrics {
+    pub scrubbed_objects: IntCounter,
+    pub skipped_objects: IntCounter,
+    pub scrubbed_checkpoints: IntCounter,
+    pub scrubbed_transactions: IntCounter,
+    pub last_scrubbed_checkpoint: IntGauge,
+    pub scrub_mismatches: IntCounterVec,
+}
+
+impl AuthorityStoreScrubberMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        let this = Self {
+            scrubbed_objects: register_int_counter_with_registry!(
+                "scrubbed_objects",
+                "Number of entries of the objects table verified by the scrubber",
+                registry
+            )
+            .unwrap(),
+            skipped_objects: register_int_counter_with_registry!(
+                "scrub_skipped_objects",
+                "Number of sampled objects that couldn't be verified because their effects were pruned",
+                registry
+            )
+            .unwrap(),
+            scrubbed_checkpoints: register_int_counter_with_registry!(
+                "scrubbed_checkpoints",
+                "Number of executed checkpoints verified by the scrubber",
+                registry
+            )
+            .unwrap(),
+            scrubbed_transactions: register_int_counter_with_registry!(
+                "scrubbed_transactions",
+                "Number of transactions and effects verified by the scrubber",
+                registry
+            )
+            .unwrap(),
+            last_scrubbed_checkpoint: register_int_gauge_with_registry!(
+                "last_scrubbed_checkpoint",
+                "Last checkpoint verified by the scrubber",
+                registry
+            )
+            .unwrap(),
+            scrub_mismatches: register_int_counter_vec_with_registry!(
+                "scrub_mismatches",
+                "Number of mismatches found by the scrubber, by kind",
+                &["kind"],
+                registry
+            )
+            .unwrap(),
+        };
+        Arc::new(this)
+    }
+
+    pub fn new_for_test() -> Arc<Self> {
+        Self::new(&Registry::new())
+    }
+}
+
+/// Where the scrubber resumes from on its next batch.
+struct ScrubCursor {
+    /// next key of the objects table to sample
+    object: Option<ObjectKey>,
+    /// next checkpoint to verify
+    checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+struct StoreScrubber {
+    perpetual_db: Arc<AuthorityPerpetualTables>,
+    checkpoint_store: Arc<CheckpointStore>,
+    config: AuthorityStoreScrubberConfig,
+    metrics: Arc<AuthorityStoreScrubberMetrics>,
+    cursor: Mutex<ScrubCursor>,
+}
+
+impl AuthorityStoreScrubber {
+    pub fn new(
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        checkpoint_store: Arc<CheckpointStore>,
+        config: AuthorityStoreScrubberConfig,
+        registry: &Registry,
+    ) -> Self {
+        info!(
+            "Starting authority store scrubber with scrub_interval_ms={}, halt_on_corruption={}",
+            config.scrub_interval_ms, config.halt_on_corruption
+        );
+        let scrubber = Arc::new(StoreScrubber {
+            perpetual_db,
+            checkpoint_store,
+            metrics: AuthorityStoreScrubberMetrics::new(registry),
+            // Start sampling objects at a random key, so that restarts don't keep scrubbing the
+            // same part of the table.
+            cursor: Mutex::new(ScrubCursor {
+                object: Some(ObjectKey(ObjectID::random(), SequenceNumber::MIN)),
+                checkpoint: None,
+            }),
+            config,
+        });
+        AuthorityStoreScrubber {
+            _cancel_handle: Self::setup_scrubbing(scrubber),
+        }
+    }
+
+    fn setup_scrubbing(scrubber: Arc<StoreScrubber>) -> oneshot::Sender<()> {
+        let (sender, mut recv) = oneshot::channel();
+        let mut scrub_interval =
+            tokio::time::interval(Duration::from_millis(scrubber.config.scrub_interval_ms));
+        // Batches that run long push back the next one rather than catching up, so that the
+        // scrubber never competes with the node for the database.
+        scrub_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        tokio::task::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = scrub_interval.tick() => {
+                        let scrubber = scrubber.clone();
+                        let result = tokio::task::spawn_blocking(move || {
+                            let _scope = monitored_scope("AuthorityStoreScrubber::scrub_batch");
+                            scrubber.scrub_batch()
+                        })
+                        .await;
+                        if let Err(err) = result {
+                            error!("Failed to scrub authority store: {:?}", err);
+                        }
+                    },
+                    _ = &mut recv => break,
+                }
+            }
+        });
+        sender
+    }
+}
+
+impl StoreScrubber {
+    fn scrub_batch(&self) {
+        let mut cursor = self.cursor.lock().unwrap();
+        if let Err(err) = self.scrub_objects(&mut cursor.object) {
+            self.report("read_error", format!("failed to scrub objects: {err}"));
+        }
+        if let Err(err) = self.scrub_checkpoints(&mut cursor.checkpoint) {
+            self.report("read_error", format!("failed to scrub checkpoints: {err}"));
+        }
+    }
+
+    /// Samples the next `objects_per_batch` entries of the objects table, wrapping around at its
+    /// end, and checks each object against the effects of the transaction that wrote it.
+    fn scrub_objects(&self, cursor: &mut Option<ObjectKey>) -> SuiResult {
+        let start = cursor.unwrap_or(ObjectKey(ObjectID::ZERO, SequenceNumber::MIN));
+        let mut next = None;
+        for (i, entry) in self
+            .perpetual_db
+            .objects
+            .safe_iter_with_bounds(Some(start), None)
+            .enumerate()
+        {
+            let (key, store_object) = entry?;
+            if i == self.config.objects_per_batch {
+                next = Some(key);
+                break;
+            }
+            self.metrics.scrubbed_objects.inc();
+
+            // Tombstones can't be checked against the effects that wrote them.
+            let Some(object) = self.perpetual_db.object(&key, store_object)? else {
+                continue;
+            };
+            let Some(effects) = self
+                .perpetual_db
+                .get_effects(&object.previous_transaction)?
+            else {
+                self.metrics.skipped_objects.inc();
+                continue;
+            };
+            let written = effects
+                .all_changed_objects()
+                .into_iter()
+                .find(|((id, _, _), _, _)| *id == key.0);
+            match written {
+                Some(((_, version, digest), _, _))
+                    if version == key.1 && digest == object.digest() => {}
+                Some((object_ref, _, _)) => self.report(
+                    "object_digest",
+                    format!(
+                        "object {:?} doesn't match {:?} written by transaction {}",
+                        key, object_ref, object.previous_transaction
+                    ),
+                ),
+                None => self.report(
+                    "object_not_in_effects",
+                    format!(
+                        "object {:?} isn't written by its previous transaction {}",
+                        key, object.previous_transaction
+                    ),
+                ),
+            }
+        }
+        *cursor = next;
+        Ok(())
+    }
+
+    /// Verifies the next `checkpoints_per_batch` executed checkpoints, wrapping around to the
+    /// lowest checkpoint that hasn't been pruned after the highest executed one.
+    fn scrub_checkpoints(&self, cursor: &mut Option<CheckpointSequenceNumber>) -> SuiResult {
+        let Some(highest_executed) = self
+            .checkpoint_store
+            .get_highest_executed_checkpoint_seq_number()?
+        else {
+            return Ok(());
+        };
+        let lowest_available = self
+            .checkpoint_store
+            .get_highest_pruned_checkpoint_seq_number()?
+            .map_or(0, |pruned| pruned + 1);
+        let highest_pruned_objects = self.perpetual_db.get_highest_pruned_checkpoint()?;
+
+        let mut next = cursor.unwrap_or(lowest_available).max(lowest_available);
+        for _ in 0..self.config.checkpoints_per_batch {
+            if next > highest_executed {
+                next = lowest_available;
+                break;
+            }
+            // Object versions written in a checkpoint can only have been pruned once a later
+            // version was written in a pruned checkpoint, so only checkpoints above the pruning
+            // watermark must still have all of theirs.
+            let check_objects = highest_pruned_objects.is_none_or(|pruned| next > pruned);
+            self.scrub_checkpoint(next, check_objects)?;
+            self.metrics.scrubbed_checkpoints.inc();
+            self.metrics.last_scrubbed_checkpoint.set(next as i64);
+            next += 1;
+        }
+        *cursor = Some(next);
+        Ok(())
+    }
+
+    fn scrub_checkpoint(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+        check_objects: bool,
+    ) -> SuiResult {
+        let Some(checkpoint) = self
+            .checkpoint_store
+            .get_checkpoint_by_sequence_number(sequence_number)?
+        else {
+            self.report(
+                "missing_checkpoint",
+                format!("executed checkpoint {sequence_number} is missing"),
+            );
+            return Ok(());
+        };
+        let content_digest = checkpoint.content_digest;
+        let Some(contents) = self
+            .checkpoint_store
+            .get_checkpoint_contents(&content_digest)?
+        else {
+            self.report(
+                "missing_checkpoint_contents",
+                format!("contents {content_digest} of checkpoint {sequence_number} are missing"),
+            );
+            return Ok(());
+        };
+        if *contents.digest() != content_digest {
+            self.report(
+                "checkpoint_contents_digest",
+                format!(
+                    "contents of checkpoint {sequence_number} have digest {}, expected {content_digest}",
+                    contents.digest()
+                ),
+            );
+        }
+
+        for digests in contents.iter() {
+            self.metrics.scrubbed_transactions.inc();
+            match self.perpetual_db.transactions.get(&digests.transaction)? {
+                Some(transaction) if *transaction.inner().digest() == digests.transaction => {}
+                Some(transaction) => self.report(
+                    "transaction_digest",
+                    format!(
+                        "transaction {} of checkpoint {sequence_number} has digest {}",
+                        digests.transaction,
+                        transaction.inner().digest()
+                    ),
+                ),
+                None => self.report(
+                    "missing_transaction",
+                    format!(
+                        "transaction {} of checkpoint {sequence_number} is missing",
+                        digests.transaction
+                    ),
+                ),
+            }
+
+            let Some(effects) = self.perpetual_db.effects.get(&digests.effects)? else {
+                self.report(
+                    "missing_effects",
+                    format!(
+                        "effects {} of checkpoint {sequence_number} are missing",
+                        digests.effects
+                    ),
+                );
+                continue;
+            };
+            if effects.digest() != digests.effects
+                || *effects.transaction_digest() != digests.transaction
+            {
+                self.report(
+                    "effects_digest",
+                    format!(
+                        "effects {} of transaction {} in checkpoint {sequence_number} have digest {} for transaction {}",
+                        digests.effects,
+                        digests.transaction,
+                        effects.digest(),
+                        effects.transaction_digest()
+                    ),
+                );
+                continue;
+            }
+
+            if !check_objects {
+                continue;
+            }
+            for ((id, version, digest), _, _) in effects.all_changed_objects() {
+                let key = ObjectKey(id, version);
+                let object = match self.perpetual_db.objects.get(&key)? {
+                    Some(store_object) => self.perpetual_db.object(&key, store_object)?,
+                    None => None,
+                };
+                match object {
+                    Some(object) if object.digest() == digest => {}
+                    Some(object) => self.report(
+                        "object_digest",
+                        format!(
+                            "object {:?} written by transaction {} has digest {}, expected {digest}",
+                            key,
+                            digests.transaction,
+                            object.digest()
+                        ),
+                    ),
+                    None => self.report(
+                        "missing_object",
+                        format!(
+                            "object {:?} written by transaction {} is missing",
+                            key, digests.transaction
+                        ),
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&self, kind: &str, message: String) {
+        self.metrics
+            .scrub_mismatches
+            .with_label_values(&[kind])
+            .inc();
+        if self.config.halt_on_corruption {
+            fatal!("Authority store corruption ({}): {}", kind, message);
+        }
+        error!("Authority store corruption ({}): {}", kind, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthorityStoreScrubberMetrics, ScrubCursor, StoreScrubber};
+    use crate::authority::authority_store_tables::AuthorityPerpetualTables;
+    use crate::checkpoints::CheckpointStore;
+    use std::sync::{Arc, Mutex};
+    use sui_config::node::AuthorityStoreScrubberConfig;
+    use sui_swarm_config::test_utils::CommitteeFixture;
+    use sui_types::base_types::{ObjectID, TransactionDigest};
+    use sui_types::effects::{TransactionEffects, TransactionEffectsAPI};
+    use sui_types::message_envelope::Message;
+    use sui_types::messages_checkpoint::{VerifiedCheckpoint, VerifiedCheckpointContents};
+    use sui_types::object::Object;
+    use typed_store::Map;
+
+    fn scrubber(
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        checkpoint_store: Arc<CheckpointStore>,
+        config: AuthorityStoreScrubberConfig,
+    ) -> StoreScrubber {
+        StoreScrubber {
+            perpetual_db,
+            checkpoint_store,
+            config,
+            metrics: AuthorityStoreScrubberMetrics::new_for_test(),
+            cursor: Mutex::new(ScrubCursor {
+                object: None,
+                checkpoint: None,
+            }),
+        }
+    }
+
+    /// Executes `number_of_checkpoints` checkpoints, all but the first with one transaction,
+    /// storing their transactions and effects but none of the objects they write.
+    fn execute_checkpoints(
+        perpetual_db: &AuthorityPerpetualTables,
+        checkpoint_store: &CheckpointStore,
+        number_of_checkpoints: usize,
+    ) -> Result<(Vec<VerifiedCheckpoint>, Vec<VerifiedCheckpointContents>), anyhow::Error> {
+        let committee = CommitteeFixture::generate(rand::rngs::OsRng, 0, 4);
+        let (checkpoints, contents, _, _) =
+            committee.make_random_checkpoints(number_of_checkpoints, None);
+        for (checkpoint, contents) in checkpoints.iter().zip(&contents) {
+            for data in contents.iter() {
+                perpetual_db.transactions.insert(
+                    data.transaction.digest(),
+                    data.transaction.serializable_ref(),
+                )?;
+                perpetual_db
+                    .effects
+                    .insert(&data.effects.digest(), &data.effects)?;
+            }
+            checkpoint_store.insert_verified_checkpoint(checkpoint)?;
+            checkpoint_store.insert_checkpoint_contents(
+                contents.clone().into_inner().into_checkpoint_contents(),
+            )?;
+            checkpoint_store.update_highest_executed_checkpoint(checkpoint)?;
+        }
+        Ok((checkpoints, contents))
+    }
+
+    fn written_objects(contents: &VerifiedCheckpointContents) -> u64 {
+        contents
+            .iter()
+            .map(|data| data.effects.all_changed_objects().len() as u64)
+            .sum()
+    }
+
+    fn mismatches(scrubber: &StoreScrubber, kind: &str) -> u64 {
+        scrubber
+            .metrics
+            .scrub_mismatches
+            .with_label_values(&[kind])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn test_scrub_objects() -> Result<(), anyhow::Error> {
+        let path = tempfile::tempdir()?;
+        let perpetual_db = Arc::new(AuthorityPerpetualTables::open(path.path(), None));
+
+        // An object whose effects have been pruned can't be verified.
+        let pruned = Object::immutable_with_id_for_testing(ObjectID::random());
+        perpetual_db.insert_object_test_only(pruned)?;
+
+        // An object its previous transaction didn't write is reported.
+        let previous_transaction = TransactionDigest::random();
+        let mut unwritten = Object::immutable_with_id_for_testing(ObjectID::random());
+        unwritten.previous_transaction = previous_transaction;
+        perpetual_db.insert_object_test_only(unwritten)?;
+        let mut effects = TransactionEffects::default();
+        *effects.transaction_digest_mut_for_testing() = previous_transaction;
+        perpetual_db
+            .executed_effects
+            .insert(&previous_transaction, &effects.digest())?;
+        perpetual_db.effects.insert(&effects.digest(), &effects)?;
+
+        let scrubber = scrubber(
+            perpetual_db,
+            CheckpointStore::new_for_tests(),
+            AuthorityStoreScrubberConfig::default(),
+        );
+        let mut cursor = None;
+        scrubber.scrub_objects(&mut cursor)?;
+        assert_eq!(cursor, None);
+        assert_eq!(scrubber.metrics.scrubbed_objects.get(), 2);
+        assert_eq!(scrubber.metrics.skipped_objects.get(), 1);
+        assert_eq!(mismatches(&scrubber, "object_not_in_effects"), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scrub_checkpoints() -> Result<(), anyhow::Error> {
+        let path = tempfile::tempdir()?;
+        let perpetual_db = Arc::new(AuthorityPerpetualTables::open(path.path(), None));
+        let checkpoint_store = CheckpointStore::new_for_tests();
+        let (_, contents) = execute_checkpoints(&perpetual_db, &checkpoint_store, 3)?;
+
+        // The objects written up to the pruning watermark may have been pruned, so only the
+        // missing objects of checkpoint 2 are reported.
+        perpetual_db.set_highest_pruned_checkpoint_without_wb(1)?;
+
+        let scrubber = scrubber(
+            perpetual_db,
+            checkpoint_store,
+            AuthorityStoreScrubberConfig {
+                checkpoints_per_batch: 2,
+                ..Default::default()
+            },
+        );
+        let mut cursor = None;
+        scrubber.scrub_checkpoints(&mut cursor)?;
+        assert_eq!(cursor, Some(2));
+
+        // The batch after the highest executed checkpoint wraps around to the lowest one.
+        scrubber.scrub_checkpoints(&mut cursor)?;
+        assert_eq!(cursor, Some(0));
+
+        assert_eq!(scrubber.metrics.scrubbed_checkpoints.get(), 3);
+        assert_eq!(scrubber.metrics.scrubbed_transactions.get(), 2);
+        assert_eq!(scrubber.metrics.last_scrubbed_checkpoint.get(), 2);
+        assert_eq!(
+            mismatches(&scrubber, "missing_object"),
+            written_objects(&contents[2])
+        );
+        assert_eq!(mismatches(&scrubber, "missing_transaction"), 0);
+        assert_eq!(mismatches(&scrubber, "missing_effects"), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scrub_checkpoints_skips_pruned() -> Result<(), anyhow::Error> {
+        let path = tempfile::tempdir()?;
+        let perpetual_db = Arc::new(AuthorityPerpetualTables::open(path.path(), None));
+        let checkpoint_store = CheckpointStore::new_for_tests();
+        let (checkpoints, contents) = execute_checkpoints(&perpetual_db, &checkpoint_store, 3)?;
+        checkpoint_store.update_highest_pruned_checkpoint(&checkpoints[1])?;
+
+        let scrubber = scrubber(
+            perpetual_db,
+            checkpoint_store,
+            AuthorityStoreScrubberConfig::default(),
+        );
+        let mut cursor = Some(0);
+        scrubber.scrub_checkpoints(&mut cursor)?;
+        assert_eq!(cursor, Some(3));
+        assert_eq!(scrubber.metrics.scrubbed_checkpoints.get(), 1);
+        assert_eq!(scrubber.metrics.last_scrubbed_checkpoint.get(), 2);
+        assert_eq!(
+            mismatches(&scrubber, "missing_object"),
+            written_objects(&contents[2])
+        );
+
+        scrubber.scrub_checkpoints(&mut cursor)?;
+        assert_eq!(cursor, Some(2));
+        assert_eq!(scrubber.metrics.scrubbed_checkpoints.get(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scrub_checkpoint_reports_corruption() -> Result<(), anyhow::Error> {
+        let path = tempfile::tempdir()?;
+        let perpetual_db = Arc::new(AuthorityPerpetualTables::open(path.path(), None));
+        let checkpoint_store = CheckpointStore::new_for_tests();
+        let (_, contents) = execute_checkpoints(&perpetual_db, &checkpoint_store, 2)?;
+        let data = &contents[1].transactions()[0];
+        perpetual_db
+            .transactions
+            .remove(data.transaction.digest())?;
+        perpetual_db.effects.remove(&data.effects.digest())?;
+
+        let scrubber = scrubber(
+            perpetual_db,
+            checkpoint_store,
+            AuthorityStoreScrubberConfig::default(),
+        );
+        scrubber.scrub_checkpoint(1, true)?;
+        assert_eq!(mismatches(&scrubber, "missing_transaction"), 1);
+        assert_eq!(mismatches(&scrubber, "missing_effects"), 1);
+        // Objects can't be checked without the effects that wrote them.
+        assert_eq!(mismatches(&scrubber, "missing_object"), 0);
+
+        scrubber.scrub_checkpoint(2, true)?;
+        assert_eq!(mismatches(&scrubber, "missing_checkpoint"), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Authority store corruption (missing_transaction)")]
+    async fn test_scrub_checkpoint_halts_on_corruption() {
+        let path = tempfile::tempdir().unwrap();
+        let perpetual_db = Arc::new(AuthorityPerpetualTables::open(path.path(), None));
+        let checkpoint_store = CheckpointStore::new_for_tests();
+        let (_, contents) = execute_checkpoints(&perpetual_db, &checkpoint_store, 2).unwrap();
+        let data = &contents[1].transactions()[0];
+        perpetual_db
+            .transactions
+            .remove(data.transaction.digest())
+            .unwrap();
+
+        let scrubber = scrubber(
+            perpetual_db,
+            checkpoint_store,
+            AuthorityStoreScrubberConfig {
+                halt_on_corruption: true,
+                ..Default::default()
+            },
+        );
+        scrubber.scrub_checkpoint(1, false).unwrap();
+    }
+}
@@ -5,6 +5,7 @@ use super::*;
 use crate::authority::authority_store::LockDetailsWrapperDeprecated;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use sui_config::node::StorageEngine;
 use sui_types::base_types::SequenceNumber;
 use sui_types::digests::TransactionEventsDigest;
 use sui_types::effects::{TransactionEffects, TransactionEvents};
@@ -31,12 +32,32 @@ pub(crate) const ENV_VAR_LOCKS_BLOCK_CACHE_SIZE: &str = "LOCKS_BLOCK_CACHE_MB";
 const ENV_VAR_TRANSACTIONS_BLOCK_CACHE_SIZE: &str = "TRANSACTIONS_BLOCK_CACHE_MB";
 const ENV_VAR_EFFECTS_BLOCK_CACHE_SIZE: &str = "EFFECTS_BLOCK_CACHE_MB";
 
+/// Resolves the storage engine of a database, defaulting to the engine of the build: TideHunter
+/// in builds with TideHunter support, RocksDB otherwise.
+pub fn resolve_storage_engine(engine: Option<StorageEngine>) -> StorageEngine {
+    engine.unwrap_or(if cfg!(tidehunter) {
+        StorageEngine::TideHunter
+    } else {
+        StorageEngine::RocksDb
+    })
+}
+
+/// Checks that `engine` can be used by this build.
+pub fn ensure_storage_engine_supported(engine: StorageEngine) -> anyhow::Result<()> {
+    if engine == StorageEngine::TideHunter && !cfg!(tidehunter) {
+        anyhow::bail!("TideHunter storage engine requires a build with USE_TIDEHUNTER set");
+    }
+    Ok(())
+}
+
 /// Options to apply to every column family of the `perpetual` DB.
 #[derive(Default)]
 pub struct AuthorityPerpetualTablesOptions {
     /// Whether to enable write stalling on all column families.
     pub enable_write_stall: bool,
     pub compaction_filter: Option<ObjectsCompactionFilter>,
+    /// Storage engine of the DB. The RocksDB options above are ignored by other engines.
+    pub storage_engine: Option<StorageEngine>,
 }
 
 impl AuthorityPerpetualTablesOptions {
@@ -165,12 +186,21 @@ impl AuthorityPerpetualTables {
         parent_path.join("perpetual")
     }
 
-    #[cfg(not(tidehunter))]
     pub fn open(
         parent_path: &Path,
         db_options_override: Option<AuthorityPerpetualTablesOptions>,
     ) -> Self {
         let db_options_override = db_options_override.unwrap_or_default();
+        match resolve_storage_engine(db_options_override.storage_engine) {
+            StorageEngine::RocksDb => Self::open_rocksdb(parent_path, db_options_override),
+            StorageEngine::TideHunter => Self::open_tidehunter(parent_path),
+        }
+    }
+
+    fn open_rocksdb(
+        parent_path: &Path,
+        db_options_override: AuthorityPerpetualTablesOptions,
+    ) -> Self {
         let db_options =
             db_options_override.apply_to(default_db_options().optimize_db_for_write_throughput(4));
         let table_options = DBMapTableConfigMap::new(BTreeMap::from([
@@ -201,8 +231,13 @@ impl AuthorityPerpetualTables {
         )
     }
 
+    #[cfg(not(tidehunter))]
+    fn open_tidehunter(_: &Path) -> Self {
+        panic!("AuthorityPerpetualTables can't use tidehunter in a build without it");
+    }
+
     #[cfg(tidehunter)]
-    pub fn open(parent_path: &Path, _: Option<AuthorityPerpetualTablesOptions>) -> Self {
+    fn open_tidehunter(parent_path: &Path) -> Self {
         tracing::warn!("AuthorityPerpetualTables using tidehunter");
         use typed_store::tidehunter_util::{
             default_cells_per_mutex, Bytes, IndexWalPosition, KeyIndexing, KeySpaceConfig, KeyType,
@@ -349,7 +384,7 @@ impl AuthorityPerpetualTables {
                 ),
             ),
         ];
-        Self::open_tables_read_write(
+        Self::open_tables_tidehunter(
             Self::path(parent_path),
             MetricConf::new("perpetual")
                 .with_sampling(SamplingInterval::new(Duration::from_secs(60), 0)),
@@ -5,7 +5,7 @@ use super::backpressure::BackpressureManager;
 use super::epoch_start_configuration::EpochFlag;
 use super::ExecutionEnv;
 use crate::authority::authority_per_epoch_store::AuthorityPerEpochStore;
-use crate::authority::authority_store_pruner::ObjectsCompactionFilter;
+use crate::authority::authority_store_pruner::{ObjectRetentionPolicy, ObjectsCompactionFilter};
 use crate::authority::authority_store_tables::{
     AuthorityPerpetualTables, AuthorityPerpetualTablesOptions, AuthorityPrunerTables,
 };
@@ -207,9 +207,16 @@ impl<'a> TestAuthorityBuilder<'a> {
         {
             pruner_db = Some(Arc::new(AuthorityPrunerTables::open(&path.join("store"))));
         }
+        let object_retention = ObjectRetentionPolicy::new(
+            config
+                .authority_store_pruning_config
+                .object_retention
+                .as_ref(),
+        )
+        .unwrap();
         let compaction_filter = pruner_db
             .clone()
-            .map(|db| ObjectsCompactionFilter::new(db, &registry));
+            .map(|db| ObjectsCompactionFilter::new(db, object_retention, &registry));
 
         let authority_store = match self.store {
             Some(store) => store,
@@ -278,6 +285,7 @@ impl<'a> TestAuthorityBuilder<'a> {
             Arc::new(genesis_committee.clone()),
             &path.join("store"),
             None,
+            None,
             EpochMetrics::new(&registry),
             epoch_start_configuration,
             cache_traits.backing_package_store.clone(),
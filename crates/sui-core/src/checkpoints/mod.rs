@@ -7,6 +7,7 @@ mod checkpoint_output;
 mod metrics;
 
 use crate::accumulators::AccumulatorSettlementTxBuilder;
+use crate::authority::authority_store_tables::resolve_storage_engine;
 use crate::authority::AuthorityState;
 use crate::authority_client::{make_network_authority_clients_with_network_config, AuthorityAPI};
 use crate::checkpoints::causal_order::CausalOrder;
@@ -30,6 +31,7 @@ use nonempty::NonEmpty;
 use parking_lot::Mutex;
 use pin_project_lite::pin_project;
 use serde::{Deserialize, Serialize};
+use sui_config::node::StorageEngine;
 use sui_macros::fail_point_arg;
 use sui_network::default_mysten_network_config;
 use sui_types::base_types::ConciseableName;
@@ -172,13 +174,33 @@ fn full_checkpoint_content_table_default_config() -> DBOptions {
 }
 
 impl CheckpointStoreTables {
-    #[cfg(not(tidehunter))]
     pub fn new(path: &Path, metric_name: &'static str) -> Self {
-        Self::open_tables_read_write(path.to_path_buf(), MetricConf::new(metric_name), None, None)
+        Self::new_with_engine(path, metric_name, None)
+    }
+
+    pub fn new_with_engine(
+        path: &Path,
+        metric_name: &'static str,
+        engine: Option<StorageEngine>,
+    ) -> Self {
+        match resolve_storage_engine(engine) {
+            StorageEngine::RocksDb => Self::open_tables_read_write(
+                path.to_path_buf(),
+                MetricConf::new(metric_name),
+                None,
+                None,
+            ),
+            StorageEngine::TideHunter => Self::open_tidehunter(path, metric_name),
+        }
+    }
+
+    #[cfg(not(tidehunter))]
+    fn open_tidehunter(_: &Path, _: &'static str) -> Self {
+        panic!("Checkpoint DB can't use tidehunter in a build without it");
     }
 
     #[cfg(tidehunter)]
-    pub fn new(path: &Path, metric_name: &'static str) -> Self {
+    fn open_tidehunter(path: &Path, metric_name: &'static str) -> Self {
         tracing::warn!("Checkpoint DB using tidehunter");
         use typed_store::tidehunter_util::{
             default_cells_per_mutex, KeySpaceConfig, KeyType, ThConfig,
@@ -222,7 +244,7 @@ impl CheckpointStoreTables {
                 ThConfig::new_with_config(1, 1, KeyType::uniform(1), watermarks_config),
             ),
         ];
-        Self::open_tables_read_write(
+        Self::open_tables_tidehunter(
             path.to_path_buf(),
             MetricConf::new(metric_name),
             configs
@@ -250,7 +272,11 @@ pub struct CheckpointStore {
 
 impl CheckpointStore {
     pub fn new(path: &Path) -> Arc<Self> {
-        let tables = CheckpointStoreTables::new(path, "checkpoint");
+        Self::new_with_engine(path, None)
+    }
+
+    pub fn new_with_engine(path: &Path, engine: Option<StorageEngine>) -> Arc<Self> {
+        let tables = CheckpointStoreTables::new_with_engine(path, "checkpoint", engine);
         Arc::new(Self {
             tables,
             synced_checkpoint_notify_read: NotifyRead::new(),
@@ -20,6 +20,9 @@ use std::sync::Arc;
 use std::time::Duration;
 use sui_config::node::AuthorityStorePruningConfig;
 use sui_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
+use sui_storage::object_store::content_addressed::{
+    garbage_collect_blobs, upload_content_addressed, DEFAULT_CHUNK_SIZE,
+};
 use sui_storage::object_store::util::{
     copy_recursively, find_all_dirs_with_epoch_prefix, find_missing_epochs_dirs,
     path_to_filesystem, put, run_manifest_update_loop, write_snapshot_manifest,
@@ -69,6 +72,8 @@ pub struct DBCheckpointHandler {
     gc_markers: Vec<String>,
     /// Boolean flag to enable/disable object pruning and manual compaction before upload
     prune_and_compact_before_upload: bool,
+    /// If true, db checkpoints are uploaded as content addressed chunks with a manifest
+    content_addressed_upload: bool,
     /// If true, upload will block on state snapshot upload completed marker
     state_snapshot_enabled: bool,
     /// Pruning objects
@@ -82,6 +87,7 @@ impl DBCheckpointHandler {
         output_object_store_config: Option<&ObjectStoreConfig>,
         interval_s: u64,
         prune_and_compact_before_upload: bool,
+        content_addressed_upload: bool,
         pruning_config: AuthorityStorePruningConfig,
         registry: &Registry,
         state_snapshot_enabled: bool,
@@ -103,6 +109,7 @@ impl DBCheckpointHandler {
             interval: Duration::from_secs(interval_s),
             gc_markers,
             prune_and_compact_before_upload,
+            content_addressed_upload,
             state_snapshot_enabled,
             pruning_config,
             metrics: DBCheckpointMetrics::new(registry),
@@ -113,6 +120,7 @@ impl DBCheckpointHandler {
         output_object_store_config: Option<&ObjectStoreConfig>,
         interval_s: u64,
         prune_and_compact_before_upload: bool,
+        content_addressed_upload: bool,
         state_snapshot_enabled: bool,
     ) -> Result<Arc<Self>> {
         Ok(Arc::new(DBCheckpointHandler {
@@ -127,6 +135,7 @@ impl DBCheckpointHandler {
             interval: Duration::from_secs(interval_s),
             gc_markers: vec![UPLOAD_COMPLETED_MARKER.to_string(), TEST_MARKER.to_string()],
             prune_and_compact_before_upload,
+            content_addressed_upload,
             state_snapshot_enabled,
             pruning_config: AuthorityStorePruningConfig::default(),
             metrics: DBCheckpointMetrics::new(&Registry::default()),
@@ -290,6 +299,7 @@ impl DBCheckpointHandler {
             .as_ref()
             .expect("Expected object store to exist")
             .clone();
+        let mut uploaded = false;
         for (epoch, db_path) in dirs {
             // Convert `db_path` to the local filesystem path to where db checkpoint is stored
             let local_db_path = path_to_filesystem(self.input_root_path.clone(), db_path)?;
@@ -309,22 +319,37 @@ impl DBCheckpointHandler {
                         .await?;
                 }
 
-                info!("Copying db checkpoint for epoch: {epoch} to remote storage");
-                copy_recursively(
-                    db_path,
-                    &self.input_object_store,
-                    &object_store,
-                    NonZeroUsize::new(20).unwrap(),
-                )
-                .await?;
-
-                // This writes a single "MANIFEST" file which contains a list of all files that make up a db snapshot
-                write_snapshot_manifest(db_path, &object_store, format!("epoch_{}/", epoch))
+                if self.content_addressed_upload {
+                    // Chunks already uploaded for earlier epochs, or before an interrupted
+                    // upload of this one, are skipped. The content manifest is written last.
+                    info!("Uploading content addressed db checkpoint for epoch: {epoch} to remote storage");
+                    upload_content_addressed(
+                        db_path,
+                        &self.input_object_store,
+                        &object_store,
+                        DEFAULT_CHUNK_SIZE,
+                        NonZeroUsize::new(20).unwrap(),
+                    )
+                    .await?;
+                } else {
+                    info!("Copying db checkpoint for epoch: {epoch} to remote storage");
+                    copy_recursively(
+                        db_path,
+                        &self.input_object_store,
+                        &object_store,
+                        NonZeroUsize::new(20).unwrap(),
+                    )
                     .await?;
+
+                    // This writes a single "MANIFEST" file which contains a list of all files that make up a db snapshot
+                    write_snapshot_manifest(db_path, &object_store, format!("epoch_{}/", epoch))
+                        .await?;
+                }
                 // Drop marker in the output directory that upload completed successfully
                 let bytes = Bytes::from_static(b"success");
                 let success_marker = db_path.child(SUCCESS_MARKER);
                 put(&object_store, &success_marker, bytes.clone()).await?;
+                uploaded = true;
             }
             let bytes = Bytes::from_static(b"success");
             let upload_completed_marker = db_path.child(UPLOAD_COMPLETED_MARKER);
@@ -335,6 +360,13 @@ impl DBCheckpointHandler {
             )
             .await?;
         }
+        if self.content_addressed_upload && uploaded {
+            // Uploads only happen from this loop, so none is in progress while blobs no longer
+            // referenced by a retained db checkpoint are swept.
+            let deleted =
+                garbage_collect_blobs(&object_store, NonZeroUsize::new(20).unwrap()).await?;
+            info!("Garbage collected {deleted} unreferenced blobs from remote storage");
+        }
         Ok(())
     }
 
@@ -380,6 +412,7 @@ mod tests {
     use itertools::Itertools;
     use std::fs;
     use sui_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
+    use sui_storage::object_store::content_addressed::{BLOBS_DIR, CONTENT_MANIFEST_FILENAME};
     use sui_storage::object_store::util::{
         find_all_dirs_with_epoch_prefix, find_missing_epochs_dirs, path_to_filesystem,
     };
@@ -420,6 +453,7 @@ mod tests {
             10,
             false,
             false,
+            false,
         )?;
         let local_checkpoints_by_epoch =
             find_all_dirs_with_epoch_prefix(&db_checkpoint_handler.input_object_store, None)
@@ -491,6 +525,7 @@ mod tests {
             10,
             false,
             false,
+            false,
         )?;
 
         fs::create_dir(&local_epoch0_checkpoint)?;
@@ -582,6 +617,66 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_content_addressed_upload() -> anyhow::Result<()> {
+        let checkpoint_dir = TempDir::new()?;
+        let checkpoint_dir_path = checkpoint_dir.path();
+        for epoch in 0..2 {
+            let local_checkpoint = checkpoint_dir_path.join(format!("epoch_{epoch}"));
+            fs::create_dir_all(local_checkpoint.join("store"))?;
+            fs::write(
+                local_checkpoint.join("store").join("000001.sst"),
+                b"Lorem ipsum",
+            )?;
+            fs::write(
+                local_checkpoint.join("store").join("CURRENT"),
+                format!("MANIFEST-00000{epoch}"),
+            )?;
+        }
+
+        let remote_checkpoint_dir = TempDir::new()?;
+        let remote_checkpoint_dir_path = remote_checkpoint_dir.path();
+
+        let input_store_config = ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::File),
+            directory: Some(checkpoint_dir_path.to_path_buf()),
+            ..Default::default()
+        };
+        let output_store_config = ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::File),
+            directory: Some(remote_checkpoint_dir_path.to_path_buf()),
+            ..Default::default()
+        };
+        let db_checkpoint_handler = DBCheckpointHandler::new_for_test(
+            &input_store_config,
+            Some(&output_store_config),
+            10,
+            false,
+            true,
+            false,
+        )?;
+
+        let missing_epochs = find_missing_epochs_dirs(
+            db_checkpoint_handler.output_object_store.as_ref().unwrap(),
+            SUCCESS_MARKER,
+        )
+        .await?;
+        db_checkpoint_handler
+            .upload_db_checkpoints_to_object_store(missing_epochs)
+            .await?;
+
+        for epoch in 0..2 {
+            let remote_checkpoint = remote_checkpoint_dir_path.join(format!("epoch_{epoch}"));
+            assert!(remote_checkpoint.join(CONTENT_MANIFEST_FILENAME).exists());
+            assert!(remote_checkpoint.join(SUCCESS_MARKER).exists());
+            assert!(!remote_checkpoint.join("store").exists());
+        }
+        // The table shared by both epochs is only stored once
+        let num_blobs = fs::read_dir(remote_checkpoint_dir_path.join(BLOBS_DIR))?.count();
+        assert_eq!(num_blobs, 3);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_missing_epochs() -> anyhow::Result<()> {
         let checkpoint_dir = TempDir::new()?;
@@ -613,6 +708,7 @@ mod tests {
             10,
             false,
             false,
+            false,
         )?;
 
         let missing_epochs = find_missing_epochs_dirs(
@@ -678,6 +774,7 @@ mod tests {
             10,
             false,
             false,
+            false,
         )?;
 
         let missing_epochs = find_missing_epochs_dirs(
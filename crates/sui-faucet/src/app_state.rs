@@ -1,17 +1,57 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::challenge::Challenges;
+use crate::quota::{QuotaCharge, Quotas};
 use crate::FaucetConfig;
+use crate::FaucetError;
 use crate::LocalFaucet;
+use http::HeaderMap;
+use std::net::IpAddr;
 use std::sync::Arc;
+use sui_sdk::types::base_types::SuiAddress;
 
 pub struct AppState<F = Arc<LocalFaucet>> {
     pub faucet: F,
     pub config: FaucetConfig,
+    pub(crate) quotas: Quotas,
+    pub(crate) challenges: Challenges,
 }
 
 impl<F> AppState<F> {
-    pub fn new(faucet: F, config: FaucetConfig) -> Self {
-        Self { faucet, config }
+    pub fn new(faucet: F, config: FaucetConfig) -> Result<Self, FaucetError> {
+        Ok(Self {
+            faucet,
+            quotas: Quotas::new(&config)?,
+            challenges: Challenges::new(&config),
+            config,
+        })
+    }
+
+    /// Checks that a request solved its challenge and is within quotas, and counts it against
+    /// them. The returned charge must be handed to the faucet with the request, so that it is
+    /// refunded if the request is not paid out. Requests carrying the shared secret are exempt
+    /// from both.
+    pub(crate) fn admit(
+        &self,
+        headers: &HeaderMap,
+        peer_ip: IpAddr,
+        recipient: SuiAddress,
+    ) -> Result<Option<QuotaCharge>, FaucetError> {
+        if self.challenges.is_trusted(headers) {
+            return Ok(None);
+        }
+        self.challenges.verify(headers, recipient)?;
+        let ip = if self.config.trust_x_forwarded_for {
+            headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|ip| ip.trim().parse().ok())
+                .unwrap_or(peer_ip)
+        } else {
+            peer_ip
+        };
+        self.quotas.check_and_record(recipient, ip)
     }
 }
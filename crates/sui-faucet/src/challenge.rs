@@ -0,0 +1,190 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use fastcrypto::hash::{HashFunction, Sha256};
+use http::HeaderMap;
+use sui_sdk::types::base_types::SuiAddress;
+use uuid::Uuid;
+
+use crate::{ChallengeResponse, FaucetConfig, FaucetError};
+
+/// Header carrying a challenge obtained from `/v2/challenge`.
+pub const CHALLENGE_HEADER: &str = "x-faucet-challenge";
+/// Header carrying the nonce solving the challenge.
+pub const NONCE_HEADER: &str = "x-faucet-nonce";
+
+/// How long an issued challenge can be solved for.
+const CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+/// Cap on outstanding challenges, so that fetching challenges cannot exhaust memory.
+const MAX_OUTSTANDING_CHALLENGES: usize = 100_000;
+
+/// Guards unauthenticated requests with a proof-of-work challenge or a shared secret.
+pub(crate) struct Challenges {
+    pow_difficulty: Option<u8>,
+    secret: Option<String>,
+    /// Challenges issued and not solved yet, with their expiry
+    outstanding: Mutex<HashMap<String, Instant>>,
+}
+
+impl Challenges {
+    pub(crate) fn new(config: &FaucetConfig) -> Self {
+        Self {
+            pow_difficulty: config.pow_difficulty,
+            secret: config.challenge_secret.clone(),
+            outstanding: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the request carries the shared secret as a bearer token.
+    pub(crate) fn is_trusted(&self, headers: &HeaderMap) -> bool {
+        let Some(secret) = &self.secret else {
+            return false;
+        };
+        headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|token| token == secret)
+    }
+
+    pub(crate) fn issue(&self) -> Result<ChallengeResponse, FaucetError> {
+        let Some(difficulty) = self.pow_difficulty else {
+            return Err(FaucetError::ChallengeFailed(
+                "faucet does not use proof-of-work challenges".to_string(),
+            ));
+        };
+        let now = Instant::now();
+        let mut outstanding = self.outstanding.lock().unwrap();
+        outstanding.retain(|_, expiry| *expiry > now);
+        if outstanding.len() >= MAX_OUTSTANDING_CHALLENGES {
+            return Err(FaucetError::TooManyRequests(
+                "too many outstanding challenges".to_string(),
+            ));
+        }
+        let challenge = Uuid::new_v4().simple().to_string();
+        outstanding.insert(challenge.clone(), now + CHALLENGE_TTL);
+        Ok(ChallengeResponse {
+            challenge,
+            difficulty,
+        })
+    }
+
+    /// Checks that an untrusted request solved a challenge, if challenges are required.
+    pub(crate) fn verify(
+        &self,
+        headers: &HeaderMap,
+        recipient: SuiAddress,
+    ) -> Result<(), FaucetError> {
+        let Some(difficulty) = self.pow_difficulty else {
+            return if self.secret.is_some() {
+                Err(FaucetError::ChallengeFailed(
+                    "missing or invalid faucet secret".to_string(),
+                ))
+            } else {
+                Ok(())
+            };
+        };
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| FaucetError::ChallengeFailed(format!("missing `{name}` header")))
+        };
+        let challenge = header(CHALLENGE_HEADER)?;
+        let nonce: u64 = header(NONCE_HEADER)?
+            .parse()
+            .map_err(|_| FaucetError::ChallengeFailed("invalid nonce".to_string()))?;
+
+        // Challenges are single use, whether or not the solution is valid
+        let expiry = self.outstanding.lock().unwrap().remove(challenge);
+        if !expiry.is_some_and(|expiry| expiry > Instant::now()) {
+            return Err(FaucetError::ChallengeFailed(
+                "unknown or expired challenge".to_string(),
+            ));
+        }
+        if leading_zero_bits(challenge, recipient, nonce) < difficulty as u32 {
+            return Err(FaucetError::ChallengeFailed(
+                "nonce does not solve the challenge".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Leading zero bits of `sha256(challenge || recipient || nonce)`, with the nonce in little
+/// endian. A nonce solves a challenge of difficulty `d` if this is at least `d`.
+fn leading_zero_bits(challenge: &str, recipient: SuiAddress, nonce: u64) -> u32 {
+    let mut hasher = Sha256::default();
+    hasher.update(challenge.as_bytes());
+    hasher.update(recipient.as_ref());
+    hasher.update(nonce.to_le_bytes());
+    let digest = hasher.finalize().digest;
+    let mut bits = 0;
+    for byte in digest {
+        bits += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Finds a nonce solving a proof-of-work challenge for the recipient, as clients have to.
+pub fn solve_challenge(challenge: &str, recipient: SuiAddress, difficulty: u8) -> u64 {
+    (0..)
+        .find(|nonce| leading_zero_bits(challenge, recipient, *nonce) >= difficulty as u32)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn solution_headers(challenge: &str, nonce: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CHALLENGE_HEADER, HeaderValue::from_str(challenge).unwrap());
+        headers.insert(NONCE_HEADER, HeaderValue::from(nonce));
+        headers
+    }
+
+    #[test]
+    fn test_proof_of_work_challenge() {
+        let challenges = Challenges::new(&FaucetConfig {
+            pow_difficulty: Some(8),
+            challenge_secret: Some("secret".to_string()),
+            ..Default::default()
+        });
+        let recipient = SuiAddress::random_for_testing_only();
+        let ChallengeResponse {
+            challenge,
+            difficulty,
+        } = challenges.issue().unwrap();
+
+        // Missing solution
+        assert!(challenges.verify(&HeaderMap::new(), recipient).is_err());
+
+        let nonce = solve_challenge(&challenge, recipient, difficulty);
+        let headers = solution_headers(&challenge, nonce);
+        challenges.verify(&headers, recipient).unwrap();
+        // Challenges cannot be reused
+        assert!(challenges.verify(&headers, recipient).is_err());
+
+        // Bearers of the secret are trusted
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert!(challenges.is_trusted(&headers));
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer wrong"),
+        );
+        assert!(!challenges.is_trusted(&headers));
+    }
+}
@@ -4,7 +4,7 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Error, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Error, Debug, Clone, PartialEq, Eq)]
 pub enum FaucetError {
     #[error("Wallet Error: `{0}`")]
     Wallet(String),
@@ -14,6 +14,18 @@ pub enum FaucetError {
 
     #[error("Internal error: {0}")]
     Internal(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Challenge failed: {0}")]
+    ChallengeFailed(String),
+
+    #[error("Request queue is full, please try again later")]
+    QueueFull,
+
+    #[error("Request `{0}` not found")]
+    RequestNotFound(String),
 }
 
 impl FaucetError {
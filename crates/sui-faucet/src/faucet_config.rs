@@ -3,9 +3,13 @@
 
 use clap::Parser;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
 pub const DEFAULT_AMOUNT: u64 = 200_000_000_000;
 pub const DEFAULT_NUM_COINS: usize = 5;
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 10;
+pub const DEFAULT_MAX_QUEUE_SIZE: usize = 1_000;
+pub const DEFAULT_QUOTA_WINDOW_SECS: u64 = 24 * 60 * 60;
 
 #[derive(Parser, Clone)]
 #[clap(
@@ -29,6 +33,46 @@ pub struct FaucetConfig {
 
     #[clap(long, default_value_t = 60)]
     pub wallet_client_timeout_secs: u64,
+
+    /// Maximum number of queued requests paid out by a single transaction.
+    #[clap(long, default_value_t = DEFAULT_MAX_BATCH_SIZE)]
+    pub max_batch_size: usize,
+
+    /// Maximum number of requests waiting to be paid out. Requests beyond it are rejected.
+    #[clap(long, default_value_t = DEFAULT_MAX_QUEUE_SIZE)]
+    pub max_queue_size: usize,
+
+    /// Maximum number of requests per recipient address within the quota window.
+    #[clap(long)]
+    pub max_requests_per_recipient: Option<u32>,
+
+    /// Maximum number of requests per client IP within the quota window.
+    #[clap(long)]
+    pub max_requests_per_ip: Option<u32>,
+
+    /// Length of the sliding window over which quotas are counted.
+    #[clap(long, default_value_t = DEFAULT_QUOTA_WINDOW_SECS)]
+    pub quota_window_secs: u64,
+
+    /// File in which quota usage is persisted across restarts. Usage is only kept in memory if
+    /// not set.
+    #[clap(long)]
+    pub quota_state_path: Option<PathBuf>,
+
+    /// Take the client IP from the `X-Forwarded-For` header, for faucets behind a proxy.
+    #[clap(long)]
+    pub trust_x_forwarded_for: bool,
+
+    /// Number of leading zero bits required of proof-of-work solutions. If set, requests must
+    /// solve a challenge obtained from `/v2/challenge`.
+    #[clap(long)]
+    pub pow_difficulty: Option<u8>,
+
+    /// Shared secret that clients can send as a bearer token instead of solving a challenge.
+    /// Such requests are also exempt from quotas. If set without `--pow-difficulty`, requests
+    /// without the secret are rejected.
+    #[clap(long)]
+    pub challenge_secret: Option<String>,
 }
 
 impl Default for FaucetConfig {
@@ -39,6 +83,15 @@ impl Default for FaucetConfig {
             amount: DEFAULT_AMOUNT,
             num_coins: DEFAULT_NUM_COINS,
             wallet_client_timeout_secs: 60,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            max_queue_size: DEFAULT_MAX_QUEUE_SIZE,
+            max_requests_per_recipient: None,
+            max_requests_per_ip: None,
+            quota_window_secs: DEFAULT_QUOTA_WINDOW_SECS,
+            quota_state_path: None,
+            trust_x_forwarded_for: false,
+            pow_difficulty: None,
+            challenge_secret: None,
         }
     }
 }
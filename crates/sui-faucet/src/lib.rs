@@ -2,15 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod app_state;
+mod challenge;
 mod errors;
 mod faucet_config;
 mod local_faucet;
+mod quota;
 mod server;
 mod types;
 
 pub use app_state::AppState;
+pub use challenge::{solve_challenge, CHALLENGE_HEADER, NONCE_HEADER};
 pub use errors::FaucetError;
 pub use faucet_config::FaucetConfig;
 pub use local_faucet::LocalFaucet;
 pub use server::{create_wallet_context, start_faucet};
-pub use types::{CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest, RequestStatus};
+pub use types::{
+    ChallengeResponse, CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest,
+    QueuedFaucetResponse, RequestStatus, TaskStatus, TaskStatusResponse,
+};
@@ -1,16 +1,20 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use anyhow::bail;
-use tokio::sync::Mutex;
-use tokio::time::Duration;
-use tracing::info;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
 
+use crate::quota::QuotaCharge;
 use crate::FaucetConfig;
 use crate::FaucetError;
+use crate::TaskStatus;
 use sui_sdk::{
     rpc_types::{SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions},
     types::quorum_driver_types::ExecuteTransactionRequestType,
@@ -19,7 +23,7 @@ use sui_sdk::{
 use crate::CoinInfo;
 use shared_crypto::intent::Intent;
 use sui_keys::keystore::AccountKeystore;
-use sui_sdk::rpc_types::SuiTransactionBlockEffectsAPI;
+use sui_sdk::rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
 use sui_sdk::types::programmable_transaction_builder::ProgrammableTransactionBuilder;
 use sui_sdk::types::{
     base_types::{ObjectID, SuiAddress},
@@ -28,15 +32,39 @@ use sui_sdk::types::{
 };
 use sui_sdk::wallet_context::WalletContext;
 
+/// Gas budget per recipient of a batch.
 const GAS_BUDGET: u64 = 10_000_000;
 const NUM_RETRIES: u8 = 2;
+/// How long the status of a completed queued request can be polled for.
+const TASK_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// A request waiting in the queue to be paid out.
+struct QueuedRequest {
+    recipient: SuiAddress,
+    reply: Reply,
+    /// Refunded if the request is not paid out
+    charge: Option<QuotaCharge>,
+}
+
+enum Reply {
+    /// The request was queued through the status API, and its outcome is kept for polling
+    Status(Uuid),
+    /// The requester waits for the outcome
+    Wait(oneshot::Sender<Result<Vec<CoinInfo>, FaucetError>>),
+}
+
+struct Task {
+    status: TaskStatus,
+    updated: Instant,
+}
 
 pub struct LocalFaucet {
     wallet: WalletContext,
     active_address: SuiAddress,
-    coin_id: Arc<Mutex<ObjectID>>,
     coin_amount: u64,
     num_coins: usize,
+    queue: mpsc::Sender<QueuedRequest>,
+    tasks: std::sync::Mutex<HashMap<Uuid, Task>>,
 }
 
 /// We do not just derive(Debug) because WalletContext and the WriteAheadLog do not implement Debug / are also hard
@@ -56,22 +84,172 @@ impl LocalFaucet {
         config: FaucetConfig,
     ) -> Result<Arc<Self>, FaucetError> {
         let (coins, active_address) = find_gas_coins_and_address(&mut wallet, &config).await?;
-        info!("Starting faucet with address: {:?}", active_address);
+        info!(
+            "Starting faucet with address: {:?} and {} gas coins",
+            active_address,
+            coins.len()
+        );
 
-        Ok(Arc::new(LocalFaucet {
+        let (queue, receiver) = mpsc::channel(config.max_queue_size.max(1));
+        let faucet = Arc::new(LocalFaucet {
             wallet,
             active_address,
-            coin_id: Arc::new(Mutex::new(*coins[0].id())),
             coin_amount: config.amount,
             num_coins: config.num_coins,
-        }))
+            queue,
+            tasks: std::sync::Mutex::new(HashMap::new()),
+        });
+
+        // Each gas coin pays out batches of requests taken off the shared queue
+        let receiver = Arc::new(Mutex::new(receiver));
+        for coin in coins {
+            tokio::spawn(run_batches(
+                Arc::downgrade(&faucet),
+                *coin.id(),
+                receiver.clone(),
+                config.max_batch_size.max(1),
+            ));
+        }
+        Ok(faucet)
     }
 
-    /// Make transaction and execute it.
+    /// Queue a request and wait for it to be paid out.
     pub async fn local_request_execute_tx(
         &self,
         recipient: SuiAddress,
     ) -> Result<Vec<CoinInfo>, FaucetError> {
+        self.execute_charged_request(recipient, None).await
+    }
+
+    /// Like `local_request_execute_tx`, refunding `charge` if the request is not paid out.
+    pub(crate) async fn execute_charged_request(
+        &self,
+        recipient: SuiAddress,
+        charge: Option<QuotaCharge>,
+    ) -> Result<Vec<CoinInfo>, FaucetError> {
+        let (sender, receiver) = oneshot::channel();
+        self.enqueue(QueuedRequest {
+            recipient,
+            reply: Reply::Wait(sender),
+            charge,
+        })?;
+        receiver
+            .await
+            .map_err(|_| FaucetError::internal("Faucet stopped before paying out the request"))?
+    }
+
+    /// Queue a request, returning the ID under which its status can be polled.
+    pub fn queue_request(&self, recipient: SuiAddress) -> Result<Uuid, FaucetError> {
+        self.queue_charged_request(recipient, None)
+    }
+
+    /// Like `queue_request`, refunding `charge` if the request is not paid out.
+    pub(crate) fn queue_charged_request(
+        &self,
+        recipient: SuiAddress,
+        charge: Option<QuotaCharge>,
+    ) -> Result<Uuid, FaucetError> {
+        let task_id = Uuid::new_v4();
+        self.tasks.lock().unwrap().insert(
+            task_id,
+            Task {
+                status: TaskStatus::Queued,
+                updated: Instant::now(),
+            },
+        );
+        let result = self.enqueue(QueuedRequest {
+            recipient,
+            reply: Reply::Status(task_id),
+            charge,
+        });
+        if result.is_err() {
+            self.tasks.lock().unwrap().remove(&task_id);
+        }
+        result.map(|_| task_id)
+    }
+
+    pub fn request_status(&self, task_id: &Uuid) -> Option<TaskStatus> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .map(|task| task.status.clone())
+    }
+
+    /// Add a request to the queue, refunding its charge if it could not be queued.
+    fn enqueue(&self, request: QueuedRequest) -> Result<(), FaucetError> {
+        self.queue.try_send(request).map_err(|e| {
+            let (request, err) = match e {
+                mpsc::error::TrySendError::Full(request) => (request, FaucetError::QueueFull),
+                mpsc::error::TrySendError::Closed(request) => (
+                    request,
+                    FaucetError::internal("Faucet request queue is closed"),
+                ),
+            };
+            if let Some(charge) = request.charge {
+                charge.refund();
+            }
+            err
+        })
+    }
+
+    /// Pay out a batch of requests with a single transaction, and report the outcome of each.
+    async fn pay_out(&self, coin_id: ObjectID, batch: Vec<QueuedRequest>) {
+        let recipients: Vec<_> = batch.iter().map(|request| request.recipient).collect();
+        let result = self.execute_batch(coin_id, &recipients).await;
+        if let Err(e) = &result {
+            warn!(
+                "Failed to pay out batch of {} requests with coin {:?}: {}",
+                batch.len(),
+                coin_id,
+                e
+            );
+        }
+
+        let now = Instant::now();
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|_, task| {
+            matches!(task.status, TaskStatus::Queued) || now - task.updated < TASK_RETENTION
+        });
+        for (i, request) in batch.into_iter().enumerate() {
+            let outcome = match &result {
+                Ok(coins) => Ok(coins[i].clone()),
+                Err(e) => {
+                    if let Some(charge) = request.charge {
+                        charge.refund();
+                    }
+                    Err(e.clone())
+                }
+            };
+            match request.reply {
+                Reply::Status(task_id) => {
+                    let status = match outcome {
+                        Ok(coins) => TaskStatus::Success(coins),
+                        Err(e) => TaskStatus::Failure(e),
+                    };
+                    tasks.insert(
+                        task_id,
+                        Task {
+                            status,
+                            updated: now,
+                        },
+                    );
+                }
+                Reply::Wait(sender) => {
+                    // The requester may have gone away
+                    let _ = sender.send(outcome);
+                }
+            }
+        }
+    }
+
+    /// Make a transaction paying `num_coins` coins to each recipient and execute it. Returns the
+    /// coins sent to each recipient, in order.
+    async fn execute_batch(
+        &self,
+        coin_id: ObjectID,
+        recipients: &[SuiAddress],
+    ) -> Result<Vec<Vec<CoinInfo>>, FaucetError> {
         let gas_price = self
             .wallet
             .get_reference_gas_price()
@@ -79,29 +257,31 @@ impl LocalFaucet {
             .map_err(|e| FaucetError::internal(format!("Failed to get gas price: {}", e)))?;
 
         let mut ptb = ProgrammableTransactionBuilder::new();
-        let recipients = vec![recipient; self.num_coins];
-        let amounts = vec![self.coin_amount; self.num_coins];
-        ptb.pay_sui(recipients, amounts)
+        let pay_recipients: Vec<_> = recipients
+            .iter()
+            .flat_map(|recipient| std::iter::repeat(*recipient).take(self.num_coins))
+            .collect();
+        let amounts = vec![self.coin_amount; pay_recipients.len()];
+        ptb.pay_sui(pay_recipients, amounts)
             .map_err(FaucetError::internal)?;
 
         let ptb = ptb.finish();
 
-        let coin_id = self.coin_id.lock().await;
         let coin_id_ref = self
             .wallet
-            .get_object_ref(*coin_id)
+            .get_object_ref(coin_id)
             .await
             .map_err(|e| FaucetError::internal(format!("Failed to get object ref: {}", e)))?;
         let tx_data = TransactionData::new_programmable(
             self.active_address,
             vec![coin_id_ref],
             ptb,
-            GAS_BUDGET,
+            GAS_BUDGET * recipients.len() as u64,
             gas_price,
         );
 
         let tx = self
-            .execute_txn_with_retries(tx_data, *coin_id, NUM_RETRIES)
+            .execute_txn_with_retries(tx_data, coin_id, NUM_RETRIES)
             .await
             .map_err(FaucetError::internal)?;
 
@@ -110,18 +290,31 @@ impl LocalFaucet {
                 "Failed to get coin id from response".to_string(),
             ));
         };
+        if let SuiExecutionStatus::Failure { error } = effects.status() {
+            return Err(FaucetError::Transfer(error.clone()));
+        }
 
-        let coins: Vec<CoinInfo> = effects
-            .created()
-            .iter()
-            .map(|o| CoinInfo {
+        let mut coins_by_recipient: HashMap<SuiAddress, Vec<CoinInfo>> = HashMap::new();
+        for o in effects.created() {
+            let Ok(owner) = o.owner.get_owner_address() else {
+                continue;
+            };
+            coins_by_recipient.entry(owner).or_default().push(CoinInfo {
                 amount: self.coin_amount,
                 id: o.object_id(),
                 transfer_tx_digest: *effects.transaction_digest(),
-            })
-            .collect();
+            });
+        }
 
-        Ok(coins)
+        // A recipient may appear several times in a batch, each time receiving `num_coins` coins
+        Ok(recipients
+            .iter()
+            .map(|recipient| {
+                let coins = coins_by_recipient.entry(*recipient).or_default();
+                let num_coins = self.num_coins.min(coins.len());
+                coins.drain(..num_coins).collect()
+            })
+            .collect())
     }
 
     async fn execute_txn(
@@ -185,6 +378,36 @@ impl LocalFaucet {
     }
 }
 
+/// Takes batches of requests off the queue and pays them out with the given gas coin, until the
+/// faucet is dropped.
+async fn run_batches(
+    faucet: Weak<LocalFaucet>,
+    coin_id: ObjectID,
+    receiver: Arc<Mutex<mpsc::Receiver<QueuedRequest>>>,
+    max_batch_size: usize,
+) {
+    loop {
+        let mut batch = vec![];
+        {
+            let mut receiver = receiver.lock().await;
+            let Some(request) = receiver.recv().await else {
+                return;
+            };
+            batch.push(request);
+            while batch.len() < max_batch_size {
+                let Ok(request) = receiver.try_recv() else {
+                    break;
+                };
+                batch.push(request);
+            }
+        }
+        let Some(faucet) = faucet.upgrade() else {
+            return;
+        };
+        faucet.pay_out(coin_id, batch).await;
+    }
+}
+
 /// Finds gas coins with sufficient balance and returns the address to use as the active address
 /// for the faucet. If the initial active address in the wallet does not have enough gas coins,
 /// it will iterate through the addresses to find one with sufficient gas coins.
@@ -261,6 +484,58 @@ mod tests {
         assert_eq!(coins.data.len(), 2 * local_faucet.num_coins);
     }
 
+    #[tokio::test]
+    async fn test_local_faucet_batches_requests() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let client = cluster.sui_client().clone();
+
+        let config = FaucetConfig::default();
+        let local_faucet = LocalFaucet::new(cluster.wallet, config).await.unwrap();
+
+        // Concurrent requests are batched, including several requests for the same recipient
+        let recipients: Vec<_> = (0..4)
+            .map(|_| SuiAddress::random_for_testing_only())
+            .chain([SuiAddress::ZERO, SuiAddress::ZERO])
+            .collect();
+        let handles: Vec<_> = recipients
+            .iter()
+            .map(|recipient| {
+                let local_faucet = local_faucet.clone();
+                let recipient = *recipient;
+                tokio::spawn(async move { local_faucet.local_request_execute_tx(recipient).await })
+            })
+            .collect();
+        for (recipient, handle) in recipients.iter().zip(handles) {
+            let coins = handle.await.unwrap().unwrap();
+            assert_eq!(coins.len(), local_faucet.num_coins);
+            for coin in coins {
+                let owner = client
+                    .read_api()
+                    .get_object_with_options(
+                        coin.id,
+                        sui_sdk::rpc_types::SuiObjectDataOptions::new().with_owner(),
+                    )
+                    .await
+                    .unwrap()
+                    .owner()
+                    .unwrap();
+                assert_eq!(owner.get_owner_address().unwrap(), *recipient);
+            }
+        }
+
+        let task_id = local_faucet.queue_request(SuiAddress::ZERO).unwrap();
+        loop {
+            match local_faucet.request_status(&task_id).unwrap() {
+                TaskStatus::Queued => tokio::time::sleep(Duration::from_millis(100)).await,
+                TaskStatus::Success(coins) => {
+                    assert_eq!(coins.len(), local_faucet.num_coins);
+                    break;
+                }
+                TaskStatus::Failure(e) => panic!("Request failed: {e}"),
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_find_gas_coins_and_address() {
         let mut cluster = TestClusterBuilder::new().build().await;
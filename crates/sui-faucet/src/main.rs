@@ -21,10 +21,10 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let context = create_wallet_context(wallet_client_timeout_secs, sui_config_dir()?)?;
 
-    let app_state = Arc::new(AppState {
-        faucet: LocalFaucet::new(context, config.clone()).await.unwrap(),
+    let app_state = Arc::new(AppState::new(
+        LocalFaucet::new(context, config.clone()).await?,
         config,
-    });
+    )?);
 
     start_faucet(app_state).await
 }
@@ -0,0 +1,268 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::base_types::SuiAddress;
+use tracing::{info, warn};
+
+use crate::{FaucetConfig, FaucetError};
+
+/// How often quota usage is written to the state file, if it changed.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Timestamps (unix ms) of the requests made within the quota window, per recipient and per IP.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct QuotaState {
+    recipients: HashMap<String, VecDeque<u64>>,
+    ips: HashMap<String, VecDeque<u64>>,
+}
+
+/// Per-recipient and per-IP request quotas over a sliding window.
+pub(crate) struct Quotas {
+    window_ms: u64,
+    max_per_recipient: Option<u32>,
+    max_per_ip: Option<u32>,
+    state_path: Option<PathBuf>,
+    ledger: Arc<QuotaLedger>,
+}
+
+/// The quota state, and whether it changed since it was last persisted.
+struct QuotaLedger {
+    state: Mutex<QuotaState>,
+    dirty: AtomicBool,
+}
+
+/// A request counted against the quotas of its recipient and IP, which can be refunded if the
+/// request is not paid out.
+pub(crate) struct QuotaCharge {
+    ledger: Arc<QuotaLedger>,
+    recipient: String,
+    ip: String,
+    at_ms: u64,
+}
+
+impl Quotas {
+    pub(crate) fn new(config: &FaucetConfig) -> Result<Self, FaucetError> {
+        let state = match &config.quota_state_path {
+            Some(path) if path.exists() => {
+                let bytes = std::fs::read(path).map_err(|e| {
+                    FaucetError::internal(format!("Failed to read quota state {path:?}: {e}"))
+                })?;
+                info!("Loaded quota state from {:?}", path);
+                serde_json::from_slice(&bytes).map_err(|e| {
+                    FaucetError::internal(format!("Failed to parse quota state {path:?}: {e}"))
+                })?
+            }
+            _ => QuotaState::default(),
+        };
+        Ok(Self {
+            window_ms: config.quota_window_secs * 1000,
+            max_per_recipient: config.max_requests_per_recipient,
+            max_per_ip: config.max_requests_per_ip,
+            state_path: config.quota_state_path.clone(),
+            ledger: Arc::new(QuotaLedger {
+                state: Mutex::new(state),
+                dirty: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// Periodically writes quota usage to the state file in the background, so that requests
+    /// never wait on the disk. Usage recorded since the last write is lost on a crash.
+    pub(crate) fn spawn_persister(&self) {
+        let Some(path) = self.state_path.clone() else {
+            return;
+        };
+        let ledger = Arc::downgrade(&self.ledger);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(ledger) = Weak::upgrade(&ledger) else {
+                    break;
+                };
+                let path = path.clone();
+                if let Err(e) =
+                    tokio::task::spawn_blocking(move || ledger.persist_if_dirty(&path)).await
+                {
+                    warn!("Quota state persister failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Checks that neither the recipient nor the IP exceeded its quota, and counts the request
+    /// against both. Returns the charge to refund if the request is not paid out, or `None` if
+    /// quotas are disabled.
+    pub(crate) fn check_and_record(
+        &self,
+        recipient: SuiAddress,
+        ip: IpAddr,
+    ) -> Result<Option<QuotaCharge>, FaucetError> {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.check_and_record_at(recipient, ip, now_ms)
+    }
+
+    fn check_and_record_at(
+        &self,
+        recipient: SuiAddress,
+        ip: IpAddr,
+        now_ms: u64,
+    ) -> Result<Option<QuotaCharge>, FaucetError> {
+        if self.max_per_recipient.is_none() && self.max_per_ip.is_none() {
+            return Ok(None);
+        }
+        let cutoff = now_ms.saturating_sub(self.window_ms);
+        let mut state = self.ledger.state.lock().unwrap();
+        let QuotaState { recipients, ips } = &mut *state;
+        for requests in recipients.values_mut().chain(ips.values_mut()) {
+            while requests.front().is_some_and(|t| *t <= cutoff) {
+                requests.pop_front();
+            }
+        }
+        recipients.retain(|_, requests| !requests.is_empty());
+        ips.retain(|_, requests| !requests.is_empty());
+
+        let recipient_key = recipient.to_string();
+        let ip_key = ip.to_string();
+        if let Some(max) = self.max_per_recipient {
+            if recipients.get(&recipient_key).map_or(0, |r| r.len()) >= max as usize {
+                return Err(FaucetError::TooManyRequests(format!(
+                    "recipient {recipient} exceeded its quota of {max} requests"
+                )));
+            }
+        }
+        if let Some(max) = self.max_per_ip {
+            if ips.get(&ip_key).map_or(0, |r| r.len()) >= max as usize {
+                return Err(FaucetError::TooManyRequests(format!(
+                    "IP {ip} exceeded its quota of {max} requests"
+                )));
+            }
+        }
+        recipients
+            .entry(recipient_key.clone())
+            .or_default()
+            .push_back(now_ms);
+        ips.entry(ip_key.clone()).or_default().push_back(now_ms);
+        self.ledger.dirty.store(true, Ordering::Relaxed);
+
+        Ok(Some(QuotaCharge {
+            ledger: self.ledger.clone(),
+            recipient: recipient_key,
+            ip: ip_key,
+            at_ms: now_ms,
+        }))
+    }
+}
+
+impl QuotaLedger {
+    /// Writes the state to `path` if it changed since it was last written. The state is only
+    /// serialized under the lock, the file is written outside of it.
+    fn persist_if_dirty(&self, path: &Path) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let bytes = serde_json::to_vec(&*self.state.lock().unwrap());
+        let result = bytes
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| persist(path, &bytes));
+        if let Err(e) = result {
+            warn!("Failed to persist quota state to {:?}: {}", path, e);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl QuotaCharge {
+    /// Removes the request from the quotas of its recipient and IP.
+    pub(crate) fn refund(self) {
+        let mut state = self.ledger.state.lock().unwrap();
+        let QuotaState { recipients, ips } = &mut *state;
+        for (requests, key) in [(recipients, &self.recipient), (ips, &self.ip)] {
+            let Some(times) = requests.get_mut(key) else {
+                continue;
+            };
+            if let Some(i) = times.iter().rposition(|t| *t == self.at_ms) {
+                times.remove(i);
+            }
+            if times.is_empty() {
+                requests.remove(key);
+            }
+        }
+        self.ledger.dirty.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Writes the state to a temporary file first, so that a crash never leaves a truncated file.
+fn persist(path: &Path, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotas_sliding_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FaucetConfig {
+            max_requests_per_recipient: Some(2),
+            max_requests_per_ip: Some(3),
+            quota_window_secs: 10,
+            quota_state_path: Some(dir.path().join("quotas.json")),
+            ..Default::default()
+        };
+        let quotas = Quotas::new(&config).unwrap();
+        let (a, b) = (
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+        );
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        quotas.check_and_record_at(a, ip, 1_000).unwrap();
+        quotas.check_and_record_at(a, ip, 2_000).unwrap();
+        // Recipient quota exhausted
+        assert!(matches!(
+            quotas.check_and_record_at(a, ip, 3_000),
+            Err(FaucetError::TooManyRequests(_))
+        ));
+        quotas.check_and_record_at(b, ip, 3_000).unwrap();
+        // IP quota exhausted
+        assert!(matches!(
+            quotas.check_and_record_at(b, ip, 4_000),
+            Err(FaucetError::TooManyRequests(_))
+        ));
+
+        // Refunded requests no longer count against the quotas
+        let c = SuiAddress::random_for_testing_only();
+        let other_ip: IpAddr = "10.0.0.2".parse().unwrap();
+        quotas
+            .check_and_record_at(c, other_ip, 4_500)
+            .unwrap()
+            .unwrap()
+            .refund();
+        quotas.check_and_record_at(c, other_ip, 5_000).unwrap();
+        quotas.check_and_record_at(c, other_ip, 5_500).unwrap();
+
+        // Usage survives a restart once persisted, and expires with the window
+        quotas
+            .ledger
+            .persist_if_dirty(config.quota_state_path.as_ref().unwrap());
+        let quotas = Quotas::new(&config).unwrap();
+        assert!(quotas.check_and_record_at(a, ip, 10_500).is_err());
+        quotas.check_and_record_at(a, ip, 11_500).unwrap();
+    }
+}
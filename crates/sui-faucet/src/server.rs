@@ -5,7 +5,8 @@ use crate::types::*;
 use crate::{AppState, FaucetConfig, FaucetError, FaucetRequest};
 use axum::{
     error_handling::HandleErrorLayer,
-    http::StatusCode,
+    extract::{ConnectInfo, Path},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     BoxError, Extension, Json, Router,
@@ -23,6 +24,7 @@ use sui_sdk::wallet_context::WalletContext;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
+use uuid::Uuid;
 
 /// basic handler that responds with a static string
 async fn health() -> &'static str {
@@ -31,19 +33,25 @@ async fn health() -> &'static str {
 
 async fn request_local_gas(
     Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<FaucetRequest>,
     // ) -> &'static str {
 ) -> impl IntoResponse {
     let FaucetRequest::FixedAmountRequest(request) = payload;
     info!("Local request for address: {}", request.recipient);
+    let charge = match state.admit(&headers, addr.ip(), request.recipient) {
+        Ok(charge) => charge,
+        Err(e) => return (error_status_code(&e), Json(FaucetResponse::from(e))),
+    };
     let request = state
         .faucet
-        .local_request_execute_tx(request.recipient)
+        .execute_charged_request(request.recipient, charge)
         .await;
 
     if let Err(e) = request {
         return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            error_status_code(&e),
             Json(FaucetResponse {
                 status: RequestStatus::Failure(e),
                 coins_sent: None,
@@ -73,6 +81,78 @@ async fn request_local_gas(
     )
 }
 
+/// Queues a request without waiting for it to be paid out
+async fn queue_local_gas(
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<FaucetRequest>,
+) -> impl IntoResponse {
+    let FaucetRequest::FixedAmountRequest(request) = payload;
+    info!("Queued request for address: {}", request.recipient);
+    let result = state
+        .admit(&headers, addr.ip(), request.recipient)
+        .and_then(|charge| {
+            state
+                .faucet
+                .queue_charged_request(request.recipient, charge)
+        });
+    match result {
+        Ok(task_id) => (
+            StatusCode::ACCEPTED,
+            Json(QueuedFaucetResponse {
+                task_id: Some(task_id.to_string()),
+                error: None,
+            }),
+        ),
+        Err(e) => (
+            error_status_code(&e),
+            Json(QueuedFaucetResponse {
+                task_id: None,
+                error: Some(e),
+            }),
+        ),
+    }
+}
+
+async fn request_status(
+    Extension(state): Extension<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskStatusResponse>, (StatusCode, Json<FaucetError>)> {
+    Uuid::parse_str(&task_id)
+        .ok()
+        .and_then(|id| state.faucet.request_status(&id))
+        .map(|status| Json(TaskStatusResponse { status }))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(FaucetError::RequestNotFound(task_id)),
+            )
+        })
+}
+
+async fn challenge(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<ChallengeResponse>, (StatusCode, Json<FaucetError>)> {
+    state
+        .challenges
+        .issue()
+        .map(Json)
+        .map_err(|e| (error_status_code(&e), Json(e)))
+}
+
+fn error_status_code(error: &FaucetError) -> StatusCode {
+    match error {
+        FaucetError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+        FaucetError::ChallengeFailed(_) => StatusCode::UNAUTHORIZED,
+        FaucetError::QueueFull => StatusCode::SERVICE_UNAVAILABLE,
+        FaucetError::RequestNotFound(_) => StatusCode::NOT_FOUND,
+        FaucetError::Wallet(_) | FaucetError::Transfer(_) | FaucetError::Internal(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
 pub fn create_wallet_context(
     timeout_secs: u64,
     config_dir: PathBuf,
@@ -109,11 +189,15 @@ pub async fn start_faucet(app_state: Arc<AppState>) -> Result<(), anyhow::Error>
     let FaucetConfig { port, host_ip, .. } = app_state.config;
 
     info!("Starting faucet in local mode");
+    app_state.quotas.spawn_persister();
     let app = Router::new()
         .route("/", get(health))
         .route("/v2/gas", post(request_local_gas))
         .route("/v1/gas", post(request_local_gas))
         .route("/gas", post(request_local_gas))
+        .route("/v2/gas/queue", post(queue_local_gas))
+        .route("/v2/gas/status/{task_id}", get(request_status))
+        .route("/v2/challenge", get(challenge))
         .layer(
             ServiceBuilder::new()
                 .layer(HandleErrorLayer::new(handle_error))
@@ -157,10 +241,7 @@ mod tests {
             .await
             .unwrap();
 
-        let app_state = Arc::new(AppState {
-            faucet: local_faucet,
-            config,
-        });
+        let app_state = Arc::new(AppState::new(local_faucet, config).unwrap());
 
         // Spawn the faucet in a background task
         let handle = tokio::spawn(async move {
@@ -203,4 +284,81 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
         handle.abort();
     }
+
+    #[tokio::test]
+    async fn test_queued_gas_endpoint() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let port = 9091;
+        let config = FaucetConfig {
+            host_ip: "127.0.0.1".parse().unwrap(),
+            port,
+            max_requests_per_recipient: Some(1),
+            ..Default::default()
+        };
+        let local_faucet = LocalFaucet::new(cluster.wallet, config.clone())
+            .await
+            .unwrap();
+        let app_state = Arc::new(AppState::new(local_faucet, config).unwrap());
+        let handle = tokio::spawn(async move {
+            start_faucet(app_state)
+                .await
+                .expect("Failed to start faucet");
+        });
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let recipient = SuiAddress::random_for_testing_only();
+        let req = FaucetRequest::new_fixed_amount_request(recipient);
+        let response = client
+            .post(format!("http://127.0.0.1:{port}/v2/gas/queue"))
+            .json(&req)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let task_id = response
+            .json::<QueuedFaucetResponse>()
+            .await
+            .unwrap()
+            .task_id
+            .unwrap();
+
+        // Poll until the request is paid out
+        let coins = loop {
+            let response = client
+                .get(format!("http://127.0.0.1:{port}/v2/gas/status/{task_id}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            match response.json::<TaskStatusResponse>().await.unwrap().status {
+                TaskStatus::Queued => {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await
+                }
+                TaskStatus::Success(coins) => break coins,
+                TaskStatus::Failure(e) => panic!("Request failed: {e}"),
+            }
+        };
+        assert_eq!(coins.len(), FaucetConfig::default().num_coins);
+
+        // The recipient used up its quota
+        let response = client
+            .post(format!("http://127.0.0.1:{port}/v2/gas/queue"))
+            .json(&req)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let response = client
+            .get(format!(
+                "http://127.0.0.1:{port}/v2/gas/status/{}",
+                Uuid::new_v4()
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        handle.abort();
+    }
 }
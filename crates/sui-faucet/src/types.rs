@@ -38,6 +38,35 @@ pub struct FaucetResponse {
     pub coins_sent: Option<Vec<CoinInfo>>,
 }
 
+/// Response to a request queued with `/v2/gas/queue`. Its `task_id` is polled at
+/// `/v2/gas/status/{task_id}`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedFaucetResponse {
+    pub task_id: Option<String>,
+    pub error: Option<FaucetError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum TaskStatus {
+    Queued,
+    Success(Vec<CoinInfo>),
+    Failure(FaucetError),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskStatusResponse {
+    pub status: TaskStatus,
+}
+
+/// A proof-of-work challenge. Requests solve it with a nonce such that
+/// `sha256(challenge || recipient || nonce)` has `difficulty` leading zero bits.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChallengeResponse {
+    pub challenge: String,
+    pub difficulty: u8,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CoinInfo {
@@ -226,6 +226,17 @@ public fun begin(sender: address): Scenario {
     }
 }
 
+/// Begin a new multi-transaction test scenario, as with `begin`, that runs against the state of
+/// network `chain` (e.g. `b"mainnet"` or `b"testnet"`) at `checkpoint`. Objects taken by ID
+/// that the test did not create, their dynamic fields, and the packages they use are loaded
+/// from the forked state the first time they are needed.
+/// Where the state of each network is loaded from is configured when running the tests, e.g.
+/// with `sui move test --fork-source`.
+public fun begin_fork(sender: address, chain: vector<u8>, checkpoint: u64): Scenario {
+    set_fork(chain, checkpoint);
+    begin(sender)
+}
+
 /// Begin a new multi-transaction test scenario with a give `rgp` and `TxContextBuilder`.
 public fun begin_with_context(ctx_builder: TxContextBuilder): Scenario {
     let txn_number = 0;
@@ -598,3 +609,6 @@ native fun deallocate_receiving_ticket_for_object(object_id: ID);
 // internal function that ends the transaction, realizing changes (may abort with
 // `ECouldNotGenerateEffects`)
 native fun end_transaction(): TransactionEffects;
+
+// Run the current test against the state of network `chain` at `checkpoint`.
+native fun set_fork(chain: vector<u8>, checkpoint: u64);
@@ -4,7 +4,7 @@
 use axum::extract::State;
 use axum::{Extension, Json};
 use axum_extra::extract::WithRejection;
-use futures::future::join_all;
+use futures::future::{join_all, try_join_all};
 
 use prost_types::FieldMask;
 use sui_rpc::field::FieldMaskUtil;
@@ -12,15 +12,15 @@ use sui_rpc::proto::sui::rpc::v2beta2::{
     Checkpoint, CheckpointSummary, GetBalanceRequest, GetCheckpointRequest, GetEpochRequest,
     ListOwnedObjectsRequest, Object,
 };
-use sui_sdk::SUI_COIN_TYPE;
 use sui_sdk_types::Address;
 use sui_types::base_types::SuiAddress;
 use tracing::info;
 
 use crate::errors::Error;
+use crate::indexer::IndexerDataSource;
 use crate::types::{
     AccountBalanceRequest, AccountBalanceResponse, AccountCoinsRequest, AccountCoinsResponse,
-    Amount, Coin, CoinID, CoinIdentifier, Currencies, Currency, SubAccountType, SubBalance,
+    Amount, Coin, CoinID, CoinIdentifier, Currencies, SubAccountType, SubBalance,
 };
 use crate::{OnlineServerContext, SuiEnv};
 use std::time::Duration;
@@ -38,6 +38,12 @@ pub async fn balance(
     env.check_network_identifier(&request.network_identifier)?;
     let address = request.account_identifier.address;
     let currencies = &request.currencies;
+    if let Some(indexer) = &ctx.indexer {
+        // Sub-account balances come from the fullnode's staking state, at the latest checkpoint.
+        if request.account_identifier.sub_account.is_none() {
+            return get_indexed_balances(&ctx, indexer, &request, address).await;
+        }
+    }
     let mut retry_attempts = 5;
     //TODO this retry logic should probably be ripped out.
     while retry_attempts > 0 {
@@ -70,6 +76,34 @@ pub async fn balance(
 }
 
 //TODO this can be a convenience method in the SDK.
+/// Balances as of the requested block, defaulting to the latest indexed block. The indexer reads
+/// all balances at the same checkpoint, so unlike the fullnode they do not need to be re-checked.
+async fn get_indexed_balances(
+    ctx: &OnlineServerContext,
+    indexer: &IndexerDataSource,
+    request: &AccountBalanceRequest,
+    address: SuiAddress,
+) -> Result<AccountBalanceResponse, Error> {
+    if request.currencies.0.is_empty() {
+        return Err(Error::InvalidInput(
+            "Coin type is required for this request".to_string(),
+        ));
+    }
+    let block_identifier = indexer.block_identifier(&request.block_identifier).await?;
+    let balance_futures = request.currencies.0.iter().map(|currency| async move {
+        ctx.coin_metadata_cache.check_currency(currency).await?;
+        let value = indexer
+            .balance(address, currency, block_identifier.index)
+            .await?;
+        Ok::<_, Error>(Amount::new(value, Some(currency.clone())))
+    });
+    let balances = try_join_all(balance_futures).await?;
+    Ok(AccountBalanceResponse {
+        block_identifier,
+        balances,
+    })
+}
+
 async fn get_checkpoint(ctx: &mut OnlineServerContext) -> Result<CheckpointSequenceNumber, Error> {
     let request = GetCheckpointRequest {
         checkpoint_id: None, // None means get latest checkpoint
@@ -125,29 +159,18 @@ async fn get_balances(
         get_sub_account_balances(account_type, &mut ctx.grpc_client, address).await
     } else if !currencies.0.is_empty() {
         let balance_futures = currencies.0.iter().map(|currency| {
-            let coin_type = currency.metadata.clone().coin_type.clone();
+            let cache = ctx.coin_metadata_cache.clone();
             let mut grpc_client = ctx.grpc_client.clone();
             async move {
-                (
-                    currency.clone(),
-                    get_account_balances(&mut grpc_client, address, &coin_type).await,
-                )
+                cache.check_currency(currency).await?;
+                let coin_type = &currency.metadata.coin_type;
+                let value = get_account_balances(&mut grpc_client, address, coin_type)
+                    .await
+                    .map_err(|_| Error::InvalidInput(format!("{:?}", coin_type)))?;
+                Ok::<_, Error>(Amount::new(value, Some(currency.clone())))
             }
         });
-        let balances: Vec<(Currency, Result<i128, Error>)> = join_all(balance_futures).await;
-        let mut amounts = Vec::new();
-        for (currency, balance_result) in balances {
-            match balance_result {
-                Ok(value) => amounts.push(Amount::new(value, Some(currency))),
-                Err(_e) => {
-                    return Err(Error::InvalidInput(format!(
-                        "{:?}",
-                        currency.metadata.coin_type
-                    )))
-                }
-            }
-        }
-        Ok(amounts)
+        join_all(balance_futures).await.into_iter().collect()
     } else {
         Err(Error::InvalidInput(
             "Coin type is required for this request".to_string(),
@@ -224,10 +247,9 @@ async fn get_sub_account_balances(
     })
 }
 
-/// Get an array of all unspent coins for an AccountIdentifier and the BlockIdentifier at which the lookup was performed. .
+/// Get an array of all unspent coins for an AccountIdentifier and the BlockIdentifier at which the lookup was performed.
+/// Coins of the requested currencies are returned, SUI coins if none are given.
 /// [Rosetta API Spec](https://docs.cdp.coinbase.com/api-reference/mesh/account/get-an-account-unspent-coins)
-/// TODO This API is supposed to return coins of all types, not just SUI. It also has a 'currencies' parameter that we
-/// are igorning which can be used to filter the type of coins that are returned.
 pub async fn coins(
     State(mut context): State<OnlineServerContext>,
     Extension(env): Extension<SuiEnv>,
@@ -237,50 +259,57 @@ pub async fn coins(
 
     //TODO bound this list.
     let mut coins = Vec::new();
-    let mut page_token = None;
+    for currency in request.currencies.0 {
+        context
+            .coin_metadata_cache
+            .check_currency(&currency)
+            .await?;
+        let object_type = format!("0x2::coin::Coin<{}>", currency.metadata.coin_type);
+        let mut page_token = None;
 
-    loop {
-        let list_request = ListOwnedObjectsRequest {
-            owner: Some(request.account_identifier.address.to_string()),
-            object_type: Some(SUI_COIN_TYPE.to_string()),
-            page_size: Some(5000),
-            page_token,
-            read_mask: Some(FieldMask::from_paths([
-                Object::OBJECT_ID_FIELD.name,
-                Object::VERSION_FIELD.name,
-                Object::BALANCE_FIELD.name,
-            ])),
-        };
+        loop {
+            let list_request = ListOwnedObjectsRequest {
+                owner: Some(request.account_identifier.address.to_string()),
+                object_type: Some(object_type.clone()),
+                page_size: Some(5000),
+                page_token,
+                read_mask: Some(FieldMask::from_paths([
+                    Object::OBJECT_ID_FIELD.name,
+                    Object::VERSION_FIELD.name,
+                    Object::BALANCE_FIELD.name,
+                ])),
+            };
 
-        let response = context
-            .grpc_client
-            .live_data_client()
-            .list_owned_objects(list_request)
-            .await?
-            .into_inner();
+            let response = context
+                .grpc_client
+                .live_data_client()
+                .list_owned_objects(list_request)
+                .await?
+                .into_inner();
 
-        for object in response.objects {
-            if let (Some(object_id), Some(version), Some(balance)) =
-                (object.object_id, object.version, object.balance)
-            {
-                let coin = Coin {
-                    coin_identifier: CoinIdentifier {
-                        identifier: CoinID {
-                            id: ObjectID::from_hex_literal(&object_id).map_err(|e| {
-                                Error::DataError(format!("Invalid object_id: {}", e))
-                            })?,
-                            version: SequenceNumber::from(version),
+            for object in response.objects {
+                if let (Some(object_id), Some(version), Some(balance)) =
+                    (object.object_id, object.version, object.balance)
+                {
+                    let coin = Coin {
+                        coin_identifier: CoinIdentifier {
+                            identifier: CoinID {
+                                id: ObjectID::from_hex_literal(&object_id).map_err(|e| {
+                                    Error::DataError(format!("Invalid object_id: {}", e))
+                                })?,
+                                version: SequenceNumber::from(version),
+                            },
                         },
-                    },
-                    amount: Amount::new(balance as i128, None),
-                };
-                coins.push(coin);
+                        amount: Amount::new(balance as i128, Some(currency.clone())),
+                    };
+                    coins.push(coin);
+                }
             }
-        }
 
-        page_token = response.next_page_token;
-        if page_token.is_none() {
-            break;
+            page_token = response.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
         }
     }
 
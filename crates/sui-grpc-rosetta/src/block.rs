@@ -44,6 +44,10 @@ pub async fn transaction(
 ) -> Result<BlockTransactionResponse, Error> {
     env.check_network_identifier(&request.network_identifier)?;
     let digest = request.transaction_identifier.hash;
+    if let Some(indexer) = &context.indexer {
+        let transaction = indexer.transaction(digest).await?;
+        return Ok(BlockTransactionResponse { transaction });
+    }
     let response = context
         .client
         .read_api()
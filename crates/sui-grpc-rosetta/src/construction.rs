@@ -243,6 +243,9 @@ pub async fn metadata(
         InternalOperation::PayCoin { currency, .. } => Some(currency.clone()),
         _ => None,
     };
+    if let Some(currency) = &currency {
+        context.coin_metadata_cache.check_currency(currency).await?;
+    }
     let coin_type = currency.as_ref().map(|c| c.metadata.coin_type.clone());
 
     let mut gas_price = context
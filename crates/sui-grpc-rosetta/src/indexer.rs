@@ -0,0 +1,446 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serves blocks and balances from a `sui-indexer-alt` database rather than a fullnode, so that
+//! they remain available for checkpoints the fullnode has pruned.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::dataloader::DataLoader;
+use async_trait::async_trait;
+use diesel::dsl::{count_star, max, min};
+use diesel::{BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper};
+use futures::future::try_join_all;
+use move_core_types::language_storage::TypeTag;
+use sui_indexer_alt_reader::kv_loader::KvLoader;
+use sui_indexer_alt_reader::object_versions::CheckpointBoundedObjectVersionKey;
+use sui_indexer_alt_reader::objects::VersionedObjectKey;
+use sui_indexer_alt_reader::pg_reader::PgReader;
+use sui_indexer_alt_reader::tx_balance_changes::TxBalanceChangeKey;
+use sui_indexer_alt_schema::objects::StoredCoinOwnerKind;
+use sui_indexer_alt_schema::schema::{
+    coin_balance_buckets, kv_genesis, tx_balance_changes, watermarks,
+};
+use sui_indexer_alt_schema::transactions::{
+    BalanceChange as StoredBalanceChange, StoredTxBalanceChange,
+};
+use sui_json_rpc_types::BalanceChange;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::digests::{CheckpointDigest, TransactionDigest};
+use sui_types::message_envelope::Message;
+use sui_types::messages_checkpoint::{
+    CheckpointContents, CheckpointSequenceNumber, CheckpointSummary,
+};
+use sui_types::parse_sui_type_tag;
+
+use crate::operations::Operations;
+use crate::state::BlockProvider;
+use crate::types::{
+    Block, BlockHash, BlockIdentifier, BlockResponse, Currency, PartialBlockIdentifier,
+    Transaction, TransactionIdentifier,
+};
+use crate::{CoinMetadataCache, Error};
+
+/// The indexer pipelines whose tables are read. Only checkpoints that all of them have indexed,
+/// and none of them have pruned, are served.
+const PIPELINES: [&str; 5] = [
+    "kv_checkpoints",
+    "tx_balance_changes",
+    "coin_balance_buckets",
+    "obj_versions",
+    "kv_objects",
+];
+
+/// Reads blocks from `kv_checkpoints` and `tx_balance_changes`, and balances as of any indexed
+/// block from `coin_balance_buckets`, with coin values read from `obj_versions` and `kv_objects`.
+///
+/// Blocks only carry `SuiBalanceChange` operations, as the indexer does not keep the inputs needed
+/// to tell gas, payments and staking apart.
+#[derive(Clone)]
+pub struct IndexerDataSource {
+    pg_reader: PgReader,
+    pg_loader: Arc<DataLoader<PgReader>>,
+    kv_loader: KvLoader,
+    coin_metadata_cache: CoinMetadataCache,
+}
+
+impl IndexerDataSource {
+    pub fn new(pg_reader: PgReader, coin_metadata_cache: CoinMetadataCache) -> Self {
+        let pg_loader = Arc::new(pg_reader.as_data_loader());
+        Self {
+            pg_reader,
+            kv_loader: KvLoader::new_with_pg(pg_loader.clone()),
+            pg_loader,
+            coin_metadata_cache,
+        }
+    }
+
+    /// Resolves a (possibly partial) block identifier from a request, defaulting to the latest
+    /// indexed block. Blocks can only be looked up by hash together with their index.
+    pub async fn block_identifier(
+        &self,
+        block: &PartialBlockIdentifier,
+    ) -> Result<BlockIdentifier, Error> {
+        let Some(index) = block.index else {
+            return match block.hash {
+                Some(_) => Err(Error::InvalidInput(
+                    "Block index is required to look up a block from the indexer".to_string(),
+                )),
+                None => self.current_block_identifier().await,
+            };
+        };
+        let identifier = self.create_block_identifier(index).await?;
+        match block.hash {
+            Some(hash) if hash != identifier.hash => Err(Error::BlockNotFound {
+                index: Some(index),
+                hash: Some(hash),
+            }),
+            _ => Ok(identifier),
+        }
+    }
+
+    /// Balance of `owner` in `currency` as of `checkpoint`, i.e. the value of the coins of that
+    /// currency it owned at the end of the checkpoint.
+    pub async fn balance(
+        &self,
+        owner: SuiAddress,
+        currency: &Currency,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<i128, Error> {
+        self.check_indexed(checkpoint).await?;
+        let coin_type_str = &currency.metadata.coin_type;
+        let coin_type = parse_sui_type_tag(coin_type_str)
+            .map_err(|e| Error::InvalidInput(format!("Invalid coin type {coin_type_str}: {e}")))?;
+        let coins = self.owned_coins(owner, &coin_type, checkpoint).await?;
+
+        let versions = self
+            .pg_loader
+            .load_many(
+                coins
+                    .iter()
+                    .map(|id| CheckpointBoundedObjectVersionKey(*id, checkpoint)),
+            )
+            .await
+            .map_err(anyhow::Error::from)?;
+        let keys = versions
+            .values()
+            .map(|v| {
+                let id = ObjectID::from_bytes(&v.object_id).map_err(anyhow::Error::from)?;
+                Ok(VersionedObjectKey(id, v.object_version as u64))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let objects = self
+            .kv_loader
+            .load_many_objects(keys)
+            .await
+            .map_err(anyhow::Error::from)?;
+        if objects.len() != coins.len() {
+            return Err(Error::DataError(format!(
+                "Found {} of the {} coins owned by {owner} at checkpoint {checkpoint}",
+                objects.len(),
+                coins.len()
+            )));
+        }
+
+        Ok(objects
+            .values()
+            .filter_map(|object| object.as_coin_maybe())
+            .map(|coin| coin.value() as i128)
+            .sum())
+    }
+
+    /// Operations of a transaction, from its balance changes.
+    pub async fn transaction(&self, digest: TransactionDigest) -> Result<Transaction, Error> {
+        let stored = self
+            .pg_loader
+            .load_one(TxBalanceChangeKey(digest))
+            .await
+            .map_err(anyhow::Error::from)?
+            .ok_or_else(|| Error::DataError(format!("Transaction {digest} is not indexed")))?;
+        self.create_transaction(digest, &stored.balance_changes)
+            .await
+    }
+
+    /// IDs of the coins of `coin_type` owned by `owner` at the end of `checkpoint`: the coins whose
+    /// latest bucket as of the checkpoint has them owned by that address.
+    async fn owned_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: &TypeTag,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<Vec<ObjectID>, Error> {
+        use coin_balance_buckets::dsl as cb;
+
+        let (candidates, newer) = diesel::alias!(
+            coin_balance_buckets as candidates,
+            coin_balance_buckets as newer
+        );
+
+        macro_rules! candidates {
+            ($field:ident) => {
+                candidates.field(cb::$field)
+            };
+        }
+
+        macro_rules! newer {
+            ($field:ident) => {
+                newer.field(cb::$field)
+            };
+        }
+
+        // Rows from after the checkpoint are ignored, so that the latest row as of the checkpoint
+        // is the one without a newer row.
+        let checkpoint = checkpoint as i64;
+        let query = candidates
+            .select(candidates!(object_id))
+            .left_join(
+                newer.on(candidates!(object_id)
+                    .eq(newer!(object_id))
+                    .and(candidates!(cp_sequence_number).lt(newer!(cp_sequence_number)))
+                    .and(newer!(cp_sequence_number).le(checkpoint))),
+            )
+            .filter(newer!(object_id).is_null())
+            .filter(candidates!(cp_sequence_number).le(checkpoint))
+            .filter(candidates!(owner_kind).eq(StoredCoinOwnerKind::Fastpath))
+            .filter(candidates!(owner_id).eq(owner.to_vec()))
+            .filter(candidates!(coin_type).eq(bcs::to_bytes(coin_type)?))
+            .into_boxed();
+
+        let mut conn = self
+            .pg_reader
+            .connect()
+            .await
+            .map_err(anyhow::Error::from)?;
+        let ids: Vec<Vec<u8>> = conn.results(query).await.map_err(anyhow::Error::from)?;
+        Ok(ids
+            .iter()
+            .map(|id| ObjectID::from_bytes(id))
+            .collect::<Result<_, _>>()
+            .map_err(anyhow::Error::from)?)
+    }
+
+    /// The range of checkpoints indexed by all pipelines that are read, and not yet pruned by any.
+    async fn indexed_range(&self) -> Result<(u64, u64), Error> {
+        use watermarks::dsl as w;
+
+        let mut conn = self
+            .pg_reader
+            .connect()
+            .await
+            .map_err(anyhow::Error::from)?;
+        let (lo, hi, pipelines): (Option<i64>, Option<i64>, i64) = conn
+            .first(
+                w::watermarks
+                    .select((
+                        max(w::reader_lo),
+                        min(w::checkpoint_hi_inclusive),
+                        count_star(),
+                    ))
+                    .filter(w::pipeline.eq_any(PIPELINES)),
+            )
+            .await
+            .map_err(anyhow::Error::from)?;
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if pipelines as usize == PIPELINES.len() && lo <= hi => {
+                Ok((lo as u64, hi as u64))
+            }
+            _ => Err(Error::DataError(
+                "Indexer has not indexed any checkpoints yet".to_string(),
+            )),
+        }
+    }
+
+    async fn check_indexed(&self, checkpoint: CheckpointSequenceNumber) -> Result<(), Error> {
+        let (lo, hi) = self.indexed_range().await?;
+        if !(lo..=hi).contains(&checkpoint) {
+            return Err(Error::BlockNotFound {
+                index: Some(checkpoint),
+                hash: None,
+            });
+        }
+        Ok(())
+    }
+
+    async fn checkpoint(
+        &self,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<(CheckpointSummary, CheckpointContents), Error> {
+        self.check_indexed(checkpoint).await?;
+        let (summary, contents, _) = self
+            .kv_loader
+            .load_one_checkpoint(checkpoint)
+            .await
+            .map_err(anyhow::Error::from)?
+            .ok_or(Error::BlockNotFound {
+                index: Some(checkpoint),
+                hash: None,
+            })?;
+        Ok((summary, contents))
+    }
+
+    async fn create_block_response(
+        &self,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<BlockResponse, Error> {
+        use tx_balance_changes::dsl as b;
+
+        let (summary, contents) = self.checkpoint(checkpoint).await?;
+        let index = summary.sequence_number;
+        let hash = summary.digest();
+        let tx_hi = summary.network_total_transactions;
+        let tx_lo = tx_hi - contents.size() as u64;
+
+        let mut conn = self
+            .pg_reader
+            .connect()
+            .await
+            .map_err(anyhow::Error::from)?;
+        let stored: Vec<StoredTxBalanceChange> = conn
+            .results(
+                b::tx_balance_changes
+                    .select(StoredTxBalanceChange::as_select())
+                    .filter(b::tx_sequence_number.ge(tx_lo as i64))
+                    .filter(b::tx_sequence_number.lt(tx_hi as i64)),
+            )
+            .await
+            .map_err(anyhow::Error::from)?;
+        let balance_changes: HashMap<_, _> = stored
+            .into_iter()
+            .map(|s| (s.tx_sequence_number as u64, s.balance_changes))
+            .collect();
+
+        let transactions = try_join_all(contents.iter().enumerate().map(|(i, digests)| {
+            let tx_sequence_number = tx_lo + i as u64;
+            let balance_changes = balance_changes.get(&tx_sequence_number);
+            async move {
+                let balance_changes = balance_changes.ok_or_else(|| {
+                    Error::DataError(format!(
+                        "Balance changes of transaction {} are not indexed",
+                        digests.transaction
+                    ))
+                })?;
+                self.create_transaction(digests.transaction, balance_changes)
+                    .await
+            }
+        }))
+        .await?;
+
+        // previous digest should only be None for genesis block.
+        if summary.previous_digest.is_none() && index != 0 {
+            return Err(Error::DataError(format!(
+                "Previous digest is None for checkpoint [{index}], digest: [{hash:?}]"
+            )));
+        }
+
+        let parent_block_identifier = summary
+            .previous_digest
+            .map(|hash| BlockIdentifier {
+                index: index - 1,
+                hash,
+            })
+            .unwrap_or_else(|| BlockIdentifier { index, hash });
+
+        Ok(BlockResponse {
+            block: Block {
+                block_identifier: BlockIdentifier { index, hash },
+                parent_block_identifier,
+                timestamp: summary.timestamp_ms,
+                transactions,
+                metadata: None,
+            },
+            other_transactions: vec![],
+        })
+    }
+
+    async fn create_transaction(
+        &self,
+        digest: TransactionDigest,
+        balance_changes: &[u8],
+    ) -> Result<Transaction, Error> {
+        let balance_changes: Vec<StoredBalanceChange> = bcs::from_bytes(balance_changes)?;
+        let balance_changes = balance_changes
+            .into_iter()
+            .map(|change| {
+                let StoredBalanceChange::V1 {
+                    owner,
+                    coin_type,
+                    amount,
+                } = change;
+                Ok(BalanceChange {
+                    owner,
+                    coin_type: parse_sui_type_tag(&coin_type)?,
+                    amount,
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(Transaction {
+            transaction_identifier: TransactionIdentifier { hash: digest },
+            operations: Operations::try_from_balance_changes(
+                balance_changes,
+                &self.coin_metadata_cache,
+            )
+            .await?,
+            related_transactions: vec![],
+            metadata: None,
+        })
+    }
+}
+
+#[async_trait]
+impl BlockProvider for IndexerDataSource {
+    async fn get_block_by_index(&self, index: u64) -> Result<BlockResponse, Error> {
+        self.create_block_response(index).await
+    }
+
+    async fn get_block_by_hash(&self, hash: BlockHash) -> Result<BlockResponse, Error> {
+        Err(Error::InvalidInput(format!(
+            "Block index is required to look up block {hash} from the indexer"
+        )))
+    }
+
+    async fn current_block(&self) -> Result<BlockResponse, Error> {
+        let (_, hi) = self.indexed_range().await?;
+        self.create_block_response(hi).await
+    }
+
+    async fn genesis_block_identifier(&self) -> Result<BlockIdentifier, Error> {
+        use kv_genesis::dsl as g;
+
+        let mut conn = self
+            .pg_reader
+            .connect()
+            .await
+            .map_err(anyhow::Error::from)?;
+        let digest: Vec<u8> = conn
+            .first(g::kv_genesis.select(g::genesis_digest))
+            .await
+            .map_err(anyhow::Error::from)?;
+        Ok(BlockIdentifier {
+            index: 0,
+            hash: CheckpointDigest::try_from(digest)?,
+        })
+    }
+
+    async fn oldest_block_identifier(&self) -> Result<BlockIdentifier, Error> {
+        let (lo, _) = self.indexed_range().await?;
+        self.create_block_identifier(lo).await
+    }
+
+    async fn current_block_identifier(&self) -> Result<BlockIdentifier, Error> {
+        let (_, hi) = self.indexed_range().await?;
+        self.create_block_identifier(hi).await
+    }
+
+    async fn create_block_identifier(
+        &self,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> Result<BlockIdentifier, Error> {
+        let (summary, _) = self.checkpoint(checkpoint).await?;
+        Ok(BlockIdentifier {
+            index: summary.sequence_number,
+            hash: summary.digest(),
+        })
+    }
+}
@@ -13,17 +13,22 @@ use anyhow::anyhow;
 use clap::Parser;
 use fastcrypto::encoding::{Encoding, Hex};
 use fastcrypto::traits::EncodeDecodeBase64;
+use prometheus::Registry;
 use serde_json::{json, Value};
 use sui_config::{sui_config_dir, Config, NodeConfig, SUI_FULLNODE_CONFIG, SUI_KEYSTORE_FILENAME};
 use sui_grpc_rosetta::types::{CurveType, PrefundedAccount, SuiEnv};
 use sui_grpc_rosetta::{RosettaOfflineServer, RosettaOnlineServer, SUI};
+use sui_indexer_alt_reader::pg_reader::db::DbArgs;
+use sui_indexer_alt_reader::pg_reader::PgReader;
 use sui_node::SuiNode;
 use sui_rpc::client::Client as GrpcClient;
 use sui_sdk::{SuiClient, SuiClientBuilder};
 use sui_types::base_types::SuiAddress;
 use sui_types::crypto::{KeypairTraits, SuiKeyPair, ToFromBytes};
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 use tracing::log::warn;
+use url::Url;
 
 #[derive(Parser)]
 #[clap(name = "sui-grpc-rosetta", rename_all = "kebab-case", author, version)]
@@ -47,6 +52,12 @@ pub enum RosettaServerCommand {
         full_node_url: String,
         #[clap(long, default_value = "/data")]
         data_path: PathBuf,
+        /// Serve blocks and balances from this `sui-indexer-alt` database instead of the full
+        /// node, so that they are available beyond the full node's pruning horizon.
+        #[clap(long)]
+        indexer_database_url: Option<Url>,
+        #[clap(flatten)]
+        db_args: DbArgs,
     },
     StartOnlineServer {
         #[clap(long, default_value = "localnet")]
@@ -57,6 +68,12 @@ pub enum RosettaServerCommand {
         node_config: Option<PathBuf>,
         #[clap(long, default_value = "/data")]
         data_path: PathBuf,
+        /// Serve blocks and balances from this `sui-indexer-alt` database instead of the full
+        /// node, so that they are available beyond the full node's pruning horizon.
+        #[clap(long)]
+        indexer_database_url: Option<Url>,
+        #[clap(flatten)]
+        db_args: DbArgs,
     },
     StartOfflineServer {
         #[clap(long, default_value = "localnet")]
@@ -137,6 +154,8 @@ impl RosettaServerCommand {
                 addr,
                 full_node_url,
                 data_path,
+                indexer_database_url,
+                db_args,
             } => {
                 info!(
                     "Starting Rosetta Online Server with remove Sui full node [{full_node_url}]."
@@ -146,7 +165,11 @@ impl RosettaServerCommand {
                 info!("Rosetta db path : {rosetta_path:?}");
                 let grpc_client = GrpcClient::new(&full_node_url)
                     .map_err(|e| anyhow::anyhow!("Failed to create gRPC client: {}", e))?;
-                let rosetta = RosettaOnlineServer::new(env, sui_client, grpc_client);
+                let mut rosetta = RosettaOnlineServer::new(env, sui_client, grpc_client);
+                if let Some(url) = indexer_database_url {
+                    let registry = Registry::default();
+                    rosetta = with_indexer(rosetta, url, db_args, &registry).await?;
+                }
                 rosetta.serve(addr).await;
             }
 
@@ -155,6 +178,8 @@ impl RosettaServerCommand {
                 addr,
                 node_config,
                 data_path,
+                indexer_database_url,
+                db_args,
             } => {
                 info!("Starting Rosetta Online Server with embedded Sui full node.");
                 info!("Data directory path: {data_path:?}");
@@ -171,6 +196,7 @@ impl RosettaServerCommand {
 
                 let registry_service =
                     mysten_metrics::start_prometheus_server(config.metrics_address);
+                let registry = registry_service.default_registry();
                 // Staring a full node for the rosetta server.
                 let rpc_address = format!("http://127.0.0.1:{}", config.json_rpc_address.port());
                 let _node = SuiNode::start(config, registry_service).await?;
@@ -181,7 +207,10 @@ impl RosettaServerCommand {
                 info!("Rosetta db path : {rosetta_path:?}");
                 let grpc_client = GrpcClient::new(&rpc_address)
                     .map_err(|e| anyhow::anyhow!("Failed to create gRPC client: {}", e))?;
-                let rosetta = RosettaOnlineServer::new(env, sui_client, grpc_client);
+                let mut rosetta = RosettaOnlineServer::new(env, sui_client, grpc_client);
+                if let Some(url) = indexer_database_url {
+                    rosetta = with_indexer(rosetta, url, db_args, &registry).await?;
+                }
                 rosetta.serve(addr).await;
             }
         };
@@ -189,6 +218,24 @@ impl RosettaServerCommand {
     }
 }
 
+async fn with_indexer(
+    rosetta: RosettaOnlineServer,
+    database_url: Url,
+    db_args: DbArgs,
+    registry: &Registry,
+) -> Result<RosettaOnlineServer, anyhow::Error> {
+    info!("Serving blocks and balances from indexer database.");
+    let pg_reader = PgReader::new(
+        Some("rosetta"),
+        Some(database_url),
+        db_args,
+        registry,
+        CancellationToken::new(),
+    )
+    .await?;
+    Ok(rosetta.with_indexer(pg_reader))
+}
+
 async fn wait_for_sui_client(rpc_address: String) -> SuiClient {
     loop {
         match SuiClientBuilder::default().build(&rpc_address).await {
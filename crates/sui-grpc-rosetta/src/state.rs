@@ -10,6 +10,7 @@ use sui_sdk::rpc_types::Checkpoint;
 use sui_sdk::SuiClient;
 use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 
+use crate::indexer::IndexerDataSource;
 use crate::operations::Operations;
 use crate::types::{
     Block, BlockHash, BlockIdentifier, BlockResponse, Transaction, TransactionIdentifier,
@@ -26,6 +27,8 @@ pub struct OnlineServerContext {
     pub grpc_client: GrpcClient,
     pub coin_metadata_cache: CoinMetadataCache,
     block_provider: Arc<dyn BlockProvider + Send + Sync>,
+    /// Serves blocks and historical balances when set, in place of the fullnode.
+    pub indexer: Option<Arc<IndexerDataSource>>,
 }
 
 impl OnlineServerContext {
@@ -40,9 +43,18 @@ impl OnlineServerContext {
             grpc_client,
             block_provider,
             coin_metadata_cache,
+            indexer: None,
         }
     }
 
+    /// Reads blocks and balances from the indexer, leaving everything else to the fullnode.
+    pub fn with_indexer(mut self, indexer: IndexerDataSource) -> Self {
+        let indexer = Arc::new(indexer);
+        self.block_provider = indexer.clone();
+        self.indexer = Some(indexer);
+        self
+    }
+
     pub fn blocks(&self) -> &(dyn BlockProvider + Sync + Send) {
         &*self.block_provider
     }
@@ -241,13 +241,15 @@ mod str_format {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AccountCoinsRequest {
     pub network_identifier: NetworkIdentifier,
     pub account_identifier: AccountIdentifier,
     pub include_mempool: bool,
+    #[serde(default, deserialize_with = "deserialize_or_default_currencies")]
+    pub currencies: Currencies,
 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AccountCoinsResponse {
     pub block_identifier: BlockIdentifier,
     pub coins: Vec<Coin>,
@@ -257,7 +259,7 @@ impl IntoResponse for AccountCoinsResponse {
         Json(self).into_response()
     }
 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Coin {
     pub coin_identifier: CoinIdentifier,
     pub amount: Amount,
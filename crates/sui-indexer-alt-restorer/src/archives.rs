@@ -12,12 +12,13 @@ pub(crate) struct ArchivalCheckpointInfo {
 
 impl ArchivalCheckpointInfo {
     /// Reads checkpoint information from archival storage to determine,
-    /// specifically the next checkpoint number after `start_epoch` for watermarking.
+    /// specifically the next checkpoint number after the restored epoch for watermarking.
     pub async fn read_archival_checkpoint_info(args: &Args) -> anyhow::Result<Self> {
         let checkpoints = end_of_epoch_data(args.archive_url.clone(), vec![], 5).await?;
-        let next_checkpoint_after_epoch = checkpoints[args.start_epoch as usize] + 1;
+        let epoch = args.restore_epoch();
+        let next_checkpoint_after_epoch = checkpoints[epoch as usize] + 1;
         info!(
-            epoch = args.start_epoch,
+            epoch,
             checkpoint = next_checkpoint_after_epoch,
             "Next checkpoint after epoch",
         );
@@ -7,6 +7,8 @@ mod snapshot;
 use archives::ArchivalCheckpointInfo;
 use clap::Parser;
 use sui_pg_db::DbArgs;
+use sui_snapshot::filter::LiveObjectFilter;
+use sui_types::base_types::{ObjectID, SuiAddress};
 use url::Url;
 
 use crate::snapshot::SnapshotRestorer;
@@ -18,6 +20,24 @@ pub struct Args {
     #[clap(long, env = "START_EPOCH", required = true)]
     pub start_epoch: u64,
 
+    /// Apply the delta snapshots of each epoch after `start_epoch` up to this one, to restore
+    /// from the end of this epoch instead.
+    #[clap(long, env = "END_EPOCH")]
+    pub end_epoch: Option<u64>,
+
+    /// Only restore objects of this type (repeatable). A type without type parameters matches
+    /// all of its instantiations.
+    #[clap(long = "type")]
+    pub types: Vec<String>,
+
+    /// Only restore packages with this ID and objects of types they define (repeatable).
+    #[clap(long = "package")]
+    pub packages: Vec<ObjectID>,
+
+    /// Only restore objects owned by this address or object (repeatable).
+    #[clap(long = "owner")]
+    pub owners: Vec<SuiAddress>,
+
     /// Url of the endpoint to fetch snapshot files from,
     /// for example <https://formal-snapshot.mainnet.sui.io>
     #[clap(long, env = "ENDPOINT", required = true)]
@@ -52,6 +72,23 @@ pub struct Args {
     pub db_args: DbArgs,
 }
 
+impl Args {
+    /// The epoch whose end the restored state is for.
+    pub fn restore_epoch(&self) -> u64 {
+        self.end_epoch.unwrap_or(self.start_epoch)
+    }
+
+    /// The filter selecting the objects to restore, if only some of them are restored.
+    pub fn filter(&self) -> anyhow::Result<Option<LiveObjectFilter>> {
+        let filter = LiveObjectFilter::new(
+            self.types.clone(),
+            self.packages.clone(),
+            self.owners.clone(),
+        )?;
+        Ok((!filter.is_empty()).then_some(filter))
+    }
+}
+
 pub async fn restore(args: &Args) -> anyhow::Result<()> {
     let archival_checkpoint_info =
         ArchivalCheckpointInfo::read_archival_checkpoint_info(args).await?;
@@ -6,7 +6,8 @@ use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use anyhow::Error;
+use anyhow::{anyhow, bail, Error};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
 use diesel_async::RunQueryDsl;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use object_store::path::Path;
@@ -19,9 +20,11 @@ use sui_field_count::FieldCount;
 use sui_indexer_alt_framework::task::TrySpawnStreamExt;
 use sui_indexer_alt_schema::objects::StoredObjInfo;
 use sui_indexer_alt_schema::schema::obj_info;
+use sui_pg_db::schema::watermarks;
 use sui_pg_db::Db;
 use sui_snapshot::{
-    reader::{download_bytes, LiveObjectIter, StateSnapshotReaderV1},
+    filter::LiveObjectFilter,
+    reader::{download_bytes, read_live_objects, StateSnapshotReaderV1},
     FileMetadata,
 };
 use sui_storage::object_store::ObjectStoreGetExt;
@@ -30,11 +33,19 @@ use crate::Args;
 
 pub type DigestByBucketAndPartition = BTreeMap<u32, BTreeMap<u32, [u8; 32]>>;
 
+/// Prefix of the pipeline name of the watermark recording a partial restore, which is followed
+/// by the description of the filter that was applied, e.g. `obj_info[type=0x2::coin::Coin]`.
+const PARTIAL_RESTORE_PIPELINE_PREFIX: &str = "obj_info[";
+
 pub struct SnapshotRestorer {
     pub restore_args: Args,
     pub next_checkpoint_after_epoch: u64,
     pub snapshot_reader: StateSnapshotReaderV1,
     pub db: Db,
+    /// If set, only the objects matching the filter are restored
+    pub filter: Option<LiveObjectFilter>,
+    remote_store_config: ObjectStoreConfig,
+    local_store_config: ObjectStoreConfig,
 }
 
 impl SnapshotRestorer {
@@ -73,17 +84,124 @@ impl SnapshotRestorer {
             snapshot_reader,
             db,
             next_checkpoint_after_epoch,
+            filter: args.filter()?,
+            remote_store_config,
+            local_store_config,
         })
     }
 
     pub async fn restore(&mut self) -> Result<(), Error> {
+        self.check_partial_restore().await?;
+        // The filter is recorded before any rows are written, so that an interrupted partial
+        // restore can be resumed rather than being mistaken for a full one.
+        if let Some(filter) = &self.filter {
+            self.record_partial_restore(filter).await?;
+        }
         info!(
             epoch = self.restore_args.start_epoch,
+            filter = self.filter.as_ref().map(|filter| filter.to_string()),
             "Starting snapshot restore"
         );
-        let (sha3_digests, num_part_files) = self.snapshot_reader.compute_checksum().await?;
+        let mut snapshot_reader = self.snapshot_reader.clone();
+        self.restore_snapshot(&mut snapshot_reader).await?;
+        info!(
+            epoch = self.restore_args.start_epoch,
+            "Finished snapshot restore"
+        );
+
+        // All rows are written at the checkpoint after the restored epoch, so applying a delta
+        // replaces the rows of the objects it removes with those of the objects it adds.
+        for epoch in self.restore_args.start_epoch + 1..=self.restore_args.restore_epoch() {
+            info!(epoch, "Starting delta snapshot restore");
+            let mut delta_reader = StateSnapshotReaderV1::new_delta(
+                epoch,
+                &self.remote_store_config,
+                &self.local_store_config,
+                NonZeroUsize::new(self.restore_args.concurrency).unwrap(),
+                self.snapshot_reader.get_multi_progress(),
+                true, // skip_reset_local_store
+            )
+            .await?;
+            if delta_reader.base_epoch() != Some(epoch - 1) {
+                return Err(anyhow!(
+                    "Delta snapshot of epoch {} is not against epoch {}",
+                    epoch,
+                    epoch - 1
+                ));
+            }
+            self.remove_object_infos(&delta_reader).await?;
+            self.restore_snapshot(&mut delta_reader).await?;
+            info!(epoch, "Finished delta snapshot restore");
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the database doesn't hold a partial restore with a different filter (or any
+    /// partial restore, for a full restore), and that a partial restore is not applied on top of
+    /// a full one, so that the two are never mixed.
+    async fn check_partial_restore(&self) -> Result<(), Error> {
+        let mut conn = self.db.connect().await?;
+        let existing: Option<String> = watermarks::table
+            .select(watermarks::pipeline)
+            .filter(watermarks::pipeline.like(format!("{PARTIAL_RESTORE_PIPELINE_PREFIX}%")))
+            .first(&mut conn)
+            .await
+            .optional()?;
+        let Some(existing) = existing else {
+            if self.filter.is_none() {
+                return Ok(());
+            }
+
+            // Without a partial restore recorded, any existing object rows come from a full one.
+            let full_restore: Option<Vec<u8>> = obj_info::table
+                .select(obj_info::object_id)
+                .first(&mut conn)
+                .await
+                .optional()?;
+            if full_restore.is_some() {
+                bail!("Database already holds a full restore, cannot partially restore into it");
+            }
+            return Ok(());
+        };
+        match &self.filter {
+            Some(filter) if existing == partial_restore_pipeline(filter) => Ok(()),
+            Some(_) => bail!(
+                "Database already holds a partial restore with a different filter: {existing}"
+            ),
+            None => bail!("Database holds a partial restore, not a full one: {existing}"),
+        }
+    }
+
+    /// Records the filter of a partial restore in the watermarks table, along with the
+    /// checkpoint the restored objects are as of. Only the epoch and checkpoint bounds of the
+    /// watermark are meaningful, and re-recording the same filter when resuming is a no-op.
+    async fn record_partial_restore(&self, filter: &LiveObjectFilter) -> Result<(), Error> {
+        let mut conn = self.db.connect().await?;
+        diesel::insert_into(watermarks::table)
+            .values((
+                watermarks::pipeline.eq(partial_restore_pipeline(filter)),
+                watermarks::epoch_hi_inclusive.eq(self.restore_args.restore_epoch() as i64),
+                watermarks::checkpoint_hi_inclusive.eq(self.next_checkpoint_after_epoch as i64 - 1),
+                watermarks::tx_hi.eq(0i64),
+                watermarks::timestamp_ms_hi_inclusive.eq(0i64),
+                watermarks::reader_lo.eq(0i64),
+                watermarks::pruner_timestamp.eq(diesel::dsl::now),
+                watermarks::pruner_hi.eq(0i64),
+            ))
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn restore_snapshot(
+        &self,
+        snapshot_reader: &mut StateSnapshotReaderV1,
+    ) -> Result<(), Error> {
+        let (sha3_digests, num_part_files) = snapshot_reader.compute_checksum().await?;
         let (input_files, epoch_dir, remote_object_store, _concurrency) =
-            self.snapshot_reader.export_metadata().await?;
+            snapshot_reader.export_metadata().await?;
         let owned_input_files: Vec<(u32, (u32, FileMetadata))> = input_files
             .into_iter()
             .map(|(bucket, (part_num, metadata))| (*bucket, (part_num, metadata.clone())))
@@ -97,10 +215,35 @@ impl SnapshotRestorer {
             num_part_files,
         )
         .await?;
-        info!(
-            epoch = self.restore_args.start_epoch,
-            "Finished snapshot restore"
-        );
+        Ok(())
+    }
+
+    /// Deletes the object infos of the objects removed by a delta snapshot.
+    async fn remove_object_infos(&self, delta_reader: &StateSnapshotReaderV1) -> Result<(), Error> {
+        let mut conn = self.db.connect().await?;
+        let next_cp = self.next_checkpoint_after_epoch as i64;
+        for file_metadata in delta_reader.removed_files() {
+            let object_ids: Vec<Vec<u8>> = delta_reader
+                .removed_ref_iter(&file_metadata)?
+                .map(|(object_id, _, _)| object_id.to_vec())
+                .collect();
+            debug!(
+                bucket = file_metadata.bucket_num,
+                part_num = file_metadata.part_num,
+                num_removed = object_ids.len(),
+                "Start removing object infos"
+            );
+            // NOTE: chunk to avoid hitting the PG limit
+            for chunk in object_ids.chunks(i16::MAX as usize) {
+                diesel::delete(
+                    obj_info::table
+                        .filter(obj_info::object_id.eq_any(chunk))
+                        .filter(obj_info::cp_sequence_number.eq(next_cp)),
+                )
+                .execute(&mut conn)
+                .await?;
+            }
+        }
         Ok(())
     }
 
@@ -131,6 +274,7 @@ impl SnapshotRestorer {
                     let bar = move_object_progress_bar.clone();
                     let db = self.db.clone();
                     let next_cp = self.next_checkpoint_after_epoch;
+                    let filter = self.filter.clone();
 
                     async move {
                         debug!(
@@ -139,7 +283,7 @@ impl SnapshotRestorer {
                             "Start downloading move object file"
                         );
                         let mut conn = db.connect().await?;
-                        let (bytes, _) = download_bytes(
+                        let (bytes, sha3_digest) = download_bytes(
                             remote_object_store,
                             &file_metadata,
                             epoch_dir,
@@ -154,14 +298,22 @@ impl SnapshotRestorer {
                             part_num = part_num,
                             "Finished downloading move object file"
                         );
-                        let object_infos = LiveObjectIter::new(&file_metadata, bytes.clone())?
-                            .filter_map(|object| match object {
-                                LiveObject::Normal(obj) => {
-                                    Some(StoredObjInfo::from_object(&obj, next_cp as i64))
-                                }
-                                LiveObject::Wrapped(_) => None,
-                            })
-                            .collect::<Result<Vec<_>, _>>()?;
+                        // Checks all objects against their references, including those the
+                        // filter skips.
+                        let object_infos = read_live_objects(
+                            &file_metadata,
+                            bytes,
+                            &sha3_digest,
+                            filter.as_ref(),
+                        )?
+                        .into_iter()
+                        .filter_map(|object| match object {
+                            LiveObject::Normal(obj) => {
+                                Some(StoredObjInfo::from_object(&obj, next_cp as i64))
+                            }
+                            LiveObject::Wrapped(_) => None,
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
                         let num_object_infos = object_infos.len();
                         // NOTE: chunk to avoid hitting the PG limit
                         let chunk_size: usize = i16::MAX as usize / StoredObjInfo::FIELD_COUNT;
@@ -194,3 +346,7 @@ impl SnapshotRestorer {
         Ok(())
     }
 }
+
+fn partial_restore_pipeline(filter: &LiveObjectFilter) -> String {
+    format!("{PARTIAL_RESTORE_PIPELINE_PREFIX}{filter}]")
+}
@@ -15,9 +15,9 @@ use sui_json_rpc_api::{ReadApiServer, QUERY_MAX_RESULT_LIMIT};
 use sui_json_rpc_types::ZkLoginIntentScope;
 use sui_json_rpc_types::ZkLoginVerifyResult;
 use sui_json_rpc_types::{
-    Checkpoint, CheckpointId, CheckpointPage, ProtocolConfigResponse, SuiEvent,
-    SuiGetPastObjectRequest, SuiObjectDataOptions, SuiObjectResponse, SuiPastObjectResponse,
-    SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+    Checkpoint, CheckpointId, CheckpointPage, ObjectRetentionGuarantee, ProtocolConfigResponse,
+    SuiEvent, SuiGetPastObjectRequest, SuiObjectDataOptions, SuiObjectResponse,
+    SuiPastObjectResponse, SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
 };
 use sui_open_rpc::Module;
 use sui_protocol_config::{ProtocolConfig, ProtocolVersion};
@@ -164,6 +164,10 @@ impl ReadApiServer for ReadApi {
         Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
     }
 
+    async fn get_object_retention_guarantee(&self) -> RpcResult<ObjectRetentionGuarantee> {
+        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+    }
+
     async fn get_latest_checkpoint_sequence_number(&self) -> RpcResult<BigInt<u64>> {
         let checkpoint = self.get_latest_checkpoint().await?;
         Ok(BigInt::from(checkpoint.sequence_number))
@@ -1167,6 +1167,24 @@ pub struct SuiGetPastObjectRequest {
     pub version: SequenceNumber,
 }
 
+/// Past object versions that a node guarantees to return, given its pruning configuration.
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectRetentionGuarantee {
+    /// Object versions superseded by transactions in checkpoints after this one are guaranteed to
+    /// be available. None if no object versions have been pruned.
+    #[schemars(with = "Option<BigInt<u64>>")]
+    #[serde_as(as = "Option<BigInt<u64>>")]
+    pub highest_pruned_checkpoint: Option<CheckpointSequenceNumber>,
+    /// All versions of these packages, and of objects whose type is defined in one of them, are
+    /// guaranteed to be available.
+    pub retained_packages: Vec<ObjectID>,
+    /// All versions of objects of these types are guaranteed to be available. A type without type
+    /// parameters covers all of its instantiations.
+    pub retained_object_types: Vec<String>,
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub enum SuiObjectDataFilter {
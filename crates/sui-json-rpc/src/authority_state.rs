@@ -12,8 +12,8 @@ use sui_core::execution_cache::ObjectCacheRead;
 use sui_core::jsonrpc_index::TotalBalance;
 use sui_core::subscription_handler::SubscriptionHandler;
 use sui_json_rpc_types::{
-    Coin as SuiCoin, DevInspectResults, DryRunTransactionBlockResponse, EventFilter, SuiEvent,
-    SuiObjectDataFilter, TransactionFilter,
+    Coin as SuiCoin, DevInspectResults, DryRunTransactionBlockResponse, EventFilter,
+    ObjectRetentionGuarantee, SuiEvent, SuiObjectDataFilter, TransactionFilter,
 };
 use sui_storage::key_value_store::{
     KVStoreTransactionData, TransactionKeyValueStore, TransactionKeyValueStoreTrait,
@@ -225,6 +225,8 @@ pub trait StateRead: Send + Sync {
     fn get_latest_checkpoint_sequence_number(&self) -> StateReadResult<CheckpointSequenceNumber>;
 
     fn get_chain_identifier(&self) -> StateReadResult<ChainIdentifier>;
+
+    fn get_object_retention_guarantee(&self) -> StateReadResult<ObjectRetentionGuarantee>;
 }
 
 #[async_trait]
@@ -538,6 +540,22 @@ impl StateRead for AuthorityState {
     fn get_chain_identifier(&self) -> StateReadResult<ChainIdentifier> {
         Ok(self.get_chain_identifier())
     }
+
+    fn get_object_retention_guarantee(&self) -> StateReadResult<ObjectRetentionGuarantee> {
+        let retention = self
+            .config
+            .authority_store_pruning_config
+            .object_retention
+            .clone()
+            .unwrap_or_default();
+        Ok(ObjectRetentionGuarantee {
+            highest_pruned_checkpoint: self
+                .get_object_cache_reader()
+                .get_highest_pruned_checkpoint(),
+            retained_packages: retention.packages,
+            retained_object_types: retention.object_types,
+        })
+    }
 }
 
 /// This implementation allows `S` to be a dynamically sized type (DST) that implements ObjectProvider
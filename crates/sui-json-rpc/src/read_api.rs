@@ -40,9 +40,10 @@ use sui_json_rpc_api::{
 };
 use sui_json_rpc_types::{
     BalanceChange, Checkpoint, CheckpointId, CheckpointPage, DisplayFieldsResponse, EventFilter,
-    ObjectChange, ProtocolConfigResponse, SuiEvent, SuiGetPastObjectRequest, SuiObjectDataOptions,
-    SuiObjectResponse, SuiPastObjectResponse, SuiTransactionBlock, SuiTransactionBlockEvents,
-    SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
+    ObjectChange, ObjectRetentionGuarantee, ProtocolConfigResponse, SuiEvent,
+    SuiGetPastObjectRequest, SuiObjectDataOptions, SuiObjectResponse, SuiPastObjectResponse,
+    SuiTransactionBlock, SuiTransactionBlockEvents, SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
 };
 use sui_open_rpc::Module;
 use sui_protocol_config::{ProtocolConfig, ProtocolVersion};
@@ -761,6 +762,11 @@ impl ReadApiServer for ReadApi {
         })
     }
 
+    #[instrument(skip(self))]
+    async fn get_object_retention_guarantee(&self) -> RpcResult<ObjectRetentionGuarantee> {
+        with_tracing!(async move { Ok(self.state.get_object_retention_guarantee()?) })
+    }
+
     #[instrument(skip(self))]
     async fn get_total_transaction_blocks(&self) -> RpcResult<BigInt<u64>> {
         with_tracing!(async move {
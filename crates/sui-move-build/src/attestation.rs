@@ -0,0 +1,435 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Build attestations: a signed record of how a package was built, from which sources and
+//! dependencies, and what modules the build produced. An attestation can be checked against the
+//! sources it claims to be built from by rebuilding them and comparing the two attestations, and
+//! against the package on-chain by comparing module digests (see `sui-source-validation`).
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
+use fastcrypto::{encoding::Encoding, encoding::Hex, hash::HashFunction};
+use move_binary_format::CompiledModule;
+use move_command_line_common::files::MOVE_EXTENSION;
+use move_core_types::account_address::AccountAddress;
+use move_package::{
+    lock_file::schema::ManagedPackage,
+    source_package::{layout::SourcePackageLayout, manifest_parser::parse_move_manifest_from_file},
+};
+use move_symbol_pool::Symbol;
+use serde::{Deserialize, Serialize};
+use shared_crypto::intent::{Intent, IntentMessage, PersonalMessage};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    crypto::{DefaultHash, Signature, SuiKeyPair, SuiSignature},
+    move_package::MovePackage,
+};
+use walkdir::WalkDir;
+
+use crate::CompiledPackage;
+
+/// Name of the attestation file written next to the build output of a package.
+pub const ATTESTATION_FILE_NAME: &str = "attestation.json";
+
+/// The version of the compiler recorded in attestations of builds by this crate.
+pub const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const PACKAGE_SECTION: &str = "package";
+const PUBLISHED_AT_FIELD: &str = "published-at";
+
+/// How a package was built.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BuildAttestation {
+    pub package_name: String,
+    /// Version of the compiler (the Sui CLI) that built the package.
+    pub compiler_version: String,
+    pub edition: String,
+    pub flavor: String,
+    /// Digest of the source files and manifest of the package (see [`source_digest`]).
+    pub source_digest: String,
+    /// The chain that the IDs of published dependencies were resolved for, from the `Move.lock`
+    /// files of the package and its dependencies.
+    pub chain_id: Option<String>,
+    /// The ID the package is recorded as being published at, if any.
+    pub published_at: Option<ObjectID>,
+    /// The ID the package was first published at, which its modules are published at. Differs
+    /// from `published_at` once the package has been upgraded.
+    pub original_id: Option<ObjectID>,
+    /// Dependencies by package name.
+    pub dependencies: BTreeMap<String, DependencyAttestation>,
+    /// Digests of the serialized modules of the package, by module name.
+    pub modules: BTreeMap<String, String>,
+    /// Digest of the package as computed on-chain, from its modules and dependency IDs.
+    pub package_digest: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DependencyAttestation {
+    /// The ID the dependency is published at, if it is published.
+    pub published_at: Option<ObjectID>,
+    /// Digest of the serialized modules of the dependency.
+    pub digest: String,
+}
+
+/// A build attestation, signed (as a personal message over its BCS bytes) by `signer`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignedBuildAttestation {
+    pub attestation: BuildAttestation,
+    pub signer: SuiAddress,
+    pub signature: Signature,
+}
+
+/// A difference between two attestations of the same package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationMismatch {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl BuildAttestation {
+    /// Attest to `package`, built from the sources at `package_path` for chain `chain_id`. The
+    /// original ID of an upgraded package is taken from its `Move.lock`, and is otherwise assumed
+    /// to be the ID it is published at.
+    pub fn new(
+        package: &CompiledPackage,
+        package_path: &Path,
+        chain_id: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let published_at = package.published_at.clone().ok();
+        let original_id = published_at
+            .map(|id| managed_original_id(package_path, chain_id.as_deref()).unwrap_or(id));
+        Self::new_published_at(package, package_path, chain_id, published_at, original_id)
+    }
+
+    /// Attest to `package` as if it were published at `published_at`, with original ID
+    /// `original_id`, e.g. to rebuild an attestation that was made before the package's
+    /// `published-at` address was recorded.
+    pub fn new_published_at(
+        package: &CompiledPackage,
+        package_path: &Path,
+        chain_id: Option<String>,
+        published_at: Option<ObjectID>,
+        original_id: Option<ObjectID>,
+    ) -> anyhow::Result<Self> {
+        let info = &package.package.compiled_package_info;
+        let manifest = parse_move_manifest_from_file(
+            &package_path.join(SourcePackageLayout::Manifest.path()),
+        )?;
+        let edition = manifest
+            .package
+            .edition
+            .or(info.build_flags.default_edition)
+            .unwrap_or_default();
+        let flavor = manifest
+            .package
+            .flavor
+            .or(info.build_flags.default_flavor)
+            .unwrap_or_default();
+        if info.source_digest.is_none() {
+            bail!(
+                "No source digest for package {}: attestations require a build from source",
+                info.package_name
+            );
+        }
+
+        let mut dependency_modules: BTreeMap<String, Vec<&CompiledModule>> = BTreeMap::new();
+        for (name, unit) in &package.package.deps_compiled_units {
+            dependency_modules
+                .entry(name.to_string())
+                .or_default()
+                .push(&unit.unit.module);
+        }
+        for (name, module) in &package.bytecode_deps {
+            dependency_modules
+                .entry(name.to_string())
+                .or_default()
+                .push(module);
+        }
+        let dependencies = dependency_modules
+            .into_iter()
+            .map(|(name, modules)| {
+                let bytes: Vec<_> = modules.into_iter().map(serialize_module).collect();
+                let dependency = DependencyAttestation {
+                    published_at: package
+                        .dependency_ids
+                        .published
+                        .get(&Symbol::from(name.as_str()))
+                        .copied(),
+                    digest: Hex::encode(MovePackage::compute_digest_for_modules_and_deps(
+                        &bytes,
+                        std::iter::empty(),
+                        /* hash_modules */ true,
+                    )),
+                };
+                (name, dependency)
+            })
+            .collect();
+
+        // Modules are digested as they are published, with the root address of a package built
+        // for publishing (0x0) set to the original ID of the package: modules keep the address
+        // the package was first published at across upgrades.
+        let modules: Vec<_> = package
+            .get_dependency_sorted_modules(/* with_unpublished_deps */ false)
+            .into_iter()
+            .map(|module| match original_id {
+                Some(id) => normalize_root_address(module, id.into()),
+                None => module,
+            })
+            .collect();
+        let module_bytes: Vec<_> = modules.iter().map(serialize_module).collect();
+        let package_digest = MovePackage::compute_digest_for_modules_and_deps(
+            &module_bytes,
+            package.dependency_ids.published.values(),
+            /* hash_modules */ true,
+        );
+        let modules = modules
+            .iter()
+            .zip(&module_bytes)
+            .map(|(module, bytes)| (module.name().to_string(), module_digest(bytes)))
+            .collect();
+
+        Ok(Self {
+            package_name: info.package_name.to_string(),
+            compiler_version: COMPILER_VERSION.to_string(),
+            edition: edition.to_string(),
+            flavor: flavor.to_string(),
+            source_digest: source_digest(package_path)?,
+            chain_id,
+            published_at,
+            original_id,
+            dependencies,
+            modules,
+            package_digest: Hex::encode(package_digest),
+        })
+    }
+
+    /// Sign the attestation with `key`.
+    pub fn sign(self, key: &SuiKeyPair) -> anyhow::Result<SignedBuildAttestation> {
+        let message = self.intent_message()?;
+        Ok(SignedBuildAttestation {
+            signer: SuiAddress::from(&key.public()),
+            signature: Signature::new_secure(&message, key),
+            attestation: self,
+        })
+    }
+
+    /// The differences between this attestation and `actual`, e.g. one produced by rebuilding the
+    /// package locally.
+    pub fn mismatches(&self, actual: &BuildAttestation) -> Vec<AttestationMismatch> {
+        let mut mismatches = vec![];
+        let mut check = |field: &str, expected: String, actual: String| {
+            if expected != actual {
+                mismatches.push(AttestationMismatch {
+                    field: field.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+        };
+
+        check(
+            "package name",
+            self.package_name.clone(),
+            actual.package_name.clone(),
+        );
+        check(
+            "compiler version",
+            self.compiler_version.clone(),
+            actual.compiler_version.clone(),
+        );
+        check("edition", self.edition.clone(), actual.edition.clone());
+        check("flavor", self.flavor.clone(), actual.flavor.clone());
+        check(
+            "source digest",
+            self.source_digest.clone(),
+            actual.source_digest.clone(),
+        );
+        check(
+            "published at",
+            format!("{:?}", self.published_at),
+            format!("{:?}", actual.published_at),
+        );
+        check(
+            "original id",
+            format!("{:?}", self.original_id),
+            format!("{:?}", actual.original_id),
+        );
+
+        let names = self.dependencies.keys().chain(actual.dependencies.keys());
+        for name in names.collect::<BTreeSet<_>>() {
+            let describe = |dep: Option<&DependencyAttestation>| match dep {
+                None => "<missing>".to_string(),
+                Some(dep) => match &dep.published_at {
+                    Some(id) => format!("{} at {id}", dep.digest),
+                    None => format!("{} (unpublished)", dep.digest),
+                },
+            };
+            check(
+                &format!("dependency {name}"),
+                describe(self.dependencies.get(name)),
+                describe(actual.dependencies.get(name)),
+            );
+        }
+
+        let names = self.modules.keys().chain(actual.modules.keys());
+        for name in names.collect::<BTreeSet<_>>() {
+            let describe = |digest: Option<&String>| {
+                digest.cloned().unwrap_or_else(|| "<missing>".to_string())
+            };
+            check(
+                &format!("module {name}"),
+                describe(self.modules.get(name)),
+                describe(actual.modules.get(name)),
+            );
+        }
+
+        check(
+            "package digest",
+            self.package_digest.clone(),
+            actual.package_digest.clone(),
+        );
+        mismatches
+    }
+
+    fn intent_message(&self) -> anyhow::Result<IntentMessage<PersonalMessage>> {
+        Ok(IntentMessage::new(
+            Intent::personal_message(),
+            PersonalMessage {
+                message: bcs::to_bytes(self)?,
+            },
+        ))
+    }
+}
+
+impl SignedBuildAttestation {
+    /// Read a signed attestation from the JSON file at `path`.
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read attestation {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid attestation in {}", path.display()))
+    }
+
+    /// Write the attestation to the JSON file at `path`.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write attestation to {}", path.display()))
+    }
+
+    /// Check that the attestation is signed by `signer`.
+    pub fn verify_signature(&self) -> anyhow::Result<()> {
+        let message = self.attestation.intent_message()?;
+        self.signature
+            .verify_secure(&message, self.signer, self.signature.scheme())
+            .map_err(|e| anyhow::anyhow!("Invalid attestation signature: {e}"))
+    }
+}
+
+impl fmt::Display for AttestationMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} differs: attested {}, found {}",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+/// The path of the attestation file for package `package_name` built at `package_path`.
+pub fn attestation_path(package_path: &Path, package_name: &str) -> PathBuf {
+    package_path
+        .join("build")
+        .join(package_name)
+        .join(ATTESTATION_FILE_NAME)
+}
+
+/// Digest of the sources of the package at `package_path`: its Move source files and its
+/// manifest, excluding the `published-at` field, which is updated after the package is built and
+/// published.
+pub fn source_digest(package_path: &Path) -> anyhow::Result<String> {
+    let manifest_path = package_path.join(SourcePackageLayout::Manifest.path());
+    let manifest = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let mut manifest: toml::Value = toml::from_str(&manifest)
+        .with_context(|| format!("Invalid manifest {}", manifest_path.display()))?;
+    if let Some(package) = manifest
+        .get_mut(PACKAGE_SECTION)
+        .and_then(|p| p.as_table_mut())
+    {
+        package.remove(PUBLISHED_AT_FIELD);
+    }
+
+    let mut hasher = DefaultHash::default();
+    hasher.update(toml::to_string(&manifest)?.as_bytes());
+    for layout in [SourcePackageLayout::Sources, SourcePackageLayout::Scripts] {
+        let dir = package_path.join(layout.path());
+        let mut files = vec![];
+        for entry in WalkDir::new(&dir).follow_links(true).sort_by_file_name() {
+            let entry = entry.with_context(|| format!("Failed to read {}", dir.display()))?;
+            if entry.file_type().is_file()
+                && entry
+                    .path()
+                    .extension()
+                    .is_some_and(|e| e == MOVE_EXTENSION)
+            {
+                files.push(entry.into_path());
+            }
+        }
+        for file in files {
+            let contents =
+                fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+            // paths relative to the package, so that the digest does not depend on where the
+            // package is built from
+            let name = file.strip_prefix(package_path).unwrap_or(&file);
+            hasher.update(name.to_string_lossy().as_bytes());
+            hasher.update(DefaultHash::digest(&contents).digest);
+        }
+    }
+    Ok(Hex::encode(hasher.finalize().digest))
+}
+
+/// The original ID of the package at `package_path` on chain `chain_id`, as recorded in its
+/// `Move.lock` by automated address management.
+fn managed_original_id(package_path: &Path, chain_id: Option<&str>) -> Option<ObjectID> {
+    let chain_id = chain_id?;
+    let mut lock = fs::File::open(package_path.join(SourcePackageLayout::Lock.path())).ok()?;
+    ManagedPackage::read(&mut lock)
+        .ok()?
+        .into_values()
+        .find(|env| env.chain_id == chain_id)
+        .and_then(|env| ObjectID::from_str(&env.original_published_id).ok())
+        .filter(|id| *id != ObjectID::ZERO)
+}
+
+/// `module` with its self address set to `root`, if it is unassigned (0x0), as it is in a package
+/// built for publishing.
+pub fn normalize_root_address(mut module: CompiledModule, root: AccountAddress) -> CompiledModule {
+    let address_idx = module.self_handle().address;
+    if let Some(addr) = module.address_identifiers.get_mut(address_idx.0 as usize) {
+        if *addr == AccountAddress::ZERO {
+            *addr = root;
+        }
+    }
+    module
+}
+
+/// Digest of a serialized module, as recorded in attestations.
+pub fn module_digest(bytes: &[u8]) -> String {
+    Hex::encode(DefaultHash::digest(bytes).digest)
+}
+
+fn serialize_module(module: &CompiledModule) -> Vec<u8> {
+    let mut bytes = vec![];
+    // safe because the package built successfully
+    module
+        .serialize_with_version(module.version, &mut bytes)
+        .unwrap();
+    bytes
+}
@@ -60,6 +60,8 @@ use sui_types::{
 };
 use sui_verifier::verifier as sui_bytecode_verifier;
 
+pub mod attestation;
+
 #[cfg(test)]
 #[path = "unit_tests/build_tests.rs"]
 mod build_tests;
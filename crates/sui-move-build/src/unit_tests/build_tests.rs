@@ -1,11 +1,11 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::path::Path;
+use std::{fs, path::Path};
 
 use move_compiler::editions::Edition;
 
-use crate::BuildConfig;
+use crate::{attestation::source_digest, BuildConfig};
 
 #[test]
 fn generate_struct_layouts() {
@@ -46,3 +46,37 @@ fn development_mode_not_allowed() {
         .to_string()
         .contains(&Edition::DEVELOPMENT.unknown_edition_error().to_string()));
 }
+
+#[test]
+fn source_digest_ignores_published_at() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = |published_at: &str| {
+        format!("[package]\nname = \"a\"\n{published_at}\n[addresses]\na = \"0x0\"\n")
+    };
+    fs::create_dir(dir.path().join("sources")).unwrap();
+    fs::write(dir.path().join("sources/a.move"), "module a::a {}").unwrap();
+
+    fs::write(dir.path().join("Move.toml"), manifest("")).unwrap();
+    let unpublished = source_digest(dir.path()).unwrap();
+    fs::write(
+        dir.path().join("Move.toml"),
+        manifest("published-at = \"0x1234\""),
+    )
+    .unwrap();
+    assert_eq!(source_digest(dir.path()).unwrap(), unpublished);
+
+    // but not other changes to the manifest or sources
+    fs::write(
+        dir.path().join("Move.toml"),
+        manifest("version = \"0.0.2\""),
+    )
+    .unwrap();
+    assert_ne!(source_digest(dir.path()).unwrap(), unpublished);
+    fs::write(dir.path().join("Move.toml"), manifest("")).unwrap();
+    fs::write(
+        dir.path().join("sources/a.move"),
+        "module a::a { fun f() {} }",
+    )
+    .unwrap();
+    assert_ne!(source_digest(dir.path()).unwrap(), unpublished);
+}
@@ -0,0 +1,628 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checking the dependencies of a package against a local database of advisories.
+//! The database is a directory of TOML or JSON files, each describing one advisory against the
+//! versions of a package, identified by its original package ID:
+//!
+//! ```toml
+//! id = "SUI-2024-0001"
+//! package = "0x..."
+//! versions = ">= 1, < 3"
+//! severity = "high"
+//! title = "Rounding error in withdraw"
+//! description = "..."
+//! patched = 3
+//! url = "https://..."
+//! ```
+//!
+//! The dependencies of a local package are read from its resolved dependency graph, with the
+//! version of each dependency taken from the automated address management in its `Move.lock`,
+//! for the chain of the active environment.
+//! When auditing a package on-chain (`--package-id`), its linkage table is used instead, which
+//! covers all of its transitive dependencies.
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use colored::Colorize;
+use move_cli::base::reroot_path;
+use move_core_types::account_address::AccountAddress;
+use move_package::{
+    lock_file::schema::ManagedPackage, source_package::layout::SourcePackageLayout, BuildConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use sui_types::base_types::ObjectID;
+
+const TOML_EXTENSION: &str = "toml";
+const JSON_EXTENSION: &str = "json";
+
+#[derive(Parser)]
+#[group(id = "sui-move-audit")]
+pub struct Audit {
+    /// Directory containing the advisory database: one TOML or JSON file per advisory.
+    #[clap(long = "advisory-db", value_name = "DIR")]
+    pub advisory_db: PathBuf,
+    /// Audit the package published at this object ID and all its transitive dependencies, as
+    /// recorded in its on-chain linkage table. The `--path` will be ignored if this field is used.
+    #[clap(long = "package-id", value_parser = ObjectID::from_hex_literal)]
+    pub package_id: Option<ObjectID>,
+    /// Advisories to ignore, by ID.
+    #[clap(long = "ignore", value_name = "ADVISORY_ID")]
+    pub ignore: Vec<String>,
+    /// Print the findings as JSON.
+    #[clap(long)]
+    pub json: bool,
+    /// The chain ID of the active environment, if resolved. The versions of local dependencies
+    /// are read from the entries of their `Move.lock` for this chain, or from all entries if it
+    /// is not known.
+    #[clap(skip)]
+    pub chain_id: Option<String>,
+}
+
+/// The packages to audit, when auditing a package on-chain.
+#[derive(Clone, Debug, Default)]
+pub struct AuditMetadata {
+    /// Packages by original package ID, with the package ID and version that is linked against.
+    pub packages: BTreeMap<ObjectID, LinkedPackage>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LinkedPackage {
+    /// A name for the package in the output, if known.
+    pub name: Option<String>,
+    pub package_id: Option<ObjectID>,
+    /// The version of the package, if known.
+    pub version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    /// The original ID of the affected package.
+    pub package: ObjectID,
+    /// The affected versions of the package.
+    pub versions: VersionRange,
+    #[serde(default)]
+    pub severity: Severity,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The first version of the package that is not affected, if any.
+    #[serde(default)]
+    pub patched: Option<u64>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+/// A set of package versions: a comma-separated list of comparisons that all hold
+/// (e.g. `>= 1, < 3`), or `*` for all versions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionRange(Vec<(Comparison, u64)>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub package: ObjectID,
+    #[serde(flatten)]
+    pub linked: LinkedPackage,
+    pub advisory: Advisory,
+}
+
+impl Audit {
+    pub fn execute(
+        self,
+        path: Option<&Path>,
+        build_config: BuildConfig,
+        metadata: Option<AuditMetadata>,
+    ) -> anyhow::Result<()> {
+        let advisories = read_advisory_db(&self.advisory_db)?;
+        let packages = match metadata {
+            Some(metadata) => metadata.packages,
+            None => {
+                let rerooted_path = reroot_path(path)?;
+                local_dependencies(&rerooted_path, build_config, self.chain_id.as_deref())?
+            }
+        };
+
+        let ignored: BTreeSet<_> = self.ignore.iter().collect();
+        let findings = audit(&packages, advisories, &ignored);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&findings)?);
+        } else {
+            println!(
+                "Checked {} package(s) against {}",
+                packages.len(),
+                self.advisory_db.display()
+            );
+            for finding in &findings {
+                println!("{finding}");
+            }
+        }
+
+        if !findings.is_empty() {
+            bail!(
+                "Found {} advisory(ies) affecting dependencies. Upgrade the affected packages, \
+                 or pass `--ignore <ADVISORY_ID>` to accept the risk",
+                findings.len()
+            );
+        }
+        if !self.json {
+            println!("{}", "No advisories found".green().bold());
+        }
+        Ok(())
+    }
+}
+
+/// The advisories in `advisories` that affect `packages`, excluding those in `ignored`, most
+/// severe first.
+pub fn audit(
+    packages: &BTreeMap<ObjectID, LinkedPackage>,
+    advisories: Vec<Advisory>,
+    ignored: &BTreeSet<&String>,
+) -> Vec<Finding> {
+    let mut findings: Vec<_> = advisories
+        .into_iter()
+        .filter(|advisory| !ignored.contains(&advisory.id))
+        .filter_map(|advisory| {
+            let linked = packages.get(&advisory.package)?;
+            // Be conservative when the version of a dependency is not known.
+            let affected = linked
+                .version
+                .is_none_or(|version| advisory.versions.contains(version));
+            affected.then(|| Finding {
+                package: advisory.package,
+                linked: linked.clone(),
+                advisory,
+            })
+        })
+        .collect();
+    findings.sort_by(|a, b| {
+        b.advisory
+            .severity
+            .cmp(&a.advisory.severity)
+            .then_with(|| a.advisory.id.cmp(&b.advisory.id))
+    });
+    findings
+}
+
+/// Read all advisories in the database at `dir`.
+pub fn read_advisory_db(dir: &Path) -> anyhow::Result<Vec<Advisory>> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read advisory database {}", dir.display()))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut advisories = vec![];
+    for path in paths {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(TOML_EXTENSION) | Some(JSON_EXTENSION) => fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+            _ => continue,
+        };
+        let advisory: Advisory = if path.extension().is_some_and(|ext| ext == TOML_EXTENSION) {
+            toml::from_str(&contents).map_err(anyhow::Error::from)
+        } else {
+            serde_json::from_str(&contents).map_err(anyhow::Error::from)
+        }
+        .with_context(|| format!("Invalid advisory in {}", path.display()))?;
+        advisories.push(advisory);
+    }
+    Ok(advisories)
+}
+
+// The dependencies of the package at `path`, by original ID. The original ID and version of a
+// dependency are read from the automated address management in its `Move.lock` for `chain_id`
+// (or any chain, if not known), falling back to the address it is published at in its manifest,
+// if any, with an unknown version.
+fn local_dependencies(
+    path: &Path,
+    build_config: BuildConfig,
+    chain_id: Option<&str>,
+) -> anyhow::Result<BTreeMap<ObjectID, LinkedPackage>> {
+    let resolved_graph =
+        build_config.resolution_graph_for_package(path, None, &mut std::io::stderr())?;
+    let root = resolved_graph.root_package();
+
+    let mut packages = BTreeMap::new();
+    for (name, package) in &resolved_graph.package_table {
+        if *name == root {
+            continue;
+        }
+        let lock_path = package.package_path.join(SourcePackageLayout::Lock.path());
+        let managed = managed_packages(name.as_str(), &lock_path, chain_id);
+        if !managed.is_empty() {
+            packages.extend(managed);
+            continue;
+        }
+
+        // Without automated address management, the package's own named address (by convention
+        // the snake case of its name) is the address it was originally published at.
+        let own_address = package
+            .resolved_table
+            .get(&name.as_str().to_lowercase().as_str().into());
+        if let Some(address) = own_address.filter(|a| **a != AccountAddress::ZERO) {
+            packages.insert(
+                ObjectID::from(*address),
+                LinkedPackage {
+                    name: Some(name.to_string()),
+                    package_id: None,
+                    version: None,
+                },
+            );
+        }
+    }
+    Ok(packages)
+}
+
+// The packages published from the package `name` with the `Move.lock` at `lock_path`, by original
+// ID, as recorded by automated address management for `chain_id` (or any chain, if not known).
+fn managed_packages(
+    name: &str,
+    lock_path: &Path,
+    chain_id: Option<&str>,
+) -> BTreeMap<ObjectID, LinkedPackage> {
+    let managed = fs::File::open(lock_path)
+        .ok()
+        .and_then(|mut lock| ManagedPackage::read(&mut lock).ok())
+        .unwrap_or_default();
+    managed
+        .values()
+        .filter(|env| chain_id.is_none_or(|chain_id| env.chain_id == chain_id))
+        .filter_map(|env| {
+            let original_id = ObjectID::from_str(&env.original_published_id).ok()?;
+            let linked = LinkedPackage {
+                name: Some(name.to_string()),
+                package_id: ObjectID::from_str(&env.latest_published_id).ok(),
+                version: env.version.parse().ok(),
+            };
+            Some((original_id, linked))
+        })
+        .collect()
+}
+
+impl VersionRange {
+    pub fn contains(&self, version: u64) -> bool {
+        self.0.iter().all(|(cmp, bound)| match cmp {
+            Comparison::Lt => version < *bound,
+            Comparison::Le => version <= *bound,
+            Comparison::Eq => version == *bound,
+            Comparison::Ge => version >= *bound,
+            Comparison::Gt => version > *bound,
+        })
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(VersionRange(vec![]));
+        }
+        s.split(',')
+            .map(|part| {
+                let part = part.trim();
+                let (cmp, bound) = [
+                    (">=", Comparison::Ge),
+                    ("<=", Comparison::Le),
+                    (">", Comparison::Gt),
+                    ("<", Comparison::Lt),
+                    ("=", Comparison::Eq),
+                ]
+                .into_iter()
+                .find_map(|(op, cmp)| part.strip_prefix(op).map(|rest| (cmp, rest)))
+                .unwrap_or((Comparison::Eq, part));
+                let bound = bound
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|e| format!("Invalid version range {s}: {e}"))?;
+                Ok((cmp, bound))
+            })
+            .collect::<Result<_, _>>()
+            .map(VersionRange)
+    }
+}
+
+impl TryFrom<String> for VersionRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<VersionRange> for String {
+    fn from(range: VersionRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "*");
+        }
+        let parts: Vec<_> = self
+            .0
+            .iter()
+            .map(|(cmp, bound)| {
+                let op = match cmp {
+                    Comparison::Lt => "<",
+                    Comparison::Le => "<=",
+                    Comparison::Eq => "=",
+                    Comparison::Ge => ">=",
+                    Comparison::Gt => ">",
+                };
+                format!("{op} {bound}")
+            })
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Severity::Low => "low".normal(),
+            Severity::Medium => "medium".yellow(),
+            Severity::High => "high".red(),
+            Severity::Critical => "critical".red().bold(),
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Finding {
+            package,
+            linked,
+            advisory,
+        } = self;
+        let name = linked.name.as_deref().unwrap_or("package");
+        writeln!(
+            f,
+            "\n[{}] {}: {}",
+            advisory.severity,
+            advisory.id.bold(),
+            advisory.title
+        )?;
+        write!(f, "  {name} (original ID {package}")?;
+        if let Some(id) = &linked.package_id {
+            write!(f, ", linked at {id}")?;
+        }
+        match linked.version {
+            Some(version) => writeln!(f, ", version {version})")?,
+            None => writeln!(f, ", version unknown)")?,
+        }
+        writeln!(f, "  Affected versions: {}", advisory.versions)?;
+        if let Some(description) = &advisory.description {
+            writeln!(f, "  {description}")?;
+        }
+        match advisory.patched {
+            Some(patched) => write!(
+                f,
+                "  Fix: depend on version {patched} or later of {name}, and upgrade your package \
+                 to link against it"
+            )?,
+            None => write!(
+                f,
+                "  Fix: no patched version is available, consider removing the dependency on \
+                 {name}"
+            )?,
+        }
+        if linked.version.is_none() {
+            write!(
+                f,
+                "\n  Note: the version of this dependency could not be determined, make sure it \
+                 is not affected"
+            )?;
+        }
+        if let Some(url) = &advisory.url {
+            write!(f, "\n  See {url}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisory(id: &str, package: ObjectID, versions: &str, severity: Severity) -> Advisory {
+        Advisory {
+            id: id.to_string(),
+            package,
+            versions: versions.parse().unwrap(),
+            severity,
+            title: format!("Advisory {id}"),
+            description: None,
+            patched: None,
+            url: None,
+        }
+    }
+
+    fn linked(version: Option<u64>) -> LinkedPackage {
+        LinkedPackage {
+            name: None,
+            package_id: None,
+            version,
+        }
+    }
+
+    #[test]
+    fn version_ranges() {
+        let range: VersionRange = ">= 2, < 5".parse().unwrap();
+        assert!(!range.contains(1));
+        assert!(range.contains(2));
+        assert!(range.contains(4));
+        assert!(!range.contains(5));
+        assert_eq!(range.to_string(), ">= 2, < 5");
+
+        let range: VersionRange = ">1,<=3".parse().unwrap();
+        assert!(!range.contains(1));
+        assert!(range.contains(3));
+        assert!(!range.contains(4));
+        assert_eq!(range.to_string(), "> 1, <= 3");
+
+        // a bare version is an exact match
+        let range: VersionRange = "3".parse().unwrap();
+        assert_eq!(range, "= 3".parse().unwrap());
+        assert!(range.contains(3));
+        assert!(!range.contains(2));
+
+        let range: VersionRange = " * ".parse().unwrap();
+        assert!(range.contains(0));
+        assert!(range.contains(u64::MAX));
+        assert_eq!(range.to_string(), "*");
+
+        assert!(VersionRange::from_str("").is_err());
+        assert!(VersionRange::from_str(">= one").is_err());
+        assert!(VersionRange::from_str(">= 1,").is_err());
+        assert!(VersionRange::from_str("=> 1").is_err());
+    }
+
+    #[test]
+    fn audit_findings() {
+        let affected = ObjectID::from_single_byte(1);
+        let patched = ObjectID::from_single_byte(2);
+        let unknown_version = ObjectID::from_single_byte(3);
+        let not_a_dependency = ObjectID::from_single_byte(4);
+        let packages = BTreeMap::from([
+            (affected, linked(Some(2))),
+            (patched, linked(Some(3))),
+            (unknown_version, linked(None)),
+        ]);
+        let advisories = vec![
+            advisory("A-1", affected, "< 3", Severity::Low),
+            advisory("A-2", affected, "*", Severity::Critical),
+            advisory("A-3", patched, "< 3", Severity::Critical),
+            advisory("A-4", unknown_version, "< 3", Severity::High),
+            advisory("A-5", not_a_dependency, "*", Severity::Critical),
+            advisory("A-6", affected, "*", Severity::High),
+            advisory("A-0", affected, "*", Severity::High),
+        ];
+
+        // most severe first, then by ID
+        let findings = audit(&packages, advisories.clone(), &BTreeSet::new());
+        let ids: Vec<_> = findings.iter().map(|f| f.advisory.id.as_str()).collect();
+        assert_eq!(ids, ["A-2", "A-0", "A-4", "A-6", "A-1"]);
+        assert_eq!(findings[0].package, affected);
+        assert_eq!(findings[0].linked.version, Some(2));
+
+        let ignored = "A-2".to_string();
+        let findings = audit(&packages, advisories, &BTreeSet::from([&ignored]));
+        let ids: Vec<_> = findings.iter().map(|f| f.advisory.id.as_str()).collect();
+        assert_eq!(ids, ["A-0", "A-4", "A-6", "A-1"]);
+    }
+
+    #[test]
+    fn managed_packages_for_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join("Move.lock");
+        fs::write(
+            &lock_path,
+            r#"
+[move]
+version = 3
+
+[env.mainnet]
+chain-id = "35834a8a"
+original-published-id = "0x1"
+latest-published-id = "0x2"
+published-version = "2"
+
+[env.testnet]
+chain-id = "4c78adac"
+original-published-id = "0x3"
+latest-published-id = "0x4"
+published-version = "5"
+"#,
+        )
+        .unwrap();
+
+        let mainnet = managed_packages("pkg", &lock_path, Some("35834a8a"));
+        assert_eq!(mainnet.len(), 1);
+        let linked = &mainnet[&ObjectID::from_single_byte(1)];
+        assert_eq!(linked.name.as_deref(), Some("pkg"));
+        assert_eq!(linked.package_id, Some(ObjectID::from_single_byte(2)));
+        assert_eq!(linked.version, Some(2));
+
+        let testnet = managed_packages("pkg", &lock_path, Some("4c78adac"));
+        assert_eq!(testnet.len(), 1);
+        assert_eq!(testnet[&ObjectID::from_single_byte(3)].version, Some(5));
+
+        assert!(managed_packages("pkg", &lock_path, Some("deadbeef")).is_empty());
+        // every chain, when the active chain is not known
+        let all = managed_packages("pkg", &lock_path, None);
+        assert_eq!(
+            all.keys().copied().collect::<Vec<_>>(),
+            [ObjectID::from_single_byte(1), ObjectID::from_single_byte(3)]
+        );
+        // no automated address management
+        assert!(managed_packages("pkg", &dir.path().join("missing.lock"), None).is_empty());
+    }
+
+    #[test]
+    fn advisory_db() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("a.toml"),
+            r#"
+id = "SUI-2024-0001"
+package = "0x1"
+versions = ">= 1, < 3"
+severity = "high"
+title = "Rounding error in withdraw"
+patched = 3
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("b.json"),
+            r#"{"id": "SUI-2024-0002", "package": "0x2", "versions": "*", "title": "Bug"}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.md"), "# Advisories").unwrap();
+
+        let advisories = read_advisory_db(dir.path()).unwrap();
+        assert_eq!(advisories.len(), 2);
+        assert_eq!(advisories[0].id, "SUI-2024-0001");
+        assert_eq!(advisories[0].severity, Severity::High);
+        assert_eq!(advisories[0].versions, ">= 1, < 3".parse().unwrap());
+        assert_eq!(advisories[0].patched, Some(3));
+        assert_eq!(advisories[1].package, ObjectID::from_single_byte(2));
+        assert_eq!(advisories[1].severity, Severity::Medium);
+
+        fs::write(dir.path().join("c.toml"), "id = \"SUI-2024-0003\"").unwrap();
+        assert!(read_advisory_db(dir.path()).is_err());
+    }
+}
@@ -2,12 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::manage_package::resolve_lock_file_path;
+use anyhow::Context;
 use clap::Parser;
 use move_cli::base;
 use move_package::BuildConfig as MoveBuildConfig;
-use std::{fs, path::Path};
-use sui_move_build::{implicit_deps, BuildConfig};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use sui_move_build::{
+    attestation::{attestation_path, BuildAttestation},
+    implicit_deps, BuildConfig,
+};
 use sui_package_management::system_package_versions::latest_system_packages;
+use sui_types::crypto::{EncodeDecodeBase64, SuiKeyPair};
 
 const LAYOUTS_DIR: &str = "layouts";
 const STRUCT_LAYOUTS_FILENAME: &str = "struct_layouts.yaml";
@@ -35,6 +43,21 @@ pub struct Build {
     /// and events.
     #[clap(long, global = true)]
     pub generate_struct_layouts: bool,
+    /// If true, write a build attestation to `build/<package>/attestation.json`, recording the
+    /// compiler version, edition, flavor, dependency digests, source digest and module digests of
+    /// the build, signed with the key in `--attest-key`. The attestation can be checked against
+    /// the sources and the on-chain package with `sui client verify-attestation`.
+    #[clap(
+        long,
+        global = true,
+        requires = "attest_key",
+        conflicts_with = "dump_bytecode_as_base64"
+    )]
+    pub attest: bool,
+    /// File containing the key to sign the build attestation with, either a Bech32 encoded
+    /// `suiprivkey` or a Base64 encoded `flag || privkey`.
+    #[clap(long, global = true, value_name = "KEY_FILE", requires = "attest")]
+    pub attest_key: Option<PathBuf>,
     /// The chain ID, if resolved. Required when the dump_bytecode_as_base64 is true,
     /// for automated address management, where package addresses are resolved for the
     /// respective chain in the Move.lock file.
@@ -55,6 +78,7 @@ impl Build {
             build_config,
             self.generate_struct_layouts,
             self.chain_id.clone(),
+            self.attest_key.as_deref(),
         )
     }
 
@@ -63,13 +87,14 @@ impl Build {
         mut config: MoveBuildConfig,
         generate_struct_layouts: bool,
         chain_id: Option<String>,
+        attest_key: Option<&Path>,
     ) -> anyhow::Result<()> {
         config.implicit_dependencies = implicit_deps(latest_system_packages());
         let pkg = BuildConfig {
             config,
             run_bytecode_verifier: true,
             print_diags_to_stderr: true,
-            chain_id,
+            chain_id: chain_id.clone(),
         }
         .build(rerooted_path)?;
 
@@ -85,6 +110,21 @@ impl Build {
             fs::write(layout_filename, layout_str)?
         }
 
+        if let Some(attest_key) = attest_key {
+            let key = read_signing_key(attest_key)?;
+            let attestation = BuildAttestation::new(&pkg, rerooted_path, chain_id)?.sign(&key)?;
+            let path = attestation_path(
+                rerooted_path,
+                pkg.package.compiled_package_info.package_name.as_str(),
+            );
+            attestation.write(&path)?;
+            println!(
+                "Build attestation signed by {} written to {}",
+                attestation.signer,
+                path.display()
+            );
+        }
+
         pkg.package
             .compiled_package_info
             .build_flags
@@ -93,3 +133,14 @@ impl Build {
         Ok(())
     }
 }
+
+/// Read a key to sign attestations with from `path`: a Bech32 encoded `suiprivkey`, or a Base64
+/// encoded `flag || privkey` as in the keystore.
+fn read_signing_key(path: &Path) -> anyhow::Result<SuiKeyPair> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read key file {}", path.display()))?;
+    let contents = contents.trim();
+    SuiKeyPair::decode(contents)
+        .or_else(|_| SuiKeyPair::decode_base64(contents))
+        .map_err(|_| anyhow::anyhow!("Invalid key in {}", path.display()))
+}
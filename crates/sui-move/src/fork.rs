@@ -0,0 +1,475 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Running unit tests against a fork of a network.
+//! A test declares the network and checkpoint it runs against with `test_scenario::begin_fork`.
+//! Objects that the test takes by id with `test_scenario` (e.g. `take_shared_by_id`) and that
+//! it did not create are then loaded from the state of that network at that checkpoint, along
+//! with their dynamic fields and the packages of modules that are not part of the package under
+//! test or its dependencies. Everything is fetched lazily, the first time it is used, either
+//! from a GraphQL RPC or from a local directory of objects.
+//! Objects fetched from an RPC are cached on disk, in the same format as a local directory, so
+//! that later runs (and offline runs, using the cache as the source) do not fetch them again.
+
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use fastcrypto::encoding::{Base64, Encoding};
+use move_package::compilation::package_layout::CompiledPackageLayout;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use sui_move_natives::test_scenario::{ForkProvider, ForkedState};
+use sui_types::{
+    base_types::ObjectID,
+    error::{SuiError, SuiResult},
+    object::Object,
+};
+
+const MAINNET_GRAPHQL_URL: &str = "https://public-rpc.sui-mainnet.mystenlabs.com/graphql";
+const TESTNET_GRAPHQL_URL: &str = "https://public-rpc.sui-testnet.mystenlabs.com/graphql";
+const FORK_CACHE_DIR: &str = "fork";
+const OBJECT_FILE_EXTENSION: &str = "bcs";
+
+const MULTI_GET_OBJECTS_QUERY: &str = r#"
+query ($keys: [ObjectKey!]!) {
+  multiGetObjects(keys: $keys) {
+    objectBcs
+  }
+}"#;
+
+#[derive(Parser, Clone, Debug, Default)]
+#[group(id = "sui-move-test-fork")]
+pub struct Fork {
+    /// Where to load the state of a network that tests fork with `test_scenario::begin_fork`
+    /// from, as `<CHAIN>=<SOURCE>`: either the URL of a GraphQL RPC, or a directory with the
+    /// objects of each checkpoint in a `<checkpoint>` subdirectory (e.g. the cache of a
+    /// previous run). `mainnet` and `testnet` are forked from their public RPCs by default.
+    #[clap(long = "fork-source", value_name = "CHAIN=SOURCE", value_parser = parse_fork_source)]
+    pub fork_sources: Vec<(String, String)>,
+    /// Directory to cache objects fetched from an RPC in, as `<chain>/<checkpoint>/<id>.bcs`.
+    /// Defaults to `build/fork` in the package directory.
+    #[clap(long = "fork-cache-dir")]
+    pub fork_cache_dir: Option<PathBuf>,
+}
+
+/// Fetches objects from the state of a network.
+pub trait ObjectFetcher: Send + Sync {
+    /// Return the latest version of object `id` at `checkpoint`, if it exists.
+    fn fetch_object(&self, checkpoint: u64, id: &ObjectID) -> anyhow::Result<Option<Object>>;
+}
+
+/// The networks that tests can fork, see the module documentation.
+pub struct ForkedNetworks {
+    sources: BTreeMap<String, ForkSource>,
+    cache_dir: PathBuf,
+    // states already forked, shared by all tests forking the same chain and checkpoint
+    forks: Mutex<BTreeMap<(String, u64), Arc<ForkedNetworkState>>>,
+}
+
+enum ForkSource {
+    Rpc(Arc<dyn ObjectFetcher>),
+    Local(PathBuf),
+}
+
+/// The state of a network at a given checkpoint that tests run against.
+pub struct ForkedNetworkState {
+    // directory of objects of the forked state, either the source of the fork or the cache of
+    // objects fetched by `fetcher`
+    dir: PathBuf,
+    checkpoint: u64,
+    fetcher: Option<Arc<dyn ObjectFetcher>>,
+    // objects already loaded, including those that do not exist in the forked state
+    loaded: Mutex<BTreeMap<ObjectID, Option<Object>>>,
+}
+
+// Fetches objects from a GraphQL RPC.
+struct GraphQlFetcher {
+    url: String,
+    client: reqwest::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Fork {
+    /// The networks that the tests of the package at `package_path` can fork.
+    pub fn forked_networks(&self, package_path: &Path) -> anyhow::Result<ForkedNetworks> {
+        let cache_dir = self.fork_cache_dir.clone().unwrap_or_else(|| {
+            package_path
+                .join(CompiledPackageLayout::Root.path())
+                .join(FORK_CACHE_DIR)
+        });
+        let mut networks = ForkedNetworks::new(cache_dir);
+        for (chain, source) in self.sources() {
+            networks = if Path::new(&source).is_dir() {
+                networks.with_local_source(&chain, source)
+            } else {
+                networks.with_fetcher(&chain, Arc::new(GraphQlFetcher::new(&source)?))
+            };
+        }
+        Ok(networks)
+    }
+
+    /// Identifies where the networks that tests fork are loaded from, so that test results
+    /// cached against one source of a network are not reused against another.
+    pub fn cache_context(&self) -> String {
+        let sources: Vec<_> = self
+            .sources()
+            .into_iter()
+            .map(|(chain, source)| format!("{chain}={source}"))
+            .collect();
+        format!("fork:{}", sources.join(","))
+    }
+
+    // Where each chain is forked from, by chain.
+    fn sources(&self) -> BTreeMap<String, String> {
+        let mut sources = BTreeMap::from([
+            ("mainnet".to_string(), MAINNET_GRAPHQL_URL.to_string()),
+            ("testnet".to_string(), TESTNET_GRAPHQL_URL.to_string()),
+        ]);
+        sources.extend(self.fork_sources.iter().cloned());
+        sources
+    }
+}
+
+impl ForkedNetworks {
+    /// No networks to fork, caching fetched objects in `cache_dir`.
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sources: BTreeMap::new(),
+            cache_dir: cache_dir.into(),
+            forks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Fork `chain` with objects fetched by `fetcher`.
+    pub fn with_fetcher(mut self, chain: &str, fetcher: Arc<dyn ObjectFetcher>) -> Self {
+        self.sources
+            .insert(chain.to_string(), ForkSource::Rpc(fetcher));
+        self
+    }
+
+    /// Fork `chain` with the objects in directory `dir`.
+    pub fn with_local_source(mut self, chain: &str, dir: impl Into<PathBuf>) -> Self {
+        self.sources
+            .insert(chain.to_string(), ForkSource::Local(dir.into()));
+        self
+    }
+}
+
+impl ForkProvider for ForkedNetworks {
+    fn fork(&self, chain: &str, checkpoint: u64) -> SuiResult<Arc<dyn ForkedState>> {
+        let mut forks = self.forks.lock().unwrap();
+        if let Some(state) = forks.get(&(chain.to_string(), checkpoint)) {
+            return Ok(state.clone());
+        }
+        let state = match self.sources.get(chain) {
+            Some(ForkSource::Rpc(fetcher)) => ForkedNetworkState::new(
+                self.cache_dir.join(chain).join(checkpoint.to_string()),
+                checkpoint,
+                Some(fetcher.clone()),
+            ),
+            Some(ForkSource::Local(dir)) => {
+                let dir = dir.join(checkpoint.to_string());
+                if !dir.is_dir() {
+                    return Err(SuiError::Storage(format!(
+                        "No objects for checkpoint {checkpoint} of {chain}: {} is not a directory",
+                        dir.display()
+                    )));
+                }
+                ForkedNetworkState::new(dir, checkpoint, None)
+            }
+            None => {
+                return Err(SuiError::Storage(format!(
+                    "Unknown chain {chain}: set where to fork it from with \
+                    --fork-source {chain}=<SOURCE>"
+                )))
+            }
+        };
+        let state = Arc::new(state);
+        forks.insert((chain.to_string(), checkpoint), state.clone());
+        Ok(state)
+    }
+}
+
+impl ForkedState for ForkedNetworkState {
+    fn get_object(&self, id: &ObjectID) -> SuiResult<Option<Object>> {
+        if let Some(object) = self.loaded.lock().unwrap().get(id) {
+            return Ok(object.clone());
+        }
+        let object = self
+            .load_object(id)
+            .map_err(|e| SuiError::Storage(format!("{e:#}")))?;
+        self.loaded.lock().unwrap().insert(*id, object.clone());
+        Ok(object)
+    }
+}
+
+impl ForkedNetworkState {
+    fn new(dir: PathBuf, checkpoint: u64, fetcher: Option<Arc<dyn ObjectFetcher>>) -> Self {
+        Self {
+            dir,
+            checkpoint,
+            fetcher,
+            loaded: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn load_object(&self, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = read_object_file(&self.dir, id)? {
+            return Ok(Some(object));
+        }
+        let Some(fetcher) = &self.fetcher else {
+            return Ok(None);
+        };
+        let object = fetcher.fetch_object(self.checkpoint, id)?;
+        if let Some(object) = &object {
+            write_object_file(&self.dir, object)?;
+        }
+        Ok(object)
+    }
+}
+
+impl GraphQlFetcher {
+    fn new(url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+            runtime: tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?,
+        })
+    }
+
+    async fn fetch(&self, checkpoint: u64, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        let url = &self.url;
+        let body = json!({
+            "query": MULTI_GET_OBJECTS_QUERY,
+            "variables": {
+                "keys": [{ "address": id.to_string(), "atCheckpoint": checkpoint }],
+            },
+        });
+        let response: serde_json::Value = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {url}"))?
+            .error_for_status()?
+            .json()
+            .await?;
+        let Some(objects) = response
+            .pointer("/data/multiGetObjects")
+            .and_then(|objects| objects.as_array())
+        else {
+            bail!(
+                "Missing data in object query response. Errors: {}",
+                response.get("errors").unwrap_or(&serde_json::Value::Null)
+            );
+        };
+        let Some(object_bcs) = objects
+            .first()
+            .and_then(|object| object.get("objectBcs"))
+            .and_then(|bcs| bcs.as_str())
+        else {
+            return Ok(None);
+        };
+        let bytes = Base64::decode(object_bcs)
+            .map_err(|e| anyhow!("Invalid object bcs for object {id}: {e}"))?;
+        Ok(Some(bcs::from_bytes(&bytes)?))
+    }
+}
+
+impl ObjectFetcher for GraphQlFetcher {
+    fn fetch_object(&self, checkpoint: u64, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        tracing::debug!(
+            "Fetching object {id} at checkpoint {checkpoint} from {}",
+            self.url
+        );
+        // tests may run within another runtime, so block on the request in a separate thread
+        std::thread::scope(|s| {
+            s.spawn(|| self.runtime.block_on(self.fetch(checkpoint, id)))
+                .join()
+        })
+        .map_err(|_| anyhow!("Failed to fetch object {id}"))?
+    }
+}
+
+fn parse_fork_source(s: &str) -> anyhow::Result<(String, String)> {
+    let Some((chain, source)) = s.split_once('=') else {
+        bail!("Invalid fork source {s}: expected <CHAIN>=<SOURCE>");
+    };
+    if chain.is_empty()
+        || !chain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("Invalid chain name {chain:?}: expected letters, digits, '-' or '_'");
+    }
+    if !Path::new(source).is_dir()
+        && !source.starts_with("http://")
+        && !source.starts_with("https://")
+    {
+        bail!("Invalid fork source {source}: expected an RPC URL or an existing directory");
+    }
+    Ok((chain.to_string(), source.to_string()))
+}
+
+fn object_file(dir: &Path, id: &ObjectID) -> PathBuf {
+    dir.join(id.to_string())
+        .with_extension(OBJECT_FILE_EXTENSION)
+}
+
+fn read_object_file(dir: &Path, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+    let path = object_file(dir, id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let object = bcs::from_bytes(&bytes)
+        .with_context(|| format!("Failed to deserialize object in {}", path.display()))?;
+    Ok(Some(object))
+}
+
+fn write_object_file(dir: &Path, object: &Object) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create fork cache directory {}", dir.display()))?;
+    let path = object_file(dir, &object.id());
+    std::fs::write(&path, bcs::to_bytes(object)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use sui_types::base_types::SuiAddress;
+
+    // Serves the objects it holds, counting the fetches.
+    struct MockFetcher {
+        objects: BTreeMap<ObjectID, Object>,
+        fetches: AtomicUsize,
+    }
+
+    impl MockFetcher {
+        fn new(objects: impl IntoIterator<Item = Object>) -> Arc<Self> {
+            Arc::new(Self {
+                objects: objects.into_iter().map(|o| (o.id(), o)).collect(),
+                fetches: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl ObjectFetcher for MockFetcher {
+        fn fetch_object(&self, checkpoint: u64, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+            assert_eq!(checkpoint, 7);
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(self.objects.get(id).cloned())
+        }
+    }
+
+    #[test]
+    fn fetched_objects_are_cached() {
+        let cache = tempfile::tempdir().unwrap();
+        let object = Object::with_id_owner_for_testing(ObjectID::random(), SuiAddress::ZERO);
+        let missing = ObjectID::random();
+        let fetcher = MockFetcher::new([object.clone()]);
+        let networks = ForkedNetworks::new(cache.path()).with_fetcher("mocknet", fetcher.clone());
+
+        let state = networks.fork("mocknet", 7).unwrap();
+        assert_eq!(
+            state.get_object(&object.id()).unwrap(),
+            Some(object.clone())
+        );
+        assert_eq!(state.get_object(&missing).unwrap(), None);
+        // loaded objects, including missing ones, are only fetched once
+        assert_eq!(
+            state.get_object(&object.id()).unwrap(),
+            Some(object.clone())
+        );
+        assert_eq!(state.get_object(&missing).unwrap(), None);
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 2);
+
+        // forks of the same chain and checkpoint share loaded objects
+        let state = networks.fork("mocknet", 7).unwrap();
+        assert_eq!(
+            state.get_object(&object.id()).unwrap(),
+            Some(object.clone())
+        );
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 2);
+
+        // fetched objects are cached on disk, and the cache can be used as a local source
+        let cached = cache.path().join("mocknet").join("7");
+        assert!(object_file(&cached, &object.id()).exists());
+        assert!(!object_file(&cached, &missing).exists());
+        let networks = ForkedNetworks::new(cache.path())
+            .with_local_source("mocknet", cache.path().join("mocknet"));
+        let state = networks.fork("mocknet", 7).unwrap();
+        assert_eq!(state.get_object(&object.id()).unwrap(), Some(object));
+        assert_eq!(state.get_object(&missing).unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_forks_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let networks = ForkedNetworks::new(dir.path()).with_local_source("localnet", dir.path());
+        assert!(networks.fork("devnet", 7).is_err());
+        // local sources must have the objects of the checkpoint
+        assert!(networks.fork("localnet", 7).is_err());
+        std::fs::create_dir(dir.path().join("7")).unwrap();
+        let state = networks.fork("localnet", 7).unwrap();
+        assert_eq!(state.get_object(&ObjectID::random()).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_fork_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().to_str().unwrap();
+        assert_eq!(
+            parse_fork_source("mainnet=https://rpc.example.com/graphql").unwrap(),
+            (
+                "mainnet".to_string(),
+                "https://rpc.example.com/graphql".to_string()
+            )
+        );
+        assert_eq!(
+            parse_fork_source(&format!("local-net={dir}")).unwrap(),
+            ("local-net".to_string(), dir.to_string())
+        );
+        assert!(parse_fork_source("mainnet").is_err());
+        assert!(parse_fork_source("=https://rpc.example.com").is_err());
+        assert!(parse_fork_source("main/net=https://rpc.example.com").is_err());
+        assert!(parse_fork_source("mainnet=not/a/directory").is_err());
+    }
+
+    #[test]
+    fn cache_context_covers_fork_sources() {
+        let defaults = Fork::default().cache_context();
+        assert!(defaults.contains(&format!("mainnet={MAINNET_GRAPHQL_URL}")));
+        assert!(defaults.contains(&format!("testnet={TESTNET_GRAPHQL_URL}")));
+
+        let fork = |sources: &[(&str, &str)]| Fork {
+            fork_sources: sources
+                .iter()
+                .map(|(chain, source)| (chain.to_string(), source.to_string()))
+                .collect(),
+            fork_cache_dir: None,
+        };
+        let overridden = fork(&[("mainnet", "https://rpc.example.com/graphql")]).cache_context();
+        assert_ne!(overridden, defaults);
+        assert!(!overridden.contains(MAINNET_GRAPHQL_URL));
+        assert_ne!(
+            fork(&[("localnet", "/tmp/fork")]).cache_context(),
+            fork(&[("localnet", "/tmp/other")]).cache_context()
+        );
+        // the cache directory does not change the state that is forked
+        let cached = Fork {
+            fork_cache_dir: Some(PathBuf::from("/tmp/cache")),
+            ..Fork::default()
+        };
+        assert_eq!(cached.cache_context(), defaults);
+    }
+}
@@ -0,0 +1,813 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Queries over the bytecode of a package and its dependencies: the call graph between
+//! functions, the functions that can mutate a struct or enum type, the types with `key` reachable
+//! from public and entry functions, and the places that emit an event type. The package is either
+//! built from source, or fetched on-chain along with the packages in its linkage table when
+//! `--package-id` is given.
+
+use anyhow::{anyhow, bail};
+use clap::{Parser, Subcommand};
+use move_binary_format::{
+    file_format::{
+        Bytecode, DatatypeHandleIndex, FieldHandleIndex, FunctionDefinition, SignatureToken,
+        StructFieldInformation, TypeParameterIndex, Visibility,
+    },
+    CompiledModule,
+};
+use move_cli::base::reroot_path;
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+};
+use move_package::BuildConfig as MoveBuildConfig;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::Path,
+};
+use sui_move_build::BuildConfig;
+use sui_types::{base_types::ObjectID, SUI_FRAMEWORK_ADDRESS};
+
+const EVENT_MODULE_NAME: &str = "event";
+const EMIT_FUNCTION_NAME: &str = "emit";
+const CALLS_MUTATOR: &str = "calls a mutator";
+
+#[derive(Parser)]
+#[group(id = "sui-move-inspect")]
+pub struct Inspect {
+    #[clap(subcommand)]
+    pub query: Query,
+    /// Inspect the package published at this object ID, along with the packages in its linkage
+    /// table. The `--path` will be ignored if this field is used.
+    #[clap(long = "package-id", global = true, value_parser = ObjectID::from_hex_literal)]
+    pub package_id: Option<ObjectID>,
+}
+
+#[derive(Subcommand)]
+pub enum Query {
+    /// The call graph between the functions of the package.
+    CallGraph {
+        /// Only include functions reachable from this function, given as `[<address>::]<module>::<function>`.
+        #[clap(long)]
+        from: Option<String>,
+        /// Print the call graph in GraphViz DOT format instead of JSON.
+        #[clap(long)]
+        graphviz: bool,
+    },
+    /// The functions that can mutate values of a struct or enum type: by taking a mutable
+    /// reference to it or to a value containing it, mutably borrowing one of its fields or a
+    /// field, vector element or generic value (e.g. a dynamic field) containing it, unpacking it,
+    /// or calling a function that does.
+    Mutators {
+        /// The struct or enum type, given as `[<address>::]<module>::<name>`.
+        #[clap(name = "type")]
+        type_: String,
+    },
+    /// The types with `key` that are reachable from the signatures of public and entry functions
+    /// of the package.
+    KeyTypes,
+    /// The places that emit an event type with `sui::event::emit`, directly or through generic
+    /// functions that emit one of their type parameters.
+    Emitters {
+        /// The event type, given as `[<address>::]<module>::<name>`.
+        event: String,
+    },
+}
+
+/// The modules to inspect, when inspecting a package on-chain.
+#[derive(Default)]
+pub struct InspectMetadata {
+    pub root_modules: Vec<CompiledModule>,
+    pub dependency_modules: Vec<CompiledModule>,
+}
+
+/// A reference to a module member (a function or a type) given on the command line.
+struct MemberPattern {
+    address: Option<AccountAddress>,
+    module: String,
+    name: String,
+}
+
+/// The modules of a package and its dependencies, indexed by module ID.
+struct Modules {
+    root: BTreeSet<ModuleId>,
+    modules: BTreeMap<ModuleId, CompiledModule>,
+}
+
+#[derive(Serialize)]
+struct CallGraph {
+    functions: BTreeSet<String>,
+    calls: BTreeSet<Call>,
+}
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct Call {
+    caller: String,
+    callee: String,
+}
+
+#[derive(Serialize)]
+struct Mutator {
+    function: String,
+    reasons: BTreeSet<&'static str>,
+}
+
+#[derive(Serialize)]
+struct KeyType {
+    #[serde(rename = "type")]
+    type_: String,
+    /// The public and entry functions whose signatures reach the type.
+    functions: BTreeSet<String>,
+}
+
+#[derive(Serialize)]
+struct EmitSite {
+    function: String,
+    /// Offset of the call that emits the event in the bytecode of the function.
+    code_offset: usize,
+    /// The function called to emit the event: `sui::event::emit`, or a generic function that
+    /// emits one of its type parameters.
+    emitter: String,
+}
+
+impl Inspect {
+    pub fn execute(
+        self,
+        path: Option<&Path>,
+        build_config: MoveBuildConfig,
+        metadata: Option<InspectMetadata>,
+    ) -> anyhow::Result<()> {
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None => {
+                let rerooted_path = reroot_path(path)?;
+                let package = BuildConfig {
+                    config: build_config,
+                    run_bytecode_verifier: false,
+                    print_diags_to_stderr: true,
+                    chain_id: None,
+                }
+                .build(&rerooted_path)?;
+                InspectMetadata {
+                    root_modules: package.get_modules().cloned().collect(),
+                    dependency_modules: package.get_dependent_modules().cloned().collect(),
+                }
+            }
+        };
+        let modules = Modules::new(metadata);
+
+        let output = match self.query {
+            Query::CallGraph { from, graphviz } => {
+                let from = from.as_deref().map(MemberPattern::parse).transpose()?;
+                let graph = modules.call_graph(from.as_ref())?;
+                if graphviz {
+                    println!("{}", graph.to_graphviz());
+                    return Ok(());
+                }
+                serde_json::to_string_pretty(&graph)?
+            }
+            Query::Mutators { type_ } => {
+                serde_json::to_string_pretty(&modules.mutators(&MemberPattern::parse(&type_)?))?
+            }
+            Query::KeyTypes => serde_json::to_string_pretty(&modules.key_types())?,
+            Query::Emitters { event } => {
+                serde_json::to_string_pretty(&modules.emitters(&MemberPattern::parse(&event)?))?
+            }
+        };
+        println!("{output}");
+        Ok(())
+    }
+}
+
+impl MemberPattern {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        let parts: Vec<_> = s.split("::").collect();
+        let (address, module, name) = match parts[..] {
+            [module, name] => (None, module, name),
+            [address, module, name] => {
+                let address = AccountAddress::from_hex_literal(address)
+                    .map_err(|_| anyhow!("Invalid address {address} in {s}"))?;
+                (Some(address), module, name)
+            }
+            _ => bail!("Invalid {s}: expected `[<address>::]<module>::<name>`"),
+        };
+        Ok(Self {
+            address,
+            module: module.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    fn matches(&self, module: &ModuleId, name: &str) -> bool {
+        self.address.is_none_or(|a| a == *module.address())
+            && self.module == module.name().as_str()
+            && self.name == name
+    }
+}
+
+impl Modules {
+    fn new(metadata: InspectMetadata) -> Self {
+        let root = metadata.root_modules.iter().map(|m| m.self_id()).collect();
+        let modules = metadata
+            .root_modules
+            .into_iter()
+            .chain(metadata.dependency_modules)
+            .map(|m| (m.self_id(), m))
+            .collect();
+        Self { root, modules }
+    }
+
+    fn root_modules(&self) -> impl Iterator<Item = &CompiledModule> {
+        self.root.iter().map(|id| &self.modules[id])
+    }
+
+    fn call_graph(&self, from: Option<&MemberPattern>) -> anyhow::Result<CallGraph> {
+        let calls = self.calls();
+
+        // Without a starting point, the graph covers the functions of the root package and the
+        // functions they call directly.
+        let roots: Vec<String> = match from {
+            Some(from) => {
+                let roots: Vec<_> = self
+                    .modules
+                    .values()
+                    .flat_map(|module| {
+                        module
+                            .function_defs()
+                            .iter()
+                            .filter(|def| {
+                                from.matches(&module.self_id(), function_ident(module, def))
+                            })
+                            .map(|def| function_name(module, def))
+                    })
+                    .collect();
+                if roots.is_empty() {
+                    bail!("Function {}::{} not found", from.module, from.name);
+                }
+                roots
+            }
+            None => self
+                .root_modules()
+                .flat_map(|module| {
+                    module
+                        .function_defs()
+                        .iter()
+                        .map(|def| function_name(module, def))
+                })
+                .collect(),
+        };
+
+        let mut functions = BTreeSet::new();
+        let mut edges = BTreeSet::new();
+        let mut stack = roots;
+        while let Some(caller) = stack.pop() {
+            if !functions.insert(caller.clone()) {
+                continue;
+            }
+            for callee in calls.get(&caller).into_iter().flatten() {
+                edges.insert(Call {
+                    caller: caller.clone(),
+                    callee: callee.clone(),
+                });
+                if from.is_some() {
+                    stack.push(callee.clone());
+                } else {
+                    functions.insert(callee.clone());
+                }
+            }
+        }
+
+        Ok(CallGraph {
+            functions,
+            calls: edges,
+        })
+    }
+
+    fn mutators(&self, type_: &MemberPattern) -> Vec<Mutator> {
+        let mut mutators: BTreeMap<String, BTreeSet<&'static str>> = BTreeMap::new();
+        for module in self.modules.values() {
+            let is_type = |idx| {
+                let (module_id, name) = datatype_name(module, idx);
+                type_.matches(&module_id, &name)
+            };
+            let struct_def_is_type = |idx| is_type(module.struct_def_at(idx).struct_handle);
+            let enum_def_is_type = |idx| is_type(module.enum_def_at(idx).enum_handle);
+            let contains_type = |token: &SignatureToken| self.contains_type(module, token, type_);
+            let any_contains_type =
+                |idx| module.signature_at(idx).0.iter().any(|t| contains_type(t));
+            // Borrowing a field mutably can mutate the struct, or a value contained in the field.
+            let field_reason = |idx: FieldHandleIndex| {
+                let handle = module.field_handle_at(idx);
+                let def = module.struct_def_at(handle.owner);
+                if is_type(def.struct_handle) {
+                    Some("mutably borrows a field")
+                } else {
+                    def.field(handle.field as usize)
+                        .is_some_and(|field| contains_type(&field.signature.0))
+                        .then_some("mutably borrows a field containing it")
+                }
+            };
+
+            for def in module.function_defs() {
+                let mut reasons = BTreeSet::new();
+                let handle = module.function_handle_at(def.function);
+                for param in &module.signature_at(handle.parameters).0 {
+                    let SignatureToken::MutableReference(inner) = param else {
+                        continue;
+                    };
+                    if datatype_of(inner).is_some_and(is_type) {
+                        reasons.insert("takes a mutable reference");
+                    } else if contains_type(inner) {
+                        reasons.insert("takes a mutable reference to a value containing it");
+                    }
+                }
+                for instr in code(def) {
+                    let reason = match instr {
+                        Bytecode::MutBorrowField(idx) => field_reason(*idx),
+                        Bytecode::MutBorrowFieldGeneric(idx) => {
+                            let inst = module.field_instantiation_at(*idx);
+                            field_reason(inst.handle).or_else(|| {
+                                any_contains_type(inst.type_parameters)
+                                    .then_some("mutably borrows a field containing it")
+                            })
+                        }
+                        Bytecode::VecMutBorrow(idx) => any_contains_type(*idx)
+                            .then_some("mutably borrows a vector element containing it"),
+                        Bytecode::Unpack(idx) => struct_def_is_type(*idx).then_some("unpacks"),
+                        Bytecode::UnpackGeneric(idx) => {
+                            struct_def_is_type(module.struct_instantiation_at(*idx).def)
+                                .then_some("unpacks")
+                        }
+                        Bytecode::UnpackVariant(idx) => {
+                            enum_def_is_type(module.variant_handle_at(*idx).enum_def)
+                                .then_some("unpacks")
+                        }
+                        Bytecode::UnpackVariantGeneric(idx) => {
+                            let handle = module.variant_instantiation_handle_at(*idx);
+                            enum_def_is_type(module.enum_instantiation_at(handle.enum_def).def)
+                                .then_some("unpacks")
+                        }
+                        Bytecode::UnpackVariantMutRef(idx) => {
+                            enum_def_is_type(module.variant_handle_at(*idx).enum_def)
+                                .then_some("mutably borrows a field")
+                        }
+                        Bytecode::UnpackVariantGenericMutRef(idx) => {
+                            let handle = module.variant_instantiation_handle_at(*idx);
+                            enum_def_is_type(module.enum_instantiation_at(handle.enum_def).def)
+                                .then_some("mutably borrows a field")
+                        }
+                        // Generic functions that take or return a mutable reference to one of
+                        // their type parameters (e.g. `dynamic_field::borrow_mut`) can mutate the
+                        // type they are instantiated with.
+                        Bytecode::CallGeneric(idx) => {
+                            let inst = module.function_instantiation_at(*idx);
+                            let handle = module.function_handle_at(inst.handle);
+                            let type_args = &module.signature_at(inst.type_parameters).0;
+                            let mut signature = module
+                                .signature_at(handle.parameters)
+                                .0
+                                .iter()
+                                .chain(&module.signature_at(handle.return_).0);
+                            signature
+                                .any(|token| match token {
+                                    SignatureToken::MutableReference(inner) => match &**inner {
+                                        SignatureToken::TypeParameter(i) => type_args
+                                            .get(*i as usize)
+                                            .is_some_and(|t| contains_type(t)),
+                                        _ => false,
+                                    },
+                                    _ => false,
+                                })
+                                .then_some("mutably borrows it through a generic function")
+                        }
+                        _ => None,
+                    };
+                    reasons.extend(reason);
+                }
+                if !reasons.is_empty() {
+                    mutators.insert(function_name(module, def), reasons);
+                }
+            }
+        }
+
+        // Functions that call a mutator can mutate the type too, transitively.
+        let calls = self.calls();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (caller, callees) in &calls {
+                if mutators
+                    .get(caller)
+                    .is_some_and(|reasons| reasons.contains(CALLS_MUTATOR))
+                {
+                    continue;
+                }
+                if callees
+                    .iter()
+                    .any(|callee| callee != caller && mutators.contains_key(callee))
+                {
+                    mutators
+                        .entry(caller.clone())
+                        .or_default()
+                        .insert(CALLS_MUTATOR);
+                    changed = true;
+                }
+            }
+        }
+
+        mutators
+            .into_iter()
+            .map(|(function, reasons)| Mutator { function, reasons })
+            .collect()
+    }
+
+    fn key_types(&self) -> Vec<KeyType> {
+        let mut key_types: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for module in self.root_modules() {
+            for def in module.function_defs() {
+                if def.visibility != Visibility::Public && !def.is_entry {
+                    continue;
+                }
+                let handle = module.function_handle_at(def.function);
+                let signature = module
+                    .signature_at(handle.parameters)
+                    .0
+                    .iter()
+                    .chain(&module.signature_at(handle.return_).0);
+
+                let mut visited = BTreeSet::new();
+                for token in signature {
+                    self.reachable_datatypes(module, token, &mut visited);
+                }
+                let function = function_name(module, def);
+                for (module_id, name) in visited {
+                    if self.has_key(&module_id, &name) {
+                        key_types
+                            .entry(qualified_name(&module_id, &name))
+                            .or_default()
+                            .insert(function.clone());
+                    }
+                }
+            }
+        }
+        key_types
+            .into_iter()
+            .map(|(type_, functions)| KeyType { type_, functions })
+            .collect()
+    }
+
+    fn emitters(&self, event: &MemberPattern) -> Vec<EmitSite> {
+        // The type parameters of each function whose instantiation is emitted as an event:
+        // `sui::event::emit` itself, and generic functions that emit one of their type
+        // parameters, transitively.
+        let emit = qualified_name(
+            &ModuleId::new(
+                SUI_FRAMEWORK_ADDRESS,
+                Identifier::new(EVENT_MODULE_NAME).unwrap(),
+            ),
+            EMIT_FUNCTION_NAME,
+        );
+        let mut emitted: BTreeMap<String, BTreeSet<TypeParameterIndex>> =
+            BTreeMap::from([(emit, BTreeSet::from([0]))]);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for module in self.modules.values() {
+                for def in module.function_defs() {
+                    let caller = function_name(module, def);
+                    for instr in code(def) {
+                        for (_, type_arg) in emitted_type_args(module, instr, &emitted) {
+                            if let SignatureToken::TypeParameter(i) = type_arg {
+                                changed |= emitted.entry(caller.clone()).or_default().insert(*i);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut sites = vec![];
+        for module in self.modules.values() {
+            for def in module.function_defs() {
+                for (code_offset, instr) in code(def).iter().enumerate() {
+                    for (emitter, type_arg) in emitted_type_args(module, instr, &emitted) {
+                        let emits_event = datatype_of(type_arg).is_some_and(|idx| {
+                            let (module_id, name) = datatype_name(module, idx);
+                            event.matches(&module_id, &name)
+                        });
+                        if emits_event {
+                            sites.push(EmitSite {
+                                function: function_name(module, def),
+                                code_offset,
+                                emitter,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        sites
+    }
+
+    // The functions of all modules, by name, and the functions they call.
+    fn calls(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut calls: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for module in self.modules.values() {
+            for def in module.function_defs() {
+                let callees = calls.entry(function_name(module, def)).or_default();
+                for instr in code(def) {
+                    if let Some((module_id, name)) = callee(module, instr) {
+                        callees.insert(qualified_name(&module_id, &name));
+                    }
+                }
+            }
+        }
+        calls
+    }
+
+    // Whether `token` in `module` is or contains (through type arguments and fields) `type_`.
+    fn contains_type(
+        &self,
+        module: &CompiledModule,
+        token: &SignatureToken,
+        type_: &MemberPattern,
+    ) -> bool {
+        let mut visited = BTreeSet::new();
+        self.reachable_datatypes(module, token, &mut visited);
+        visited
+            .iter()
+            .any(|(module_id, name)| type_.matches(module_id, name))
+    }
+
+    // Add the datatypes reachable from `token` in `module` to `visited`, through type arguments
+    // and the fields of the datatypes.
+    fn reachable_datatypes(
+        &self,
+        module: &CompiledModule,
+        token: &SignatureToken,
+        visited: &mut BTreeSet<(ModuleId, String)>,
+    ) {
+        for token in token.preorder_traversal() {
+            let Some(idx) = datatype_of(token) else {
+                continue;
+            };
+            let (module_id, name) = datatype_name(module, idx);
+            if !visited.insert((module_id.clone(), name.clone())) {
+                continue;
+            }
+
+            // Types are reachable through the fields of the types that contain them.
+            let Some(defining) = self.modules.get(&module_id) else {
+                continue;
+            };
+            let Some((_, def)) = defining.find_struct_def_by_name(&name) else {
+                if let Some((_, def)) = defining.find_enum_def_by_name(&name) {
+                    for variant in &def.variants {
+                        for field in &variant.fields {
+                            self.reachable_datatypes(defining, &field.signature.0, visited);
+                        }
+                    }
+                }
+                continue;
+            };
+            if let StructFieldInformation::Declared(fields) = &def.field_information {
+                for field in fields {
+                    self.reachable_datatypes(defining, &field.signature.0, visited);
+                }
+            }
+        }
+    }
+
+    fn has_key(&self, module_id: &ModuleId, name: &str) -> bool {
+        let Some(module) = self.modules.get(module_id) else {
+            return false;
+        };
+        module
+            .find_struct_def_by_name(name)
+            .is_some_and(|(_, def)| {
+                module
+                    .datatype_handle_at(def.struct_handle)
+                    .abilities
+                    .has_key()
+            })
+    }
+}
+
+impl CallGraph {
+    fn to_graphviz(&self) -> String {
+        let mut dot = String::from("digraph call_graph {\n");
+        for function in &self.functions {
+            writeln!(dot, "    \"{function}\";").unwrap();
+        }
+        for Call { caller, callee } in &self.calls {
+            writeln!(dot, "    \"{caller}\" -> \"{callee}\";").unwrap();
+        }
+        dot.push('}');
+        dot
+    }
+}
+
+fn code(def: &FunctionDefinition) -> &[Bytecode] {
+    def.code.as_ref().map_or(&[], |code| &code.code)
+}
+
+fn callee(module: &CompiledModule, instr: &Bytecode) -> Option<(ModuleId, String)> {
+    let handle = match instr {
+        Bytecode::Call(idx) => module.function_handle_at(*idx),
+        Bytecode::CallGeneric(idx) => {
+            module.function_handle_at(module.function_instantiation_at(*idx).handle)
+        }
+        _ => return None,
+    };
+    Some((
+        module.module_id_for_handle(module.module_handle_at(handle.module)),
+        module.identifier_at(handle.name).to_string(),
+    ))
+}
+
+// The type arguments of the call at `instr`, if any, that are emitted as events by the callee,
+// along with the callee, given the type parameters that each function emits in `emitted`.
+fn emitted_type_args<'a>(
+    module: &'a CompiledModule,
+    instr: &Bytecode,
+    emitted: &BTreeMap<String, BTreeSet<TypeParameterIndex>>,
+) -> Vec<(String, &'a SignatureToken)> {
+    let Bytecode::CallGeneric(idx) = instr else {
+        return vec![];
+    };
+    let inst = module.function_instantiation_at(*idx);
+    let handle = module.function_handle_at(inst.handle);
+    let callee = qualified_name(
+        &module.module_id_for_handle(module.module_handle_at(handle.module)),
+        module.identifier_at(handle.name).as_str(),
+    );
+    let Some(params) = emitted.get(&callee) else {
+        return vec![];
+    };
+    let type_args = &module.signature_at(inst.type_parameters).0;
+    params
+        .iter()
+        .filter_map(|i| type_args.get(*i as usize))
+        .map(|type_arg| (callee.clone(), type_arg))
+        .collect()
+}
+
+fn datatype_of(token: &SignatureToken) -> Option<DatatypeHandleIndex> {
+    match token {
+        SignatureToken::Datatype(idx) => Some(*idx),
+        SignatureToken::DatatypeInstantiation(inst) => Some(inst.0),
+        _ => None,
+    }
+}
+
+fn datatype_name(module: &CompiledModule, idx: DatatypeHandleIndex) -> (ModuleId, String) {
+    let handle = module.datatype_handle_at(idx);
+    (
+        module.module_id_for_handle(module.module_handle_at(handle.module)),
+        module.identifier_at(handle.name).to_string(),
+    )
+}
+
+fn function_ident<'a>(module: &'a CompiledModule, def: &FunctionDefinition) -> &'a str {
+    module
+        .identifier_at(module.function_handle_at(def.function).name)
+        .as_str()
+}
+
+fn function_name(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    qualified_name(&module.self_id(), function_ident(module, def))
+}
+
+fn qualified_name(module: &ModuleId, name: &str) -> String {
+    format!(
+        "0x{}::{}::{}",
+        module.address().short_str_lossless(),
+        module.name(),
+        name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use once_cell::sync::Lazy;
+
+    static FIXTURE: Lazy<Modules> = Lazy::new(|| {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/inspect");
+        let package = BuildConfig::new_for_testing().build(&path).unwrap();
+        Modules::new(InspectMetadata {
+            root_modules: package.get_modules().cloned().collect(),
+            dependency_modules: package.get_dependent_modules().cloned().collect(),
+        })
+    });
+
+    fn mutators(type_: &str) -> BTreeMap<String, BTreeSet<&'static str>> {
+        FIXTURE
+            .mutators(&MemberPattern::parse(type_).unwrap())
+            .into_iter()
+            .map(|m| (m.function, m.reasons))
+            .collect()
+    }
+
+    fn reasons<'a>(
+        mutators: &'a BTreeMap<String, BTreeSet<&'static str>>,
+        function: &str,
+    ) -> Vec<&'a str> {
+        mutators
+            .get(&format!("0x0::counter::{function}"))
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn struct_mutators() {
+        let mutators = mutators("0x0::counter::Counter");
+        assert_eq!(
+            reasons(&mutators, "increment"),
+            ["mutably borrows a field", "takes a mutable reference"]
+        );
+        assert_eq!(
+            reasons(&mutators, "reset"),
+            ["mutably borrows a field", "takes a mutable reference"]
+        );
+        assert_eq!(reasons(&mutators, "destroy"), ["unpacks"]);
+        assert!(reasons(&mutators, "wrapped")
+            .contains(&"takes a mutable reference to a value containing it"));
+        let increment_first = reasons(&mutators, "increment_first");
+        assert!(increment_first.contains(&"takes a mutable reference to a value containing it"));
+        assert!(increment_first.contains(&"mutably borrows a vector element containing it"));
+        assert_eq!(
+            reasons(&mutators, "increment_dynamic"),
+            [
+                "calls a mutator",
+                "mutably borrows it through a generic function"
+            ]
+        );
+        assert_eq!(reasons(&mutators, "increment_owned"), ["calls a mutator"]);
+
+        for function in ["new", "value", "notify", "advance", "steps", "is_running"] {
+            assert!(
+                reasons(&mutators, function).is_empty(),
+                "{function} does not mutate counters"
+            );
+        }
+    }
+
+    #[test]
+    fn enum_mutators() {
+        let mutators = mutators("counter::State");
+        let advance = reasons(&mutators, "advance");
+        assert!(advance.contains(&"takes a mutable reference"));
+        assert!(advance.contains(&"mutably borrows a field"));
+        assert_eq!(reasons(&mutators, "steps"), ["unpacks"]);
+        assert!(reasons(&mutators, "is_running").is_empty());
+        assert!(reasons(&mutators, "increment").is_empty());
+    }
+
+    #[test]
+    fn emitters() {
+        let emit_sites = |event: &str| {
+            let sites = FIXTURE.emitters(&MemberPattern::parse(event).unwrap());
+            for site in &sites {
+                // every site is a call
+                let (module, function) = site.function.rsplit_once("::").unwrap();
+                let module = &FIXTURE.modules[&ModuleId::new(
+                    AccountAddress::ZERO,
+                    Identifier::new(module.rsplit_once("::").unwrap().1).unwrap(),
+                )];
+                let (_, def) = module
+                    .find_function_def_by_name(function)
+                    .expect("emitting function");
+                assert!(matches!(
+                    code(def)[site.code_offset],
+                    Bytecode::CallGeneric(_)
+                ));
+            }
+            sites
+                .into_iter()
+                .map(|site| (site.function, site.emitter))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            emit_sites("counter::Reset"),
+            [(
+                "0x0::counter::reset".to_string(),
+                "0x2::event::emit".to_string()
+            )]
+        );
+        // through a generic function that emits its type parameter
+        assert_eq!(
+            emit_sites("0x0::counter::Incremented"),
+            [(
+                "0x0::counter::increment".to_string(),
+                "0x0::counter::notify".to_string()
+            )]
+        );
+        assert!(emit_sites("counter::Counter").is_empty());
+    }
+}
@@ -8,9 +8,12 @@ use std::path::Path;
 use sui_move_build::{implicit_deps, set_sui_flavor, SuiPackageHooks};
 use sui_package_management::system_package_versions::latest_system_packages;
 
+pub mod audit;
 pub mod build;
 pub mod coverage;
 pub mod disassemble;
+pub mod fork;
+pub mod inspect;
 pub mod manage_package;
 pub mod migrate;
 pub mod new;
@@ -19,9 +22,11 @@ pub mod unit_test;
 
 #[derive(Parser)]
 pub enum Command {
+    Audit(audit::Audit),
     Build(build::Build),
     Coverage(coverage::Coverage),
     Disassemble(disassemble::Disassemble),
+    Inspect(inspect::Inspect),
     ManagePackage(manage_package::ManagePackage),
     Migrate(migrate::Migrate),
     New(new::New),
@@ -32,6 +37,8 @@ pub enum Command {
 // Additional per-command metadata that can be passed from other commands (e.g., the Sui CLI) that
 // don't appear in the CLI args.
 pub enum CommandMeta {
+    Audit(audit::AuditMetadata),
+    Inspect(inspect::InspectMetadata),
     Summary(summary::PackageSummaryMetadata),
 }
 
@@ -49,17 +56,31 @@ pub fn execute_move_command(
 
     move_package::package_hooks::register_package_hooks(Box::new(SuiPackageHooks));
     match command {
+        Command::Audit(c) => {
+            let metadata = command_meta.and_then(|meta| match meta {
+                CommandMeta::Audit(metadata) => Some(metadata),
+                _ => None,
+            });
+            c.execute(package_path, build_config, metadata)
+        }
         Command::Build(c) => c.execute(package_path, build_config),
         Command::Coverage(c) => c.execute(package_path, build_config),
         Command::Disassemble(c) => c.execute(package_path, build_config),
+        Command::Inspect(c) => {
+            let metadata = command_meta.and_then(|meta| match meta {
+                CommandMeta::Inspect(metadata) => Some(metadata),
+                _ => None,
+            });
+            c.execute(package_path, build_config, metadata)
+        }
         Command::ManagePackage(c) => c.execute(package_path, build_config),
         Command::Migrate(c) => c.execute(package_path, build_config),
         Command::New(c) => c.execute(package_path),
         Command::Summary(s) => {
             let additional_metadata = command_meta
-                .map(|meta| {
-                    let CommandMeta::Summary(metadata) = meta;
-                    metadata
+                .and_then(|meta| match meta {
+                    CommandMeta::Summary(metadata) => Some(metadata),
+                    _ => None,
                 })
                 .unwrap_or_default();
             s.execute(package_path, build_config, additional_metadata)
@@ -1,20 +1,26 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::fork::Fork;
 use clap::Parser;
 use move_cli::base::{
     self,
     test::{self, UnitTestResult},
 };
 use move_package::BuildConfig;
-use move_unit_test::{extensions::set_extension_hook, UnitTestingConfig};
+use move_unit_test::{
+    extensions::{set_extension_hook, set_module_fetch_hook},
+    UnitTestingConfig,
+};
 use move_vm_runtime::native_extensions::NativeContextExtensions;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use std::{cell::RefCell, collections::BTreeMap, path::Path, rc::Rc, sync::Arc};
 use sui_move_build::{decorate_warnings, implicit_deps};
 use sui_move_natives::{
-    object_runtime::ObjectRuntime, test_scenario::InMemoryTestStore,
-    transaction_context::TransactionContext, NativesCostTable,
+    object_runtime::ObjectRuntime,
+    test_scenario::{ForkProvider, InMemoryTestStore},
+    transaction_context::TransactionContext,
+    NativesCostTable,
 };
 use sui_package_management::system_package_versions::latest_system_packages;
 use sui_protocol_config::ProtocolConfig;
@@ -34,6 +40,8 @@ const MAX_UNIT_TEST_INSTRUCTIONS: u64 = 1_000_000;
 pub struct Test {
     #[clap(flatten)]
     pub test: test::Test,
+    #[clap(flatten)]
+    pub fork: Fork,
 }
 
 impl Test {
@@ -53,7 +61,11 @@ impl Test {
         let save_disassembly = self.test.trace_execution;
         // find manifest file directory from a given path or (if missing) from current dir
         let rerooted_path = base::reroot_path(path)?;
-        let unit_test_config = self.test.unit_test_config();
+        set_fork_provider(Box::new(self.fork.forked_networks(&rerooted_path)?))?;
+        let unit_test_config = UnitTestingConfig {
+            cache_context: Some(self.fork.cache_context()),
+            ..self.test.unit_test_config()
+        };
         run_move_unit_tests(
             &rerooted_path,
             build_config,
@@ -69,10 +81,34 @@ thread_local! {
     static TEST_STORE_INNER: RefCell<InMemoryStorage> = RefCell::new(InMemoryStorage::default());
 }
 
-static TEST_STORE: Lazy<InMemoryTestStore> = Lazy::new(|| InMemoryTestStore(&TEST_STORE_INNER));
+// The networks that tests can fork with `test_scenario::begin_fork`, shared by all threads.
+static FORK_PROVIDER: OnceCell<Box<dyn ForkProvider>> = OnceCell::new();
+
+static TEST_STORE: Lazy<InMemoryTestStore> = Lazy::new(|| {
+    InMemoryTestStore(
+        &TEST_STORE_INNER,
+        FORK_PROVIDER.get().map(|provider| provider.as_ref()),
+    )
+});
 
-static SET_EXTENSION_HOOK: Lazy<()> =
-    Lazy::new(|| set_extension_hook(Box::new(new_testing_object_and_natives_cost_runtime)));
+static SET_EXTENSION_HOOK: Lazy<()> = Lazy::new(|| {
+    set_extension_hook(Box::new(new_testing_object_and_natives_cost_runtime));
+    set_module_fetch_hook(Box::new(|id| {
+        Lazy::force(&TEST_STORE)
+            .forked_module(id)
+            .map_err(|e| format!("Failed to load module {id} from the forked state: {e}"))
+    }));
+});
+
+/// Let tests fork the networks provided by `provider`. Must be called before any test is run.
+pub fn set_fork_provider(provider: Box<dyn ForkProvider>) -> anyhow::Result<()> {
+    if Lazy::get(&TEST_STORE).is_some() {
+        anyhow::bail!("Cannot set the networks to fork after tests have started running");
+    }
+    FORK_PROVIDER
+        .set(provider)
+        .map_err(|_| anyhow::anyhow!("The networks to fork are already set"))
+}
 
 /// This function returns a result of UnitTestResult. The outer result indicates whether it
 /// successfully started running the test, and the inner result indicatests whether all tests pass.
@@ -121,6 +157,7 @@ fn new_testing_object_and_natives_cost_runtime(ext: &mut NativeContextExtensions
     let registry = prometheus::Registry::new();
     let metrics = Arc::new(LimitsMetrics::new(&registry));
     let store = Lazy::force(&TEST_STORE);
+    store.start_test();
     let protocol_config = ProtocolConfig::get_for_max_version_UNSAFE();
 
     ext.add(ObjectRuntime::new(
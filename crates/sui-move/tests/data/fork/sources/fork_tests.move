@@ -0,0 +1,27 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[test_only]
+module fork::fork_tests;
+
+use sui::clock::Clock;
+use sui::test_scenario;
+
+#[test]
+fun take_forked_clock() {
+    let mut scenario = test_scenario::begin_fork(@0x0, b"mocknet", 7);
+    let clock = scenario.take_shared_by_id<Clock>(object::id_from_address(@0x6));
+    assert!(clock.timestamp_ms() == 42);
+    test_scenario::return_shared(clock);
+    scenario.next_tx(@0x0);
+    scenario.end();
+}
+
+#[test]
+#[expected_failure(abort_code = test_scenario::EObjectNotFound)]
+fun clock_is_not_forked_without_fork() {
+    let scenario = test_scenario::begin(@0x0);
+    let clock = scenario.take_shared_by_id<Clock>(object::id_from_address(@0x6));
+    test_scenario::return_shared(clock);
+    scenario.end();
+}
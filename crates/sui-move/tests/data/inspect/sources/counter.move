@@ -0,0 +1,100 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+module inspect::counter;
+
+use sui::dynamic_field as df;
+use sui::event;
+
+public struct Counter has key, store {
+    id: UID,
+    value: u64,
+}
+
+public struct Wrapper has store {
+    counter: Counter,
+}
+
+public struct Holder has key {
+    id: UID,
+    counters: vector<Counter>,
+}
+
+public enum State has drop, store {
+    Idle,
+    Running { steps: u64 },
+}
+
+public struct Incremented has copy, drop {
+    value: u64,
+}
+
+public struct Reset has copy, drop {}
+
+public fun new(ctx: &mut TxContext): Counter {
+    Counter { id: object::new(ctx), value: 0 }
+}
+
+public fun value(counter: &Counter): u64 {
+    counter.value
+}
+
+public fun increment(counter: &mut Counter) {
+    counter.value = counter.value + 1;
+    notify(Incremented { value: counter.value });
+}
+
+public fun reset(counter: &mut Counter) {
+    counter.value = 0;
+    event::emit(Reset {});
+}
+
+public fun destroy(counter: Counter) {
+    let Counter { id, value: _ } = counter;
+    id.delete();
+}
+
+public fun wrapped(wrapper: &mut Wrapper): u64 {
+    wrapper.counter.value
+}
+
+public fun increment_first(holder: &Holder, counters: &mut vector<Counter>) {
+    let _ = holder;
+    let counter = &mut counters[0];
+    counter.value = counter.value + 1;
+}
+
+public fun increment_dynamic(parent: &mut UID) {
+    let counter: &mut Counter = df::borrow_mut(parent, 0u8);
+    increment(counter);
+}
+
+public fun increment_owned(mut counter: Counter): Counter {
+    increment(&mut counter);
+    counter
+}
+
+public fun advance(state: &mut State) {
+    match (state) {
+        State::Running { steps } => *steps = *steps + 1,
+        State::Idle => (),
+    }
+}
+
+public fun steps(state: State): u64 {
+    match (state) {
+        State::Running { steps } => steps,
+        State::Idle => 0,
+    }
+}
+
+public fun is_running(state: &State): bool {
+    match (state) {
+        State::Running { .. } => true,
+        State::Idle => false,
+    }
+}
+
+fun notify<T: copy + drop>(event: T) {
+    event::emit(event)
+}
@@ -0,0 +1,233 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use move_binary_format::file_format::basic_test_module;
+use move_cli::base::test::UnitTestResult;
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use move_package::BuildConfig;
+use once_cell::sync::Lazy;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use sui_move::{
+    fork::{ForkedNetworks, ObjectFetcher},
+    unit_test::{run_move_unit_tests, set_fork_provider},
+};
+use sui_move_natives::test_scenario::{ForkProvider, ForkedState, InMemoryTestStore};
+use sui_types::{
+    base_types::{MoveObjectType, ObjectID, SequenceNumber},
+    clock::Clock,
+    digests::TransactionDigest,
+    error::{SuiError, SuiResult},
+    id::UID,
+    in_memory_storage::InMemoryStorage,
+    object::{MoveObject, Object, Owner},
+    storage::ChildObjectResolver,
+    SUI_CLOCK_OBJECT_ID,
+};
+
+const CHECKPOINT: u64 = 7;
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/fork");
+const SUI_FRAMEWORK: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../sui-framework/packages/sui-framework"
+);
+
+thread_local! {
+    static TEST_STORE_INNER: RefCell<InMemoryStorage> = RefCell::new(InMemoryStorage::default());
+}
+
+static PARENT: Lazy<ObjectID> = Lazy::new(ObjectID::random);
+
+// The objects of the mocked network, at `CHECKPOINT`.
+static FORKED_OBJECTS: Lazy<BTreeMap<ObjectID, Object>> = Lazy::new(|| {
+    let child = Object::with_id_owner_version_for_testing(
+        ObjectID::random(),
+        SequenceNumber::from_u64(3),
+        Owner::ObjectOwner((*PARENT).into()),
+    );
+    let package = Object::new_package_for_testing(
+        &[basic_test_module()],
+        TransactionDigest::genesis_marker(),
+        [],
+    )
+    .unwrap();
+    [clock(42), child, package]
+        .into_iter()
+        .map(|o| (o.id(), o))
+        .collect()
+});
+
+static MOCK_STATE: Lazy<Arc<MockState>> = Lazy::new(|| {
+    Arc::new(MockState {
+        fetches: AtomicUsize::new(0),
+    })
+});
+
+static MOCK_PROVIDER: MockProvider = MockProvider;
+
+struct MockState {
+    fetches: AtomicUsize,
+}
+
+impl ForkedState for MockState {
+    fn get_object(&self, id: &ObjectID) -> SuiResult<Option<Object>> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        Ok(FORKED_OBJECTS.get(id).cloned())
+    }
+}
+
+struct MockProvider;
+
+impl ForkProvider for MockProvider {
+    fn fork(&self, chain: &str, checkpoint: u64) -> SuiResult<Arc<dyn ForkedState>> {
+        if chain != "mocknet" || checkpoint != CHECKPOINT {
+            return Err(SuiError::Storage(format!("Unknown fork {chain}")));
+        }
+        Ok(MOCK_STATE.clone())
+    }
+}
+
+struct MockFetcher;
+
+impl ObjectFetcher for MockFetcher {
+    fn fetch_object(&self, checkpoint: u64, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        assert_eq!(checkpoint, CHECKPOINT);
+        Ok(FORKED_OBJECTS.get(id).cloned())
+    }
+}
+
+fn clock(timestamp_ms: u64) -> Object {
+    let clock = Clock {
+        id: UID::new(SUI_CLOCK_OBJECT_ID),
+        timestamp_ms,
+    };
+    let move_object = unsafe {
+        MoveObject::new_from_execution_with_limit(
+            MoveObjectType::from(Clock::type_()),
+            false,
+            SequenceNumber::from_u64(10),
+            bcs::to_bytes(&clock).unwrap(),
+            256,
+        )
+    }
+    .unwrap();
+    Object::new_move(
+        move_object,
+        Owner::Shared {
+            initial_shared_version: SequenceNumber::from_u64(1),
+        },
+        TransactionDigest::genesis_marker(),
+    )
+}
+
+#[test]
+fn forked_objects_are_loaded_per_test() {
+    let store = InMemoryTestStore(&TEST_STORE_INNER, Some(&MOCK_PROVIDER));
+    let child = FORKED_OBJECTS
+        .values()
+        .find(|o| o.owner == Owner::ObjectOwner((*PARENT).into()))
+        .unwrap()
+        .clone();
+    let package = FORKED_OBJECTS.values().find(|o| o.is_package()).unwrap();
+    let module = ModuleId::new(package.id().into(), Identifier::new("DUMMY").unwrap());
+    let missing_module = ModuleId::new(*module.address(), Identifier::new("missing").unwrap());
+    let bound = SequenceNumber::from_u64(5);
+
+    // nothing is loaded before the test forks
+    store.start_test();
+    assert_eq!(
+        store
+            .read_child_object(&PARENT, &child.id(), bound)
+            .unwrap(),
+        None
+    );
+    assert_eq!(store.forked_module(&module).unwrap(), None);
+    assert_eq!(MOCK_STATE.fetches.load(Ordering::SeqCst), 0);
+
+    assert!(store.set_fork("devnet", CHECKPOINT).is_err());
+    store.set_fork("mocknet", CHECKPOINT).unwrap();
+    assert!(store.set_fork("mocknet", CHECKPOINT).is_err());
+
+    // children of forked objects and modules of forked packages are loaded lazily
+    assert_eq!(
+        store
+            .read_child_object(&PARENT, &child.id(), bound)
+            .unwrap(),
+        Some(child.clone())
+    );
+    assert!(store.forked_module(&module).unwrap().is_some());
+    assert_eq!(store.forked_module(&missing_module).unwrap(), None);
+    // children of objects created by the test are not loaded
+    assert_eq!(
+        store
+            .read_child_object(&PARENT, &ObjectID::random(), SequenceNumber::new())
+            .unwrap(),
+        None
+    );
+
+    // the next test does not run against the fork, and forked objects are removed
+    store.start_test();
+    assert!(TEST_STORE_INNER.with_borrow(|s| s.get_object(&child.id()).is_none()));
+    assert_eq!(
+        store
+            .read_child_object(&PARENT, &child.id(), bound)
+            .unwrap(),
+        None
+    );
+    assert_eq!(store.forked_module(&module).unwrap(), None);
+    // and can fork again
+    store.set_fork("mocknet", CHECKPOINT).unwrap();
+    store.start_test();
+}
+
+#[test]
+fn forking_requires_a_provider() {
+    let store = InMemoryTestStore(&TEST_STORE_INNER, None);
+    store.start_test();
+    assert!(store.set_fork("mocknet", CHECKPOINT).is_err());
+}
+
+#[test]
+fn move_tests_run_against_forks() {
+    // copy the fixture so that building it does not write to the source tree
+    let dir = tempfile::tempdir().unwrap();
+    let package = dir.path().join("fork");
+    std::fs::create_dir_all(package.join("sources")).unwrap();
+    std::fs::copy(
+        Path::new(FIXTURE).join("sources/fork_tests.move"),
+        package.join("sources/fork_tests.move"),
+    )
+    .unwrap();
+    let manifest = std::fs::read_to_string(Path::new(FIXTURE).join("Move.toml"))
+        .unwrap()
+        .replace(
+            "../../../../sui-framework/packages/sui-framework",
+            SUI_FRAMEWORK,
+        );
+    std::fs::write(package.join("Move.toml"), manifest).unwrap();
+
+    let networks = ForkedNetworks::new(dir.path().join("cache"))
+        .with_fetcher("mocknet", Arc::new(MockFetcher));
+    set_fork_provider(Box::new(networks)).unwrap();
+    let build_config = BuildConfig {
+        test_mode: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        run_move_unit_tests(&package, build_config, None, false, false).unwrap(),
+        UnitTestResult::Success
+    );
+    // the forked clock was cached
+    assert!(dir
+        .path()
+        .join("cache/mocknet/7")
+        .join(format!("{SUI_CLOCK_OBJECT_ID}.bcs"))
+        .exists());
+}
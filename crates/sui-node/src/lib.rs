@@ -26,7 +26,7 @@ use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 use sui_core::authority::authority_store_tables::{
-    AuthorityPerpetualTablesOptions, AuthorityPrunerTables,
+    ensure_storage_engine_supported, AuthorityPerpetualTablesOptions, AuthorityPrunerTables,
 };
 use sui_core::authority::backpressure::BackpressureManager;
 use sui_core::authority::epoch_start_configuration::EpochFlag;
@@ -72,7 +72,7 @@ pub use handle::SuiNodeHandle;
 use mysten_metrics::{spawn_monitored_task, RegistryService};
 use mysten_network::server::ServerBuilder;
 use mysten_service::server_timing::server_timing_middleware;
-use sui_config::node::{DBCheckpointConfig, RunWithRange};
+use sui_config::node::{DBCheckpointConfig, RunWithRange, StorageEngine};
 use sui_config::node::{ForkCrashBehavior, ForkRecoveryConfig};
 use sui_config::node_config_metrics::NodeConfigMetrics;
 use sui_config::{ConsensusConfig, NodeConfig};
@@ -231,7 +231,7 @@ mod simulator {
 pub use simulator::set_jwk_injector;
 #[cfg(msim)]
 use simulator::*;
-use sui_core::authority::authority_store_pruner::ObjectsCompactionFilter;
+use sui_core::authority::authority_store_pruner::{ObjectRetentionPolicy, ObjectsCompactionFilter};
 use sui_core::{
     consensus_handler::ConsensusHandlerInitializer, safe_client::SafeClientMetricsBase,
     validator_tx_finalizer::ValidatorTxFinalizer,
@@ -487,7 +487,26 @@ impl SuiNode {
             None,
         ));
 
-        let checkpoint_store = CheckpointStore::new(&config.db_path().join("checkpoints"));
+        let storage_engines = config.storage_engine_config.clone().unwrap_or_default();
+        for engine in [
+            storage_engines.perpetual,
+            storage_engines.epoch,
+            storage_engines.checkpoint,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            ensure_storage_engine_supported(engine)?;
+        }
+        // The index stores only have RocksDB table options.
+        if storage_engines.index == Some(StorageEngine::TideHunter) {
+            anyhow::bail!("TideHunter storage engine is not supported for the index stores");
+        }
+
+        let checkpoint_store = CheckpointStore::new_with_engine(
+            &config.db_path().join("checkpoints"),
+            storage_engines.checkpoint,
+        );
         let checkpoint_metrics = CheckpointMetrics::new(&registry_service.default_registry());
 
         Self::check_and_recover_forks(
@@ -506,15 +525,25 @@ impl SuiNode {
                 &config.db_path().join("store"),
             )));
         }
+        let object_retention = ObjectRetentionPolicy::new(
+            config
+                .authority_store_pruning_config
+                .object_retention
+                .as_ref(),
+        )?;
+        if !object_retention.is_empty() && pruner_db.is_none() {
+            anyhow::bail!("object-retention requires enable-compaction-filter to be set");
+        }
         let compaction_filter = pruner_db
             .clone()
-            .map(|db| ObjectsCompactionFilter::new(db, &prometheus_registry));
+            .map(|db| ObjectsCompactionFilter::new(db, object_retention, &prometheus_registry));
 
         // By default, only enable write stall on validators for perpetual db.
         let enable_write_stall = config.enable_db_write_stall.unwrap_or(is_validator);
         let perpetual_tables_options = AuthorityPerpetualTablesOptions {
             enable_write_stall,
             compaction_filter,
+            storage_engine: storage_engines.perpetual,
         };
         let perpetual_tables = Arc::new(AuthorityPerpetualTables::open(
             &config.db_path().join("store"),
@@ -572,6 +601,7 @@ impl SuiNode {
             committee.clone(),
             &config.db_path().join("store"),
             Some(epoch_options.options),
+            storage_engines.epoch,
             EpochMetrics::new(&registry_service.default_registry()),
             epoch_start_configuration,
             cache_traits.backing_package_store.clone(),
@@ -1007,6 +1037,7 @@ impl SuiNode {
                 prometheus_registry,
                 checkpoint_store,
                 chain_identifier,
+                config.state_snapshot_write_config.delta_snapshots,
             )?;
             Ok(Some(snapshot_uploader.start()))
         } else {
@@ -1062,6 +1093,9 @@ impl SuiNode {
                     db_checkpoint_config
                         .prune_and_compact_before_upload
                         .unwrap_or(true),
+                    db_checkpoint_config
+                        .content_addressed_upload
+                        .unwrap_or(false),
                     config.authority_store_pruning_config.clone(),
                     prometheus_registry,
                     state_snapshot_enabled,
@@ -23,11 +23,12 @@ use sui_json_rpc_types::DevInspectArgs;
 use sui_json_rpc_types::{
     Balance, Checkpoint, CheckpointId, CheckpointPage, Coin, CoinPage, DelegatedStake,
     DevInspectResults, DynamicFieldPage, EventFilter, EventPage, MoveCallParams,
-    MoveFunctionArgType, ObjectChange, ObjectValueKind::ByImmutableReference,
-    ObjectValueKind::ByMutableReference, ObjectValueKind::ByValue, ObjectsPage, OwnedObjectRef,
-    Page, ProtocolConfigResponse, RPCTransactionRequestParams, Stake, StakeStatus, SuiCoinMetadata,
-    SuiCommittee, SuiData, SuiEvent, SuiExecutionStatus, SuiGetPastObjectRequest, SuiMoveAbility,
-    SuiMoveAbilitySet, SuiMoveNormalizedFunction, SuiMoveNormalizedModule, SuiMoveNormalizedStruct,
+    MoveFunctionArgType, ObjectChange, ObjectRetentionGuarantee,
+    ObjectValueKind::ByImmutableReference, ObjectValueKind::ByMutableReference,
+    ObjectValueKind::ByValue, ObjectsPage, OwnedObjectRef, Page, ProtocolConfigResponse,
+    RPCTransactionRequestParams, Stake, StakeStatus, SuiCoinMetadata, SuiCommittee, SuiData,
+    SuiEvent, SuiExecutionStatus, SuiGetPastObjectRequest, SuiMoveAbility, SuiMoveAbilitySet,
+    SuiMoveNormalizedFunction, SuiMoveNormalizedModule, SuiMoveNormalizedStruct,
     SuiMoveNormalizedType, SuiMoveVisibility, SuiObjectData, SuiObjectDataFilter,
     SuiObjectDataOptions, SuiObjectRef, SuiObjectResponse, SuiObjectResponseQuery, SuiParsedData,
     SuiPastObjectResponse, SuiTransactionBlock, SuiTransactionBlockData,
@@ -132,6 +133,7 @@ impl RpcExampleProvider {
             self.suix_get_latest_sui_system_state(),
             self.get_protocol_config(),
             self.sui_get_chain_identifier(),
+            self.sui_get_object_retention_guarantee(),
             self.suix_get_stakes(),
             self.suix_get_stakes_by_ids(),
             self.suix_resolve_name_service_address(),
@@ -1375,6 +1377,22 @@ impl RpcExampleProvider {
         )
     }
 
+    fn sui_get_object_retention_guarantee(&mut self) -> Examples {
+        let result = ObjectRetentionGuarantee {
+            highest_pruned_checkpoint: Some(2048),
+            retained_packages: vec![ObjectID::from_hex_literal("0xdee9").unwrap()],
+            retained_object_types: vec!["0x2::coin::Coin".to_string()],
+        };
+        Examples::new(
+            "sui_getObjectRetentionGuarantee",
+            vec![ExamplePairing::new(
+                "Gets the past object versions that are retained by the node receiving the POST.",
+                vec![],
+                json!(result),
+            )],
+        )
+    }
+
     fn suix_get_stakes(&mut self) -> Examples {
         let principal = 200000000000;
         let owner = SuiAddress::from(ObjectID::new(self.rng.gen()));
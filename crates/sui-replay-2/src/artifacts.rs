@@ -9,14 +9,18 @@ use std::{
 use move_trace_format::format::{MoveTrace, MoveTraceReader};
 use sui_types::{effects::TransactionEffects, gas::GasUsageReport};
 
+use crate::{gas_profile::GasProfile, package_overrides::OverrideReport};
+
 pub const ARTIFACTS_ENCODING_EXT: &str = "json";
 pub const ARTIFACTS_ENCODING_COMPRESSION_EXT: &str = "json.zst";
 
-pub const ARTIFACTS: [Artifact; 4] = [
+pub const ARTIFACTS: [Artifact; 6] = [
     Artifact::Trace,
     Artifact::TransactionEffects,
     Artifact::TransactionGasReport,
     Artifact::ForkedTransactionEffects,
+    Artifact::GasProfile,
+    Artifact::OverrideReport,
 ];
 
 /// The types of artifacts that the replay tool knows about and may output.
@@ -26,6 +30,8 @@ pub enum Artifact {
     TransactionEffects,
     TransactionGasReport,
     ForkedTransactionEffects,
+    GasProfile,
+    OverrideReport,
 }
 
 /// Encoding types for artifacts that may be output by the replay tool.
@@ -93,6 +99,8 @@ impl Artifact {
             Artifact::TransactionEffects => "transaction_effects",
             Artifact::ForkedTransactionEffects => "forked_transaction_effects",
             Artifact::TransactionGasReport => "transaction_gas_report",
+            Artifact::GasProfile => "gas_profile",
+            Artifact::OverrideReport => "override_report",
         }
     }
 
@@ -102,7 +110,9 @@ impl Artifact {
             Artifact::Trace => EncodingType::JsonCompressed,
             Artifact::ForkedTransactionEffects
             | Artifact::TransactionEffects
-            | Artifact::TransactionGasReport => EncodingType::Json,
+            | Artifact::TransactionGasReport
+            | Artifact::GasProfile
+            | Artifact::OverrideReport => EncodingType::Json,
         }
     }
 
@@ -189,6 +199,40 @@ impl ArtifactMember<'_, '_> {
             None
         }
     }
+
+    /// Try to get the `GasProfile` if the artifact type is `GasProfile`.
+    /// If the artifact type is not `GasProfile` `None` is returned.
+    pub fn try_get_gas_profile(&self) -> Option<anyhow::Result<GasProfile>> {
+        if self.artifact_type == Artifact::GasProfile {
+            Some(self.get_json().and_then(|json| {
+                serde_json::from_value::<GasProfile>(json).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to deserialize gas profile from {}: {e}",
+                        self.artifact_path.display()
+                    )
+                })
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Try to get the `OverrideReport` if the artifact type is `OverrideReport`.
+    /// If the artifact type is not `OverrideReport` `None` is returned.
+    pub fn try_get_override_report(&self) -> Option<anyhow::Result<OverrideReport>> {
+        if self.artifact_type == Artifact::OverrideReport {
+            Some(self.get_json().and_then(|json| {
+                serde_json::from_value::<OverrideReport>(json).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to deserialize override report from {}: {e}",
+                        self.artifact_path.display()
+                    )
+                })
+            }))
+        } else {
+            None
+        }
+    }
 }
 
 /// Serialization methods for `ArtifactManager`.
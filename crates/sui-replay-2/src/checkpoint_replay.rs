@@ -0,0 +1,333 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay of a contiguous range of checkpoints.
+//! Transactions are replayed in checkpoint order and the objects written by each transaction
+//! are kept in a `CheckpointRangeStore`. Later transactions in the range load their inputs
+//! (e.g. shared objects) from those outputs rather than fetching every version from the
+//! remote store, so that the range is replayed as a realistic contiguous workload where each
+//! transaction executes on the state produced by the replay of the previous ones.
+//!
+//! The outputs of a transaction whose replay fails or whose effects diverge from the on-chain
+//! ones are not kept, and any earlier outputs of the objects it changed on chain are evicted,
+//! so that later transactions read the on-chain versions instead and a failure does not
+//! cascade through the rest of the range. With package overrides divergence is expected and
+//! the outputs are always kept, so the range is replayed on the "what-if" state.
+
+use crate::{
+    artifacts::ArtifactManager,
+    data_store::DataStore,
+    package_overrides::{OverrideObjectStore, PackageOverrides},
+    replay_interface::{ObjectKey, ObjectStore, TransactionStore, VersionQuery},
+    replay_txn::{replay_transaction_with_store, ObjectVersion},
+    summary::TransactionSummary,
+    tracing::TraceOptions,
+};
+use anyhow::{anyhow, bail};
+use std::{
+    cell::RefCell, collections::BTreeMap, ops::RangeInclusive, path::Path, str::FromStr,
+    time::Instant,
+};
+use sui_types::{base_types::ObjectID, effects::TransactionEffectsAPI, object::Object};
+use tracing::{debug, error, info, warn};
+
+/// An inclusive range of checkpoints as provided on the command line: `<start>..<end>`,
+/// or a single checkpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointRange(pub RangeInclusive<u64>);
+
+/// An `ObjectStore` that serves the objects written by the transactions replayed so far,
+/// falling back to the wrapped store for everything else.
+pub struct CheckpointRangeStore<'a> {
+    store: &'a dyn ObjectStore,
+    outputs: RefCell<BTreeMap<ObjectID, BTreeMap<ObjectVersion, Object>>>,
+}
+
+impl FromStr for CheckpointRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid checkpoint {n}: {e}"))
+        };
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let checkpoint = parse(s)?;
+                (checkpoint, checkpoint)
+            }
+        };
+        if start > end {
+            return Err(format!("Invalid checkpoint range {s}: start is after end"));
+        }
+        Ok(CheckpointRange(start..=end))
+    }
+}
+
+impl<'a> CheckpointRangeStore<'a> {
+    pub fn new(store: &'a dyn ObjectStore) -> Self {
+        Self {
+            store,
+            outputs: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record the objects written by a replayed transaction.
+    pub fn record_outputs<'o>(&self, written: impl IntoIterator<Item = &'o Object>) {
+        let mut outputs = self.outputs.borrow_mut();
+        for object in written {
+            outputs
+                .entry(object.id())
+                .or_default()
+                .insert(object.version().value(), object.clone());
+        }
+    }
+
+    /// Forget the replayed versions of objects, so that they are read from the wrapped store.
+    /// Used for the objects changed on chain by a transaction whose replay failed, as the
+    /// latest local versions of those objects are stale.
+    pub fn evict(&self, ids: impl IntoIterator<Item = ObjectID>) {
+        let mut outputs = self.outputs.borrow_mut();
+        for id in ids {
+            outputs.remove(&id);
+        }
+    }
+
+    // Look up a key in the outputs of the replayed transactions.
+    // Any version of an object after the first one written in the range is also written in the
+    // range, so the latest local version at or below a bound is the right one if it exists.
+    fn get_local(&self, key: &ObjectKey) -> Option<Object> {
+        let outputs = self.outputs.borrow();
+        let versions = outputs.get(&key.object_id)?;
+        match key.version_query {
+            VersionQuery::Version(version) => versions.get(&version).cloned(),
+            VersionQuery::RootVersion(version) => versions
+                .range(..=version)
+                .next_back()
+                .map(|(_, obj)| obj.clone()),
+            VersionQuery::AtCheckpoint(_) | VersionQuery::ImmutableOrLatest => {
+                versions.last_key_value().map(|(_, obj)| obj.clone())
+            }
+        }
+    }
+}
+
+impl ObjectStore for CheckpointRangeStore<'_> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        let mut objects = keys
+            .iter()
+            .map(|key| self.get_local(key))
+            .collect::<Vec<_>>();
+        let (remote_idx, remote_keys): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| objects[*i].is_none())
+            .map(|(i, key)| (i, key.clone()))
+            .unzip();
+        debug!(
+            "{} objects loaded from the replayed range, {} from the store",
+            keys.len() - remote_keys.len(),
+            remote_keys.len()
+        );
+        if !remote_keys.is_empty() {
+            let remote = self.store.get_objects(&remote_keys)?;
+            for (i, object) in remote_idx.into_iter().zip(remote) {
+                objects[i] = object;
+            }
+        }
+        Ok(objects)
+    }
+}
+
+/// Options for a checkpoint range replay.
+pub struct CheckpointReplayOptions<'a> {
+    pub output_root_dir: &'a Path,
+    pub overwrite_existing: bool,
+    pub trace: Option<&'a TraceOptions>,
+    pub terminate_early: bool,
+}
+
+/// Replay every transaction in `range` in checkpoint order, adding a summary per transaction
+/// to `summaries`.
+pub async fn replay_checkpoint_range(
+    range: &CheckpointRange,
+    data_store: &DataStore,
+    overrides: &PackageOverrides,
+    options: &CheckpointReplayOptions<'_>,
+    summaries: &mut Vec<TransactionSummary>,
+) -> anyhow::Result<()> {
+    let override_store = OverrideObjectStore::new(data_store, overrides);
+    let range_store = CheckpointRangeStore::new(&override_store);
+
+    for checkpoint in range.0.clone() {
+        let digests = data_store
+            .checkpoint_transactions(checkpoint)
+            .map_err(|e| anyhow!("Failed to load transactions of checkpoint {checkpoint}: {e}"))?;
+        info!(
+            "Replaying {} transactions in checkpoint {}",
+            digests.len(),
+            checkpoint
+        );
+        for tx_digest in digests {
+            let start = Instant::now();
+            let tx_dir = options.output_root_dir.join(&tx_digest);
+            let artifact_manager = ArtifactManager::new(&tx_dir, options.overwrite_existing)?;
+            let result = replay_transaction_with_store(
+                &artifact_manager,
+                &tx_digest,
+                data_store,
+                &range_store,
+                options.trace,
+                overrides,
+            )
+            .await
+            .map(|(outcome, context_and_effects)| {
+                range_store.record_outputs(context_and_effects.inner_store.written.values());
+                outcome
+            });
+            if result.is_err() {
+                evict_on_chain_changes(&range_store, data_store, &tx_digest);
+            }
+            let mut summary = TransactionSummary::new(tx_digest.clone(), &result, start.elapsed());
+            summary.checkpoint = Some(checkpoint);
+            summaries.push(summary);
+            match result {
+                Err(e) if options.terminate_early => {
+                    error!(
+                        "Error while replaying transaction {} in checkpoint {}: {:?}",
+                        tx_digest, checkpoint, e
+                    );
+                    bail!("Replay terminated due to error: {}", e);
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to replay transaction {} in checkpoint {}: {:?}",
+                        tx_digest, checkpoint, e
+                    );
+                }
+                Ok(_) => {
+                    debug!("Successfully replayed transaction {}", tx_digest);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Evict the objects changed by the on-chain execution of a transaction whose replay failed.
+fn evict_on_chain_changes(
+    range_store: &CheckpointRangeStore,
+    data_store: &DataStore,
+    tx_digest: &str,
+) {
+    match data_store.transaction_data_and_effects(tx_digest) {
+        Ok((_, effects, _)) => {
+            range_store.evict(effects.object_changes().into_iter().map(|change| change.id))
+        }
+        Err(e) => warn!(
+            "Cannot load effects of transaction {} to evict the objects it changed, later \
+            transactions may read stale versions: {:?}",
+            tx_digest, e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::{
+        base_types::{SequenceNumber, SuiAddress},
+        object::Owner,
+    };
+
+    #[test]
+    fn parse_checkpoint_range() {
+        assert_eq!(
+            "10..20".parse::<CheckpointRange>().unwrap(),
+            CheckpointRange(10..=20)
+        );
+        assert_eq!(
+            "42".parse::<CheckpointRange>().unwrap(),
+            CheckpointRange(42..=42)
+        );
+        assert!("20..10".parse::<CheckpointRange>().is_err());
+        assert!("a..b".parse::<CheckpointRange>().is_err());
+    }
+
+    // Objects as stored on chain.
+    struct MockStore(BTreeMap<(ObjectID, ObjectVersion), Object>);
+
+    impl ObjectStore for MockStore {
+        fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+            Ok(keys
+                .iter()
+                .map(|key| {
+                    let versions = self
+                        .0
+                        .range((key.object_id, 0)..=(key.object_id, u64::MAX))
+                        .map(|(_, obj)| obj);
+                    match key.version_query {
+                        VersionQuery::Version(v) => {
+                            versions.filter(|obj| obj.version().value() == v).last()
+                        }
+                        VersionQuery::RootVersion(v) => {
+                            versions.filter(|obj| obj.version().value() <= v).last()
+                        }
+                        _ => versions.last(),
+                    }
+                    .cloned()
+                })
+                .collect())
+        }
+    }
+
+    fn object(id: ObjectID, version: u64) -> Object {
+        Object::with_id_owner_version_for_testing(
+            id,
+            SequenceNumber::from_u64(version),
+            Owner::AddressOwner(SuiAddress::ZERO),
+        )
+    }
+
+    fn get(store: &CheckpointRangeStore, id: ObjectID, version_query: VersionQuery) -> u64 {
+        store
+            .get_objects(&[ObjectKey {
+                object_id: id,
+                version_query,
+            }])
+            .unwrap()[0]
+            .as_ref()
+            .unwrap()
+            .version()
+            .value()
+    }
+
+    #[test]
+    fn failed_transaction_outputs_are_read_from_store() {
+        let id = ObjectID::random();
+        // On chain, tx1 writes version 2 and tx2 writes version 3
+        let chain = MockStore(BTreeMap::from([
+            ((id, 1), object(id, 1)),
+            ((id, 2), object(id, 2)),
+            ((id, 3), object(id, 3)),
+        ]));
+        let store = CheckpointRangeStore::new(&chain);
+
+        // tx1 is replayed successfully
+        store.record_outputs([&object(id, 2)]);
+        assert_eq!(get(&store, id, VersionQuery::ImmutableOrLatest), 2);
+
+        // tx2 fails: a later reader must see the on-chain version it wrote, not tx1's output
+        store.evict([id]);
+        assert_eq!(get(&store, id, VersionQuery::ImmutableOrLatest), 3);
+        assert_eq!(get(&store, id, VersionQuery::RootVersion(3)), 3);
+        assert_eq!(get(&store, id, VersionQuery::Version(2)), 2);
+
+        // Outputs of later transactions are recorded again
+        store.record_outputs([&object(id, 4)]);
+        assert_eq!(get(&store, id, VersionQuery::ImmutableOrLatest), 4);
+    }
+}
@@ -21,6 +21,7 @@ use sui_types::{
     base_types::ObjectID,
     committee::ProtocolVersion,
     effects::TransactionEffects,
+    event::Event,
     object::Object,
     supported_protocol_versions::{Chain, ProtocolConfig},
     transaction::TransactionData,
@@ -162,6 +163,30 @@ impl DataStore {
         data
     }
 
+    /// Events emitted by a transaction as recorded on chain.
+    pub fn transaction_events(&self, digest: &str) -> Result<Vec<Event>, anyhow::Error> {
+        block_on!(self.events(digest))
+    }
+
+    async fn events(&self, digest: &str) -> Result<Vec<Event>, anyhow::Error> {
+        debug!("Start transaction events query");
+        let data = gql_queries::event_query::query(digest.to_string(), self).await;
+        debug!("End transaction events query");
+        data
+    }
+
+    /// Digests of the transactions in a checkpoint, in checkpoint order.
+    pub fn checkpoint_transactions(&self, checkpoint: u64) -> Result<Vec<String>, anyhow::Error> {
+        block_on!(self.checkpoint(checkpoint))
+    }
+
+    async fn checkpoint(&self, checkpoint: u64) -> Result<Vec<String>, anyhow::Error> {
+        debug!("Start checkpoint transactions query");
+        let data = gql_queries::checkpoint_query::query(checkpoint, self).await;
+        debug!("End checkpoint transactions query");
+        data
+    }
+
     async fn epoch(&self, epoch_id: u64) -> Result<EpochData, anyhow::Error> {
         debug!("Start epoch query");
         let data = gql_queries::epoch_query::query(epoch_id, self).await;
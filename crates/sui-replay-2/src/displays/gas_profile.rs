@@ -0,0 +1,88 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::displays::Pretty;
+use crate::gas_profile::{FunctionGasCost, GasProfile, GasProfileDiff};
+use std::fmt::{Display, Formatter};
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{style::HorizontalLine, Style as TableStyle},
+};
+
+impl Display for Pretty<'_, GasProfile> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(profile) = self;
+        let mut functions = profile.functions.iter().collect::<Vec<_>>();
+        functions.sort_by(|(n1, c1), (n2, c2)| c2.exclusive.cmp(&c1.exclusive).then(n1.cmp(n2)));
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec!["Function", "Calls", "Inclusive", "Exclusive"]);
+        for (name, cost) in functions {
+            builder.push_record(vec![
+                name.clone(),
+                cost.calls.to_string(),
+                cost.inclusive.to_string(),
+                cost.exclusive.to_string(),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+            1,
+            TableStyle::modern().get_horizontal(),
+        )]));
+        write!(f, "\nTotal Computation Gas: {}\n{}\n", profile.total, table)
+    }
+}
+
+impl Display for Pretty<'_, GasProfileDiff> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(diff) = self;
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec![
+            "Function",
+            "Calls",
+            "Inclusive",
+            "Exclusive",
+            "Inclusive Delta",
+            "Exclusive Delta",
+        ]);
+        for delta in &diff.functions {
+            let field = |get: fn(&FunctionGasCost) -> u64| {
+                let side = |cost: &Option<FunctionGasCost>| {
+                    cost.as_ref()
+                        .map_or("-".to_string(), |c| get(c).to_string())
+                };
+                format!("{} -> {}", side(&delta.base), side(&delta.target))
+            };
+            builder.push_record(vec![
+                delta.function.clone(),
+                field(|c| c.calls),
+                field(|c| c.inclusive),
+                field(|c| c.exclusive),
+                signed(delta.inclusive_delta()),
+                signed(delta.exclusive_delta()),
+            ]);
+        }
+        let mut table = builder.build();
+        table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+            1,
+            TableStyle::modern().get_horizontal(),
+        )]));
+        write!(
+            f,
+            "\nTotal Computation Gas: {} -> {} ({})\n{}\n",
+            diff.base_total,
+            diff.target_total,
+            signed(diff.total_delta()),
+            table
+        )
+    }
+}
+
+fn signed(value: i128) -> String {
+    if value > 0 {
+        format!("+{value}")
+    } else {
+        value.to_string()
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod gas_profile;
 mod gas_report;
+mod override_report;
 
 pub struct Pretty<'a, T>(pub &'a T);
@@ -0,0 +1,100 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::displays::Pretty;
+use crate::package_overrides::OverrideReport;
+use std::fmt::{Display, Formatter};
+use sui_types::event::Event;
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{style::HorizontalLine, Style as TableStyle},
+};
+
+impl Display for Pretty<'_, OverrideReport> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(report) = self;
+        summary_table(f, report)?;
+        gas_table(f, report)?;
+        events_table(f, report)?;
+        Ok(())
+    }
+}
+
+fn summary_table(f: &mut Formatter<'_>, report: &OverrideReport) -> std::fmt::Result {
+    let mut builder = TableBuilder::default();
+    builder.push_record(vec!["Overridden Packages".to_string()]);
+    for package_id in &report.overridden_packages {
+        builder.push_record(vec![package_id.to_string()]);
+    }
+    builder.push_record(vec![format!("Effects Match: {}", report.effects_match)]);
+    builder.push_record(vec![format!("Events Match: {}", report.events_match())]);
+    builder.push_record(vec![format!(
+        "Status: {:?} -> {:?}",
+        report.original_status, report.replay_status
+    )]);
+    let mut table = builder.build();
+    table.with(TableStyle::rounded());
+    write!(f, "\n{}\n", table)
+}
+
+fn gas_table(f: &mut Formatter<'_>, report: &OverrideReport) -> std::fmt::Result {
+    let mut builder = TableBuilder::default();
+    builder.push_record(vec!["Gas", "Original", "Replay", "Delta"]);
+    let (original, replay) = (&report.original_gas, &report.replay_gas);
+    let rows = [
+        (
+            "Computation Cost",
+            original.computation_cost,
+            replay.computation_cost,
+        ),
+        ("Storage Cost", original.storage_cost, replay.storage_cost),
+        (
+            "Storage Rebate",
+            original.storage_rebate,
+            replay.storage_rebate,
+        ),
+        (
+            "Non-Refundable Storage Fee",
+            original.non_refundable_storage_fee,
+            replay.non_refundable_storage_fee,
+        ),
+        ("Gas Used", original.gas_used(), replay.gas_used()),
+    ];
+    for (name, original, replay) in rows {
+        builder.push_record(vec![
+            name.to_string(),
+            original.to_string(),
+            replay.to_string(),
+            (replay as i128 - original as i128).to_string(),
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
+
+fn events_table(f: &mut Formatter<'_>, report: &OverrideReport) -> std::fmt::Result {
+    let mut builder = TableBuilder::default();
+    builder.push_record(vec!["#", "Original Event", "Replay Event", "Same Contents"]);
+    let event_type = |event: Option<&Event>| event.map_or("-".to_string(), |e| e.type_.to_string());
+    let count = report.original_events.len().max(report.replay_events.len());
+    for i in 0..count {
+        let original = report.original_events.get(i);
+        let replay = report.replay_events.get(i);
+        builder.push_record(vec![
+            i.to_string(),
+            event_type(original),
+            event_type(replay),
+            (original == replay).to_string(),
+        ]);
+    }
+    let mut table = builder.build();
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
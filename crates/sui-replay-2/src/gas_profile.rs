@@ -0,0 +1,352 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gas profiling over replay traces.
+//! A `GasProfile` is built by folding the events of a Move trace into per-function
+//! computation gas costs. Every trace event carries the gas left at that point of execution,
+//! so the cost of a call is the difference between the gas left when its frame is opened
+//! and when it is closed (inclusive cost). The exclusive cost of a call is its inclusive cost
+//! minus the inclusive cost of all the calls it makes.
+//!
+//! Two profiles can be compared with `GasProfileDiff`, which reports, per function, how the
+//! costs changed between a "base" and a "target" run (e.g. two protocol versions or an
+//! on-chain package against a locally modified one).
+
+use crate::artifacts::{Artifact, ArtifactManager};
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use move_trace_format::format::TraceEvent;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Arguments for the `gas-profile-diff` sub-command.
+#[derive(Parser, Clone, Debug)]
+#[clap(
+    name = "gas-profile-diff",
+    about = "Compare the gas profiles of two replays of the same transaction.",
+    rename_all = "kebab-case"
+)]
+pub struct GasProfileDiffConfig {
+    /// The base gas profile. Either a gas profile artifact or a replay output directory
+    /// for a transaction (e.g. `.replay/<digest>`).
+    #[arg(long)]
+    pub base: PathBuf,
+    /// The gas profile to compare against the base one. Either a gas profile artifact or a
+    /// replay output directory for a transaction.
+    #[arg(long)]
+    pub target: PathBuf,
+    /// Only report functions whose cost changed.
+    #[arg(long, default_value = "false")]
+    pub changed_only: bool,
+    /// Optional file to save the diff to, as JSON.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+/// Gas cost of a single function, aggregated across all its calls in a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionGasCost {
+    /// Number of times the function was called.
+    pub calls: u64,
+    /// Gas spent in the function and in all the functions it called.
+    pub inclusive: u64,
+    /// Gas spent in the function body only.
+    pub exclusive: u64,
+}
+
+/// Per-function computation gas costs for a replayed transaction.
+/// Functions are keyed by their fully qualified name (`<address>::<module>::<function>`)
+/// using the runtime (original) package id, so that profiles for different versions of
+/// the same package can be compared.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasProfile {
+    /// Total computation gas consumed by Move execution in the trace.
+    pub total: u64,
+    pub functions: BTreeMap<String, FunctionGasCost>,
+}
+
+/// Change in cost of a single function between two profiles.
+/// A missing side means the function was not called in that run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionGasDelta {
+    pub function: String,
+    pub base: Option<FunctionGasCost>,
+    pub target: Option<FunctionGasCost>,
+}
+
+/// Difference between two `GasProfile`s, sorted by the largest change in exclusive cost first.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasProfileDiff {
+    pub base_total: u64,
+    pub target_total: u64,
+    pub functions: Vec<FunctionGasDelta>,
+}
+
+// An open call while folding the trace.
+struct OpenCall {
+    function: String,
+    gas_left_at_open: u64,
+    children_cost: u64,
+}
+
+impl GasProfile {
+    /// Build a profile from the events of a Move trace.
+    /// Frames still open at the end of the trace (e.g. on abort) are closed with the last
+    /// gas value observed.
+    pub fn from_trace_events<I>(events: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = std::io::Result<TraceEvent>>,
+    {
+        let mut profile = GasProfile::default();
+        let mut stack: Vec<OpenCall> = vec![];
+        let mut first_gas_left = None;
+        let mut last_gas_left = 0;
+        for event in events {
+            let event = event.context("Failed to read trace event")?;
+            match event {
+                TraceEvent::OpenFrame { frame, gas_left } => {
+                    first_gas_left.get_or_insert(gas_left);
+                    last_gas_left = gas_left;
+                    stack.push(OpenCall {
+                        function: format!(
+                            "{}::{}",
+                            frame.module.to_canonical_display(/* with_prefix */ true),
+                            frame.function_name
+                        ),
+                        gas_left_at_open: gas_left,
+                        children_cost: 0,
+                    });
+                }
+                TraceEvent::CloseFrame { gas_left, .. } => {
+                    last_gas_left = gas_left;
+                    let call = stack
+                        .pop()
+                        .ok_or_else(|| anyhow!("Malformed trace: frame closed but none open"))?;
+                    profile.close_call(call, gas_left, &mut stack);
+                }
+                TraceEvent::Instruction { gas_left, .. } => {
+                    first_gas_left.get_or_insert(gas_left);
+                    last_gas_left = gas_left;
+                }
+                TraceEvent::Effect(_) | TraceEvent::External(_) => (),
+            }
+        }
+        while let Some(call) = stack.pop() {
+            profile.close_call(call, last_gas_left, &mut stack);
+        }
+        profile.total = first_gas_left.map_or(0, |first| first.saturating_sub(last_gas_left));
+        Ok(profile)
+    }
+
+    /// Build a profile from the trace artifact saved under the given `ArtifactManager`.
+    pub fn from_trace_artifact(artifact_manager: &ArtifactManager<'_>) -> anyhow::Result<Self> {
+        let reader = artifact_manager
+            .member(Artifact::Trace)
+            .try_get_trace()
+            .transpose()?
+            .unwrap();
+        Self::from_trace_events(reader)
+    }
+
+    /// Load a profile from a gas profile artifact file or from a replay output directory
+    /// containing one.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.is_dir() {
+            let file = std::fs::File::open(path)
+                .map_err(|e| anyhow!("Failed to open gas profile {}: {e}", path.display()))?;
+            return serde_json::from_reader(file).map_err(|e| {
+                anyhow!(
+                    "Failed to deserialize gas profile from {}: {e}",
+                    path.display()
+                )
+            });
+        }
+        let manager = ArtifactManager::new(path, false)?;
+        let member = manager.member(Artifact::GasProfile);
+        if !member.exists() {
+            anyhow::bail!(
+                "No gas profile found in {}. Replay with `--gas-profile` to generate one",
+                path.display()
+            );
+        }
+        Ok(member.try_get_gas_profile().transpose()?.unwrap())
+    }
+
+    fn close_call(&mut self, call: OpenCall, gas_left: u64, stack: &mut [OpenCall]) {
+        let inclusive = call.gas_left_at_open.saturating_sub(gas_left);
+        let exclusive = inclusive.saturating_sub(call.children_cost);
+        if let Some(parent) = stack.last_mut() {
+            parent.children_cost += inclusive;
+        }
+        // Recursive calls are already accounted for in the outermost call.
+        let recursive = stack.iter().any(|open| open.function == call.function);
+        let cost = self.functions.entry(call.function).or_default();
+        cost.calls += 1;
+        cost.exclusive += exclusive;
+        if !recursive {
+            cost.inclusive += inclusive;
+        }
+    }
+}
+
+impl GasProfileDiff {
+    pub fn new(base: &GasProfile, target: &GasProfile, changed_only: bool) -> Self {
+        let names = base
+            .functions
+            .keys()
+            .chain(target.functions.keys())
+            .collect::<BTreeSet<_>>();
+        let mut functions = names
+            .into_iter()
+            .map(|name| FunctionGasDelta {
+                function: name.clone(),
+                base: base.functions.get(name).cloned(),
+                target: target.functions.get(name).cloned(),
+            })
+            .filter(|delta| !changed_only || delta.base != delta.target)
+            .collect::<Vec<_>>();
+        functions.sort_by(|a, b| {
+            b.exclusive_delta()
+                .unsigned_abs()
+                .cmp(&a.exclusive_delta().unsigned_abs())
+                .then_with(|| a.function.cmp(&b.function))
+        });
+        Self {
+            base_total: base.total,
+            target_total: target.total,
+            functions,
+        }
+    }
+
+    pub fn total_delta(&self) -> i128 {
+        self.target_total as i128 - self.base_total as i128
+    }
+}
+
+impl FunctionGasDelta {
+    pub fn inclusive_delta(&self) -> i128 {
+        let cost = |c: &Option<FunctionGasCost>| c.as_ref().map_or(0, |c| c.inclusive as i128);
+        cost(&self.target) - cost(&self.base)
+    }
+
+    pub fn exclusive_delta(&self) -> i128 {
+        let cost = |c: &Option<FunctionGasCost>| c.as_ref().map_or(0, |c| c.exclusive as i128);
+        cost(&self.target) - cost(&self.base)
+    }
+}
+
+pub fn handle_gas_profile_diff_command(config: GasProfileDiffConfig) -> anyhow::Result<()> {
+    let GasProfileDiffConfig {
+        base,
+        target,
+        changed_only,
+        output,
+    } = config;
+
+    let base = GasProfile::load(&base)?;
+    let target = GasProfile::load(&target)?;
+    let diff = GasProfileDiff::new(&base, &target, changed_only);
+
+    if let Some(output) = output {
+        let file = std::fs::File::create(&output)
+            .map_err(|e| anyhow!("Failed to create file {}: {e}", output.display()))?;
+        serde_json::to_writer_pretty(file, &diff)
+            .map_err(|e| anyhow!("Failed to write gas profile diff: {e}"))?;
+    }
+    println!("{}", crate::displays::Pretty(&diff));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+    };
+    use move_trace_format::format::Frame;
+
+    fn open(function: &str, gas_left: u64) -> std::io::Result<TraceEvent> {
+        Ok(TraceEvent::OpenFrame {
+            frame: Box::new(Frame {
+                frame_id: 0,
+                function_name: function.to_string(),
+                module: ModuleId::new(AccountAddress::TWO, Identifier::new("m").unwrap()),
+                version_id: AccountAddress::TWO,
+                binary_member_index: 0,
+                type_instantiation: vec![],
+                parameters: vec![],
+                return_types: vec![],
+                locals_types: vec![],
+                is_native: false,
+            }),
+            gas_left,
+        })
+    }
+
+    fn close(gas_left: u64) -> std::io::Result<TraceEvent> {
+        Ok(TraceEvent::CloseFrame {
+            frame_id: 0,
+            return_: vec![],
+            gas_left,
+        })
+    }
+
+    fn name(function: &str) -> String {
+        format!(
+            "{}::m::{function}",
+            AccountAddress::TWO.to_canonical_string(/* with_prefix */ true)
+        )
+    }
+
+    #[test]
+    fn inclusive_and_exclusive_costs() {
+        // a (100 -> 40) calls b (90 -> 70) twice and c (60 -> 50) once.
+        let events = vec![
+            open("a", 100),
+            open("b", 90),
+            close(70),
+            open("b", 70),
+            close(60),
+            open("c", 60),
+            close(50),
+            close(40),
+        ];
+        let profile = GasProfile::from_trace_events(events).unwrap();
+        assert_eq!(profile.total, 60);
+        assert_eq!(
+            profile.functions[&name("a")],
+            FunctionGasCost {
+                calls: 1,
+                inclusive: 60,
+                exclusive: 20,
+            }
+        );
+        assert_eq!(
+            profile.functions[&name("b")],
+            FunctionGasCost {
+                calls: 2,
+                inclusive: 30,
+                exclusive: 30,
+            }
+        );
+        assert_eq!(profile.functions[&name("c")].exclusive, 10);
+    }
+
+    #[test]
+    fn unclosed_frames_and_diff() {
+        // abort inside `a` after `b` returned: `a` is never closed
+        let base =
+            GasProfile::from_trace_events(vec![open("a", 100), open("b", 80), close(75)]).unwrap();
+        assert_eq!(base.functions[&name("a")].exclusive, 20);
+        let target = GasProfile::from_trace_events(vec![open("a", 100), close(90)]).unwrap();
+        let diff = GasProfileDiff::new(&base, &target, true);
+        assert_eq!(diff.total_delta(), -15);
+        assert_eq!(diff.functions[0].function, name("a"));
+        assert_eq!(diff.functions[0].exclusive_delta(), -10);
+        assert!(diff.functions[1].target.is_none());
+    }
+}
@@ -3,7 +3,7 @@
 
 //! GQL Queries
 //! Interface to the rpc for the gql schema defined in `crates\sui-indexer-alt-graphql/schema.graphql`.
-//! Built in 3 modules: epoch_query, txn_query, object_query.
+//! Built in 5 modules: epoch_query, txn_query, event_query, checkpoint_query, object_query.
 //! No GQL type escapes this module. From here we return structures defined by the replay tool
 //! or bcs encoded data of runtime structures.
 //!
@@ -207,6 +207,191 @@ pub mod txn_query {
     }
 }
 
+pub mod event_query {
+    use super::*;
+    use anyhow::Context;
+    use sui_types::event::Event;
+
+    #[derive(cynic::Scalar, Debug, Clone)]
+    #[cynic(graphql_type = "Base64")]
+    pub struct Base64(pub String);
+
+    #[derive(cynic::QueryVariables)]
+    pub struct TransactionEventsArgs {
+        pub digest: String,
+        pub after: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(variables = "TransactionEventsArgs")]
+    pub struct Query {
+        #[arguments(digest: $digest)]
+        transaction: Option<Transaction>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(variables = "TransactionEventsArgs")]
+    pub struct Transaction {
+        effects: Option<TransactionEffects>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(variables = "TransactionEventsArgs")]
+    pub struct TransactionEffects {
+        #[arguments(after: $after)]
+        events: Option<EventConnection>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    pub struct EventConnection {
+        page_info: PageInfo,
+        nodes: Vec<EventFragment>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    pub struct PageInfo {
+        has_next_page: bool,
+        end_cursor: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(graphql_type = "Event")]
+    pub struct EventFragment {
+        event_bcs: Option<Base64>,
+    }
+
+    pub async fn query(
+        digest: String,
+        data_store: &DataStore,
+    ) -> Result<Vec<Event>, anyhow::Error> {
+        let mut events = vec![];
+        let mut after = None;
+        loop {
+            let query = Query::build(TransactionEventsArgs {
+                digest: digest.clone(),
+                after,
+            });
+            let response = data_store
+                .run_query(&query)
+                .await
+                .context("Failed to run transaction events query")?;
+
+            let Some(connection) = response
+                .data
+                .and_then(|data| data.transaction)
+                .and_then(|txn| txn.effects)
+                .and_then(|effects| effects.events)
+            else {
+                anyhow::bail!(
+                    "Missing events in transaction events response for digest {}. Errors: {:?}",
+                    digest,
+                    response.errors,
+                );
+            };
+
+            for event in connection.nodes {
+                let b64 = event
+                    .event_bcs
+                    .ok_or_else(|| anyhow::anyhow!("Event bcs is None for digest {}", digest))?
+                    .0;
+                let bytes = CryptoBase64::decode(&b64)
+                    .context(format!("Event does not decode for digest: {}", digest))?;
+                events.push(
+                    bcs::from_bytes(&bytes)
+                        .context(format!("Cannot deserialize event for digest {}", digest))?,
+                );
+            }
+
+            if !connection.page_info.has_next_page {
+                break;
+            }
+            after = connection.page_info.end_cursor;
+        }
+        Ok(events)
+    }
+}
+
+pub mod checkpoint_query {
+    use super::*;
+    use anyhow::Context;
+
+    #[derive(cynic::QueryVariables)]
+    pub struct CheckpointTransactionsArgs {
+        pub sequence_number: Option<u64>,
+        pub after: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(variables = "CheckpointTransactionsArgs")]
+    pub struct Query {
+        #[arguments(sequenceNumber: $sequence_number)]
+        checkpoint: Option<Checkpoint>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    #[cynic(variables = "CheckpointTransactionsArgs")]
+    pub struct Checkpoint {
+        #[arguments(after: $after)]
+        transactions: Option<TransactionConnection>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    pub struct TransactionConnection {
+        page_info: PageInfo,
+        nodes: Vec<Transaction>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    pub struct PageInfo {
+        has_next_page: bool,
+        end_cursor: Option<String>,
+    }
+
+    #[derive(cynic::QueryFragment)]
+    pub struct Transaction {
+        digest: String,
+    }
+
+    // Return the digests of the transactions in a checkpoint, in checkpoint order.
+    pub async fn query(
+        checkpoint: u64,
+        data_store: &DataStore,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let mut digests = vec![];
+        let mut after = None;
+        loop {
+            let query = Query::build(CheckpointTransactionsArgs {
+                sequence_number: Some(checkpoint),
+                after,
+            });
+            let response = data_store
+                .run_query(&query)
+                .await
+                .context("Failed to run checkpoint transactions query")?;
+
+            let Some(connection) = response
+                .data
+                .and_then(|data| data.checkpoint)
+                .and_then(|checkpoint| checkpoint.transactions)
+            else {
+                anyhow::bail!(
+                    "Missing transactions in checkpoint response for checkpoint {}. Errors: {:?}",
+                    checkpoint,
+                    response.errors,
+                );
+            };
+
+            digests.extend(connection.nodes.into_iter().map(|txn| txn.digest));
+
+            if !connection.page_info.has_next_page {
+                break;
+            }
+            after = connection.page_info.end_cursor;
+        }
+        Ok(digests)
+    }
+}
+
 pub mod object_query {
     use sui_types::object::Object;
 
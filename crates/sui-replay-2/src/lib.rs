@@ -3,27 +3,40 @@
 
 use crate::artifacts::{Artifact, ArtifactManager};
 use crate::build::BuildCmdConfig;
+use crate::checkpoint_replay::{replay_checkpoint_range, CheckpointRange, CheckpointReplayOptions};
 use crate::data_store::DataStore;
 use crate::displays::Pretty;
-use crate::replay_txn::replay_transaction;
+use crate::gas_profile::GasProfileDiffConfig;
+use crate::package_overrides::{PackageOverrideArg, PackageOverrides};
+use crate::replay_txn::{replay_transaction, ReplayOutcome};
+use crate::summary::{write_summary, ReplayStatus, SummaryFormat, TransactionSummary};
+use crate::tracing::{PackageSourceArg, TraceOptions};
 use anyhow::{anyhow, bail};
 use clap::{Parser, Subcommand};
+use futures::StreamExt;
 use similar::{ChangeTag, TextDiff};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use sui_json_rpc_types::SuiTransactionBlockEffects;
 use sui_types::effects::TransactionEffects;
 use sui_types::supported_protocol_versions::Chain;
+use tokio::sync::Semaphore;
 
 pub mod artifacts;
 pub mod build;
+pub mod checkpoint_replay;
 pub mod data_store;
 pub mod displays;
 pub mod execution;
+pub mod gas_profile;
 pub mod gql_queries;
+pub mod package_overrides;
 pub mod replay_interface;
 pub mod replay_txn;
+pub mod summary;
 pub mod tracing;
 
 const DEFAULT_OUTPUT_DIR: &str = ".replay";
@@ -54,6 +67,8 @@ pub enum Commands {
     /// Build and prepare replay data
     #[clap(alias = "b")]
     Build(BuildCmdConfig),
+    /// Compare the gas profiles of two replays
+    GasProfileDiff(GasProfileDiffConfig),
 }
 
 /// Arguments for the (implicit) replay command.
@@ -65,6 +80,11 @@ pub struct ReplayConfig {
     /// File containing a list of digest, one per line.
     #[arg(long)]
     pub digests_path: Option<PathBuf>,
+    /// Replay all transactions in a range of checkpoints (`<start>..<end>`, inclusive, or a
+    /// single checkpoint) in checkpoint order. Objects written by a transaction are used as
+    /// inputs of the following ones and divergences from the on-chain effects are reported.
+    #[arg(long, value_name = "START..END", conflicts_with_all = ["digest", "digests_path"])]
+    pub checkpoints: Option<CheckpointRange>,
     /// RPC of the fullnode used to replay the transaction.
     #[arg(long, short, default_value = "mainnet")]
     pub node: Node,
@@ -72,6 +92,16 @@ pub struct ReplayConfig {
     /// directory (or `<cur_dir>/.replay/<digest>` if none provided).
     #[arg(long = "trace", default_value = "false")]
     pub trace: bool,
+    /// Whether to produce a per-function gas profile of the transaction execution.
+    /// The profile is computed from the execution trace, so this implies `--trace`.
+    #[arg(long = "gas-profile", default_value = "false")]
+    pub gas_profile: bool,
+    /// Save the sources of a package with the trace so that the trace can be debugged at the
+    /// source level. Specified as `<package-id>=<path>` where `<package-id>` is the on-chain
+    /// (storage) id of the package and `<path>` is the package built with the `build`
+    /// subcommand. Requires `--trace`. Can be repeated.
+    #[arg(long = "source", value_name = "PACKAGE_ID=PATH")]
+    pub sources: Vec<PackageSourceArg>,
     /// Terminate a batch replay early if an error occurs when replaying one of the transactions.
     #[arg(long, default_value = "false")]
     pub terminate_early: bool,
@@ -85,6 +115,26 @@ pub struct ReplayConfig {
     /// should be overwritten or an error raised if they already exist.
     #[arg(long, default_value = "false")]
     pub overwrite_existing: bool,
+    /// Replay with a local version of a package in place of the on-chain one ("what-if" replay).
+    /// Specified as `[<package-id>=]<path>` where `<path>` is a Move package (defaulting to its
+    /// `published-at` id) or a directory of compiled modules. Can be repeated.
+    /// The new effects, events and gas are compared against the on-chain ones.
+    #[arg(long = "package-override", value_name = "[PACKAGE_ID=]PATH")]
+    pub package_overrides: Vec<PackageOverrideArg>,
+    /// Number of transactions replayed concurrently in a batch replay.
+    #[arg(long, default_value = "1")]
+    pub workers: usize,
+    /// Maximum time in seconds allowed to replay a single transaction. A transaction that
+    /// times out is reported as an error.
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+    /// File to save a summary of the replay to, with the status (match, forked, error),
+    /// gas delta and wall time of every transaction.
+    #[arg(long)]
+    pub summary_path: Option<PathBuf>,
+    /// Format of the summary file.
+    #[arg(long, value_enum, default_value = "json")]
+    pub summary_format: SummaryFormat,
 }
 
 /// Enum around rpc gql endpoints.
@@ -127,11 +177,19 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
         node,
         digest,
         digests_path,
+        checkpoints,
         trace,
+        gas_profile,
+        sources,
         mut terminate_early,
         output_dir,
         show_effects: _,
         overwrite_existing,
+        package_overrides,
+        workers,
+        timeout_secs,
+        summary_path,
+        summary_format,
     } = config;
 
     let output_root_dir = if let Some(dir) = output_dir {
@@ -145,13 +203,21 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
 
     // If trying to trace but the binary was not built with the tracing feature flag raise an error.
     #[cfg(not(feature = "tracing"))]
-    if *trace {
+    if *trace || *gas_profile {
         bail!(
             "Tracing is not enabled in this build. Please rebuild with the \
             `tracing` feature (`--features tracing`) to use tracing in replay"
         );
     }
 
+    if !sources.is_empty() && !(*trace || *gas_profile) {
+        bail!("--source requires --trace");
+    }
+    let trace_options = (*trace || *gas_profile).then(|| TraceOptions {
+        gas_profile: *gas_profile,
+        sources: sources.clone(),
+    });
+
     // If a file is specified it is read and the digest ignored.
     // Once we decide on the options we want this is likely to change.
     let digests = if let Some(digests_path) = digests_path {
@@ -165,14 +231,18 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
             })?
             .lines()
             .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
             .collect::<Vec<_>>()
     } else if let Some(tx_digest) = digest {
         // terminate early if a single digest is provided this way we get proper error messages from
         terminate_early = true;
         // single digest provided
         vec![tx_digest.clone()]
+    } else if checkpoints.is_some() {
+        // digests are loaded per checkpoint
+        vec![]
     } else {
-        bail!("either --digest or --digests-path must be provided");
+        bail!("either --digest, --digests-path or --checkpoints must be provided");
     };
 
     ::tracing::debug!("Binary version: {version}");
@@ -181,14 +251,57 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
     let data_store = DataStore::new(node.clone(), version)
         .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
 
-    // load and replay transactions
-    for tx_digest in digests {
-        let tx_dir = output_root_dir.join(&tx_digest);
-        let artifact_manager =
-            ArtifactManager::new(&tx_dir, *overwrite_existing /* overrides_allowed */)?;
-        match replay_transaction(&artifact_manager, &tx_digest, &data_store, *trace).await {
+    let overrides = PackageOverrides::load(package_overrides, node)?;
+
+    if let Some(range) = checkpoints {
+        if *workers > 1 {
+            ::tracing::warn!(
+                "Transactions in a checkpoint range are replayed sequentially, ignoring --workers"
+            );
+        }
+        let options = CheckpointReplayOptions {
+            output_root_dir: &output_root_dir,
+            overwrite_existing: *overwrite_existing,
+            trace: trace_options.as_ref(),
+            terminate_early,
+        };
+        let mut summaries = vec![];
+        let result =
+            replay_checkpoint_range(range, &data_store, &overrides, &options, &mut summaries).await;
+        log_summaries(&summaries);
+        if let Some(path) = summary_path {
+            write_summary(path, *summary_format, &summaries)?;
+        }
+        result?;
+        return Ok(output_root_dir);
+    }
+
+    let workers = (*workers).max(1);
+    let batch = Arc::new(BatchReplay {
+        data_store,
+        running: Arc::new(Semaphore::new(workers)),
+        overrides,
+        output_root_dir: output_root_dir.clone(),
+        trace: trace_options,
+        overwrite_existing: *overwrite_existing,
+    });
+    let timeout = timeout_secs.map(Duration::from_secs);
+
+    // load and replay transactions, `workers` at a time. Results are collected in the order
+    // of the digests.
+    let mut replays = futures::stream::iter(digests)
+        .map(|tx_digest| batch.clone().replay_with_timeout(tx_digest, timeout))
+        .buffered(workers);
+    let mut summaries = vec![];
+    while let Some((summary, result)) = replays.next().await {
+        let tx_digest = summary.digest.clone();
+        summaries.push(summary);
+        match result {
             Err(e) if terminate_early => {
                 ::tracing::error!("Error while replaying transaction {}: {:?}", tx_digest, e);
+                if let Some(path) = summary_path {
+                    write_summary(path, *summary_format, &summaries)?;
+                }
                 bail!("Replay terminated due to error: {}", e);
             }
             Err(e) => {
@@ -200,9 +313,104 @@ pub async fn handle_replay_config(config: &ReplayConfig, version: &str) -> anyho
         }
     }
 
+    log_summaries(&summaries);
+    if let Some(path) = summary_path {
+        write_summary(path, *summary_format, &summaries)?;
+    }
+
     Ok(output_root_dir)
 }
 
+fn log_summaries(summaries: &[TransactionSummary]) {
+    let count = |status| summaries.iter().filter(|s| s.status == status).count();
+    ::tracing::info!(
+        "Replayed {} transactions: {} matched, {} forked, {} failed",
+        summaries.len(),
+        count(ReplayStatus::Match),
+        count(ReplayStatus::Forked),
+        count(ReplayStatus::Error),
+    );
+    for summary in summaries
+        .iter()
+        .filter(|s| s.status == ReplayStatus::Forked)
+    {
+        ::tracing::warn!(
+            "Transaction {} diverged from on-chain effects{}",
+            summary.digest,
+            summary
+                .checkpoint
+                .map(|cp| format!(" (checkpoint {cp})"))
+                .unwrap_or_default(),
+        );
+    }
+}
+
+// Shared state for the transactions of a batch replay.
+struct BatchReplay {
+    data_store: DataStore,
+    // Replays running on blocking threads, including those that timed out. Bounds the number
+    // of replays in the background, as a replay cannot be interrupted.
+    running: Arc<Semaphore>,
+    overrides: PackageOverrides,
+    output_root_dir: PathBuf,
+    trace: Option<TraceOptions>,
+    overwrite_existing: bool,
+}
+
+impl BatchReplay {
+    // Replay a transaction on a blocking thread, giving up after `timeout` if provided.
+    // A replay that times out cannot be interrupted and keeps running in the background, holding
+    // its permit until it finishes, so that no more than `workers` replays ever run at once.
+    async fn replay_with_timeout(
+        self: Arc<Self>,
+        tx_digest: String,
+        timeout: Option<Duration>,
+    ) -> (TransactionSummary, anyhow::Result<ReplayOutcome>) {
+        let permit = self
+            .running
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Semaphore is never closed");
+        let start = Instant::now();
+        let digest = tx_digest.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            let result = self.replay(&digest);
+            drop(permit);
+            result
+        });
+        let joined = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, handle)
+                .await
+                .unwrap_or_else(|_| {
+                    Ok(Err(anyhow!(
+                        "Replay timed out after {} seconds",
+                        timeout.as_secs()
+                    )))
+                }),
+            None => handle.await,
+        };
+        let result = joined.unwrap_or_else(|e| Err(anyhow!("Replay task failed: {e}")));
+        let summary = TransactionSummary::new(tx_digest, &result, start.elapsed());
+        (summary, result)
+    }
+
+    fn replay(&self, tx_digest: &str) -> anyhow::Result<ReplayOutcome> {
+        let tx_dir = self.output_root_dir.join(tx_digest);
+        let artifact_manager = ArtifactManager::new(
+            &tx_dir,
+            self.overwrite_existing, /* overrides_allowed */
+        )?;
+        tokio::runtime::Handle::current().block_on(replay_transaction(
+            &artifact_manager,
+            tx_digest,
+            &self.data_store,
+            self.trace.as_ref(),
+            &self.overrides,
+        ))
+    }
+}
+
 pub fn print_effects_or_fork<W: Write>(
     digest: &str,
     output_root: &Path,
@@ -211,7 +419,30 @@ pub fn print_effects_or_fork<W: Write>(
 ) -> anyhow::Result<()> {
     let output_dir = output_root.join(digest);
     let manager = ArtifactManager::new(&output_dir, false)?;
-    if manager.member(Artifact::ForkedTransactionEffects).exists() {
+    let override_report = manager.member(Artifact::OverrideReport);
+    if override_report.exists() {
+        let report = override_report
+            .try_get_override_report()
+            .transpose()?
+            .unwrap();
+        writeln!(
+            w,
+            "Replay of {digest} with package overrides\n{}",
+            Pretty(&report)
+        )?;
+        if !report.effects_match {
+            let forked_effects = manager
+                .member(Artifact::ForkedTransactionEffects)
+                .try_get_transaction_effects()
+                .transpose()?
+                .unwrap();
+            writeln!(
+                w,
+                "Transaction Effects Diff for {digest}\n{}",
+                diff_effects(&report.original_effects, &forked_effects)
+            )?;
+        }
+    } else if manager.member(Artifact::ForkedTransactionEffects).exists() {
         writeln!(w, "Transaction {digest} forked")?;
         let forked_effects = manager
             .member(Artifact::ForkedTransactionEffects)
@@ -255,6 +486,15 @@ pub fn print_effects_or_fork<W: Write>(
             .unwrap_or_else(|| {
                 writeln!(w, "No gas report available for transaction {digest}").unwrap();
             });
+        let gas_profile = manager.member(Artifact::GasProfile);
+        if gas_profile.exists() {
+            let profile = gas_profile.try_get_gas_profile().transpose()?.unwrap();
+            writeln!(
+                w,
+                "Transaction Gas Profile for {digest}\n{}",
+                Pretty(&profile)
+            )?;
+        }
     }
     Ok(())
 }
@@ -4,7 +4,8 @@
 use clap::*;
 use core::panic;
 use sui_replay_2::{
-    build::handle_build_command, handle_replay_config, print_effects_or_fork, Commands, Config,
+    build::handle_build_command, gas_profile::handle_gas_profile_diff_command,
+    handle_replay_config, print_effects_or_fork, Commands, Config,
 };
 use tracing::debug;
 
@@ -24,6 +25,9 @@ async fn main() -> anyhow::Result<()> {
         Some(Commands::Build(build_config)) => {
             handle_build_command(build_config)?;
         }
+        Some(Commands::GasProfileDiff(diff_config)) => {
+            handle_gas_profile_diff_command(diff_config)?;
+        }
         None => {
             let output_root = handle_replay_config(&config.replay, VERSION).await?;
 
@@ -0,0 +1,541 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Package overrides for "what-if" replays.
+//! A package override replaces the modules of an on-chain package with a locally built
+//! version of that package when the package is loaded at replay time.
+//! Overrides are applied by `OverrideObjectStore`, an `ObjectStore` that wraps the store used
+//! for the replay and patches any package it returns. The on-chain package is still fetched,
+//! and its id, version and linkage table are preserved, so the override behaves as if the
+//! on-chain package contained the local modules.
+//!
+//! An override is specified as `[<package-id>=]<path>` where `<path>` is either the root of a
+//! Move package, which is built and defaults to the package's `published-at` id, or a directory
+//! of compiled modules (`.mv` files), in which case the package id is required.
+
+use crate::{
+    replay_interface::{ObjectKey, ObjectStore},
+    Node,
+};
+use anyhow::{anyhow, bail, Context};
+use move_binary_format::CompiledModule;
+use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
+use move_package::{source_package::layout::SourcePackageLayout, BuildConfig as MoveBuildConfig};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+use sui_move_build::{implicit_deps, set_sui_flavor, BuildConfig};
+use sui_package_management::system_package_versions::latest_system_packages;
+use sui_types::{
+    base_types::ObjectID,
+    digests::{get_mainnet_chain_identifier, get_testnet_chain_identifier},
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    event::Event,
+    execution_status::ExecutionStatus,
+    gas::GasCostSummary,
+    move_package::{MovePackage, TypeOrigin},
+    object::{Data, Object, ObjectInner},
+};
+use tracing::debug;
+
+/// A package override as provided on the command line: `[<package-id>=]<path>`.
+#[derive(Clone, Debug)]
+pub struct PackageOverrideArg {
+    pub package_id: Option<ObjectID>,
+    pub path: PathBuf,
+}
+
+/// Modules to use in place of those of an on-chain package, keyed by module name.
+pub type OverrideModules = BTreeMap<String, Vec<u8>>;
+
+/// The set of packages to override in a replay, keyed by the storage id of the on-chain
+/// package being replaced.
+#[derive(Clone, Debug, Default)]
+pub struct PackageOverrides {
+    overrides: BTreeMap<ObjectID, OverrideModules>,
+}
+
+/// An `ObjectStore` that replaces overridden packages with their local version.
+pub struct OverrideObjectStore<'a> {
+    store: &'a dyn ObjectStore,
+    overrides: &'a PackageOverrides,
+}
+
+impl FromStr for PackageOverrideArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((id, path)) => Ok(PackageOverrideArg {
+                package_id: Some(
+                    ObjectID::from_hex_literal(id)
+                        .map_err(|e| format!("Invalid package id {id}: {e}"))?,
+                ),
+                path: PathBuf::from(path),
+            }),
+            None => Ok(PackageOverrideArg {
+                package_id: None,
+                path: PathBuf::from(s),
+            }),
+        }
+    }
+}
+
+impl PackageOverrides {
+    /// Load all overrides, building source packages with respect to the chain of `node`.
+    pub fn load(args: &[PackageOverrideArg], node: &Node) -> anyhow::Result<Self> {
+        let mut overrides = BTreeMap::new();
+        for arg in args {
+            let (package_id, modules) =
+                if arg.path.join(SourcePackageLayout::Manifest.path()).exists() {
+                    build_override_package(arg, node)?
+                } else {
+                    let package_id = arg.package_id.ok_or_else(|| {
+                        anyhow!(
+                            "A package id is required to override a package with compiled modules \
+                        (use `<package-id>={}`)",
+                            arg.path.display()
+                        )
+                    })?;
+                    (package_id, read_compiled_modules(arg)?)
+                };
+            debug!(
+                "Overriding package {} with {} modules from {}",
+                package_id,
+                modules.len(),
+                arg.path.display()
+            );
+            if overrides.insert(package_id, modules).is_some() {
+                bail!("Package {} is overridden more than once", package_id);
+            }
+        }
+        Ok(Self { overrides })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn package_ids(&self) -> impl Iterator<Item = &ObjectID> {
+        self.overrides.keys()
+    }
+
+    /// Return the overridden version of `object` if it is an overridden package,
+    /// `object` itself otherwise.
+    pub fn apply(&self, object: Object) -> anyhow::Result<Object> {
+        let Some(modules) = self.overrides.get(&object.id()) else {
+            return Ok(object);
+        };
+        let Data::Package(pkg) = &object.data else {
+            bail!(
+                "Object {} is overridden but it is not a package",
+                object.id()
+            );
+        };
+        check_override_addresses(pkg, modules)?;
+        let type_origin_table = override_type_origins(pkg, modules)?;
+        let new_pkg = MovePackage::new(
+            pkg.id(),
+            pkg.version(),
+            modules.clone(),
+            u64::MAX, // overrides are not subject to the size limit
+            type_origin_table,
+            pkg.linkage_table().clone(),
+        )
+        .map_err(|e| anyhow!("Failed to create override for package {}: {e}", pkg.id()))?;
+        Ok(ObjectInner {
+            data: Data::Package(new_pkg),
+            owner: object.owner.clone(),
+            previous_transaction: object.previous_transaction,
+            storage_rebate: object.storage_rebate,
+        }
+        .into())
+    }
+}
+
+impl<'a> OverrideObjectStore<'a> {
+    pub fn new(store: &'a dyn ObjectStore, overrides: &'a PackageOverrides) -> Self {
+        Self { store, overrides }
+    }
+}
+
+impl ObjectStore for OverrideObjectStore<'_> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<Object>>, anyhow::Error> {
+        self.store
+            .get_objects(keys)?
+            .into_iter()
+            .map(|object| object.map(|obj| self.overrides.apply(obj)).transpose())
+            .collect()
+    }
+}
+
+// Build the source package at `arg.path` and return the id of the package to override and
+// the compiled modules of the package.
+fn build_override_package(
+    arg: &PackageOverrideArg,
+    node: &Node,
+) -> anyhow::Result<(ObjectID, OverrideModules)> {
+    let mut move_build_config = MoveBuildConfig::default();
+    if let Some(err_msg) = set_sui_flavor(&mut move_build_config) {
+        bail!(err_msg);
+    }
+    move_build_config.implicit_dependencies = implicit_deps(latest_system_packages());
+    let chain_id = match node {
+        Node::Mainnet => Some(get_mainnet_chain_identifier().to_string()),
+        Node::Testnet => Some(get_testnet_chain_identifier().to_string()),
+        Node::Custom(_) => None,
+    };
+    let pkg = BuildConfig {
+        config: move_build_config,
+        run_bytecode_verifier: true,
+        print_diags_to_stderr: true,
+        chain_id,
+    }
+    .build(&arg.path)
+    .map_err(|e| anyhow!("Failed to build package {}: {e}", arg.path.display()))?;
+
+    let package_id = match (arg.package_id, &pkg.published_at) {
+        (Some(id), _) => id,
+        (None, Ok(id)) => *id,
+        (None, Err(e)) => bail!(
+            "Cannot determine the package to override for {} ({e:?}), \
+            use `<package-id>={}`",
+            arg.path.display(),
+            arg.path.display()
+        ),
+    };
+    let modules = pkg
+        .get_modules()
+        .map(|module| {
+            let mut bytes = vec![];
+            module
+                .serialize_with_version(module.version, &mut bytes)
+                .map_err(|e| anyhow!("Failed to serialize module {}: {e}", module.self_id()))?;
+            Ok((module.name().to_string(), bytes))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok((package_id, modules))
+}
+
+// Read all compiled modules in the directory at `arg.path`.
+fn read_compiled_modules(arg: &PackageOverrideArg) -> anyhow::Result<OverrideModules> {
+    let mut modules = BTreeMap::new();
+    let entries = std::fs::read_dir(&arg.path)
+        .with_context(|| format!("Failed to read directory {}", arg.path.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(MOVE_COMPILED_EXTENSION) {
+            continue;
+        }
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read module {}", path.display()))?;
+        let module = CompiledModule::deserialize_with_defaults(&bytes)
+            .with_context(|| format!("Failed to deserialize module {}", path.display()))?;
+        modules.insert(module.name().to_string(), bytes);
+    }
+    if modules.is_empty() {
+        bail!("No compiled modules found in {}", arg.path.display());
+    }
+    Ok(modules)
+}
+
+// Check that all override modules are at the original id of the package they override, as
+// the modules of the on-chain package are.
+fn check_override_addresses(pkg: &MovePackage, modules: &OverrideModules) -> anyhow::Result<()> {
+    let original_id = pkg.original_package_id();
+    for (name, bytes) in modules {
+        let module = CompiledModule::deserialize_with_defaults(bytes)
+            .with_context(|| format!("Failed to deserialize override module {name}"))?;
+        let address = ObjectID::from(*module.address());
+        if address != original_id {
+            bail!(
+                "Override module {name} of package {} is at address {address}, expected the \
+                original id of the package {original_id}",
+                pkg.id()
+            );
+        }
+    }
+    Ok(())
+}
+
+// Type origins for the overridden package: the origins of the on-chain package, plus
+// the package itself as the origin of any type only defined locally.
+fn override_type_origins(
+    pkg: &MovePackage,
+    modules: &OverrideModules,
+) -> anyhow::Result<Vec<TypeOrigin>> {
+    let mut type_origin_table = pkg.type_origin_table().clone();
+    let type_origins = pkg.type_origin_map();
+    for (name, bytes) in modules {
+        let module = CompiledModule::deserialize_with_defaults(bytes)
+            .with_context(|| format!("Failed to deserialize override module {name}"))?;
+        let datatype_handles = module
+            .struct_defs()
+            .iter()
+            .map(|def| def.struct_handle)
+            .chain(module.enum_defs().iter().map(|def| def.enum_handle));
+        for handle in datatype_handles {
+            let datatype_name = module
+                .identifier_at(module.datatype_handle_at(handle).name)
+                .to_string();
+            if !type_origins.contains_key(&(name.clone(), datatype_name.clone())) {
+                type_origin_table.push(TypeOrigin {
+                    module_name: name.clone(),
+                    datatype_name,
+                    package: pkg.id(),
+                });
+            }
+        }
+    }
+    Ok(type_origin_table)
+}
+
+/// Comparison between the on-chain execution of a transaction and its replay with package
+/// overrides. Saved as the `OverrideReport` artifact.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OverrideReport {
+    pub overridden_packages: Vec<ObjectID>,
+    pub effects_match: bool,
+    pub original_effects: TransactionEffects,
+    pub original_status: ExecutionStatus,
+    pub replay_status: ExecutionStatus,
+    pub original_gas: GasCostSummary,
+    pub replay_gas: GasCostSummary,
+    pub original_events: Vec<Event>,
+    pub replay_events: Vec<Event>,
+}
+
+impl OverrideReport {
+    pub fn new(
+        overrides: &PackageOverrides,
+        original_effects: &TransactionEffects,
+        replay_effects: &TransactionEffects,
+        original_events: Vec<Event>,
+        replay_events: Vec<Event>,
+    ) -> Self {
+        Self {
+            overridden_packages: overrides.package_ids().copied().collect(),
+            effects_match: original_effects == replay_effects,
+            original_effects: original_effects.clone(),
+            original_status: original_effects.status().clone(),
+            replay_status: replay_effects.status().clone(),
+            original_gas: original_effects.gas_cost_summary().clone(),
+            replay_gas: replay_effects.gas_cost_summary().clone(),
+            original_events,
+            replay_events,
+        }
+    }
+
+    pub fn events_match(&self) -> bool {
+        self.original_events == self.replay_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{basic_test_module, basic_test_module_with_enum};
+    use sui_types::{
+        base_types::{SequenceNumber, SuiAddress},
+        digests::TransactionDigest,
+        move_package::UpgradeInfo,
+    };
+
+    fn module_bytes(module: &CompiledModule) -> Vec<u8> {
+        let mut bytes = vec![];
+        module
+            .serialize_with_version(module.version, &mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    fn modules(module: &CompiledModule) -> OverrideModules {
+        BTreeMap::from([(module.name().to_string(), module_bytes(module))])
+    }
+
+    fn at_address(mut module: CompiledModule, address: ObjectID) -> CompiledModule {
+        let address_idx = module.self_handle().address;
+        module.address_identifiers[address_idx.0 as usize] = address.into();
+        module
+    }
+
+    // An upgraded package whose `Bar` type originates from the original package.
+    fn on_chain_package(id: ObjectID, original_id: ObjectID) -> MovePackage {
+        MovePackage::new(
+            id,
+            SequenceNumber::from_u64(2),
+            modules(&at_address(basic_test_module(), original_id)),
+            u64::MAX,
+            vec![TypeOrigin {
+                module_name: "DUMMY".to_string(),
+                datatype_name: "Bar".to_string(),
+                package: original_id,
+            }],
+            BTreeMap::from([(
+                ObjectID::from_single_byte(2),
+                UpgradeInfo {
+                    upgraded_id: ObjectID::from_single_byte(2),
+                    upgraded_version: SequenceNumber::from_u64(1),
+                },
+            )]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_override_arg() {
+        let arg: PackageOverrideArg = "0x42=path/to/pkg".parse().unwrap();
+        assert_eq!(arg.package_id, Some(ObjectID::from_single_byte(0x42)));
+        assert_eq!(arg.path, PathBuf::from("path/to/pkg"));
+
+        let arg: PackageOverrideArg = "path/to/pkg".parse().unwrap();
+        assert_eq!(arg.package_id, None);
+        assert_eq!(arg.path, PathBuf::from("path/to/pkg"));
+
+        assert!("not_an_id=path".parse::<PackageOverrideArg>().is_err());
+        assert!("=path".parse::<PackageOverrideArg>().is_err());
+    }
+
+    #[test]
+    fn type_origins_of_new_types() {
+        let (id, original_id) = (ObjectID::random(), ObjectID::random());
+        let pkg = on_chain_package(id, original_id);
+        let origins =
+            override_type_origins(&pkg, &modules(&basic_test_module_with_enum())).unwrap();
+        // Existing types keep their origin, new ones originate from the overridden package
+        assert_eq!(
+            origins,
+            vec![
+                TypeOrigin {
+                    module_name: "DUMMY".to_string(),
+                    datatype_name: "Bar".to_string(),
+                    package: original_id,
+                },
+                TypeOrigin {
+                    module_name: "DUMMY".to_string(),
+                    datatype_name: "enum".to_string(),
+                    package: id,
+                },
+            ]
+        );
+
+        let malformed = BTreeMap::from([("DUMMY".to_string(), vec![0xde, 0xad])]);
+        assert!(override_type_origins(&pkg, &malformed).is_err());
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let (id, original_id) = (ObjectID::random(), ObjectID::random());
+        let pkg = on_chain_package(id, original_id);
+        let override_modules = modules(&at_address(basic_test_module_with_enum(), original_id));
+        let overrides = PackageOverrides {
+            overrides: BTreeMap::from([(id, override_modules.clone())]),
+        };
+
+        let object = Object::new_from_package(pkg.clone(), TransactionDigest::genesis_marker());
+        let Data::Package(overridden) = overrides.apply(object).unwrap().data else {
+            panic!("Override is not a package");
+        };
+        assert_eq!(overridden.id(), id);
+        assert_eq!(overridden.version(), pkg.version());
+        assert_eq!(overridden.linkage_table(), pkg.linkage_table());
+        assert_eq!(overridden.serialized_module_map(), &override_modules);
+        assert_eq!(overridden.type_origin_table().len(), 2);
+
+        // Other packages are untouched
+        let other = Object::new_from_package(
+            on_chain_package(ObjectID::random(), original_id),
+            TransactionDigest::genesis_marker(),
+        );
+        assert_eq!(overrides.apply(other.clone()).unwrap(), other);
+
+        // Only packages can be overridden
+        let overrides = PackageOverrides {
+            overrides: BTreeMap::from([(id, override_modules)]),
+        };
+        let object = Object::with_id_owner_for_testing(id, SuiAddress::ZERO);
+        assert!(overrides.apply(object).is_err());
+    }
+
+    #[test]
+    fn apply_overrides_at_wrong_address() {
+        let (id, original_id) = (ObjectID::random(), ObjectID::random());
+        let object = Object::new_from_package(
+            on_chain_package(id, original_id),
+            TransactionDigest::genesis_marker(),
+        );
+
+        // Modules must be at the original id of the package, not at 0x0 or its storage id
+        for address in [ObjectID::ZERO, id] {
+            let overrides = PackageOverrides {
+                overrides: BTreeMap::from([(
+                    id,
+                    modules(&at_address(basic_test_module_with_enum(), address)),
+                )]),
+            };
+            let err = overrides.apply(object.clone()).unwrap_err().to_string();
+            assert!(err.contains(&address.to_string()), "{err}");
+            assert!(err.contains(&original_id.to_string()), "{err}");
+        }
+    }
+
+    #[test]
+    fn load_compiled_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("DUMMY.mv"),
+            module_bytes(&basic_test_module()),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a module").unwrap();
+        let id = ObjectID::random();
+
+        // A package id is required for compiled modules
+        let arg = PackageOverrideArg {
+            package_id: None,
+            path: dir.path().to_owned(),
+        };
+        assert!(PackageOverrides::load(&[arg], &Node::Mainnet).is_err());
+
+        let arg = PackageOverrideArg {
+            package_id: Some(id),
+            path: dir.path().to_owned(),
+        };
+        let overrides = PackageOverrides::load(&[arg.clone()], &Node::Mainnet).unwrap();
+        assert_eq!(overrides.package_ids().collect::<Vec<_>>(), vec![&id]);
+        assert_eq!(overrides.overrides[&id], modules(&basic_test_module()),);
+        assert!(PackageOverrides::load(&[arg.clone(), arg], &Node::Mainnet).is_err());
+
+        let empty = tempfile::tempdir().unwrap();
+        let arg = PackageOverrideArg {
+            package_id: Some(id),
+            path: empty.path().to_owned(),
+        };
+        assert!(PackageOverrides::load(&[arg], &Node::Mainnet).is_err());
+    }
+
+    #[test]
+    fn override_report() {
+        let overrides = PackageOverrides {
+            overrides: BTreeMap::from([(ObjectID::from_single_byte(0x42), BTreeMap::new())]),
+        };
+        let effects = TransactionEffects::default();
+        let event = Event::random_for_testing();
+        let report = OverrideReport::new(
+            &overrides,
+            &effects,
+            &effects,
+            vec![event.clone()],
+            vec![event.clone()],
+        );
+        assert_eq!(
+            report.overridden_packages,
+            vec![ObjectID::from_single_byte(0x42)]
+        );
+        assert!(report.effects_match);
+        assert!(report.events_match());
+        assert_eq!(report.original_gas, report.replay_gas);
+
+        let report = OverrideReport::new(&overrides, &effects, &effects, vec![event], vec![]);
+        assert!(!report.events_match());
+    }
+}
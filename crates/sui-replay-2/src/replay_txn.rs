@@ -13,9 +13,11 @@
 use crate::{
     artifacts::{Artifact, ArtifactManager},
     data_store::DataStore,
-    execution::{execute_transaction_to_effects, ReplayExecutor},
+    execution::{execute_transaction_to_effects, ReplayExecutor, TxnContextAndEffects},
+    gas_profile::GasProfile,
+    package_overrides::{OverrideObjectStore, OverrideReport, PackageOverrides},
     replay_interface::{EpochStore, ObjectKey, ObjectStore, TransactionStore, VersionQuery},
-    tracing::save_trace_output,
+    tracing::{save_trace_output, TraceOptions},
 };
 use anyhow::{anyhow, bail, Context};
 use move_trace_format::format::MoveTraceBuilder;
@@ -23,10 +25,11 @@ use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
 use sui_types::{base_types::SequenceNumber, TypeTag};
 use sui_types::{
     base_types::{ObjectID, SuiAddress},
-    digests::TransactionDigest,
+    digests::{TransactionDigest, TransactionEffectsDigest},
     effects::{
         InputConsensusObject, TransactionEffects, TransactionEffectsAPI, UnchangedConsensusKind,
     },
+    message_envelope::Message,
     object::Object,
     transaction::{
         CallArg, Command, GasData, InputObjects, ObjectArg, TransactionData, TransactionDataAPI,
@@ -54,6 +57,30 @@ pub struct ReplayTransaction {
     pub object_cache: BTreeMap<ObjectID, BTreeMap<ObjectVersion, Object>>,
 }
 
+/// Result of a successful replay.
+#[derive(Clone, Debug)]
+pub struct ReplayOutcome {
+    /// Whether the replayed effects match the on-chain ones. Only `false` when replaying
+    /// with package overrides, otherwise a mismatch is a `ForkedTransactionError`.
+    pub effects_match: bool,
+    /// Gas used by the replay minus gas used on chain.
+    pub gas_delta: i64,
+}
+
+/// Error returned when the effects of a replay do not match the on-chain ones.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Transaction effects do not match expected effects for transaction {digest} \
+    (expected effects digest {expected_effects_digest}, got {effects_digest})"
+)]
+pub struct ForkedTransactionError {
+    pub digest: TransactionDigest,
+    pub expected_effects_digest: TransactionEffectsDigest,
+    pub effects_digest: TransactionEffectsDigest,
+    /// Gas used by the replay minus gas used on chain.
+    pub gas_delta: i64,
+}
+
 //
 // Run a single transaction and print results to stdout
 //
@@ -61,10 +88,37 @@ pub(crate) async fn replay_transaction(
     artifact_manager: &ArtifactManager<'_>,
     tx_digest: &str,
     data_store: &DataStore,
-    trace: bool,
-) -> anyhow::Result<()> {
+    trace: Option<&TraceOptions>,
+    overrides: &PackageOverrides,
+) -> anyhow::Result<ReplayOutcome> {
+    // packages in `overrides` are replaced with their local version when loaded
+    let object_store = OverrideObjectStore::new(data_store, overrides);
+    replay_transaction_with_store(
+        artifact_manager,
+        tx_digest,
+        data_store,
+        &object_store,
+        trace,
+        overrides,
+    )
+    .await
+    .map(|(outcome, _context_and_effects)| outcome)
+}
+
+// Replay a transaction loading objects from `object_store`.
+// Returns the execution context and effects along with the outcome, so that callers can
+// make use of the objects written by the transaction.
+pub(crate) async fn replay_transaction_with_store(
+    artifact_manager: &ArtifactManager<'_>,
+    tx_digest: &str,
+    data_store: &DataStore,
+    object_store: &dyn ObjectStore,
+    trace: Option<&TraceOptions>,
+    overrides: &PackageOverrides,
+) -> anyhow::Result<(ReplayOutcome, TxnContextAndEffects)> {
     // load a `ReplayTranaction`
-    let replay_txn = match ReplayTransaction::load(tx_digest, data_store, data_store, data_store) {
+    let replay_txn = match ReplayTransaction::load(tx_digest, data_store, data_store, object_store)
+    {
         Ok(replay_txn) => replay_txn,
         Err(e) => {
             bail!("Failed to load transaction {}: {:?}", tx_digest, e);
@@ -72,14 +126,24 @@ pub(crate) async fn replay_transaction(
     };
 
     // replay the transaction
-    let mut trace_builder_opt = trace.then(MoveTraceBuilder::new);
+    let mut trace_builder_opt = trace.is_some().then(MoveTraceBuilder::new);
 
-    let (result, context_and_effects) =
-        execute_transaction_to_effects(replay_txn, data_store, data_store, &mut trace_builder_opt)?;
+    let (result, context_and_effects) = execute_transaction_to_effects(
+        replay_txn,
+        data_store,
+        object_store,
+        &mut trace_builder_opt,
+    )?;
 
     // TODO: make tracing better abstracted? different tracers?
-    if let Some(trace_builder) = trace_builder_opt {
-        save_trace_output(artifact_manager, trace_builder, &context_and_effects).map_err(|e| {
+    if let (Some(trace_builder), Some(options)) = (trace_builder_opt, trace) {
+        save_trace_output(
+            artifact_manager,
+            trace_builder,
+            &context_and_effects,
+            &options.sources,
+        )
+        .map_err(|e| {
             anyhow!(
                 "transaction {} failed to build a trace output path -> {:?}",
                 tx_digest,
@@ -96,6 +160,21 @@ pub(crate) async fn replay_transaction(
         artifact_manager.base_path.display()
     );
 
+    if trace.is_some_and(|options| options.gas_profile) {
+        let profile = GasProfile::from_trace_artifact(artifact_manager).map_err(|e| {
+            anyhow!(
+                "transaction {} failed to build a gas profile -> {:?}",
+                tx_digest,
+                e
+            )
+        })?;
+        artifact_manager
+            .member(Artifact::GasProfile)
+            .serialize_artifact(&profile)
+            .transpose()?
+            .unwrap();
+    }
+
     artifact_manager
         .member(Artifact::TransactionEffects)
         .serialize_artifact(&context_and_effects.execution_effects)
@@ -108,13 +187,76 @@ pub(crate) async fn replay_transaction(
         .transpose()?
         .unwrap();
 
-    verify_txn_and_save_forked_effects(
-        artifact_manager,
-        &context_and_effects.expected_effects,
-        &context_and_effects.execution_effects,
-    )?;
+    let effects_match = if overrides.is_empty() {
+        verify_txn_and_save_forked_effects(
+            artifact_manager,
+            &context_and_effects.expected_effects,
+            &context_and_effects.execution_effects,
+        )?;
+        true
+    } else {
+        save_override_report(
+            artifact_manager,
+            tx_digest,
+            data_store,
+            overrides,
+            &context_and_effects,
+        )?
+    };
 
-    Ok(())
+    let outcome = ReplayOutcome {
+        effects_match,
+        gas_delta: gas_delta(
+            &context_and_effects.expected_effects,
+            &context_and_effects.execution_effects,
+        ),
+    };
+    Ok((outcome, context_and_effects))
+}
+
+// Gas used by the replay minus gas used on chain.
+fn gas_delta(expected_effects: &TransactionEffects, effects: &TransactionEffects) -> i64 {
+    effects.gas_cost_summary().gas_used() as i64
+        - expected_effects.gas_cost_summary().gas_used() as i64
+}
+
+// With package overrides the replay is expected to diverge from the on-chain execution,
+// so differences are recorded in an `OverrideReport` rather than reported as a fork error.
+// Returns whether the effects match.
+fn save_override_report(
+    artifact_manager: &ArtifactManager<'_>,
+    tx_digest: &str,
+    data_store: &DataStore,
+    overrides: &PackageOverrides,
+    context_and_effects: &TxnContextAndEffects,
+) -> anyhow::Result<bool> {
+    let expected_effects = &context_and_effects.expected_effects;
+    let effects = &context_and_effects.execution_effects;
+    let original_events = if expected_effects.events_digest().is_some() {
+        data_store.transaction_events(tx_digest)?
+    } else {
+        vec![]
+    };
+    let report = OverrideReport::new(
+        overrides,
+        expected_effects,
+        effects,
+        original_events,
+        context_and_effects.inner_store.events.data.clone(),
+    );
+    if !report.effects_match {
+        artifact_manager
+            .member(Artifact::ForkedTransactionEffects)
+            .serialize_artifact(effects)
+            .transpose()?
+            .unwrap();
+    }
+    artifact_manager
+        .member(Artifact::OverrideReport)
+        .serialize_artifact(&report)
+        .transpose()?
+        .unwrap();
+    Ok(report.effects_match)
 }
 
 fn verify_txn_and_save_forked_effects(
@@ -132,10 +274,13 @@ fn verify_txn_and_save_forked_effects(
             .serialize_artifact(effects)
             .transpose()?
             .unwrap();
-        bail!(
-            "Transaction effects do not match expected effects for transaction {}",
-            effects.transaction_digest()
-        );
+        Err(ForkedTransactionError {
+            digest: *effects.transaction_digest(),
+            expected_effects_digest: expected_effects.digest(),
+            effects_digest: effects.digest(),
+            gas_delta: gas_delta(expected_effects, effects),
+        }
+        .into())
     } else {
         Ok(())
     }
@@ -0,0 +1,213 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Machine-readable summary of a batch replay.
+//! Every replayed digest gets a `TransactionSummary` with the outcome of the replay, the gas
+//! delta against the on-chain execution and the wall time the replay took. The summary is
+//! written as JSON or CSV once the batch completes.
+
+use crate::replay_txn::{ForkedTransactionError, ReplayOutcome};
+use anyhow::anyhow;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::Duration};
+
+/// Outcome of the replay of a single transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayStatus {
+    /// The replayed effects match the on-chain effects.
+    Match,
+    /// The replayed effects differ from the on-chain effects.
+    Forked,
+    /// The transaction could not be replayed (including timeouts).
+    Error,
+}
+
+/// Summary of the replay of a single transaction.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub digest: String,
+    /// Checkpoint of the transaction, when replaying a checkpoint range.
+    pub checkpoint: Option<u64>,
+    pub status: ReplayStatus,
+    /// Gas used by the replay minus gas used on chain. Missing on error.
+    pub gas_delta: Option<i64>,
+    pub wall_time_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Format of the batch replay summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SummaryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl TransactionSummary {
+    pub fn new(
+        digest: String,
+        result: &anyhow::Result<ReplayOutcome>,
+        wall_time: Duration,
+    ) -> Self {
+        let wall_time_ms = wall_time.as_millis() as u64;
+        match result {
+            Ok(outcome) => Self {
+                digest,
+                checkpoint: None,
+                status: if outcome.effects_match {
+                    ReplayStatus::Match
+                } else {
+                    ReplayStatus::Forked
+                },
+                gas_delta: Some(outcome.gas_delta),
+                wall_time_ms,
+                error: None,
+            },
+            Err(e) => match e.downcast_ref::<ForkedTransactionError>() {
+                Some(fork) => Self {
+                    digest,
+                    checkpoint: None,
+                    status: ReplayStatus::Forked,
+                    gas_delta: Some(fork.gas_delta),
+                    wall_time_ms,
+                    error: None,
+                },
+                None => Self::error(digest, format!("{e:?}"), wall_time),
+            },
+        }
+    }
+
+    pub fn error(digest: String, error: String, wall_time: Duration) -> Self {
+        Self {
+            digest,
+            checkpoint: None,
+            status: ReplayStatus::Error,
+            gas_delta: None,
+            wall_time_ms: wall_time.as_millis() as u64,
+            error: Some(error),
+        }
+    }
+}
+
+/// Write the summaries to `path` in the given format.
+pub fn write_summary(
+    path: &Path,
+    format: SummaryFormat,
+    summaries: &[TransactionSummary],
+) -> anyhow::Result<()> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow!("Failed to create summary file {}: {e}", path.display()))?;
+    match format {
+        SummaryFormat::Json => serde_json::to_writer_pretty(file, summaries)
+            .map_err(|e| anyhow!("Failed to write JSON summary: {e}")),
+        SummaryFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(file);
+            for summary in summaries {
+                writer
+                    .serialize(summary)
+                    .map_err(|e| anyhow!("Failed to write CSV summary: {e}"))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| anyhow!("Failed to write CSV summary: {e}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::digests::{TransactionDigest, TransactionEffectsDigest};
+
+    fn summaries() -> Vec<TransactionSummary> {
+        let wall_time = Duration::from_millis(12);
+        let matched = Ok(ReplayOutcome {
+            effects_match: true,
+            gas_delta: 0,
+        });
+        let overridden = Ok(ReplayOutcome {
+            effects_match: false,
+            gas_delta: -5,
+        });
+        let forked = Err(anyhow!(ForkedTransactionError {
+            digest: TransactionDigest::new([1; 32]),
+            expected_effects_digest: TransactionEffectsDigest::new([2; 32]),
+            effects_digest: TransactionEffectsDigest::new([3; 32]),
+            gas_delta: 7,
+        }));
+        let failed = Err(anyhow!("missing object, version 3"));
+        let mut summaries = vec![
+            TransactionSummary::new("a".to_string(), &matched, wall_time),
+            TransactionSummary::new("b".to_string(), &overridden, wall_time),
+            TransactionSummary::new("c".to_string(), &forked, wall_time),
+            TransactionSummary::new("d".to_string(), &failed, wall_time),
+        ];
+        summaries[0].checkpoint = Some(42);
+        summaries
+    }
+
+    #[test]
+    fn summary_status() {
+        let summaries = summaries();
+        let statuses: Vec<_> = summaries.iter().map(|s| (s.status, s.gas_delta)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (ReplayStatus::Match, Some(0)),
+                (ReplayStatus::Forked, Some(-5)),
+                (ReplayStatus::Forked, Some(7)),
+                (ReplayStatus::Error, None),
+            ]
+        );
+        assert!(summaries[..3].iter().all(|s| s.error.is_none()));
+        assert_eq!(
+            summaries[3].error.as_deref(),
+            Some("missing object, version 3")
+        );
+        assert!(summaries.iter().all(|s| s.wall_time_ms == 12));
+    }
+
+    #[test]
+    fn write_csv_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.csv");
+        write_summary(&path, SummaryFormat::Csv, &summaries()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "digest,checkpoint,status,gas_delta,wall_time_ms,error\n\
+             a,42,match,0,12,\n\
+             b,,forked,-5,12,\n\
+             c,,forked,7,12,\n\
+             d,,error,,12,\"missing object, version 3\"\n"
+        );
+    }
+
+    #[test]
+    fn write_json_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.json");
+        write_summary(&path, SummaryFormat::Json, &summaries()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "digest": "a",
+                "checkpoint": 42,
+                "status": "match",
+                "gas_delta": 0,
+                "wall_time_ms": 12,
+                "error": null,
+            })
+        );
+        assert_eq!(json[3]["status"], "error");
+        assert_eq!(json[3]["gas_delta"], serde_json::Value::Null);
+
+        let parsed: Vec<TransactionSummary> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[2].status, ReplayStatus::Forked);
+    }
+}
@@ -3,30 +3,80 @@
 
 //! Tracing utilities.
 //! Mostly deals with directory/file saving and what gets saved in the trace output.
+//!
+//! For every package used by a traced transaction the trace output contains a
+//! `<package-id>/bytecode` directory with the disassembled modules and their debug info, and a
+//! `<package-id>/source` directory for the package sources. The latter is populated from the
+//! build directory of a local package (see `PackageSourceArg`) so that the trace can be
+//! debugged at the source level.
 
 use crate::{
     artifacts::{Artifact, ArtifactManager},
     execution::TxnContextAndEffects,
 };
-use anyhow::Context;
+use anyhow::{anyhow, bail, Context};
 use move_binary_format::CompiledModule;
 use move_bytecode_source_map::utils::serialize_to_json_string;
 use move_command_line_common::files::MOVE_BYTECODE_EXTENSION;
 use move_disassembler::disassembler::Disassembler;
 use move_ir_types::location::Spanned;
+use move_package::{
+    compilation::package_layout::CompiledPackageLayout,
+    source_package::manifest_parser::parse_move_manifest_from_file,
+};
 use move_trace_format::format::MoveTraceBuilder;
-use std::fs;
-use sui_types::object::Data;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use sui_types::{base_types::ObjectID, object::Data};
 
 const BCODE_DIR: &str = "bytecode";
 const SOURCE_DIR: &str = "source";
 
+/// Local sources of an on-chain package as provided on the command line:
+/// `<package-id>=<path>`, where `<path>` is the root of the package built with
+/// `sui-replay-2 build`.
+#[derive(Clone, Debug)]
+pub struct PackageSourceArg {
+    pub package_id: ObjectID,
+    pub path: PathBuf,
+}
+
+/// What to save along with the trace of a replayed transaction.
+#[derive(Clone, Debug, Default)]
+pub struct TraceOptions {
+    /// Save a per-function gas profile computed from the trace.
+    pub gas_profile: bool,
+    /// Packages whose sources and debug info are saved with the trace.
+    pub sources: Vec<PackageSourceArg>,
+}
+
+impl FromStr for PackageSourceArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((id, path)) = s.split_once('=') else {
+            return Err(format!(
+                "Invalid package source {s}, expected <package-id>=<path>"
+            ));
+        };
+        Ok(PackageSourceArg {
+            package_id: ObjectID::from_hex_literal(id)
+                .map_err(|e| format!("Invalid package id {id}: {e}"))?,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
 /// Saves the trace and additional metadata needed to analyze the trace
 /// to a subderectory named after the transaction digest.
 pub fn save_trace_output(
     artifact_manager: &ArtifactManager<'_>,
     trace_builder: MoveTraceBuilder,
     context_and_effects: &TxnContextAndEffects,
+    sources: &[PackageSourceArg],
 ) -> Result<(), anyhow::Error> {
     let trace = trace_builder.into_trace();
     let trace_member = artifact_manager.member(Artifact::Trace);
@@ -111,14 +161,132 @@ pub fn save_trace_output(
                 mod_name, &pkg_addr,
             ))?;
         }
-        // create empty sources directory as a known placeholder for the users
-        // to put optional source files there
+        // create sources directory, populated with the package sources if they were provided,
+        // otherwise left empty as a known placeholder for the users to put optional source
+        // files there
         let src_pkg_dir = pkg_dir.join(SOURCE_DIR);
         fs::create_dir_all(&src_pkg_dir).context(format!(
             "Failed to create source output directory '{:?}'",
             src_pkg_dir,
         ))?;
+        if let Some(source) = sources.iter().find(|s| s.package_id == pkg.id()) {
+            save_package_sources(source, &src_pkg_dir).context(format!(
+                "Failed to save sources of package {} from {}",
+                &pkg_addr,
+                source.path.display(),
+            ))?;
+        }
     }
 
     Ok(())
 }
+
+// Copy the sources and source debug info of a built package to `src_pkg_dir`.
+// The trace debugger only picks debug info matching a Move source file, so copying the
+// sources of the dependencies (under `sources/dependencies`) along with the package ones
+// is harmless and lets the debugger show them too.
+fn save_package_sources(source: &PackageSourceArg, src_pkg_dir: &Path) -> anyhow::Result<()> {
+    let manifest = parse_move_manifest_from_file(&source.path)?;
+    let build_dir = source
+        .path
+        .join(CompiledPackageLayout::Root.path())
+        .join(manifest.package.name.as_str());
+    for category in [
+        CompiledPackageLayout::Sources,
+        CompiledPackageLayout::DebugInfo,
+    ] {
+        let from = build_dir.join(category.path());
+        if !from.is_dir() {
+            bail!(
+                "Missing '{}', build the package with `sui-replay-2 build` first",
+                from.display()
+            );
+        }
+        copy_dir(&from, &src_pkg_dir.join(category.path()))?;
+    }
+    Ok(())
+}
+
+// Recursively copy the directory `from` to `to`.
+fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(to).map_err(|e| anyhow!("Failed to create {}: {e}", to.display()))?;
+    for entry in
+        fs::read_dir(from).map_err(|e| anyhow!("Failed to read {}: {e}", from.display()))?
+    {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target).map_err(|e| {
+                anyhow!(
+                    "Failed to copy {} to {}: {e}",
+                    entry.path().display(),
+                    target.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_package_source_arg() {
+        let arg: PackageSourceArg = "0x42=path/to/pkg".parse().unwrap();
+        assert_eq!(arg.package_id, ObjectID::from_single_byte(0x42));
+        assert_eq!(arg.path, PathBuf::from("path/to/pkg"));
+
+        // The package id is required
+        assert!("path/to/pkg".parse::<PackageSourceArg>().is_err());
+        assert!("not_an_id=path".parse::<PackageSourceArg>().is_err());
+    }
+
+    #[test]
+    fn save_built_package_sources() {
+        let pkg = tempfile::tempdir().unwrap();
+        fs::write(
+            pkg.path().join("Move.toml"),
+            "[package]\nname = \"example\"\nedition = \"2024\"\n",
+        )
+        .unwrap();
+        let build_dir = pkg
+            .path()
+            .join(CompiledPackageLayout::Root.path())
+            .join("example");
+        let sources = build_dir.join(CompiledPackageLayout::Sources.path());
+        let debug_info = build_dir.join(CompiledPackageLayout::DebugInfo.path());
+        fs::create_dir_all(sources.join("dependencies/Sui")).unwrap();
+        fs::create_dir_all(&debug_info).unwrap();
+        fs::write(sources.join("m.move"), "module example::m;").unwrap();
+        fs::write(
+            sources.join("dependencies/Sui/coin.move"),
+            "module sui::coin;",
+        )
+        .unwrap();
+        fs::write(debug_info.join("m.json"), "{}").unwrap();
+
+        let source = PackageSourceArg {
+            package_id: ObjectID::from_single_byte(0x42),
+            path: pkg.path().to_owned(),
+        };
+        let out = tempfile::tempdir().unwrap();
+        save_package_sources(&source, out.path()).unwrap();
+
+        let saved_sources = out.path().join(CompiledPackageLayout::Sources.path());
+        let saved_debug_info = out.path().join(CompiledPackageLayout::DebugInfo.path());
+        assert_eq!(
+            fs::read_to_string(saved_sources.join("m.move")).unwrap(),
+            "module example::m;"
+        );
+        assert!(saved_sources.join("dependencies/Sui/coin.move").is_file());
+        assert!(saved_debug_info.join("m.json").is_file());
+
+        // Sources of a package that was not built cannot be saved
+        fs::remove_dir_all(&debug_info).unwrap();
+        assert!(save_package_sources(&source, tempfile::tempdir().unwrap().path()).is_err());
+    }
+}
@@ -4,17 +4,19 @@
 use axum::extract::State;
 use axum::{Extension, Json};
 use axum_extra::extract::WithRejection;
-use futures::{future::join_all, StreamExt};
+use futures::future::{join_all, try_join_all};
+use futures::StreamExt;
 
 use sui_sdk::rpc_types::StakeStatus;
-use sui_sdk::{SuiClient, SUI_COIN_TYPE};
+use sui_sdk::SuiClient;
 use sui_types::base_types::SuiAddress;
 use tracing::info;
 
 use crate::errors::Error;
+use crate::indexer::IndexerDataSource;
 use crate::types::{
     AccountBalanceRequest, AccountBalanceResponse, AccountCoinsRequest, AccountCoinsResponse,
-    Amount, Coin, Currencies, Currency, SubAccountType, SubBalance,
+    Amount, Coin, Currencies, SubAccountType, SubBalance,
 };
 use crate::{OnlineServerContext, SuiEnv};
 use std::time::Duration;
@@ -32,6 +34,12 @@ pub async fn balance(
     env.check_network_identifier(&request.network_identifier)?;
     let address = request.account_identifier.address;
     let currencies = &request.currencies;
+    if let Some(indexer) = &ctx.indexer {
+        // Sub-account balances come from the fullnode's staking state, at the latest checkpoint.
+        if request.account_identifier.sub_account.is_none() {
+            return get_indexed_balances(&ctx, indexer, &request, address).await;
+        }
+    }
     let mut retry_attempts = 5;
     while retry_attempts > 0 {
         let balances_first = get_balances(&ctx, &request, address, currencies.clone()).await?;
@@ -62,6 +70,34 @@ pub async fn balance(
     Err(Error::RetryExhausted(String::from("retry")))
 }
 
+/// Balances as of the requested block, defaulting to the latest indexed block. The indexer reads
+/// all balances at the same checkpoint, so unlike the fullnode they do not need to be re-checked.
+async fn get_indexed_balances(
+    ctx: &OnlineServerContext,
+    indexer: &IndexerDataSource,
+    request: &AccountBalanceRequest,
+    address: SuiAddress,
+) -> Result<AccountBalanceResponse, Error> {
+    if request.currencies.0.is_empty() {
+        return Err(Error::InvalidInput(
+            "Coin type is required for this request".to_string(),
+        ));
+    }
+    let block_identifier = indexer.block_identifier(&request.block_identifier).await?;
+    let balance_futures = request.currencies.0.iter().map(|currency| async move {
+        ctx.coin_metadata_cache.check_currency(currency).await?;
+        let value = indexer
+            .balance(address, currency, block_identifier.index)
+            .await?;
+        Ok::<_, Error>(Amount::new(value, Some(currency.clone())))
+    });
+    let balances = try_join_all(balance_futures).await?;
+    Ok(AccountBalanceResponse {
+        block_identifier,
+        balances,
+    })
+}
+
 async fn get_checkpoint(ctx: &OnlineServerContext) -> SuiRpcResult<CheckpointSequenceNumber> {
     ctx.client
         .read_api()
@@ -79,29 +115,15 @@ async fn get_balances(
         let account_type = sub_account.account_type.clone();
         get_sub_account_balances(account_type, &ctx.client, address).await
     } else if !currencies.0.is_empty() {
-        let balance_futures = currencies.0.iter().map(|currency| {
-            let coin_type = currency.metadata.clone().coin_type.clone();
-            async move {
-                (
-                    currency.clone(),
-                    get_account_balances(ctx, address, &coin_type).await,
-                )
-            }
+        let balance_futures = currencies.0.iter().map(|currency| async move {
+            ctx.coin_metadata_cache.check_currency(currency).await?;
+            let coin_type = &currency.metadata.coin_type;
+            let value = get_account_balances(ctx, address, coin_type)
+                .await
+                .map_err(|_| Error::InvalidInput(format!("{:?}", coin_type)))?;
+            Ok::<_, Error>(Amount::new(value, Some(currency.clone())))
         });
-        let balances: Vec<(Currency, Result<i128, Error>)> = join_all(balance_futures).await;
-        let mut amounts = Vec::new();
-        for (currency, balance_result) in balances {
-            match balance_result {
-                Ok(value) => amounts.push(Amount::new(value, Some(currency))),
-                Err(_e) => {
-                    return Err(Error::InvalidInput(format!(
-                        "{:?}",
-                        currency.metadata.coin_type
-                    )))
-                }
-            }
-        }
-        Ok(amounts)
+        join_all(balance_futures).await.into_iter().collect()
     } else {
         Err(Error::InvalidInput(
             "Coin type is required for this request".to_string(),
@@ -184,7 +206,8 @@ async fn get_sub_account_balances(
     })
 }
 
-/// Get an array of all unspent coins for an AccountIdentifier and the BlockIdentifier at which the lookup was performed. .
+/// Get an array of all unspent coins for an AccountIdentifier and the BlockIdentifier at which the lookup was performed.
+/// Coins of the requested currencies are returned, SUI coins if none are given.
 /// [Rosetta API Spec](https://www.rosetta-api.org/docs/AccountApi.html#accountcoins)
 pub async fn coins(
     State(context): State<OnlineServerContext>,
@@ -192,16 +215,24 @@ pub async fn coins(
     WithRejection(Json(request), _): WithRejection<Json<AccountCoinsRequest>, Error>,
 ) -> Result<AccountCoinsResponse, Error> {
     env.check_network_identifier(&request.network_identifier)?;
-    let coins = context
-        .client
-        .coin_read_api()
-        .get_coins_stream(
-            request.account_identifier.address,
-            Some(SUI_COIN_TYPE.to_string()),
-        )
-        .map(Coin::from)
-        .collect()
-        .await;
+    let mut coins = vec![];
+    for currency in request.currencies.0 {
+        context
+            .coin_metadata_cache
+            .check_currency(&currency)
+            .await?;
+        let currency_coins: Vec<Coin> = context
+            .client
+            .coin_read_api()
+            .get_coins_stream(
+                request.account_identifier.address,
+                Some(currency.metadata.coin_type.clone()),
+            )
+            .map(|coin| Coin::new(coin, currency.clone()))
+            .collect()
+            .await;
+        coins.extend(currency_coins);
+    }
 
     Ok(AccountCoinsResponse {
         block_identifier: context.blocks().current_block_identifier().await?,
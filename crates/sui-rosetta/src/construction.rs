@@ -239,6 +239,9 @@ pub async fn metadata(
         InternalOperation::PayCoin(PayCoin { currency, .. }) => Some(currency.clone()),
         _ => None,
     };
+    if let Some(currency) = &currency {
+        context.coin_metadata_cache.check_currency(currency).await?;
+    }
 
     let mut gas_price = context
         .client
@@ -14,10 +14,14 @@ use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
 use tracing::info;
 
+use sui_indexer_alt_reader::pg_reader::PgReader;
 use sui_sdk::{SuiClient, SUI_COIN_TYPE};
+use sui_types::parse_sui_type_tag;
 
 use crate::errors::Error;
 use crate::errors::Error::MissingMetadata;
+use crate::indexer::IndexerDataSource;
+pub use crate::state::BlockProvider;
 use crate::state::{CheckpointBlockProvider, OnlineServerContext};
 use crate::types::{Currency, CurrencyMetadata, SuiEnv};
 
@@ -30,6 +34,7 @@ mod account;
 mod block;
 mod construction;
 mod errors;
+pub mod indexer;
 mod network;
 pub mod operations;
 mod state;
@@ -61,6 +66,15 @@ impl RosettaOnlineServer {
         }
     }
 
+    /// Serves blocks and balances from a `sui-indexer-alt` database, so that they are available
+    /// over its full history. Construction and sub-account balances are still served by the
+    /// fullnode.
+    pub fn with_indexer(mut self, pg_reader: PgReader) -> Self {
+        let indexer = IndexerDataSource::new(pg_reader, self.context.coin_metadata_cache.clone());
+        self.context = self.context.with_indexer(indexer);
+        self
+    }
+
     pub async fn serve(self, addr: SocketAddr) {
         // Online endpoints
         let app = Router::new()
@@ -152,4 +166,20 @@ impl CoinMetadataCache {
         }
         cache.get(type_tag).cloned().ok_or(MissingMetadata)
     }
+
+    /// Checks that a currency given by a client matches the coin's on-chain `CoinMetadata`, so that
+    /// amounts we report or construct with it agree with the ones found in block operations.
+    pub async fn check_currency(&self, currency: &Currency) -> Result<(), Error> {
+        let coin_type = &currency.metadata.coin_type;
+        let type_tag = parse_sui_type_tag(coin_type)
+            .map_err(|e| Error::InvalidInput(format!("Invalid coin type {coin_type}: {e}")))?;
+        let expected = self.get_currency(&type_tag).await?;
+        if expected.symbol != currency.symbol || expected.decimals != currency.decimals {
+            return Err(Error::InvalidInput(format!(
+                "Currency {}/{} does not match the metadata of {coin_type}, expecting {}/{}",
+                currency.symbol, currency.decimals, expected.symbol, expected.decimals
+            )));
+        }
+        Ok(())
+    }
 }
@@ -13,16 +13,21 @@ use anyhow::anyhow;
 use clap::Parser;
 use fastcrypto::encoding::{Encoding, Hex};
 use fastcrypto::traits::EncodeDecodeBase64;
+use prometheus::Registry;
 use serde_json::{json, Value};
 use sui_config::{sui_config_dir, Config, NodeConfig, SUI_FULLNODE_CONFIG, SUI_KEYSTORE_FILENAME};
+use sui_indexer_alt_reader::pg_reader::db::DbArgs;
+use sui_indexer_alt_reader::pg_reader::PgReader;
 use sui_node::SuiNode;
 use sui_rosetta::types::{CurveType, PrefundedAccount, SuiEnv};
 use sui_rosetta::{RosettaOfflineServer, RosettaOnlineServer, SUI};
 use sui_sdk::{SuiClient, SuiClientBuilder};
 use sui_types::base_types::SuiAddress;
 use sui_types::crypto::{KeypairTraits, SuiKeyPair, ToFromBytes};
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 use tracing::log::warn;
+use url::Url;
 
 #[derive(Parser)]
 #[clap(name = "sui-rosetta", rename_all = "kebab-case", author, version)]
@@ -46,6 +51,12 @@ pub enum RosettaServerCommand {
         full_node_url: String,
         #[clap(long, default_value = "/data")]
         data_path: PathBuf,
+        /// Serve blocks and balances from this `sui-indexer-alt` database instead of the full
+        /// node, so that they are available beyond the full node's pruning horizon.
+        #[clap(long)]
+        indexer_database_url: Option<Url>,
+        #[clap(flatten)]
+        db_args: DbArgs,
     },
     StartOnlineServer {
         #[clap(long, default_value = "localnet")]
@@ -56,6 +67,12 @@ pub enum RosettaServerCommand {
         node_config: Option<PathBuf>,
         #[clap(long, default_value = "/data")]
         data_path: PathBuf,
+        /// Serve blocks and balances from this `sui-indexer-alt` database instead of the full
+        /// node, so that they are available beyond the full node's pruning horizon.
+        #[clap(long)]
+        indexer_database_url: Option<Url>,
+        #[clap(flatten)]
+        db_args: DbArgs,
     },
     StartOfflineServer {
         #[clap(long, default_value = "localnet")]
@@ -136,6 +153,8 @@ impl RosettaServerCommand {
                 addr,
                 full_node_url,
                 data_path,
+                indexer_database_url,
+                db_args,
             } => {
                 info!(
                     "Starting Rosetta Online Server with remote Sui full node [{full_node_url}]."
@@ -143,7 +162,11 @@ impl RosettaServerCommand {
                 let sui_client = wait_for_sui_client(full_node_url).await;
                 let rosetta_path = data_path.join("rosetta_db");
                 info!("Rosetta db path : {rosetta_path:?}");
-                let rosetta = RosettaOnlineServer::new(env, sui_client);
+                let mut rosetta = RosettaOnlineServer::new(env, sui_client);
+                if let Some(url) = indexer_database_url {
+                    let registry = Registry::default();
+                    rosetta = with_indexer(rosetta, url, db_args, &registry).await?;
+                }
                 rosetta.serve(addr).await;
             }
 
@@ -152,6 +175,8 @@ impl RosettaServerCommand {
                 addr,
                 node_config,
                 data_path,
+                indexer_database_url,
+                db_args,
             } => {
                 info!("Starting Rosetta Online Server with embedded Sui full node.");
                 info!("Data directory path: {data_path:?}");
@@ -168,6 +193,7 @@ impl RosettaServerCommand {
 
                 let registry_service =
                     mysten_metrics::start_prometheus_server(config.metrics_address);
+                let registry = registry_service.default_registry();
                 // Staring a full node for the rosetta server.
                 let rpc_address = format!("http://127.0.0.1:{}", config.json_rpc_address.port());
                 let _node = SuiNode::start(config, registry_service).await?;
@@ -176,7 +202,10 @@ impl RosettaServerCommand {
 
                 let rosetta_path = data_path.join("rosetta_db");
                 info!("Rosetta db path : {rosetta_path:?}");
-                let rosetta = RosettaOnlineServer::new(env, sui_client);
+                let mut rosetta = RosettaOnlineServer::new(env, sui_client);
+                if let Some(url) = indexer_database_url {
+                    rosetta = with_indexer(rosetta, url, db_args, &registry).await?;
+                }
                 rosetta.serve(addr).await;
             }
         };
@@ -184,6 +213,24 @@ impl RosettaServerCommand {
     }
 }
 
+async fn with_indexer(
+    rosetta: RosettaOnlineServer,
+    database_url: Url,
+    db_args: DbArgs,
+    registry: &Registry,
+) -> Result<RosettaOnlineServer, anyhow::Error> {
+    info!("Serving blocks and balances from indexer database.");
+    let pg_reader = PgReader::new(
+        Some("rosetta"),
+        Some(database_url),
+        db_args,
+        registry,
+        CancellationToken::new(),
+    )
+    .await?;
+    Ok(rosetta.with_indexer(pg_reader))
+}
+
 async fn wait_for_sui_client(rpc_address: String) -> SuiClient {
     loop {
         match SuiClientBuilder::default().build(&rpc_address).await {
@@ -886,6 +886,40 @@ impl Operations {
 
         Ok(ops)
     }
+
+    /// Operations of a transaction for which only balance changes are known, e.g. when read from
+    /// an indexer: one `SuiBalanceChange` per address and currency. Balance changes are final
+    /// whether or not the transaction succeeded, so all operations are successful.
+    pub async fn try_from_balance_changes(
+        balance_changes: Vec<BalanceChange>,
+        cache: &CoinMetadataCache,
+    ) -> Result<Self, Error> {
+        let mut balances: Vec<((SuiAddress, Currency), i128)> = vec![];
+        for balance_change in balance_changes {
+            // Rosetta only care about address owner
+            let Owner::AddressOwner(owner) = balance_change.owner else {
+                continue;
+            };
+            let Ok(currency) = cache.get_currency(&balance_change.coin_type).await else {
+                continue;
+            };
+            if currency.symbol.is_empty() {
+                continue;
+            }
+            let key = (owner, currency);
+            match balances.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, amount)) => *amount += balance_change.amount,
+                None => balances.push((key, balance_change.amount)),
+            }
+        }
+        Ok(balances
+            .into_iter()
+            .filter(|(_, amount)| *amount != 0)
+            .map(|((addr, currency), amount)| {
+                Operation::balance_change(Some(OperationStatus::Success), addr, amount, currency)
+            })
+            .collect())
+    }
 }
 
 fn is_unstake_event(tag: &StructTag) -> bool {
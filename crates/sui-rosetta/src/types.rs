@@ -238,13 +238,15 @@ mod str_format {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AccountCoinsRequest {
     pub network_identifier: NetworkIdentifier,
     pub account_identifier: AccountIdentifier,
     pub include_mempool: bool,
+    #[serde(default, deserialize_with = "deserialize_or_default_currencies")]
+    pub currencies: Currencies,
 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AccountCoinsResponse {
     pub block_identifier: BlockIdentifier,
     pub coins: Vec<Coin>,
@@ -254,14 +256,14 @@ impl IntoResponse for AccountCoinsResponse {
         Json(self).into_response()
     }
 }
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Coin {
     pub coin_identifier: CoinIdentifier,
     pub amount: Amount,
 }
 
-impl From<sui_sdk::rpc_types::Coin> for Coin {
-    fn from(coin: sui_sdk::rpc_types::Coin) -> Self {
+impl Coin {
+    pub fn new(coin: sui_sdk::rpc_types::Coin, currency: Currency) -> Self {
         Self {
             coin_identifier: CoinIdentifier {
                 identifier: CoinID {
@@ -271,13 +273,19 @@ impl From<sui_sdk::rpc_types::Coin> for Coin {
             },
             amount: Amount {
                 value: coin.balance as i128,
-                currency: SUI.clone(),
+                currency,
                 metadata: None,
             },
         }
     }
 }
 
+impl From<sui_sdk::rpc_types::Coin> for Coin {
+    fn from(coin: sui_sdk::rpc_types::Coin) -> Self {
+        Self::new(coin, SUI.clone())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct CoinIdentifier {
     pub identifier: CoinID,
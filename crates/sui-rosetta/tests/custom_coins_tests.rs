@@ -20,8 +20,8 @@ use sui_json_rpc_types::{
 use sui_keys::keystore::AccountKeystore;
 use sui_rosetta::operations::Operations;
 use sui_rosetta::types::{
-    AccountBalanceRequest, AccountBalanceResponse, AccountIdentifier, Amount, Currency,
-    CurrencyMetadata, NetworkIdentifier, SuiEnv,
+    AccountBalanceRequest, AccountBalanceResponse, AccountCoinsRequest, AccountCoinsResponse,
+    AccountIdentifier, Amount, Currency, CurrencyMetadata, NetworkIdentifier, SuiEnv,
 };
 use sui_rosetta::types::{Currencies, OperationType};
 use sui_rosetta::CoinMetadataCache;
@@ -173,6 +173,92 @@ async fn test_custom_coin_balance() {
     );
 }
 
+#[tokio::test]
+async fn test_custom_coin_account_coins() {
+    const COIN1_BALANCE: u64 = 100_000_000;
+    const COIN2_BALANCE: u64 = 200_000_000;
+    let test_cluster = TestClusterBuilder::new().build().await;
+    let client = test_cluster.wallet.get_client().await.unwrap();
+    let keystore = &test_cluster.wallet.config.keystore;
+
+    let (rosetta_client, _handle) = start_rosetta_test_server(client.clone()).await;
+
+    let sender = test_cluster.get_address_0();
+    let init_ret = init_package(
+        &client,
+        keystore,
+        sender,
+        Path::new("tests/custom_coins/test_coin"),
+    )
+    .await
+    .unwrap();
+
+    let address1 = test_cluster.get_address_1();
+    let balances_to = vec![(COIN1_BALANCE, address1), (COIN2_BALANCE, address1)];
+    let coin_type = init_ret.coin_tag.to_canonical_string(true);
+    let _mint_res = mint(&client, keystore, init_ret, balances_to)
+        .await
+        .unwrap();
+
+    let network_identifier = NetworkIdentifier {
+        blockchain: "sui".to_string(),
+        network: SuiEnv::LocalNet,
+    };
+    let test_coin_currency = Currency {
+        symbol: "TEST_COIN".to_string(),
+        decimals: TEST_COIN_DECIMALS,
+        metadata: CurrencyMetadata {
+            coin_type: coin_type.clone(),
+        },
+    };
+    let request = AccountCoinsRequest {
+        network_identifier: network_identifier.clone(),
+        account_identifier: AccountIdentifier {
+            address: address1,
+            sub_account: None,
+        },
+        include_mempool: false,
+        currencies: Currencies(vec![SUI.clone(), test_coin_currency.clone()]),
+    };
+    let response: AccountCoinsResponse = rosetta_client
+        .call(RosettaEndpoint::Coins, &request)
+        .await
+        .unwrap();
+    let mut test_coin_values = response
+        .coins
+        .iter()
+        .filter(|coin| coin.amount.currency == test_coin_currency)
+        .map(|coin| coin.amount.value)
+        .collect::<Vec<_>>();
+    test_coin_values.sort();
+    assert_eq!(
+        test_coin_values,
+        vec![COIN1_BALANCE as i128, COIN2_BALANCE as i128]
+    );
+    assert!(response
+        .coins
+        .iter()
+        .any(|coin| coin.amount.currency == *SUI));
+
+    // Currencies disagreeing with the coin metadata are rejected
+    let request = AccountBalanceRequest {
+        network_identifier,
+        account_identifier: AccountIdentifier {
+            address: address1,
+            sub_account: None,
+        },
+        block_identifier: Default::default(),
+        currencies: Currencies(vec![Currency {
+            decimals: 9,
+            ..test_coin_currency
+        }]),
+    };
+    let response: Result<AccountBalanceResponse, _> = rosetta_client
+        .call(RosettaEndpoint::Balance, &request)
+        .await;
+    assert!(response.is_err());
+}
+
 #[tokio::test]
 async fn test_default_balance() {
     // mint coins to `test_culset.get_address_1()` and `test_culset.get_address_2()`
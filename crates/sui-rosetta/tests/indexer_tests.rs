@@ -0,0 +1,98 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::num::NonZeroUsize;
+
+use prometheus::Registry;
+use sui_indexer_alt_e2e_tests::FullCluster;
+use sui_indexer_alt_reader::pg_reader::db::DbArgs;
+use sui_indexer_alt_reader::pg_reader::PgReader;
+use sui_rosetta::indexer::IndexerDataSource;
+use sui_rosetta::types::{OperationType, PartialBlockIdentifier};
+use sui_rosetta::{BlockProvider, CoinMetadataCache, SUI};
+use sui_sdk::SuiClientBuilder;
+use sui_types::base_types::SuiAddress;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::transaction::{Transaction, TransactionData};
+use tokio_util::sync::CancellationToken;
+
+/// 5 SUI gas budget
+const DEFAULT_GAS_BUDGET: u64 = 5_000_000_000;
+
+#[tokio::test]
+async fn test_indexer_historical_balance_and_block() {
+    let mut cluster = FullCluster::new().await.unwrap();
+    let (sender, kp, gas) = cluster.funded_account(10 * DEFAULT_GAS_BUDGET).unwrap();
+    let recipient = SuiAddress::random_for_testing_only();
+    let before = cluster.create_checkpoint().await.sequence_number;
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.transfer_sui(recipient, Some(1_000_000));
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![gas],
+        builder.finish(),
+        DEFAULT_GAS_BUDGET,
+        cluster.reference_gas_price(),
+    );
+    let (_, error) = cluster
+        .execute_transaction(Transaction::from_data_and_signer(data, vec![&kp]))
+        .unwrap();
+    assert!(error.is_none(), "{error:?}");
+    let after = cluster.create_checkpoint().await.sequence_number;
+
+    let client = SuiClientBuilder::default()
+        .build(cluster.jsonrpc_url())
+        .await
+        .unwrap();
+    let pg_reader = PgReader::new(
+        None,
+        Some(cluster.db_url()),
+        DbArgs::default(),
+        &Registry::new(),
+        CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+    let indexer = IndexerDataSource::new(
+        pg_reader,
+        CoinMetadataCache::new(client, NonZeroUsize::new(2).unwrap()),
+    );
+
+    // Balances are read as of the requested block, defaulting to the latest one.
+    assert_eq!(indexer.balance(recipient, &SUI, before).await.unwrap(), 0);
+    assert_eq!(
+        indexer.balance(recipient, &SUI, after).await.unwrap(),
+        1_000_000
+    );
+    let latest = indexer
+        .block_identifier(&PartialBlockIdentifier::default())
+        .await
+        .unwrap();
+    assert_eq!(latest.index, after);
+
+    // The transfer shows up as a balance change of the recipient in its block.
+    let block = indexer
+        .block_identifier(&PartialBlockIdentifier {
+            index: Some(after),
+            hash: Some(latest.hash),
+        })
+        .await
+        .unwrap();
+    assert_eq!(block.hash, latest.hash);
+    let response = indexer.get_block_by_index(after).await.unwrap();
+    assert_eq!(response.block.block_identifier.hash, latest.hash);
+    let received = response
+        .block
+        .transactions
+        .into_iter()
+        .flat_map(|tx| tx.operations)
+        .find(|op| {
+            op.type_ == OperationType::SuiBalanceChange
+                && op.account.as_ref().map(|a| a.address) == Some(recipient)
+        })
+        .expect("Recipient balance change not found");
+    assert_eq!(received.amount.unwrap().value, 1_000_000);
+
+    cluster.stopped().await;
+}
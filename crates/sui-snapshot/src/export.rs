@@ -0,0 +1,324 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use arrow_array::builder::{BooleanBuilder, ListBuilder, StringBuilder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use async_trait::async_trait;
+use indicatif::{ProgressBar, ProgressStyle};
+use move_core_types::account_address::AccountAddress;
+use move_core_types::annotated_value::MoveTypeLayout;
+use object_store::path::Path as ObjectStorePath;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use sui_core::authority::authority_store_tables::LiveObject;
+use sui_indexer_alt_framework::task::TrySpawnStreamExt;
+use sui_package_resolver::{error::Error as PackageResolverError, Package, PackageStore, Resolver};
+use sui_storage::object_store::ObjectStoreGetExt;
+use sui_types::object::bounded_visitor::BoundedVisitor;
+use sui_types::object::{Object, Owner};
+use sui_types::TypeTag;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::reader::{download_bytes, read_live_objects, Sha3DigestType, StateSnapshotReaderV1};
+use crate::FileMetadata;
+
+/// Exports the live objects of a full formal snapshot to Parquet, with one file per *.obj file of
+/// the snapshot: `{output_dir}/epoch_{epoch}/{bucket}_{part}.parquet`. Files are written
+/// independently (and in parallel), so an interrupted export resumes by skipping the files that
+/// already exist.
+///
+/// If `decode_json` is set, the contents of Move objects are also decoded to JSON, using the
+/// packages in the snapshot. This needs an extra pass over the snapshot to load them.
+pub async fn export_parquet(
+    reader: &mut StateSnapshotReaderV1,
+    output_dir: &Path,
+    decode_json: bool,
+) -> Result<()> {
+    if reader.base_epoch().is_some() {
+        return Err(anyhow!(
+            "Only full snapshots can be exported, epoch {} is a delta snapshot",
+            reader.epoch()
+        ));
+    }
+    let export_dir = output_dir.join(format!("epoch_{}", reader.epoch()));
+    fs::create_dir_all(&export_dir)?;
+
+    let (sha3_digests, _) = reader.compute_checksum().await?;
+    let (input_files, epoch_dir, remote_object_store, concurrency) =
+        reader.export_metadata().await?;
+    let input_files: Vec<FileMetadata> = input_files
+        .into_iter()
+        .map(|(_, (_, file_metadata))| file_metadata)
+        .collect();
+    let source = ObjectFileSource {
+        remote_object_store,
+        epoch_dir,
+        sha3_digests,
+    };
+
+    let resolver = if decode_json {
+        let store = SnapshotPackageStore::load(&source, input_files.clone(), concurrency).await?;
+        Some(Arc::new(Resolver::new(store)))
+    } else {
+        None
+    };
+
+    let pending_files: Vec<_> = input_files
+        .into_iter()
+        .filter(|file_metadata| !part_file_path(&export_dir, file_metadata).exists())
+        .collect();
+    info!(
+        "Exporting {} object files to {}",
+        pending_files.len(),
+        export_dir.display()
+    );
+    let progress_bar = reader.get_multi_progress().add(
+        ProgressBar::new(pending_files.len() as u64).with_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] {wide_bar} {pos} out of {len} object files exported ({msg})",
+            )
+            .unwrap(),
+        ),
+    );
+    futures::stream::iter(pending_files)
+        .try_for_each_spawned(concurrency, |file_metadata| {
+            let source = source.clone();
+            let resolver = resolver.clone();
+            let path = part_file_path(&export_dir, &file_metadata);
+            let bar = progress_bar.clone();
+            async move {
+                let mut columns = ObjectColumns::default();
+                for object in source.objects(&file_metadata).await? {
+                    // Wrapped objects are only tombstones in the live object set.
+                    let LiveObject::Normal(object) = object else {
+                        continue;
+                    };
+                    let json = match &resolver {
+                        Some(resolver) => object_json(resolver, &object).await,
+                        None => None,
+                    };
+                    columns.append(&object, json)?;
+                }
+                columns.write(&path)?;
+                bar.inc(1);
+                bar.set_message(format!(
+                    "Bucket: {}, Part: {}",
+                    file_metadata.bucket_num, file_metadata.part_num
+                ));
+                Ok::<(), anyhow::Error>(())
+            }
+        })
+        .await?;
+    progress_bar.finish_with_message("Export complete");
+    Ok(())
+}
+
+fn part_file_path(export_dir: &Path, file_metadata: &FileMetadata) -> PathBuf {
+    export_dir.join(format!(
+        "{}_{}.parquet",
+        file_metadata.bucket_num, file_metadata.part_num
+    ))
+}
+
+/// Downloads *.obj files of a snapshot, checking their objects against their references.
+#[derive(Clone)]
+struct ObjectFileSource {
+    remote_object_store: Arc<dyn ObjectStoreGetExt>,
+    epoch_dir: ObjectStorePath,
+    sha3_digests: Sha3DigestType,
+}
+
+impl ObjectFileSource {
+    async fn objects(&self, file_metadata: &FileMetadata) -> Result<Vec<LiveObject>> {
+        let (bytes, sha3_digest) = download_bytes(
+            self.remote_object_store.clone(),
+            file_metadata,
+            self.epoch_dir.clone(),
+            self.sha3_digests.clone(),
+            &&file_metadata.bucket_num,
+            &file_metadata.part_num,
+            None,
+        )
+        .await;
+        read_live_objects(file_metadata, bytes, &sha3_digest, None)
+    }
+}
+
+/// The packages in a snapshot, to resolve the layouts of the objects in it.
+struct SnapshotPackageStore {
+    packages: HashMap<AccountAddress, Arc<Package>>,
+}
+
+impl SnapshotPackageStore {
+    async fn load(
+        source: &ObjectFileSource,
+        input_files: Vec<FileMetadata>,
+        concurrency: usize,
+    ) -> Result<Self> {
+        info!("Loading packages from {} object files", input_files.len());
+        let packages = Arc::new(Mutex::new(HashMap::new()));
+        futures::stream::iter(input_files)
+            .try_for_each_spawned(concurrency, |file_metadata| {
+                let source = source.clone();
+                let packages = packages.clone();
+                async move {
+                    for object in source.objects(&file_metadata).await? {
+                        let LiveObject::Normal(object) = object else {
+                            continue;
+                        };
+                        if object.is_package() {
+                            let package = Package::read_from_object(&object)?;
+                            packages
+                                .lock()
+                                .await
+                                .insert(AccountAddress::from(object.id()), Arc::new(package));
+                        }
+                    }
+                    Ok::<(), anyhow::Error>(())
+                }
+            })
+            .await?;
+        let packages = std::mem::take(&mut *packages.lock().await);
+        info!("Loaded {} packages", packages.len());
+        Ok(Self { packages })
+    }
+}
+
+#[async_trait]
+impl PackageStore for SnapshotPackageStore {
+    async fn fetch(&self, id: AccountAddress) -> sui_package_resolver::Result<Arc<Package>> {
+        self.packages
+            .get(&id)
+            .cloned()
+            .ok_or(PackageResolverError::PackageNotFound(id))
+    }
+}
+
+/// Decodes the contents of a Move object to JSON. Objects that can't be decoded (e.g. because
+/// they are too large) are exported without it.
+async fn object_json(resolver: &Resolver<SnapshotPackageStore>, object: &Object) -> Option<String> {
+    let tag = object.struct_tag()?;
+    let contents = object.data.try_as_move()?.contents();
+    let json = async {
+        let MoveTypeLayout::Struct(layout) = resolver
+            .type_layout(TypeTag::Struct(Box::new(tag.clone())))
+            .await?
+        else {
+            return Err(anyhow!("Object is not a move struct"));
+        };
+        let move_struct = BoundedVisitor::deserialize_struct(contents, &layout)?;
+        Ok(serde_json::to_string(&move_struct)?)
+    }
+    .await;
+    json.inspect_err(|e| warn!("Failed to decode object {} of type {tag}: {e}", object.id()))
+        .ok()
+}
+
+/// The columns of an exported Parquet file, built up an object at a time.
+#[derive(Default)]
+struct ObjectColumns {
+    object_id: StringBuilder,
+    version: UInt64Builder,
+    digest: StringBuilder,
+    owner_kind: StringBuilder,
+    owner_address: StringBuilder,
+    is_package: BooleanBuilder,
+    type_package: StringBuilder,
+    type_module: StringBuilder,
+    type_name: StringBuilder,
+    type_args: ListBuilder<StringBuilder>,
+    storage_rebate: UInt64Builder,
+    size: UInt64Builder,
+    coin_balance: UInt64Builder,
+    object_json: StringBuilder,
+}
+
+impl ObjectColumns {
+    fn append(&mut self, object: &Object, json: Option<String>) -> Result<()> {
+        self.object_id.append_value(object.id().to_string());
+        self.version.append_value(object.version().value());
+        self.digest.append_value(object.digest().to_string());
+
+        let (owner_kind, owner_address) = match &object.owner {
+            Owner::AddressOwner(address) => ("address", Some(address.to_string())),
+            Owner::ObjectOwner(address) => ("object", Some(address.to_string())),
+            Owner::Shared { .. } => ("shared", None),
+            Owner::Immutable => ("immutable", None),
+            Owner::ConsensusAddressOwner { owner, .. } => {
+                ("consensus_address", Some(owner.to_string()))
+            }
+        };
+        self.owner_kind.append_value(owner_kind);
+        self.owner_address.append_option(owner_address);
+
+        self.is_package.append_value(object.is_package());
+        match object.struct_tag() {
+            Some(tag) => {
+                self.type_package
+                    .append_value(tag.address.to_canonical_string(/* with_prefix */ true));
+                self.type_module.append_value(tag.module.as_str());
+                self.type_name.append_value(tag.name.as_str());
+                for type_param in &tag.type_params {
+                    self.type_args
+                        .values()
+                        .append_value(type_param.to_canonical_string(/* with_prefix */ true));
+                }
+                self.type_args.append(true);
+            }
+            None => {
+                self.type_package.append_null();
+                self.type_module.append_null();
+                self.type_name.append_null();
+                self.type_args.append_null();
+            }
+        }
+
+        self.storage_rebate.append_value(object.storage_rebate);
+        self.size.append_value(bcs::to_bytes(object)?.len() as u64);
+        self.coin_balance
+            .append_option(object.as_coin_maybe().map(|coin| coin.value()));
+        self.object_json.append_option(json);
+        Ok(())
+    }
+
+    /// Writes the columns to a Parquet file at `path`. The file only appears once it is complete,
+    /// so that a resumed export never skips a partially written file.
+    fn write(mut self, path: &Path) -> Result<()> {
+        let columns: Vec<(&str, ArrayRef)> = vec![
+            ("object_id", Arc::new(self.object_id.finish())),
+            ("version", Arc::new(self.version.finish())),
+            ("digest", Arc::new(self.digest.finish())),
+            ("owner_kind", Arc::new(self.owner_kind.finish())),
+            ("owner_address", Arc::new(self.owner_address.finish())),
+            ("is_package", Arc::new(self.is_package.finish())),
+            ("type_package", Arc::new(self.type_package.finish())),
+            ("type_module", Arc::new(self.type_module.finish())),
+            ("type_name", Arc::new(self.type_name.finish())),
+            ("type_args", Arc::new(self.type_args.finish())),
+            ("storage_rebate", Arc::new(self.storage_rebate.finish())),
+            ("size", Arc::new(self.size.finish())),
+            ("coin_balance", Arc::new(self.coin_balance.finish())),
+            ("object_json", Arc::new(self.object_json.finish())),
+        ];
+        let batch = RecordBatch::try_from_iter(columns)?;
+
+        let tmp_path = path.with_extension("parquet.tmp");
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&tmp_path)?, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sui_core::authority::authority_store_tables::LiveObject;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::object::Object;
+use sui_types::parse_sui_struct_tag;
+
+/// Selects the live objects to keep in a partial restore. An object is kept if it matches any of
+/// the types, packages or owners in the filter.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveObjectFilter {
+    /// Types of objects to keep, as struct tags. A type without type parameters matches all of
+    /// its instantiations.
+    pub types: Vec<String>,
+    /// Packages whose objects to keep: objects of a type defined in one of these packages, and
+    /// the packages themselves.
+    pub packages: Vec<ObjectID>,
+    /// Addresses (or objects) whose objects to keep.
+    pub owners: Vec<SuiAddress>,
+}
+
+impl LiveObjectFilter {
+    pub fn new(
+        types: Vec<String>,
+        packages: Vec<ObjectID>,
+        owners: Vec<SuiAddress>,
+    ) -> anyhow::Result<Self> {
+        // Normalize types, so that the filter has a single description.
+        let mut types = types
+            .iter()
+            .map(|type_| {
+                parse_sui_struct_tag(type_)
+                    .map(|tag| tag.to_canonical_string(/* with_prefix */ true))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        types.sort();
+        types.dedup();
+        let mut packages = packages;
+        packages.sort();
+        packages.dedup();
+        let mut owners = owners;
+        owners.sort();
+        owners.dedup();
+        Ok(Self {
+            types,
+            packages,
+            owners,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.packages.is_empty() && self.owners.is_empty()
+    }
+
+    pub fn matches(&self, object: &LiveObject) -> bool {
+        match object {
+            LiveObject::Normal(object) => self.matches_object(object),
+            // Wrapped objects have no type or owner of their own.
+            LiveObject::Wrapped(_) => false,
+        }
+    }
+
+    fn matches_object(&self, object: &Object) -> bool {
+        if self.packages.contains(&object.id()) && object.is_package() {
+            return true;
+        }
+        if object
+            .owner
+            .get_owner_address()
+            .is_ok_and(|owner| self.owners.contains(&owner))
+        {
+            return true;
+        }
+        let Some(tag) = object.struct_tag() else {
+            return false;
+        };
+        if self.packages.contains(&ObjectID::from(tag.address)) {
+            return true;
+        }
+        let instantiation = tag.to_canonical_string(/* with_prefix */ true);
+        let uninstantiated = format!(
+            "{}::{}::{}",
+            tag.address.to_canonical_string(/* with_prefix */ true),
+            tag.module,
+            tag.name
+        );
+        self.types
+            .iter()
+            .any(|type_| *type_ == instantiation || *type_ == uninstantiated)
+    }
+}
+
+impl fmt::Display for LiveObjectFilter {
+    /// A canonical description of the filter, e.g. to record which filter a partial restore
+    /// applied.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let types = self.types.iter().map(|type_| format!("type={type_}"));
+        let packages = self.packages.iter().map(|id| format!("package={id}"));
+        let owners = self.owners.iter().map(|owner| format!("owner={owner}"));
+        let parts: Vec<_> = types.chain(packages).chain(owners).collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
@@ -5,6 +5,8 @@
 #[cfg(test)]
 mod tests;
 
+pub mod export;
+pub mod filter;
 pub mod reader;
 pub mod uploader;
 mod writer;
@@ -72,6 +74,19 @@ use tokio::time::Instant;
 ///     - epoch_1/
 ///       - 1_1.obj
 ///       - ...
+///     - delta/
+///       - epoch_1/
+///          - 1_1.obj
+///          - 1_1.ref
+///          - 1_1.rm
+///          - MANIFEST
+///
+/// A delta snapshot holds the change in the live object set between the end of a base epoch and
+/// the end of a later one: the objects that were created or mutated (in *.obj and *.ref files, as
+/// in a full snapshot) and the references of the base epoch's live objects that are no longer live,
+/// whether because they were deleted, wrapped or mutated (in *.rm files, in the REFERENCE file
+/// format). Applying a chain of deltas to a restored base snapshot yields the live object set of
+/// the last delta's epoch. The MANIFEST of a delta snapshot records its base epoch.
 ///
 /// Object File Disk Format
 ///┌──────────────────────────────┐
@@ -133,6 +148,7 @@ const FILE_TYPE_BYTES: usize = 1;
 const BUCKET_BYTES: usize = 4;
 const BUCKET_PARTITION_BYTES: usize = 4;
 const COMPRESSION_TYPE_BYTES: usize = 1;
+const DELTA_SNAPSHOT_DIR: &str = "delta";
 const FILE_METADATA_BYTES: usize =
     FILE_TYPE_BYTES + BUCKET_BYTES + BUCKET_PARTITION_BYTES + COMPRESSION_TYPE_BYTES + SHA3_BYTES;
 
@@ -143,6 +159,7 @@ const FILE_METADATA_BYTES: usize =
 pub enum FileType {
     Object = 0,
     Reference,
+    Removed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -163,6 +180,9 @@ impl FileMetadata {
             FileType::Reference => {
                 dir_path.child(&*format!("{}_{}.ref", self.bucket_num, self.part_num))
             }
+            FileType::Removed => {
+                dir_path.child(&*format!("{}_{}.rm", self.bucket_num, self.part_num))
+            }
         }
     }
     pub fn local_file_path(&self, root_path: &std::path::Path, dir_path: &Path) -> Result<PathBuf> {
@@ -178,34 +198,66 @@ pub struct ManifestV1 {
     pub epoch: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DeltaManifestV1 {
+    pub snapshot_version: u8,
+    pub address_length: u64,
+    pub file_metadata: Vec<FileMetadata>,
+    pub epoch: u64,
+    /// Epoch of the snapshot this delta applies to.
+    pub base_epoch: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Manifest {
     V1(ManifestV1),
+    DeltaV1(DeltaManifestV1),
 }
 
 impl Manifest {
     pub fn snapshot_version(&self) -> u8 {
         match self {
             Self::V1(manifest) => manifest.snapshot_version,
+            Self::DeltaV1(manifest) => manifest.snapshot_version,
         }
     }
     pub fn address_length(&self) -> u64 {
         match self {
             Self::V1(manifest) => manifest.address_length,
+            Self::DeltaV1(manifest) => manifest.address_length,
         }
     }
     pub fn file_metadata(&self) -> &Vec<FileMetadata> {
         match self {
             Self::V1(manifest) => &manifest.file_metadata,
+            Self::DeltaV1(manifest) => &manifest.file_metadata,
         }
     }
     pub fn epoch(&self) -> u64 {
         match self {
             Self::V1(manifest) => manifest.epoch,
+            Self::DeltaV1(manifest) => manifest.epoch,
+        }
+    }
+    /// The epoch of the snapshot this one is a delta against, or `None` for a full snapshot.
+    pub fn base_epoch(&self) -> Option<u64> {
+        match self {
+            Self::V1(_) => None,
+            Self::DeltaV1(manifest) => Some(manifest.base_epoch),
         }
     }
 }
 
+/// Directory holding the full snapshot of `epoch`.
+pub fn snapshot_dir(epoch: u64) -> Path {
+    Path::from(format!("epoch_{}", epoch))
+}
+
+/// Directory holding the delta snapshot of `epoch`.
+pub fn delta_snapshot_dir(epoch: u64) -> Path {
+    Path::from(DELTA_SNAPSHOT_DIR).child(format!("epoch_{}", epoch))
+}
+
 pub fn create_file_metadata(
     file_path: &std::path::Path,
     file_compression: FileCompression,
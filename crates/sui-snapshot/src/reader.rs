@@ -1,9 +1,11 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::filter::LiveObjectFilter;
 use crate::{
-    FileMetadata, FileType, Manifest, MAGIC_BYTES, MANIFEST_FILE_MAGIC, OBJECT_FILE_MAGIC,
-    OBJECT_ID_BYTES, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC, SEQUENCE_NUM_BYTES, SHA3_BYTES,
+    compute_sha3_checksum, delta_snapshot_dir, snapshot_dir, FileMetadata, FileType, Manifest,
+    MAGIC_BYTES, MANIFEST_FILE_MAGIC, OBJECT_FILE_MAGIC, OBJECT_ID_BYTES, OBJECT_REF_BYTES,
+    REFERENCE_FILE_MAGIC, SEQUENCE_NUM_BYTES, SHA3_BYTES,
 };
 use anyhow::{anyhow, Context, Result};
 use byteorder::{BigEndian, ReadBytesExt};
@@ -45,11 +47,18 @@ pub type Sha3DigestType = Arc<Mutex<BTreeMap<u32, BTreeMap<u32, [u8; 32]>>>>;
 #[derive(Clone)]
 pub struct StateSnapshotReaderV1 {
     epoch: u64,
+    /// Epoch of the snapshot this one is a delta against, if it is a delta snapshot
+    base_epoch: Option<u64>,
+    dir: Path,
     local_staging_dir_root: PathBuf,
     remote_object_store: Arc<dyn ObjectStoreGetExt>,
     local_object_store: Arc<dyn ObjectStorePutExt>,
     ref_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
     object_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
+    removed_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
+    num_removed_objects: u64,
+    /// If set, only the live objects matching the filter are restored
+    filter: Option<LiveObjectFilter>,
     m: MultiProgress,
     concurrency: usize,
 }
@@ -63,7 +72,54 @@ impl StateSnapshotReaderV1 {
         m: MultiProgress,
         skip_reset_local_store: bool,
     ) -> Result<Self> {
-        let epoch_dir = format!("epoch_{}", epoch);
+        Self::new_impl(
+            epoch,
+            false, // delta
+            remote_store_config,
+            local_store_config,
+            download_concurrency,
+            m,
+            skip_reset_local_store,
+        )
+        .await
+    }
+
+    /// Creates a reader for the delta snapshot of `epoch`, which can only be read into a store
+    /// holding the live object set of its base epoch (see [`Self::base_epoch`]).
+    pub async fn new_delta(
+        epoch: u64,
+        remote_store_config: &ObjectStoreConfig,
+        local_store_config: &ObjectStoreConfig,
+        download_concurrency: NonZeroUsize,
+        m: MultiProgress,
+        skip_reset_local_store: bool,
+    ) -> Result<Self> {
+        Self::new_impl(
+            epoch,
+            true, // delta
+            remote_store_config,
+            local_store_config,
+            download_concurrency,
+            m,
+            skip_reset_local_store,
+        )
+        .await
+    }
+
+    async fn new_impl(
+        epoch: u64,
+        delta: bool,
+        remote_store_config: &ObjectStoreConfig,
+        local_store_config: &ObjectStoreConfig,
+        download_concurrency: NonZeroUsize,
+        m: MultiProgress,
+        skip_reset_local_store: bool,
+    ) -> Result<Self> {
+        let epoch_dir_path = if delta {
+            delta_snapshot_dir(epoch)
+        } else {
+            snapshot_dir(epoch)
+        };
         let remote_object_store = if remote_store_config.no_sign_request {
             remote_store_config.make_http()?
         } else {
@@ -79,14 +135,15 @@ impl StateSnapshotReaderV1 {
             .context("No directory specified")?
             .clone();
         if !skip_reset_local_store {
-            let local_epoch_dir_path = local_staging_dir_root.join(&epoch_dir);
+            let local_epoch_dir_path =
+                path_to_filesystem(local_staging_dir_root.clone(), &epoch_dir_path)?;
             if local_epoch_dir_path.exists() {
                 fs::remove_dir_all(&local_epoch_dir_path)?;
             }
             fs::create_dir_all(&local_epoch_dir_path)?;
         }
         // Download MANIFEST first
-        let manifest_file_path = Path::from(epoch_dir.clone()).child("MANIFEST");
+        let manifest_file_path = epoch_dir_path.child("MANIFEST");
         copy_file(
             &manifest_file_path,
             &manifest_file_path,
@@ -111,8 +168,17 @@ impl StateSnapshotReaderV1 {
         if manifest.epoch() != epoch {
             return Err(anyhow!("Download manifest is not for epoch: {}", epoch,));
         }
+        let base_epoch = manifest.base_epoch();
+        if base_epoch.is_some() != delta {
+            return Err(anyhow!(
+                "Downloaded manifest for epoch {} is not for a {} snapshot",
+                epoch,
+                if delta { "delta" } else { "full" },
+            ));
+        }
         let mut object_files = BTreeMap::new();
         let mut ref_files = BTreeMap::new();
+        let mut removed_files = BTreeMap::new();
         for file_metadata in manifest.file_metadata() {
             match file_metadata.file_type {
                 FileType::Object => {
@@ -127,11 +193,17 @@ impl StateSnapshotReaderV1 {
                         .or_insert_with(BTreeMap::new);
                     entry.insert(file_metadata.part_num, file_metadata.clone());
                 }
+                FileType::Removed => {
+                    let entry = removed_files
+                        .entry(file_metadata.bucket_num)
+                        .or_insert_with(BTreeMap::new);
+                    entry.insert(file_metadata.part_num, file_metadata.clone());
+                }
             }
         }
-        let epoch_dir_path = Path::from(epoch_dir);
         let files: Vec<Path> = ref_files
             .values()
+            .chain(removed_files.values())
             .flat_map(|entry| {
                 let files: Vec<_> = entry
                     .values()
@@ -179,11 +251,16 @@ impl StateSnapshotReaderV1 {
         progress_bar.finish_with_message("Missing ref files download complete");
         Ok(StateSnapshotReaderV1 {
             epoch,
+            base_epoch,
+            dir: epoch_dir_path,
             local_staging_dir_root,
             remote_object_store,
             local_object_store,
             ref_files,
             object_files,
+            removed_files,
+            num_removed_objects: 0,
+            filter: None,
             m,
             concurrency: download_concurrency.get(),
         })
@@ -200,6 +277,9 @@ impl StateSnapshotReaderV1 {
         // per *.obj file against this. We do this so during restore we can pre fetch object
         // references and start building state accumulator and fail early if the state root hash
         // doesn't match but we still need to ensure that objects match references exactly.
+        if self.base_epoch.is_some() {
+            self.remove_objects(perpetual_db, sender.clone()).await?;
+        }
         let (sha3_digests, num_part_files) = self.compute_checksum().await?;
         let accum_handle =
             sender.map(|sender| self.spawn_accumulation_tasks(sender, num_part_files));
@@ -211,6 +291,37 @@ impl StateSnapshotReaderV1 {
         Ok(())
     }
 
+    /// Restore only the live objects matching `filter`. The snapshot is still verified in full:
+    /// the accumulator sent by `read` and the checksums of the object files cover all objects.
+    pub fn set_filter(&mut self, filter: LiveObjectFilter) {
+        self.filter = Some(filter);
+    }
+
+    /// Removes the objects listed in the *.rm files of a delta snapshot from `perpetual_db`. Their
+    /// digests are subtracted from the accumulator sent to `sender`.
+    async fn remove_objects(
+        &mut self,
+        perpetual_db: &AuthorityPerpetualTables,
+        sender: Option<tokio::sync::mpsc::Sender<(GlobalStateHash, u64)>>,
+    ) -> Result<()> {
+        let mut acc = GlobalStateHash::default();
+        let mut num_removed_objects = 0;
+        for file_metadata in self.removed_files() {
+            let object_refs: Vec<ObjectRef> = self.removed_ref_iter(&file_metadata)?.collect();
+            AuthorityStore::bulk_remove_live_objects(perpetual_db, object_refs.iter().copied())?;
+            num_removed_objects += object_refs.len() as u64;
+            acc.remove_all(object_refs.into_iter().map(|object_ref| object_ref.2));
+        }
+        self.num_removed_objects = num_removed_objects;
+        if let Some(sender) = sender {
+            sender
+                .send((acc, 0))
+                .await
+                .map_err(|_| anyhow!("Unable to send accumulator from snapshot reader"))?;
+        }
+        Ok(())
+    }
+
     pub async fn compute_checksum(
         &mut self,
     ) -> Result<(Arc<Mutex<BTreeMap<u32, BTreeMap<u32, [u8; 32]>>>>, usize), anyhow::Error> {
@@ -383,6 +494,7 @@ impl StateSnapshotReaderV1 {
         let epoch_dir = self.epoch_dir();
         let concurrency = self.concurrency;
         let remote_object_store = self.remote_object_store.clone();
+        let filter = self.filter.clone();
         let input_files: Vec<_> = self
             .object_files
             .iter()
@@ -438,9 +550,10 @@ impl StateSnapshotReaderV1 {
                         let bytes_len = bytes.len();
                         let result: Result<(), anyhow::Error> =
                             LiveObjectIter::new(&file_metadata, bytes).map(|obj_iter| {
-                                AuthorityStore::bulk_insert_live_objects(
+                                AuthorityStore::bulk_insert_filtered_live_objects(
                                     perpetual_db,
                                     obj_iter,
+                                    |object| filter.as_ref().is_none_or(|f| f.matches(object)),
                                     &sha3_digest,
                                 )
                                 .expect("Failed to insert live objects");
@@ -513,8 +626,47 @@ impl StateSnapshotReaderV1 {
         Ok(self.ref_files.keys().copied().collect())
     }
 
+    /// The *.rm files of a delta snapshot, listing the references of the objects that are no
+    /// longer live since its base epoch.
+    pub fn removed_files(&self) -> Vec<FileMetadata> {
+        self.removed_files
+            .values()
+            .flat_map(|parts| parts.values().cloned())
+            .collect()
+    }
+
+    /// Iterates over the object refs in a *.rm file, after checking the file against its
+    /// checksum in the MANIFEST.
+    pub fn removed_ref_iter(&self, file_metadata: &FileMetadata) -> Result<ObjectRefIter> {
+        let file_path =
+            file_metadata.local_file_path(&self.local_staging_dir_root, &self.epoch_dir())?;
+        let sha3_digest = compute_sha3_checksum(&file_path)?;
+        if sha3_digest != file_metadata.sha3_digest {
+            return Err(anyhow!(
+                "Checksum of {} doesn't match MANIFEST",
+                file_path.display()
+            ));
+        }
+        ObjectRefIter::new(
+            file_metadata,
+            self.local_staging_dir_root.clone(),
+            self.epoch_dir(),
+        )
+    }
+
+    /// The epoch of the snapshot this one is a delta against, or `None` for a full snapshot.
+    pub fn base_epoch(&self) -> Option<u64> {
+        self.base_epoch
+    }
+
+    /// The number of objects removed from the live object set by the last `read` of a delta
+    /// snapshot.
+    pub fn num_removed_objects(&self) -> u64 {
+        self.num_removed_objects
+    }
+
     fn epoch_dir(&self) -> Path {
-        Path::from(format!("epoch_{}", self.epoch))
+        self.dir.clone()
     }
 
     fn read_manifest(path: PathBuf) -> anyhow::Result<Manifest> {
@@ -602,6 +754,33 @@ pub async fn download_bytes(
     (bytes, sha3_digest)
 }
 
+/// Reads the objects in a *.obj file, checking them against the sha3 digest of their references,
+/// and keeps those that match `filter`.
+pub fn read_live_objects(
+    file_metadata: &FileMetadata,
+    bytes: Bytes,
+    expected_sha3_digest: &[u8; 32],
+    filter: Option<&LiveObjectFilter>,
+) -> Result<Vec<LiveObject>> {
+    let mut hasher = Sha3_256::default();
+    let mut objects = vec![];
+    for object in LiveObjectIter::new(file_metadata, bytes)? {
+        hasher.update(object.object_reference().2.inner());
+        if filter.is_none_or(|filter| filter.matches(&object)) {
+            objects.push(object);
+        }
+    }
+    let sha3_digest = hasher.finalize().digest;
+    if *expected_sha3_digest != sha3_digest {
+        return Err(anyhow!(
+            "Checksum of objects in bucket: {}, part: {} doesn't match their references",
+            file_metadata.bucket_num,
+            file_metadata.part_num
+        ));
+    }
+    Ok(objects)
+}
+
 /// An iterator over all object refs in a .ref file.
 pub struct ObjectRefIter {
     reader: Box<dyn Read>,
@@ -1,12 +1,16 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::export::export_parquet;
+use crate::filter::LiveObjectFilter;
 use crate::reader::StateSnapshotReaderV1;
 use crate::writer::StateSnapshotWriterV1;
 use crate::FileCompression;
+use arrow_array::cast::AsArray;
 use fastcrypto::hash::MultisetHash;
 use futures::future::AbortHandle;
 use indicatif::MultiProgress;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use std::collections::HashSet;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
@@ -14,10 +18,10 @@ use sui_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
 use sui_core::authority::authority_store_tables::AuthorityPerpetualTables;
 use sui_core::global_state_hasher::GlobalStateHasher;
 use sui_protocol_config::ProtocolConfig;
-use sui_types::base_types::ObjectID;
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress};
 use sui_types::global_state_hash::GlobalStateHash;
 use sui_types::messages_checkpoint::ECMHLiveObjectSetDigest;
-use sui_types::object::Object;
+use sui_types::object::{Object, Owner};
 use tempfile::tempdir;
 
 fn temp_dir() -> std::path::PathBuf {
@@ -181,3 +185,283 @@ async fn test_snapshot_empty_db() -> Result<(), anyhow::Error> {
     )?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_delta_snapshot() -> Result<(), anyhow::Error> {
+    let base_db_path = temp_dir();
+    let db_path = temp_dir();
+    let restored_db_path = temp_dir();
+    let local = temp_dir().join("local_dir");
+    let remote = temp_dir().join("remote_dir");
+    let restored_local = temp_dir().join("local_dir_restore");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(local),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(remote),
+        ..Default::default()
+    };
+
+    // The live object set at the end of epoch 0, and at the end of epoch 1 after deleting the
+    // first 100 objects, mutating the next 100 and creating 200 more.
+    let base_db = Arc::new(AuthorityPerpetualTables::open(&base_db_path, None));
+    insert_keys(&base_db, 1000)?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    let ids = ObjectID::in_range(ObjectID::ZERO, 1200)?;
+    for (i, id) in ids.into_iter().enumerate().skip(100) {
+        let object = if i < 200 {
+            Object::with_id_owner_version_for_testing(
+                id,
+                SequenceNumber::from_u64(2),
+                Owner::Immutable,
+            )
+        } else {
+            Object::immutable_with_id_for_testing(id)
+        };
+        perpetual_db.insert_object_test_only(object)?;
+    }
+
+    let base_accumulator =
+        ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&base_db, true).digest());
+    StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?
+    .write_internal(0, true, base_db.clone(), base_accumulator)
+    .await?;
+    let root_accumulator = accumulate_live_object_set(&perpetual_db, true);
+    StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?
+    .write_delta_internal(
+        0,
+        1,
+        true,
+        base_db.clone(),
+        perpetual_db.clone(),
+        ECMHLiveObjectSetDigest::from(root_accumulator.digest()),
+    )
+    .await?;
+
+    let local_store_restore_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(restored_local),
+        ..Default::default()
+    };
+    let restored_perpetual_db = AuthorityPerpetualTables::open(&restored_db_path, None);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(100);
+    let mut snapshot_reader = StateSnapshotReaderV1::new(
+        0,
+        &remote_store_config,
+        &local_store_restore_config,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+    )
+    .await?;
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    snapshot_reader
+        .read(
+            &restored_perpetual_db,
+            abort_registration,
+            Some(sender.clone()),
+        )
+        .await?;
+    let mut delta_reader = StateSnapshotReaderV1::new_delta(
+        1,
+        &remote_store_config,
+        &local_store_restore_config,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+    )
+    .await?;
+    assert_eq!(delta_reader.base_epoch(), Some(0));
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    delta_reader
+        .read(&restored_perpetual_db, abort_registration, Some(sender))
+        .await?;
+    assert_eq!(delta_reader.num_removed_objects(), 200);
+
+    let mut restored_accumulator = GlobalStateHash::default();
+    let mut num_added_objects = 0;
+    while let Some((partial_acc, num_objects)) = receiver.recv().await {
+        restored_accumulator.union(&partial_acc);
+        num_added_objects += num_objects;
+    }
+    assert_eq!(num_added_objects, 1000 + 300);
+    assert_eq!(restored_accumulator.digest(), root_accumulator.digest());
+    compare_live_objects(&perpetual_db, &restored_perpetual_db, true)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_filtered() -> Result<(), anyhow::Error> {
+    let db_path = temp_dir();
+    let restored_db_path = temp_dir();
+    let local = temp_dir().join("local_dir");
+    let remote = temp_dir().join("remote_dir");
+    let restored_local = temp_dir().join("local_dir_restore");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(local),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(remote),
+        ..Default::default()
+    };
+
+    let snapshot_writer = StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    insert_keys(&perpetual_db, 1000)?;
+    let owner = SuiAddress::random_for_testing_only();
+    let owned_ids = ObjectID::in_range(ObjectID::from_single_byte(0xff), 10)?;
+    for id in &owned_ids {
+        perpetual_db.insert_object_test_only(Object::with_id_owner_version_for_testing(
+            *id,
+            SequenceNumber::from_u64(1),
+            Owner::AddressOwner(owner),
+        ))?;
+    }
+    let root_accumulator = accumulate_live_object_set(&perpetual_db, true);
+    snapshot_writer
+        .write_internal(
+            0,
+            true,
+            perpetual_db.clone(),
+            ECMHLiveObjectSetDigest::from(root_accumulator.digest()),
+        )
+        .await?;
+    let local_store_restore_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(restored_local),
+        ..Default::default()
+    };
+    let mut snapshot_reader = StateSnapshotReaderV1::new(
+        0,
+        &remote_store_config,
+        &local_store_restore_config,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+    )
+    .await?;
+    snapshot_reader.set_filter(LiveObjectFilter::new(vec![], vec![], vec![owner])?);
+    let restored_perpetual_db = AuthorityPerpetualTables::open(&restored_db_path, None);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(100);
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    snapshot_reader
+        .read(&restored_perpetual_db, abort_registration, Some(sender))
+        .await?;
+
+    // Only the owned objects are restored, but the whole snapshot is verified.
+    let restored: HashSet<_> = restored_perpetual_db
+        .iter_live_object_set(true)
+        .map(|live_object| live_object.object_id())
+        .collect();
+    assert_eq!(restored, owned_ids.into_iter().collect());
+    let mut snapshot_accumulator = GlobalStateHash::default();
+    while let Some((partial_acc, _)) = receiver.recv().await {
+        snapshot_accumulator.union(&partial_acc);
+    }
+    assert_eq!(snapshot_accumulator.digest(), root_accumulator.digest());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_export_parquet() -> Result<(), anyhow::Error> {
+    let db_path = temp_dir();
+    let export_path = temp_dir();
+    let local = temp_dir().join("local_dir");
+    let remote = temp_dir().join("remote_dir");
+    let export_local = temp_dir().join("local_dir_export");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(local),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(remote),
+        ..Default::default()
+    };
+
+    let snapshot_writer = StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    insert_keys(&perpetual_db, 1000)?;
+    let owner = SuiAddress::random_for_testing_only();
+    let owned_ids = ObjectID::in_range(ObjectID::from_single_byte(0xff), 10)?;
+    for id in &owned_ids {
+        perpetual_db.insert_object_test_only(Object::with_id_owner_version_for_testing(
+            *id,
+            SequenceNumber::from_u64(1),
+            Owner::AddressOwner(owner),
+        ))?;
+    }
+    let root_accumulator = accumulate_live_object_set(&perpetual_db, true);
+    snapshot_writer
+        .write_internal(
+            0,
+            true,
+            perpetual_db.clone(),
+            ECMHLiveObjectSetDigest::from(root_accumulator.digest()),
+        )
+        .await?;
+    let local_store_export_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(export_local),
+        ..Default::default()
+    };
+    let mut snapshot_reader = StateSnapshotReaderV1::new(
+        0,
+        &remote_store_config,
+        &local_store_export_config,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+    )
+    .await?;
+    export_parquet(&mut snapshot_reader, &export_path, false).await?;
+
+    let mut num_objects = 0;
+    let mut owners = HashSet::new();
+    for entry in std::fs::read_dir(export_path.join("epoch_0"))? {
+        let file = std::fs::File::open(entry?.path())?;
+        for batch in ParquetRecordBatchReaderBuilder::try_new(file)?.build()? {
+            let batch = batch?;
+            num_objects += batch.num_rows();
+            let owner_address = batch
+                .column_by_name("owner_address")
+                .unwrap()
+                .as_string::<i32>();
+            owners.extend(owner_address.iter().flatten().map(str::to_owned));
+        }
+    }
+    assert_eq!(num_objects, 1010);
+    assert_eq!(owners, HashSet::from([owner.to_string()]));
+    Ok(())
+}
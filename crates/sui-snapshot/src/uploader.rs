@@ -4,6 +4,7 @@
 use crate::writer::StateSnapshotWriterV1;
 use anyhow::Result;
 use bytes::Bytes;
+use object_store::path::Path;
 use object_store::DynObjectStore;
 use prometheus::{
     register_int_counter_with_registry, register_int_gauge_with_registry, IntCounter, IntGauge,
@@ -70,6 +71,8 @@ pub struct StateSnapshotUploader {
     /// The chain identifier is derived from the genesis checkpoint and used to identify the
     /// network.
     chain_identifier: ChainIdentifier,
+    /// Whether to also write a delta snapshot of each epoch against the previous one
+    delta_snapshots: bool,
 }
 
 impl StateSnapshotUploader {
@@ -81,6 +84,7 @@ impl StateSnapshotUploader {
         registry: &Registry,
         checkpoint_store: Arc<CheckpointStore>,
         chain_identifier: ChainIdentifier,
+        delta_snapshots: bool,
     ) -> Result<Arc<Self>> {
         let db_checkpoint_store_config = ObjectStoreConfig {
             object_store: Some(ObjectStoreType::File),
@@ -102,6 +106,7 @@ impl StateSnapshotUploader {
             interval: Duration::from_secs(interval_s),
             metrics: StateSnapshotUploaderMetrics::new(registry),
             chain_identifier,
+            delta_snapshots,
         }))
     }
 
@@ -121,8 +126,11 @@ impl StateSnapshotUploader {
             find_all_dirs_with_epoch_prefix(&self.db_checkpoint_store, None).await?;
         let mut dirs: Vec<_> = local_checkpoints_by_epoch.iter().collect();
         dirs.sort_by_key(|(epoch_num, _path)| *epoch_num);
+        let latest_local_epoch = dirs.last().map(|(epoch, _path)| **epoch);
+        let is_missing =
+            |epoch: u64| missing_epochs.contains(&epoch) || epoch >= last_missing_epoch;
         for (epoch, db_path) in dirs {
-            if missing_epochs.contains(epoch) || *epoch >= last_missing_epoch {
+            if is_missing(*epoch) {
                 info!("Starting state snapshot creation for epoch: {}", *epoch);
                 let state_snapshot_writer = StateSnapshotWriterV1::new_from_store(
                     &self.staging_path,
@@ -146,39 +154,89 @@ impl StateSnapshotUploader {
                     .expect("Expected at least one commitment")
                     .clone();
                 state_snapshot_writer
-                    .write(*epoch, db, state_hash_commitment, self.chain_identifier)
+                    .write(
+                        *epoch,
+                        db.clone(),
+                        state_hash_commitment.clone(),
+                        self.chain_identifier,
+                    )
                     .await?;
                 info!("State snapshot creation successful for epoch: {}", *epoch);
+                // The db checkpoint of the previous epoch is kept until the delta against it
+                // is written, and is no longer needed after that
+                let base = epoch
+                    .checked_sub(1)
+                    .and_then(|base_epoch| local_checkpoints_by_epoch.get_key_value(&base_epoch))
+                    .filter(|_| self.delta_snapshots);
+                if let Some((base_epoch, base_path)) = base {
+                    info!(
+                        "Starting delta state snapshot creation for epoch: {}",
+                        *epoch
+                    );
+                    let delta_snapshot_writer = StateSnapshotWriterV1::new_from_store(
+                        &self.staging_path,
+                        &self.staging_store,
+                        &self.snapshot_store,
+                        FileCompression::Zstd,
+                        NonZeroUsize::new(20).unwrap(),
+                    )
+                    .await?;
+                    let base_db = Arc::new(AuthorityPerpetualTables::open(
+                        &path_to_filesystem(
+                            self.db_checkpoint_path.clone(),
+                            &base_path.child("store"),
+                        )?,
+                        None,
+                    ));
+                    delta_snapshot_writer
+                        .write_delta(
+                            *base_epoch,
+                            *epoch,
+                            base_db,
+                            db,
+                            state_hash_commitment,
+                            self.chain_identifier,
+                        )
+                        .await?;
+                    info!(
+                        "Delta state snapshot creation successful for epoch: {}",
+                        *epoch
+                    );
+                    self.mark_state_snapshot_completed(base_path).await?;
+                }
                 // Drop marker in the output directory that upload completed successfully
                 let bytes = Bytes::from_static(b"success");
                 let success_marker = db_path.child(SUCCESS_MARKER);
                 put(&self.snapshot_store, &success_marker, bytes.clone()).await?;
-                let bytes = Bytes::from_static(b"success");
-                let state_snapshot_completed_marker =
-                    db_path.child(STATE_SNAPSHOT_COMPLETED_MARKER);
-                put(
-                    &self.db_checkpoint_store.clone(),
-                    &state_snapshot_completed_marker,
-                    bytes.clone(),
-                )
-                .await?;
+                if !self.delta_snapshots {
+                    self.mark_state_snapshot_completed(db_path).await?;
+                }
                 info!("State snapshot completed for epoch: {epoch}");
+            } else if self.delta_snapshots
+                && (Some(*epoch) == latest_local_epoch || is_missing(*epoch + 1))
+            {
+                info!("Keeping db checkpoint of epoch {epoch} for the next delta snapshot");
             } else {
-                let bytes = Bytes::from_static(b"success");
-                let state_snapshot_completed_marker =
-                    db_path.child(STATE_SNAPSHOT_COMPLETED_MARKER);
-                put(
-                    &self.db_checkpoint_store.clone(),
-                    &state_snapshot_completed_marker,
-                    bytes.clone(),
-                )
-                .await?;
+                self.mark_state_snapshot_completed(db_path).await?;
                 info!("State snapshot skipped for epoch: {epoch}");
             }
         }
         Ok(())
     }
 
+    /// Drops the marker that allows the db checkpoint at `db_path` to be garbage collected
+    async fn mark_state_snapshot_completed(&self, db_path: &Path) -> Result<()> {
+        let bytes = Bytes::from_static(b"success");
+        let state_snapshot_completed_marker = db_path.child(STATE_SNAPSHOT_COMPLETED_MARKER);
+        put(
+            &self.db_checkpoint_store.clone(),
+            &state_snapshot_completed_marker,
+            bytes.clone(),
+        )
+        .await?;
+        Ok(())
+    }
+
     async fn run_upload_loop(
         self: Arc<Self>,
         mut recv: tokio::sync::broadcast::Receiver<()>,
@@ -3,11 +3,12 @@
 #![allow(dead_code)]
 
 use crate::{
-    compute_sha3_checksum, create_file_metadata, FileCompression, FileMetadata, FileType, Manifest,
-    ManifestV1, FILE_MAX_BYTES, MAGIC_BYTES, MANIFEST_FILE_MAGIC, OBJECT_FILE_MAGIC,
-    OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC, SEQUENCE_NUM_BYTES,
+    compute_sha3_checksum, create_file_metadata, delta_snapshot_dir, snapshot_dir, DeltaManifestV1,
+    FileCompression, FileMetadata, FileType, Manifest, ManifestV1, FILE_MAX_BYTES, MAGIC_BYTES,
+    MANIFEST_FILE_MAGIC, OBJECT_FILE_MAGIC, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC,
+    SEQUENCE_NUM_BYTES,
 };
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use byteorder::{BigEndian, ByteOrder};
 use fastcrypto::hash::MultisetHash;
 use futures::StreamExt;
@@ -102,18 +103,7 @@ impl LiveObjectSetWriterV1 {
         Ok((n, f))
     }
     fn ref_file(dir_path: PathBuf, bucket_num: u32, part_num: u32) -> Result<File> {
-        let ref_path = dir_path.join(format!("{bucket_num}_{part_num}.ref"));
-        let ref_tmp_path = dir_path.join(format!("{bucket_num}_{part_num}.ref.tmp"));
-        let mut f = File::create(ref_tmp_path.clone())?;
-        f.rewind()?;
-        let mut metab = [0u8; MAGIC_BYTES];
-        BigEndian::write_u32(&mut metab, REFERENCE_FILE_MAGIC);
-        let n = f.write(&metab)?;
-        drop(f);
-        fs::rename(ref_tmp_path, ref_path.clone())?;
-        let mut f = OpenOptions::new().append(true).open(ref_path)?;
-        f.seek(SeekFrom::Start(n as u64))?;
-        Ok(f)
+        create_ref_file(dir_path, &format!("{bucket_num}_{part_num}.ref"))
     }
     fn finalize(&mut self) -> Result<()> {
         self.wbuf.flush()?;
@@ -193,19 +183,119 @@ impl LiveObjectSetWriterV1 {
         Ok(())
     }
     fn write_object_ref(&mut self, object_ref: &ObjectRef) -> Result<()> {
-        let mut buf = [0u8; OBJECT_REF_BYTES];
-        buf[0..ObjectID::LENGTH].copy_from_slice(object_ref.0.as_ref());
-        BigEndian::write_u64(
-            &mut buf[ObjectID::LENGTH..OBJECT_REF_BYTES],
-            object_ref.1.value(),
-        );
-        buf[ObjectID::LENGTH + SEQUENCE_NUM_BYTES..OBJECT_REF_BYTES]
-            .copy_from_slice(object_ref.2.as_ref());
-        self.ref_wbuf.write_all(&buf)?;
+        write_object_ref(&mut self.ref_wbuf, object_ref)
+    }
+}
+
+/// RemovedObjectSetWriterV1 writes the references of objects that are no longer live to *.rm files,
+/// for delta snapshots
+struct RemovedObjectSetWriterV1 {
+    dir_path: PathBuf,
+    bucket_num: u32,
+    current_part_num: u32,
+    wbuf: BufWriter<File>,
+    n: usize,
+    files: Vec<FileMetadata>,
+    sender: Option<Sender<FileMetadata>>,
+    file_compression: FileCompression,
+}
+
+impl RemovedObjectSetWriterV1 {
+    fn new(
+        dir_path: PathBuf,
+        bucket_num: u32,
+        file_compression: FileCompression,
+        sender: Sender<FileMetadata>,
+    ) -> Result<Self> {
+        let part_num = 1;
+        let f = Self::removed_file(dir_path.clone(), bucket_num, part_num)?;
+        Ok(RemovedObjectSetWriterV1 {
+            dir_path,
+            bucket_num,
+            current_part_num: part_num,
+            wbuf: BufWriter::new(f),
+            n: MAGIC_BYTES,
+            files: vec![],
+            sender: Some(sender),
+            file_compression,
+        })
+    }
+    pub fn write(&mut self, object_ref: &ObjectRef) -> Result<()> {
+        if self.n + OBJECT_REF_BYTES > FILE_MAX_BYTES {
+            self.finalize()?;
+            self.current_part_num += 1;
+            let f = Self::removed_file(
+                self.dir_path.clone(),
+                self.bucket_num,
+                self.current_part_num,
+            )?;
+            self.wbuf = BufWriter::new(f);
+            self.n = MAGIC_BYTES;
+        }
+        write_object_ref(&mut self.wbuf, object_ref)?;
+        self.n += OBJECT_REF_BYTES;
+        Ok(())
+    }
+    pub fn done(mut self) -> Result<Vec<FileMetadata>> {
+        self.finalize()?;
+        self.sender = None;
+        Ok(self.files.clone())
+    }
+    fn removed_file(dir_path: PathBuf, bucket_num: u32, part_num: u32) -> Result<File> {
+        create_ref_file(dir_path, &format!("{bucket_num}_{part_num}.rm"))
+    }
+    fn finalize(&mut self) -> Result<()> {
+        self.wbuf.flush()?;
+        self.wbuf.get_ref().sync_data()?;
+        let off = self.wbuf.get_ref().stream_position()?;
+        self.wbuf.get_ref().set_len(off)?;
+        let file_path = self
+            .dir_path
+            .join(format!("{}_{}.rm", self.bucket_num, self.current_part_num));
+        let file_metadata = create_file_metadata(
+            &file_path,
+            self.file_compression,
+            FileType::Removed,
+            self.bucket_num,
+            self.current_part_num,
+        )?;
+        self.files.push(file_metadata.clone());
+        if let Some(sender) = &self.sender {
+            sender.blocking_send(file_metadata)?;
+        }
         Ok(())
     }
 }
 
+/// Creates a file in the REFERENCE file format, positioned for appending object references
+fn create_ref_file(dir_path: PathBuf, file_name: &str) -> Result<File> {
+    let ref_path = dir_path.join(file_name);
+    let ref_tmp_path = dir_path.join(format!("{file_name}.tmp"));
+    let mut f = File::create(ref_tmp_path.clone())?;
+    f.rewind()?;
+    let mut metab = [0u8; MAGIC_BYTES];
+    BigEndian::write_u32(&mut metab, REFERENCE_FILE_MAGIC);
+    let n = f.write(&metab)?;
+    drop(f);
+    fs::rename(ref_tmp_path, ref_path.clone())?;
+    let mut f = OpenOptions::new().append(true).open(ref_path)?;
+    f.seek(SeekFrom::Start(n as u64))?;
+    Ok(f)
+}
+
+fn write_object_ref(wbuf: &mut BufWriter<File>, object_ref: &ObjectRef) -> Result<()> {
+    let mut buf = [0u8; OBJECT_REF_BYTES];
+    buf[0..ObjectID::LENGTH].copy_from_slice(object_ref.0.as_ref());
+    BigEndian::write_u64(
+        &mut buf[ObjectID::LENGTH..OBJECT_REF_BYTES],
+        object_ref.1.value(),
+    );
+    buf[ObjectID::LENGTH + SEQUENCE_NUM_BYTES..OBJECT_REF_BYTES]
+        .copy_from_slice(object_ref.2.as_ref());
+    wbuf.write_all(&buf)?;
+    Ok(())
+}
+
 /// StateSnapshotWriterV1 writes snapshot files to a local staging dir and simultaneously uploads them
 /// to a remote object store
 pub struct StateSnapshotWriterV1 {
@@ -262,14 +352,8 @@ impl StateSnapshotWriterV1 {
         root_state_hash: ECMHLiveObjectSetDigest,
         chain_identifier: ChainIdentifier,
     ) -> Result<()> {
-        let system_state_object = get_sui_system_state(&perpetual_db)?;
-
-        let protocol_version = system_state_object.protocol_version();
-        let protocol_config = ProtocolConfig::get_for_version(
-            ProtocolVersion::new(protocol_version),
-            chain_identifier.chain(),
-        );
-        let include_wrapped_tombstone = !protocol_config.simplified_unwrap_then_delete();
+        let include_wrapped_tombstone =
+            Self::include_wrapped_tombstone(&perpetual_db, chain_identifier)?;
         self.write_internal(
             epoch,
             include_wrapped_tombstone,
@@ -279,6 +363,44 @@ impl StateSnapshotWriterV1 {
         .await
     }
 
+    /// Writes a delta snapshot of `epoch` against the snapshot of `base_epoch`, from the db
+    /// checkpoints taken at the end of both epochs.
+    pub async fn write_delta(
+        self,
+        base_epoch: u64,
+        epoch: u64,
+        base_db: Arc<AuthorityPerpetualTables>,
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        root_state_hash: ECMHLiveObjectSetDigest,
+        chain_identifier: ChainIdentifier,
+    ) -> Result<()> {
+        let include_wrapped_tombstone =
+            Self::include_wrapped_tombstone(&perpetual_db, chain_identifier)?;
+        self.write_delta_internal(
+            base_epoch,
+            epoch,
+            include_wrapped_tombstone,
+            base_db,
+            perpetual_db,
+            root_state_hash,
+        )
+        .await
+    }
+
+    fn include_wrapped_tombstone(
+        perpetual_db: &AuthorityPerpetualTables,
+        chain_identifier: ChainIdentifier,
+    ) -> Result<bool> {
+        let system_state_object = get_sui_system_state(perpetual_db)?;
+
+        let protocol_version = system_state_object.protocol_version();
+        let protocol_config = ProtocolConfig::get_for_version(
+            ProtocolVersion::new(protocol_version),
+            chain_identifier.chain(),
+        );
+        Ok(!protocol_config.simplified_unwrap_then_delete())
+    }
+
     pub(crate) async fn write_internal(
         mut self,
         epoch: u64,
@@ -286,18 +408,20 @@ impl StateSnapshotWriterV1 {
         perpetual_db: Arc<AuthorityPerpetualTables>,
         root_state_hash: ECMHLiveObjectSetDigest,
     ) -> Result<()> {
-        self.setup_epoch_dir(epoch).await?;
+        let dir = snapshot_dir(epoch);
+        self.setup_dir(&dir).await?;
 
-        let manifest_file_path = self.epoch_dir(epoch).child("MANIFEST");
+        let manifest_file_path = dir.child("MANIFEST");
         let local_staging_dir = self.local_staging_dir.clone();
         let local_object_store = self.local_staging_store.clone();
         let remote_object_store = self.remote_object_store.clone();
 
         let (sender, receiver) = mpsc::channel::<FileMetadata>(1000);
-        let upload_handle = self.start_upload(epoch, receiver)?;
+        let upload_handle = self.start_upload(dir.clone(), receiver)?;
         let write_handler = tokio::task::spawn_blocking(move || {
             self.write_live_object_set(
                 epoch,
+                &dir,
                 perpetual_db,
                 sender,
                 Self::bucket_func,
@@ -325,15 +449,71 @@ impl StateSnapshotWriterV1 {
         Ok(())
     }
 
+    pub(crate) async fn write_delta_internal(
+        mut self,
+        base_epoch: u64,
+        epoch: u64,
+        include_wrapped_tombstone: bool,
+        base_db: Arc<AuthorityPerpetualTables>,
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        root_state_hash: ECMHLiveObjectSetDigest,
+    ) -> Result<()> {
+        if base_epoch >= epoch {
+            return Err(anyhow!(
+                "Base epoch {base_epoch} of delta snapshot must precede epoch {epoch}"
+            ));
+        }
+        let dir = delta_snapshot_dir(epoch);
+        self.setup_dir(&dir).await?;
+
+        let manifest_file_path = dir.child("MANIFEST");
+        let local_staging_dir = self.local_staging_dir.clone();
+        let local_object_store = self.local_staging_store.clone();
+        let remote_object_store = self.remote_object_store.clone();
+
+        let (sender, receiver) = mpsc::channel::<FileMetadata>(1000);
+        let upload_handle = self.start_upload(dir.clone(), receiver)?;
+        let write_handler = tokio::task::spawn_blocking(move || {
+            self.write_delta_object_set(
+                base_epoch,
+                epoch,
+                &dir,
+                base_db,
+                perpetual_db,
+                sender,
+                Self::bucket_func,
+                include_wrapped_tombstone,
+                root_state_hash,
+            )
+        });
+        write_handler.await?.context(format!(
+            "Failed to write delta state snapshot for epoch: {}",
+            &epoch
+        ))?;
+
+        upload_handle.await?.context(format!(
+            "Failed to upload delta state snapshot for epoch: {}",
+            &epoch
+        ))?;
+
+        Self::sync_file_to_remote(
+            local_staging_dir,
+            manifest_file_path,
+            local_object_store,
+            remote_object_store,
+        )
+        .await?;
+        Ok(())
+    }
+
     fn start_upload(
         &self,
-        epoch: u64,
+        epoch_dir: Path,
         receiver: Receiver<FileMetadata>,
     ) -> Result<JoinHandle<Result<Vec<()>, anyhow::Error>>> {
         let remote_object_store = self.remote_object_store.clone();
         let local_staging_store = self.local_staging_store.clone();
         let local_dir_path = self.local_staging_dir.clone();
-        let epoch_dir = self.epoch_dir(epoch);
         let upload_concurrency = self.concurrency;
         let join_handle = tokio::spawn(async move {
             let results: Vec<Result<(), anyhow::Error>> = ReceiverStream::new(receiver)
@@ -364,9 +544,11 @@ impl StateSnapshotWriterV1 {
         Ok(join_handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn write_live_object_set<F>(
         &mut self,
         epoch: u64,
+        dir: &Path,
         perpetual_db: Arc<AuthorityPerpetualTables>,
         sender: Sender<FileMetadata>,
         bucket_func: F,
@@ -377,8 +559,7 @@ impl StateSnapshotWriterV1 {
         F: Fn(&LiveObject) -> u32,
     {
         let mut object_writers: HashMap<u32, LiveObjectSetWriterV1> = HashMap::new();
-        let local_staging_dir_path =
-            path_to_filesystem(self.local_staging_dir.clone(), &self.epoch_dir(epoch))?;
+        let local_staging_dir_path = path_to_filesystem(self.local_staging_dir.clone(), dir)?;
         let mut acc = GlobalStateHash::default();
         for object in perpetual_db.iter_live_object_set(include_wrapped_tombstone) {
             GlobalStateHasher::accumulate_live_object(&mut acc, &object);
@@ -405,19 +586,122 @@ impl StateSnapshotWriterV1 {
         for (_, writer) in object_writers.into_iter() {
             files.extend(writer.done()?);
         }
-        self.write_manifest(epoch, files)?;
+        self.write_manifest(
+            dir,
+            Manifest::V1(ManifestV1 {
+                snapshot_version: 1,
+                address_length: ObjectID::LENGTH as u64,
+                file_metadata: files,
+                epoch,
+            }),
+        )?;
         Ok(())
     }
 
-    fn write_manifest(&mut self, epoch: u64, file_metadata: Vec<FileMetadata>) -> Result<()> {
-        let (f, manifest_file_path) = self.manifest_file(epoch)?;
+    /// Writes the difference between the live object sets of `base_db` and `perpetual_db`. Both
+    /// are iterated in object ID order, so they can be merged in a single pass: an object live in
+    /// `perpetual_db` is written unless the same version is live in `base_db`, and a version live
+    /// in `base_db` is removed unless it is still live in `perpetual_db`.
+    #[allow(clippy::too_many_arguments)]
+    fn write_delta_object_set<F>(
+        &mut self,
+        base_epoch: u64,
+        epoch: u64,
+        dir: &Path,
+        base_db: Arc<AuthorityPerpetualTables>,
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        sender: Sender<FileMetadata>,
+        bucket_func: F,
+        include_wrapped_tombstone: bool,
+        root_state_hash: ECMHLiveObjectSetDigest,
+    ) -> Result<()>
+    where
+        F: Fn(&LiveObject) -> u32,
+    {
+        let mut object_writers: HashMap<u32, LiveObjectSetWriterV1> = HashMap::new();
+        let mut removed_writers: HashMap<u32, RemovedObjectSetWriterV1> = HashMap::new();
+        let local_staging_dir_path = path_to_filesystem(self.local_staging_dir.clone(), dir)?;
+        let mut remove = |object: LiveObject| -> Result<()> {
+            let bucket_num = bucket_func(&object);
+            if let Vacant(entry) = removed_writers.entry(bucket_num) {
+                entry.insert(RemovedObjectSetWriterV1::new(
+                    local_staging_dir_path.clone(),
+                    bucket_num,
+                    self.file_compression,
+                    sender.clone(),
+                )?);
+            }
+            removed_writers
+                .get_mut(&bucket_num)
+                .context("Unexpected missing bucket writer")?
+                .write(&object.object_reference())
+        };
+        let mut acc = GlobalStateHash::default();
+        let mut base_objects = base_db
+            .iter_live_object_set(include_wrapped_tombstone)
+            .peekable();
+        for object in perpetual_db.iter_live_object_set(include_wrapped_tombstone) {
+            GlobalStateHasher::accumulate_live_object(&mut acc, &object);
+            let mut unchanged = false;
+            while let Some(base_object) =
+                base_objects.next_if(|base_object| base_object.object_id() <= object.object_id())
+            {
+                if base_object.object_id() == object.object_id()
+                    && base_object.version() == object.version()
+                {
+                    unchanged = true;
+                } else {
+                    remove(base_object)?;
+                }
+            }
+            if unchanged {
+                continue;
+            }
+            let bucket_num = bucket_func(&object);
+            if let Vacant(entry) = object_writers.entry(bucket_num) {
+                entry.insert(LiveObjectSetWriterV1::new(
+                    local_staging_dir_path.clone(),
+                    bucket_num,
+                    self.file_compression,
+                    sender.clone(),
+                )?);
+            }
+            let writer = object_writers
+                .get_mut(&bucket_num)
+                .context("Unexpected missing bucket writer")?;
+            writer.write(&object)?;
+        }
+        for base_object in base_objects {
+            remove(base_object)?;
+        }
+        assert_eq!(
+            ECMHLiveObjectSetDigest::from(acc.digest()),
+            root_state_hash,
+            "Root state hash mismatch!"
+        );
+        let mut files = vec![];
+        for (_, writer) in object_writers.into_iter() {
+            files.extend(writer.done()?);
+        }
+        for (_, writer) in removed_writers.into_iter() {
+            files.extend(writer.done()?);
+        }
+        self.write_manifest(
+            dir,
+            Manifest::DeltaV1(DeltaManifestV1 {
+                snapshot_version: 1,
+                address_length: ObjectID::LENGTH as u64,
+                file_metadata: files,
+                epoch,
+                base_epoch,
+            }),
+        )?;
+        Ok(())
+    }
+
+    fn write_manifest(&mut self, dir: &Path, manifest: Manifest) -> Result<()> {
+        let (f, manifest_file_path) = self.manifest_file(dir)?;
         let mut wbuf = BufWriter::new(f);
-        let manifest: Manifest = Manifest::V1(ManifestV1 {
-            snapshot_version: 1,
-            address_length: ObjectID::LENGTH as u64,
-            file_metadata,
-            epoch,
-        });
         let serialized_manifest = bcs::to_bytes(&manifest)?;
         wbuf.write_all(&serialized_manifest)?;
         wbuf.flush()?;
@@ -431,15 +715,11 @@ impl StateSnapshotWriterV1 {
         Ok(())
     }
 
-    fn manifest_file(&mut self, epoch: u64) -> Result<(File, PathBuf)> {
-        let manifest_file_path = path_to_filesystem(
-            self.local_staging_dir.clone(),
-            &self.epoch_dir(epoch).child("MANIFEST"),
-        )?;
-        let manifest_file_tmp_path = path_to_filesystem(
-            self.local_staging_dir.clone(),
-            &self.epoch_dir(epoch).child("MANIFEST.tmp"),
-        )?;
+    fn manifest_file(&mut self, dir: &Path) -> Result<(File, PathBuf)> {
+        let manifest_file_path =
+            path_to_filesystem(self.local_staging_dir.clone(), &dir.child("MANIFEST"))?;
+        let manifest_file_tmp_path =
+            path_to_filesystem(self.local_staging_dir.clone(), &dir.child("MANIFEST.tmp"))?;
         let mut f = File::create(manifest_file_tmp_path.clone())?;
         let mut metab = vec![0u8; MAGIC_BYTES];
         BigEndian::write_u32(&mut metab, MANIFEST_FILE_MAGIC);
@@ -459,21 +739,16 @@ impl StateSnapshotWriterV1 {
         1u32
     }
 
-    fn epoch_dir(&self, epoch: u64) -> Path {
-        Path::from(format!("epoch_{}", epoch))
-    }
-
-    async fn setup_epoch_dir(&self, epoch: u64) -> Result<()> {
-        let epoch_dir = self.epoch_dir(epoch);
+    async fn setup_dir(&self, epoch_dir: &Path) -> Result<()> {
         // Delete remote epoch dir if it exists
         delete_recursively(
-            &epoch_dir,
+            epoch_dir,
             &self.remote_object_store,
             NonZeroUsize::new(self.concurrency).unwrap(),
         )
         .await?;
         // Delete local staging epoch dir if it exists
-        let local_epoch_dir_path = self.local_staging_dir.join(format!("epoch_{}", epoch));
+        let local_epoch_dir_path = path_to_filesystem(self.local_staging_dir.clone(), epoch_dir)?;
         if local_epoch_dir_path.exists() {
             fs::remove_dir_all(&local_epoch_dir_path)?;
         }
@@ -31,9 +31,10 @@ use move_core_types::account_address::AccountAddress;
 use move_package::{BuildConfig as MoveBuildConfig, LintFlag};
 use move_symbol_pool::Symbol;
 use sui_move::manage_package::resolve_lock_file_path;
+use sui_move_build::attestation::{BuildAttestation, SignedBuildAttestation};
 use sui_move_build::{implicit_deps, BuildConfig, SuiPackageHooks};
 use sui_sdk::rpc_types::SuiTransactionBlockEffects;
-use sui_sdk::types::base_types::ObjectID;
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
 use sui_sdk::SuiClientBuilder;
 use sui_source_validation::{BytecodeSourceVerifier, ValidationMode};
 
@@ -100,6 +101,14 @@ pub struct Package {
     /// Optional object ID to watch for upgrades. For framework packages, this is an address like 0x2.
     /// For non-framework packages this is an upgrade cap (possibly wrapped).
     pub watch: Option<ObjectID>,
+    /// Optional path, relative to the package, of a build attestation for the package (see
+    /// `sui move build --attest`). The attestation is verified against the package sources and
+    /// the on-chain package, and served alongside the sources.
+    #[serde(default)]
+    pub attestation: Option<String>,
+    /// Optional address that the attestation must be signed by.
+    #[serde(default)]
+    pub attestation_signer: Option<SuiAddress>,
 }
 
 #[derive(Clone, Serialize, Debug)]
@@ -145,11 +154,19 @@ pub type SourceLookup = BTreeMap<Symbol, SourceInfo>;
 pub type AddressLookup = BTreeMap<AccountAddress, SourceLookup>;
 /// Top-level lookup that maps network to sources for corresponding on-chain networks.
 pub type NetworkLookup = BTreeMap<Network, AddressLookup>;
+/// Map networks and package addresses to verified build attestations.
+pub type AttestationLookup = BTreeMap<Network, BTreeMap<AccountAddress, SignedBuildAttestation>>;
 
 pub async fn verify_package(
     network: &Network,
     package_path: impl AsRef<Path>,
-) -> anyhow::Result<(Network, AddressLookup)> {
+    attestation: Option<String>,
+    attestation_signer: Option<SuiAddress>,
+) -> anyhow::Result<(
+    Network,
+    AddressLookup,
+    Option<(AccountAddress, SignedBuildAttestation)>,
+)> {
     move_package::package_hooks::register_package_hooks(Box::new(SuiPackageHooks));
     // TODO(rvantonder): use config RPC URL instead of hardcoded URLs
     let network_url = match network {
@@ -169,15 +186,37 @@ pub async fn verify_package(
         config,
         run_bytecode_verifier: false, /* no need to run verifier if code is on-chain */
         print_diags_to_stderr: false,
-        chain_id: Some(chain_id),
+        chain_id: Some(chain_id.clone()),
     };
     let compiled_package = build_config.build(package_path.as_ref())?;
 
-    BytecodeSourceVerifier::new(client.read_api())
+    let verifier = BytecodeSourceVerifier::new(client.read_api());
+    verifier
         .verify(&compiled_package, ValidationMode::root())
         .await
         .map_err(|e| anyhow!("Network {network}: {e}"))?;
 
+    let attestation = match attestation {
+        Some(path) => {
+            let path = package_path.as_ref().join(path);
+            info!("verifying attestation {}", path.display());
+            let attestation = SignedBuildAttestation::read(&path)?;
+            let local = BuildAttestation::new_published_at(
+                &compiled_package,
+                package_path.as_ref(),
+                Some(chain_id),
+                attestation.attestation.published_at,
+                attestation.attestation.original_id,
+            )?;
+            let address = verifier
+                .verify_attestation(&attestation, &local, None, attestation_signer)
+                .await
+                .map_err(|e| anyhow!("Network {network}, attestation {}: {e}", path.display()))?;
+            Some((address, attestation))
+        }
+        None => None,
+    };
+
     let mut address_map = AddressLookup::new();
     let address = compiled_package
         .published_at
@@ -197,7 +236,7 @@ pub async fn verify_package(
             address_map.insert(address, source_map);
         }
     }
-    Ok((network.clone(), address_map))
+    Ok((network.clone(), address_map, attestation))
 }
 
 pub fn parse_config(config_path: impl AsRef<Path>) -> anyhow::Result<Config> {
@@ -329,7 +368,7 @@ pub async fn clone_repositories(repos: Vec<&RepositorySource>, dir: &Path) -> an
 pub async fn initialize(
     config: &Config,
     dir: &Path,
-) -> anyhow::Result<(NetworkLookup, NetworkLookup)> {
+) -> anyhow::Result<(NetworkLookup, NetworkLookup, AttestationLookup)> {
     let mut repos = vec![];
     for s in &config.packages {
         match s {
@@ -338,9 +377,9 @@ pub async fn initialize(
         }
     }
     clone_repositories(repos, dir).await?;
-    let sources = verify_packages(config, dir).await?;
+    let (sources, attestations) = verify_packages(config, dir).await?;
     let sources_list = sources_list(&sources).await;
-    Ok((sources, sources_list))
+    Ok((sources, sources_list, attestations))
 }
 
 pub async fn sources_list(sources: &NetworkLookup) -> NetworkLookup {
@@ -365,7 +404,10 @@ pub async fn sources_list(sources: &NetworkLookup) -> NetworkLookup {
     sources_list
 }
 
-pub async fn verify_packages(config: &Config, dir: &Path) -> anyhow::Result<NetworkLookup> {
+pub async fn verify_packages(
+    config: &Config,
+    dir: &Path,
+) -> anyhow::Result<(NetworkLookup, AttestationLookup)> {
     let mut tasks = vec![];
     for p in &config.packages {
         match p {
@@ -381,10 +423,12 @@ pub async fn verify_packages(config: &Config, dir: &Path) -> anyhow::Result<Netw
                             .join(p.path.clone())
                             .clone();
                         let network = r.network.clone().unwrap_or_default();
-                        let t =
-                            tokio::spawn(
-                                async move { verify_package(&network, package_path).await },
-                            );
+                        let attestation = p.attestation.clone();
+                        let attestation_signer = p.attestation_signer;
+                        let t = tokio::spawn(async move {
+                            verify_package(&network, package_path, attestation, attestation_signer)
+                                .await
+                        });
                         tasks.push(t)
                     }
                 }
@@ -393,8 +437,12 @@ pub async fn verify_packages(config: &Config, dir: &Path) -> anyhow::Result<Netw
                 for p in &packages_dir.paths {
                     let package_path = PathBuf::from(p.path.clone());
                     let network = packages_dir.network.clone().unwrap_or_default();
-                    let t =
-                        tokio::spawn(async move { verify_package(&network, package_path).await });
+                    let attestation = p.attestation.clone();
+                    let attestation_signer = p.attestation_signer;
+                    let t = tokio::spawn(async move {
+                        verify_package(&network, package_path, attestation, attestation_signer)
+                            .await
+                    });
                     tasks.push(t)
                 }
             }
@@ -405,8 +453,15 @@ pub async fn verify_packages(config: &Config, dir: &Path) -> anyhow::Result<Netw
     let mut testnet_lookup = AddressLookup::new();
     let mut devnet_lookup = AddressLookup::new();
     let mut localnet_lookup = AddressLookup::new();
+    let mut attestations = AttestationLookup::new();
     for t in tasks {
-        let (network, new_lookup) = t.await.unwrap()?;
+        let (network, new_lookup, attestation) = t.await.unwrap()?;
+        if let Some((address, attestation)) = attestation {
+            attestations
+                .entry(network.clone())
+                .or_default()
+                .insert(address, attestation);
+        }
         match network {
             Network::Mainnet => mainnet_lookup.extend(new_lookup),
             Network::Testnet => testnet_lookup.extend(new_lookup),
@@ -419,7 +474,7 @@ pub async fn verify_packages(config: &Config, dir: &Path) -> anyhow::Result<Netw
     lookup.insert(Network::Testnet, testnet_lookup);
     lookup.insert(Network::Devnet, devnet_lookup);
     lookup.insert(Network::Localnet, localnet_lookup);
-    Ok(lookup)
+    Ok((lookup, attestations))
 }
 
 // A thread that monitors on-chain transactions for package upgrades. `config` specifies which packages
@@ -440,12 +495,14 @@ pub struct AppState {
     pub sources: NetworkLookup,
     pub metrics: Option<SourceServiceMetrics>,
     pub sources_list: NetworkLookup,
+    pub attestations: AttestationLookup,
 }
 
 pub async fn serve(app_state: Arc<RwLock<AppState>>) -> anyhow::Result<()> {
     let app = Router::new()
         .route("/api", get(api_route))
         .route("/api/list", get(list_route))
+        .route("/api/attestation", get(attestation_route))
         .layer(
             ServiceBuilder::new()
                 .layer(
@@ -533,6 +590,46 @@ async fn api_route(
     }
 }
 
+#[derive(Deserialize)]
+pub struct AttestationRequest {
+    #[serde(default)]
+    network: Network,
+    address: String,
+}
+
+async fn attestation_route(
+    State(app_state): State<Arc<RwLock<AppState>>>,
+    Query(AttestationRequest { network, address }): Query<AttestationRequest>,
+) -> impl IntoResponse {
+    debug!("attestation request network={network}&address={address}");
+    let Ok(address) = AccountAddress::from_hex_literal(&address) else {
+        let error = format!("Invalid hex address {address}");
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error }).into_response(),
+        );
+    };
+
+    let app_state = app_state.read().unwrap();
+    if let Some(metrics) = &app_state.metrics {
+        metrics.total_requests_received.inc();
+    }
+    match app_state
+        .attestations
+        .get(&network)
+        .and_then(|n| n.get(&address))
+    {
+        Some(attestation) => (StatusCode::OK, Json(attestation).into_response()),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("No attestation found for address {address} on network {network}"),
+            })
+            .into_response(),
+        ),
+    }
+}
+
 async fn check_version_header(
     headers: HeaderMap,
     req: hyper::Request<axum::body::Body>,
@@ -30,7 +30,7 @@ pub async fn main() -> anyhow::Result<()> {
     let package_config = parse_config(args.config_path)?;
     let tmp_dir = tempfile::tempdir()?;
     let start = tokio::time::Instant::now();
-    let (sources, sources_list) = initialize(&package_config, tmp_dir.path()).await?;
+    let (sources, sources_list, attestations) = initialize(&package_config, tmp_dir.path()).await?;
     info!("verification complete in {:?}", start.elapsed());
 
     let metrics_listener = std::net::TcpListener::bind(METRICS_HOST_PORT)?;
@@ -42,6 +42,7 @@ pub async fn main() -> anyhow::Result<()> {
         sources,
         metrics: Some(metrics),
         sources_list,
+        attestations,
     }));
     let mut threads = vec![];
     let networks_to_watch = vec![
@@ -26,9 +26,10 @@ use move_core_types::account_address::AccountAddress;
 use move_symbol_pool::Symbol;
 use sui_source_validation_service::{
     host_port, initialize, serve, start_prometheus_server, verify_packages, watch_for_upgrades,
-    AddressLookup, AppState, Branch, CloneCommand, Config, DirectorySource, ErrorResponse, Network,
-    NetworkLookup, Package, PackageSource, RepositorySource, SourceInfo, SourceLookup,
-    SourceResponse, SourceServiceMetrics, METRICS_HOST_PORT, SUI_SOURCE_VALIDATION_VERSION_HEADER,
+    AddressLookup, AppState, AttestationLookup, Branch, CloneCommand, Config, DirectorySource,
+    ErrorResponse, Network, NetworkLookup, Package, PackageSource, RepositorySource, SourceInfo,
+    SourceLookup, SourceResponse, SourceServiceMetrics, METRICS_HOST_PORT,
+    SUI_SOURCE_VALIDATION_VERSION_HEADER,
 };
 use test_cluster::TestClusterBuilder;
 
@@ -86,6 +87,8 @@ async fn test_end_to_end() -> anyhow::Result<()> {
             paths: vec![Package {
                 path: "unused".into(),
                 watch: Some(cap.reference.object_id), // watch the upgrade cap
+                attestation: None,
+                attestation_signer: None,
             }],
             network: Some(Network::Localnet),
         })],
@@ -100,6 +103,7 @@ async fn test_end_to_end() -> anyhow::Result<()> {
         sources,
         metrics: None,
         sources_list,
+        attestations: AttestationLookup::new(),
     }));
     let app_state_ref = app_state.clone();
     let (tx, rx) = oneshot::channel();
@@ -140,6 +144,8 @@ async fn test_end_to_end() -> anyhow::Result<()> {
                 paths: vec![Package {
                     path: "move-stdlib".into(),
                     watch: None,
+                    attestation: None,
+                    attestation_signer: None,
                 }],
             }],
             network: Some(Network::Localnet),
@@ -221,6 +227,8 @@ async fn run_upgrade(
         verify_deps: true,
         with_unpublished_dependencies: false,
         verify_compatibility: true,
+        advisories: false,
+        advisories_json: None,
         payment: PaymentArgs {
             gas: vec![gas_obj_id],
         },
@@ -315,6 +323,7 @@ async fn test_api_route() -> anyhow::Result<()> {
         sources,
         metrics: None,
         sources_list,
+        attestations: AttestationLookup::new(),
     }));
     tokio::spawn(async move { serve(app_state).await.expect("Cannot start service.") });
     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -415,7 +424,7 @@ paths = [
     [packages.values]
     paths = [
         { path = "home/user/some/upgradeable-package", watch = "0x1234" },
-        { path = "home/user/some/immutable-package" },
+        { path = "home/user/some/immutable-package", attestation = "build/pkg/attestation.json", attestation_signer = "0x000000000000000000000000000000000000000000000000000000000000abcd" },
     ]
 "#;
 
@@ -438,24 +447,32 @@ paths = [
                                         watch: Some(
                                             0x000000000000000000000000000000000000000000000000000000000000dee9,
                                         ),
+                                        attestation: None,
+                                        attestation_signer: None,
                                     },
                                     Package {
                                         path: "crates/sui-framework/packages/move-stdlib",
                                         watch: Some(
                                             0x0000000000000000000000000000000000000000000000000000000000000001,
                                         ),
+                                        attestation: None,
+                                        attestation_signer: None,
                                     },
                                     Package {
                                         path: "crates/sui-framework/packages/sui-framework",
                                         watch: Some(
                                             0x0000000000000000000000000000000000000000000000000000000000000002,
                                         ),
+                                        attestation: None,
+                                        attestation_signer: None,
                                     },
                                     Package {
                                         path: "crates/sui-framework/packages/sui-system",
                                         watch: Some(
                                             0x0000000000000000000000000000000000000000000000000000000000000003,
                                         ),
+                                        attestation: None,
+                                        attestation_signer: None,
                                     },
                                 ],
                             },
@@ -470,10 +487,18 @@ paths = [
                                 watch: Some(
                                     0x0000000000000000000000000000000000000000000000000000000000001234,
                                 ),
+                                attestation: None,
+                                attestation_signer: None,
                             },
                             Package {
                                 path: "home/user/some/immutable-package",
                                 watch: None,
+                                attestation: Some(
+                                    "build/pkg/attestation.json",
+                                ),
+                                attestation_signer: Some(
+                                    0x000000000000000000000000000000000000000000000000000000000000abcd,
+                                ),
                             },
                         ],
                         network: None,
@@ -495,10 +520,14 @@ fn test_clone_command() -> anyhow::Result<()> {
                 Package {
                     path: "a".into(),
                     watch: None,
+                    attestation: None,
+                    attestation_signer: None,
                 },
                 Package {
                     path: "b".into(),
                     watch: None,
+                    attestation: None,
+                    attestation_signer: None,
                 },
             ],
         }],
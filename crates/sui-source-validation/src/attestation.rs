@@ -0,0 +1,128 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::encoding::{Encoding, Hex};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_symbol_pool::Symbol;
+use sui_move_build::attestation::{module_digest, BuildAttestation, SignedBuildAttestation};
+use sui_sdk::rpc_types::SuiRawMovePackage;
+use sui_types::{base_types::SuiAddress, move_package::MovePackage};
+
+use crate::error::{AggregateError, Error};
+use crate::BytecodeSourceVerifier;
+
+impl BytecodeSourceVerifier<'_> {
+    /// Verify that `attestation` is correctly signed (by `signer`, if provided), that it matches
+    /// `local` (the attestation of a build of the sources it claims to be built from), and that
+    /// its modules match the package on-chain. The package is looked for at `at` if provided, and
+    /// otherwise at the address it was attested to be published at.
+    pub async fn verify_attestation(
+        &self,
+        attestation: &SignedBuildAttestation,
+        local: &BuildAttestation,
+        at: Option<AccountAddress>,
+        signer: Option<SuiAddress>,
+    ) -> Result<AccountAddress, AggregateError> {
+        let mut errs = vec![];
+
+        if let Err(e) = attestation.verify_signature() {
+            errs.push(Error::InvalidAttestationSignature(e.to_string()));
+        }
+
+        if let Some(expected) = signer.filter(|s| *s != attestation.signer) {
+            errs.push(Error::UnexpectedAttestationSigner {
+                expected,
+                actual: attestation.signer,
+            });
+        }
+
+        let attested = &attestation.attestation;
+        errs.extend(
+            attested
+                .mismatches(local)
+                .into_iter()
+                .map(Error::AttestationMismatch),
+        );
+
+        let Some(address) = at.or(attested.published_at.map(AccountAddress::from)) else {
+            errs.push(Error::UnpublishedAttestation);
+            return Err(AggregateError(errs));
+        };
+
+        let SuiRawMovePackage {
+            module_map,
+            linkage_table,
+            ..
+        } = self.pkg_for_address(address).await?;
+
+        // A package attested to before it was published was digested with its root address
+        // unassigned (0x0), so its on-chain modules are digested the same way.
+        let module_map = if attested.published_at.is_none() {
+            module_map
+                .into_iter()
+                .map(|(name, bytes)| {
+                    let bytes = unassign_root_address(&name, &bytes)?;
+                    Ok((name, bytes))
+                })
+                .collect::<Result<_, Error>>()?
+        } else {
+            module_map
+        };
+
+        let mut attested_modules = attested.modules.clone();
+        for (name, bytes) in &module_map {
+            let module = Symbol::from(name.as_str());
+            match attested_modules.remove(name) {
+                None => errs.push(Error::UnattestedModule { address, module }),
+                Some(digest) if digest != module_digest(bytes) => {
+                    errs.push(Error::AttestedModuleMismatch { address, module })
+                }
+                Some(_) => (),
+            }
+        }
+
+        for name in attested_modules.into_keys() {
+            errs.push(Error::AttestedModuleNotFound {
+                address,
+                module: Symbol::from(name),
+            });
+        }
+
+        let package_digest = MovePackage::compute_digest_for_modules_and_deps(
+            module_map.values(),
+            linkage_table.values().map(|info| &info.upgraded_id),
+            /* hash_modules */ true,
+        );
+        if Hex::encode(package_digest) != attested.package_digest {
+            errs.push(Error::AttestedPackageDigestMismatch(address));
+        }
+
+        if !errs.is_empty() {
+            return Err(AggregateError(errs));
+        }
+
+        Ok(address)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn unassign_root_address(name: &str, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = |message: String| Error::InvalidModuleFailure {
+        name: name.to_string(),
+        message,
+    };
+    let mut module =
+        CompiledModule::deserialize_with_defaults(bytes).map_err(|e| invalid(e.to_string()))?;
+    let address_idx = module.self_handle().address;
+    let Some(addr) = module.address_identifiers.get_mut(address_idx.0 as usize) else {
+        return Err(invalid("Self address field missing".to_string()));
+    };
+    *addr = AccountAddress::ZERO;
+
+    let mut bytes = vec![];
+    module
+        .serialize_with_version(module.version, &mut bytes)
+        .map_err(|e| invalid(e.to_string()))?;
+    Ok(bytes)
+}
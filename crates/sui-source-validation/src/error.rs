@@ -6,15 +6,37 @@ use std::fmt;
 use move_core_types::account_address::AccountAddress;
 use move_symbol_pool::Symbol;
 use sui_json_rpc_types::SuiRawMoveObject;
+use sui_move_build::attestation::AttestationMismatch;
 use sui_package_management::PublishedAtError;
 use sui_sdk::error::Error as SdkError;
-use sui_types::{base_types::ObjectID, error::SuiObjectResponseError};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    error::SuiObjectResponseError,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub struct AggregateError(pub(crate) Vec<Error>);
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("Attestation does not match the local build: {0}")]
+    AttestationMismatch(AttestationMismatch),
+
+    #[error("Attested module {module} did not match its on-chain version at {address}")]
+    AttestedModuleMismatch {
+        address: AccountAddress,
+        module: Symbol,
+    },
+
+    #[error("Attested module {module} was not found on-chain at {address}")]
+    AttestedModuleNotFound {
+        address: AccountAddress,
+        module: Symbol,
+    },
+
+    #[error("Attested package digest did not match the digest of the package at {0}")]
+    AttestedPackageDigestMismatch(AccountAddress),
+
     #[error("Cannot check local module for {package}: {message}")]
     CannotCheckLocalModules { package: Symbol, message: String },
 
@@ -24,6 +46,9 @@ pub enum Error {
     #[error("On-chain package {0} is empty")]
     EmptyOnChainPackage(AccountAddress),
 
+    #[error("{0}")]
+    InvalidAttestationSignature(String),
+
     #[error("Invalid module {name} with error: {message}")]
     InvalidModuleFailure { name: String, message: String },
 
@@ -66,6 +91,24 @@ pub enum Error {
     #[error("Dependency object does not exist or was deleted: {0:?}")]
     SuiObjectRefFailure(SuiObjectResponseError),
 
+    #[error("On-chain module {address}::{module} is not in the attestation")]
+    UnattestedModule {
+        address: AccountAddress,
+        module: Symbol,
+    },
+
+    #[error(
+        "Attestation is for a package that was not published when it was built. \
+         Please supply an explicit on-chain address for the package"
+    )]
+    UnpublishedAttestation,
+
+    #[error("Attestation was signed by {actual}, expected {expected}")]
+    UnexpectedAttestationSigner {
+        expected: SuiAddress,
+        actual: SuiAddress,
+    },
+
     #[error("On-chain address cannot be zero")]
     ZeroOnChainAddresSpecifiedFailure,
 }
@@ -15,6 +15,7 @@ use sui_sdk::rpc_types::{SuiObjectDataOptions, SuiRawData, SuiRawMovePackage};
 use sui_types::base_types::ObjectID;
 use toolchain::units_for_toolchain;
 
+mod attestation;
 pub mod error;
 mod toolchain;
 
@@ -6,10 +6,13 @@ use move_core_types::account_address::AccountAddress;
 use std::collections::HashMap;
 use std::{fs, io, path::Path};
 use std::{path::PathBuf, str};
-use sui_move_build::{BuildConfig, CompiledPackage, SuiPackageHooks};
+use sui_move_build::{
+    attestation::BuildAttestation, BuildConfig, CompiledPackage, SuiPackageHooks,
+};
 use sui_sdk::wallet_context::WalletContext;
 use sui_test_transaction_builder::{make_publish_transaction, make_publish_transaction_with_deps};
 use sui_types::base_types::ObjectID;
+use sui_types::crypto::{get_key_pair, AccountKeyPair, SuiKeyPair};
 use sui_types::move_package::UpgradePolicy;
 use sui_types::transaction::TEST_ONLY_GAS_UNIT_FOR_PUBLISH;
 use sui_types::{
@@ -18,6 +21,7 @@ use sui_types::{
 };
 use test_cluster::TestClusterBuilder;
 
+use crate::error::{AggregateError, Error};
 use crate::toolchain::CURRENT_COMPILER_VERSION;
 use crate::{BytecodeSourceVerifier, ValidationMode};
 
@@ -181,6 +185,222 @@ async fn successful_verification_upgrades() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn successful_attestation_verification() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await;
+    let context = &mut cluster.wallet;
+
+    let b_ref_fixtures = tempfile::tempdir()?;
+    let b_ref = {
+        let b_src = copy_published_package(&b_ref_fixtures, "b", SuiAddress::ZERO).await?;
+        publish_package(context, b_src).await.0
+    };
+
+    let b_pkg_fixtures = tempfile::tempdir()?;
+    let b_src = copy_published_package(&b_pkg_fixtures, "b", b_ref.0.into()).await?;
+    let b_pkg = compile_package(&b_src);
+
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let local = BuildAttestation::new(&b_pkg, &b_src, None)?;
+    let attestation = local.clone().sign(&SuiKeyPair::Ed25519(key))?;
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api());
+    let address = verifier
+        .verify_attestation(&attestation, &local, None, Some(attestation.signer))
+        .await
+        .unwrap();
+    assert_eq!(address, AccountAddress::from(b_ref.0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn successful_attestation_verification_unassigned_root() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await;
+    let context = &mut cluster.wallet;
+
+    // Attest to the package as it is built for publishing, with its address unassigned.
+    let b_ref_fixtures = tempfile::tempdir()?;
+    let b_ref_src = copy_published_package(&b_ref_fixtures, "b", SuiAddress::ZERO).await?;
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let attestation = BuildAttestation::new(&compile_package(&b_ref_src), &b_ref_src, None)?
+        .sign(&SuiKeyPair::Ed25519(key))?;
+    assert_eq!(attestation.attestation.published_at, None);
+    let b_ref = publish_package(context, b_ref_src).await.0;
+
+    // Record the address the package was published at, leaving its named address unassigned.
+    let b_pkg_fixtures = tempfile::tempdir()?;
+    let b_src =
+        copy_upgraded_package(&b_pkg_fixtures, "b", b_ref.0.into(), SuiAddress::ZERO).await?;
+    let b_pkg = compile_package(&b_src);
+
+    // The root address is normalized to the published address when attesting...
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let published = BuildAttestation::new(&b_pkg, &b_src, None)?;
+    assert_eq!(published.published_at, Some(b_ref.0));
+    assert_eq!(
+        published.source_digest,
+        attestation.attestation.source_digest
+    );
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api());
+    let address = verifier
+        .verify_attestation(
+            &published.clone().sign(&SuiKeyPair::Ed25519(key))?,
+            &published,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(address, AccountAddress::from(b_ref.0));
+
+    // ...and the attestation made before publishing matches the published package.
+    let local = BuildAttestation::new_published_at(&b_pkg, &b_src, None, None, None)?;
+    let address = verifier
+        .verify_attestation(&attestation, &local, Some(b_ref.0.into()), None)
+        .await
+        .unwrap();
+    assert_eq!(address, AccountAddress::from(b_ref.0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn successful_attestation_verification_upgraded() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await;
+    let context = &mut cluster.wallet;
+
+    let b_v1_fixtures = tempfile::tempdir()?;
+    let (b_v1, b_cap) = {
+        let b_src = copy_published_package(&b_v1_fixtures, "b", SuiAddress::ZERO).await?;
+        publish_package(context, b_src).await
+    };
+
+    let b_v2_fixtures = tempfile::tempdir()?;
+    let b_v2 = {
+        let b_src = copy_published_package(&b_v2_fixtures, "b-v2", SuiAddress::ZERO).await?;
+        upgrade_package(context, b_v1.0, b_cap.0, b_src).await
+    };
+
+    // The upgraded package, built with its address unassigned.
+    let b_fixtures = tempfile::tempdir()?;
+    let b_src = copy_upgraded_package(&b_fixtures, "b-v2", b_v2.0.into(), SuiAddress::ZERO).await?;
+    let b_pkg = compile_package(&b_src);
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api());
+
+    // The root address is normalized to the original ID, which the modules of every version of
+    // the package are published at...
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let local =
+        BuildAttestation::new_published_at(&b_pkg, &b_src, None, Some(b_v2.0), Some(b_v1.0))?;
+    let attestation = local.clone().sign(&SuiKeyPair::Ed25519(key))?;
+    let address = verifier
+        .verify_attestation(&attestation, &local, None, None)
+        .await
+        .unwrap();
+    assert_eq!(address, AccountAddress::from(b_v2.0));
+
+    // ...not to the ID of the latest version.
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let local =
+        BuildAttestation::new_published_at(&b_pkg, &b_src, None, Some(b_v2.0), Some(b_v2.0))?;
+    let attestation = local.clone().sign(&SuiKeyPair::Ed25519(key))?;
+    let AggregateError(errors) = verifier
+        .verify_attestation(&attestation, &local, None, None)
+        .await
+        .unwrap_err();
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, Error::AttestedModuleMismatch { .. })));
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, Error::AttestedPackageDigestMismatch(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fail_attestation_verification_unexpected_signer() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await;
+    let context = &mut cluster.wallet;
+
+    let b_ref_fixtures = tempfile::tempdir()?;
+    let b_ref = {
+        let b_src = copy_published_package(&b_ref_fixtures, "b", SuiAddress::ZERO).await?;
+        publish_package(context, b_src).await.0
+    };
+
+    let b_pkg_fixtures = tempfile::tempdir()?;
+    let b_src = copy_published_package(&b_pkg_fixtures, "b", b_ref.0.into()).await?;
+    let b_pkg = compile_package(&b_src);
+
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let local = BuildAttestation::new(&b_pkg, &b_src, None)?;
+    let attestation = local.clone().sign(&SuiKeyPair::Ed25519(key))?;
+    let expected = SuiAddress::random_for_testing_only();
+
+    let client = context.get_client().await?;
+    let AggregateError(errors) = BytecodeSourceVerifier::new(client.read_api())
+        .verify_attestation(&attestation, &local, None, Some(expected))
+        .await
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        Error::UnexpectedAttestationSigner { expected: e, actual }
+            if *e == expected && *actual == attestation.signer
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fail_attestation_verification_tampered() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await;
+    let context = &mut cluster.wallet;
+
+    let b_ref_fixtures = tempfile::tempdir()?;
+    let b_ref = {
+        let b_src = copy_published_package(&b_ref_fixtures, "b", SuiAddress::ZERO).await?;
+        publish_package(context, b_src).await.0
+    };
+
+    let b_pkg_fixtures = tempfile::tempdir()?;
+    let b_src = copy_published_package(&b_pkg_fixtures, "b", b_ref.0.into()).await?;
+    let b_pkg = compile_package(&b_src);
+
+    let (_, key): (_, AccountKeyPair) = get_key_pair();
+    let local = BuildAttestation::new(&b_pkg, &b_src, None)?;
+    let mut attestation = local.clone().sign(&SuiKeyPair::Ed25519(key))?;
+    attestation
+        .attestation
+        .modules
+        .insert("b".to_string(), "00".repeat(32));
+
+    let client = context.get_client().await?;
+    let AggregateError(errors) = BytecodeSourceVerifier::new(client.read_api())
+        .verify_attestation(&attestation, &local, None, None)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(errors[0], Error::InvalidAttestationSignature(_)));
+    assert!(errors.iter().any(|e| matches!(
+        e,
+        Error::AttestationMismatch(mismatch) if mismatch.field == "module b"
+    )));
+    assert!(errors.iter().any(|e| matches!(
+        e,
+        Error::AttestedModuleMismatch { module, .. } if module.as_str() == "b"
+    )));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn fail_verification_bad_address() -> anyhow::Result<()> {
     let mut cluster = TestClusterBuilder::new().build().await;
@@ -0,0 +1,462 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Content addressed upload and download of directories, such as db checkpoints, to and from an
+//! object store.
+//!
+//! Files are split into chunks of a fixed size, and every chunk is stored once under
+//! `blobs/<sha3-256 digest>`, however many files or uploads it is part of. An uploaded directory
+//! only holds a [`ContentManifest`] mapping each of its files to its chunks. Chunks which are
+//! already present in the store are not uploaded again, so files shared with a previous upload,
+//! such as immutable RocksDB SST files, are deduplicated and an interrupted upload resumes where
+//! it stopped. Chunks no longer referenced by any manifest, e.g. once old uploads have been
+//! deleted, are removed by [`garbage_collect_blobs`].
+
+use crate::object_store::util::{delete_files, find_all_dirs_with_epoch_prefix, get, put};
+use crate::object_store::{ObjectStoreGetExt, ObjectStoreListExt};
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use futures::{StreamExt, TryStreamExt};
+use indicatif::ProgressBar;
+use object_store::path::Path;
+use object_store::{DynObjectStore, ObjectStore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub const CONTENT_MANIFEST_FILENAME: &str = "CONTENT_MANIFEST";
+pub const BLOBS_DIR: &str = "blobs";
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Lists the chunks making up each file of a directory uploaded with
+/// [`upload_content_addressed`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentManifest {
+    pub chunk_size: usize,
+    /// Files keyed by their path relative to the uploaded directory
+    pub files: BTreeMap<String, ContentManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentManifestEntry {
+    pub size: usize,
+    /// Hex encoded SHA3-256 digests of the chunks of the file, in order
+    pub chunks: Vec<String>,
+}
+
+impl ContentManifest {
+    pub fn files_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.files
+            .keys()
+            .filter(|file| file.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn blob_path(digest: &str) -> Path {
+    Path::from(BLOBS_DIR).child(digest)
+}
+
+fn chunk_digest(bytes: &[u8]) -> String {
+    Hex::encode(Sha3_256::digest(bytes).digest)
+}
+
+async fn blob_exists(store: &Arc<DynObjectStore>, path: &Path) -> Result<bool> {
+    match store.head(path).await {
+        Ok(_) => Ok(true),
+        Err(object_store::Error::NotFound { .. }) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn read_content_manifest<S: ObjectStoreGetExt>(
+    store: &S,
+    dir: &Path,
+) -> Result<ContentManifest> {
+    let bytes = get(store, &dir.child(CONTENT_MANIFEST_FILENAME)).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|err| anyhow!("Error parsing {dir}/{CONTENT_MANIFEST_FILENAME}: {err}"))
+}
+
+/// Uploads all files under `dir` in `src_store` to `dest_store` in content addressed form, and
+/// writes the manifest of the upload to `dir` in `dest_store` once all chunks are uploaded.
+/// Files are always read and hashed in full, as the same file name can have different content in
+/// different dbs, but only chunks missing from `dest_store` are uploaded.
+pub async fn upload_content_addressed(
+    dir: &Path,
+    src_store: &Arc<DynObjectStore>,
+    dest_store: &Arc<DynObjectStore>,
+    chunk_size: usize,
+    concurrency: NonZeroUsize,
+) -> Result<ContentManifest> {
+    let dir_prefix = format!("{dir}/");
+    let mut objects = vec![];
+    let mut paths = src_store.list_objects(Some(dir)).await;
+    while let Some(res) = paths.next().await {
+        let object_metadata = res?;
+        let name = object_metadata
+            .location
+            .as_ref()
+            .strip_prefix(&dir_prefix)
+            .with_context(|| format!("{} is not in {dir}", object_metadata.location))?
+            .to_string();
+        objects.push((name, object_metadata));
+    }
+
+    let files = futures::stream::iter(objects)
+        .map(|(name, object_metadata)| async move {
+            let mut chunks = vec![];
+            for start in (0..object_metadata.size).step_by(chunk_size) {
+                let end = object_metadata.size.min(start + chunk_size);
+                let bytes = src_store
+                    .get_range(&object_metadata.location, start..end)
+                    .await?;
+                let digest = chunk_digest(&bytes);
+                let blob = blob_path(&digest);
+                if !blob_exists(dest_store, &blob).await? {
+                    put(dest_store, &blob, bytes).await?;
+                }
+                chunks.push(digest);
+            }
+            Ok::<_, anyhow::Error>((
+                name,
+                ContentManifestEntry {
+                    size: object_metadata.size,
+                    chunks,
+                },
+            ))
+        })
+        .boxed()
+        .buffer_unordered(concurrency.get())
+        .try_collect::<BTreeMap<_, _>>()
+        .await?;
+
+    let manifest = ContentManifest { chunk_size, files };
+    put(
+        dest_store,
+        &dir.child(CONTENT_MANIFEST_FILENAME),
+        Bytes::from(serde_json::to_vec(&manifest)?),
+    )
+    .await?;
+    Ok(manifest)
+}
+
+/// Deletes the blobs of `store` which aren't referenced by the content manifest of any of its
+/// `epoch_*` directories, and returns how many were deleted. Blobs uploaded by an upload in
+/// progress are only referenced once its manifest is written, so this must not run concurrently
+/// with [`upload_content_addressed`] to the same store.
+pub async fn garbage_collect_blobs(
+    store: &Arc<DynObjectStore>,
+    concurrency: NonZeroUsize,
+) -> Result<usize> {
+    let mut referenced = HashSet::new();
+    for dir in find_all_dirs_with_epoch_prefix(store, None).await?.values() {
+        // Directories uploaded as plain files have no content manifest
+        if !blob_exists(store, &dir.child(CONTENT_MANIFEST_FILENAME)).await? {
+            continue;
+        }
+        let manifest = read_content_manifest(store, dir).await?;
+        referenced.extend(manifest.files.into_values().flat_map(|entry| entry.chunks));
+    }
+
+    let mut unreferenced = vec![];
+    let mut blobs = store.list_objects(Some(&Path::from(BLOBS_DIR))).await;
+    while let Some(res) = blobs.next().await {
+        let location = res?.location;
+        if !location
+            .filename()
+            .is_some_and(|digest| referenced.contains(digest))
+        {
+            unreferenced.push(location);
+        }
+    }
+    delete_files(&unreferenced, store, concurrency).await?;
+    Ok(unreferenced.len())
+}
+
+/// Returns whether the file at `path` has the size and chunks of `entry`.
+fn matches_entry(
+    path: &std::path::Path,
+    entry: &ContentManifestEntry,
+    chunk_size: usize,
+) -> Result<bool> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() == entry.size as u64 => {}
+        _ => return Ok(false),
+    }
+    let mut file = fs::File::open(path)?;
+    let mut buf = vec![0; chunk_size];
+    for digest in &entry.chunks {
+        let mut len = 0;
+        while len < chunk_size {
+            let read = file.read(&mut buf[len..])?;
+            if read == 0 {
+                break;
+            }
+            len += read;
+        }
+        if chunk_digest(&buf[..len]) != *digest {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Downloads `files` of an upload described by `manifest` into `local_dir`. Files which are
+/// already present in `local_dir` with the expected content are skipped, so an interrupted
+/// download resumes where it stopped. Files present with the expected content under the same
+/// path in one of `reuse_dirs`, e.g. an earlier snapshot of the same db, are taken from there
+/// instead of being downloaded.
+pub async fn download_content_addressed<S: ObjectStoreGetExt>(
+    store: &S,
+    manifest: &ContentManifest,
+    files: &[String],
+    local_dir: &std::path::Path,
+    reuse_dirs: &[PathBuf],
+    concurrency: NonZeroUsize,
+    progress_bar: Option<ProgressBar>,
+) -> Result<()> {
+    let chunk_size = manifest.chunk_size;
+    futures::stream::iter(files)
+        .map(|file| async move {
+            let entry = manifest
+                .files
+                .get(file)
+                .with_context(|| format!("{file} is not in the manifest"))?;
+            let dest = local_dir.join(file);
+            let tmp = local_dir.join(format!("{file}.tmp"));
+            if matches_entry(&dest, entry, chunk_size)? {
+                return Ok(file);
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            for reuse_dir in reuse_dirs {
+                let candidate = reuse_dir.join(file);
+                if !matches_entry(&candidate, entry, chunk_size)? {
+                    continue;
+                }
+                if dest.exists() {
+                    fs::remove_file(&dest)?;
+                }
+                // SST files are never modified, so they can be shared with the other snapshot
+                if !file.ends_with(".sst") || fs::hard_link(&candidate, &dest).is_err() {
+                    fs::copy(&candidate, &tmp)?;
+                    fs::rename(&tmp, &dest)?;
+                }
+                return Ok(file);
+            }
+            let mut out = fs::File::create(&tmp)?;
+            for digest in &entry.chunks {
+                let bytes = get(store, &blob_path(digest)).await?;
+                if chunk_digest(&bytes) != *digest {
+                    bail!("Chunk {digest} of {file} has unexpected digest");
+                }
+                out.write_all(&bytes)?;
+            }
+            out.sync_all()?;
+            // Files are only moved into place once complete, so that an interrupted download
+            // never leaves a truncated file under its final name.
+            fs::rename(&tmp, &dest)?;
+            Ok::<_, anyhow::Error>(file)
+        })
+        .boxed()
+        .buffer_unordered(concurrency.get())
+        .try_for_each(|file| {
+            if let Some(progress_bar) = &progress_bar {
+                progress_bar.inc(1);
+                progress_bar.set_message(format!("file: {file}"));
+            }
+            futures::future::ready(Ok(()))
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object_store::content_addressed::{
+        download_content_addressed, garbage_collect_blobs, upload_content_addressed, BLOBS_DIR,
+    };
+    use object_store::path::Path;
+    use std::fs;
+    use std::num::NonZeroUsize;
+    use sui_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
+    use tempfile::TempDir;
+
+    fn file_store(
+        path: &std::path::Path,
+    ) -> anyhow::Result<std::sync::Arc<object_store::DynObjectStore>> {
+        ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::File),
+            directory: Some(path.to_path_buf()),
+            ..Default::default()
+        }
+        .make()
+    }
+
+    fn num_blobs(remote: &std::path::Path) -> anyhow::Result<usize> {
+        Ok(fs::read_dir(remote.join(BLOBS_DIR))?.count())
+    }
+
+    #[tokio::test]
+    pub async fn test_upload_deduplicates_and_download_reuses() -> anyhow::Result<()> {
+        let input = TempDir::new()?;
+        let input_path = input.path();
+        let epoch_0 = input_path.join("epoch_0");
+        fs::create_dir_all(epoch_0.join("store"))?;
+        fs::write(epoch_0.join("store").join("000001.sst"), b"immutable table")?;
+        fs::write(
+            epoch_0.join("store").join("MANIFEST-000002"),
+            b"first manifest",
+        )?;
+        let epoch_1 = input_path.join("epoch_1");
+        fs::create_dir_all(epoch_1.join("store"))?;
+        fs::write(epoch_1.join("store").join("000001.sst"), b"immutable table")?;
+        fs::write(
+            epoch_1.join("store").join("MANIFEST-000003"),
+            b"second manifest",
+        )?;
+
+        let remote = TempDir::new()?;
+        let input_store = file_store(input_path)?;
+        let remote_store = file_store(remote.path())?;
+        let concurrency = NonZeroUsize::new(2).unwrap();
+
+        // Chunks of 4 bytes, so that files span several chunks
+        let manifest_0 = upload_content_addressed(
+            &Path::from("epoch_0"),
+            &input_store,
+            &remote_store,
+            4,
+            concurrency,
+        )
+        .await?;
+        let blobs_after_epoch_0 = num_blobs(remote.path())?;
+        assert_eq!(manifest_0.files["store/000001.sst"].chunks.len(), 4);
+
+        let manifest_1 = upload_content_addressed(
+            &Path::from("epoch_1"),
+            &input_store,
+            &remote_store,
+            4,
+            concurrency,
+        )
+        .await?;
+        assert_eq!(
+            manifest_0.files["store/000001.sst"],
+            manifest_1.files["store/000001.sst"]
+        );
+        // Only the chunks of the new manifest file are uploaded
+        assert_eq!(num_blobs(remote.path())?, blobs_after_epoch_0 + 4);
+
+        let output = TempDir::new()?;
+        let files = manifest_0.files_with_prefix("store");
+        download_content_addressed(
+            &remote_store,
+            &manifest_0,
+            &files,
+            &output.path().join("epoch_0"),
+            &[],
+            concurrency,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            fs::read(output.path().join("epoch_0/store/MANIFEST-000002"))?,
+            b"first manifest"
+        );
+
+        // Remove the blobs of the shared table, so that epoch 1 can only be restored from epoch 0
+        for digest in &manifest_0.files["store/000001.sst"].chunks {
+            let _ = fs::remove_file(remote.path().join(BLOBS_DIR).join(digest));
+        }
+        let files = manifest_1.files_with_prefix("store");
+        download_content_addressed(
+            &remote_store,
+            &manifest_1,
+            &files,
+            &output.path().join("epoch_1"),
+            &[output.path().join("epoch_0")],
+            concurrency,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            fs::read(output.path().join("epoch_1/store/000001.sst"))?,
+            b"immutable table"
+        );
+        assert_eq!(
+            fs::read(output.path().join("epoch_1/store/MANIFEST-000003"))?,
+            b"second manifest"
+        );
+        assert!(!output
+            .path()
+            .join("epoch_1/store/MANIFEST-000003.tmp")
+            .exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn test_garbage_collect_blobs() -> anyhow::Result<()> {
+        let input = TempDir::new()?;
+        let input_path = input.path();
+        for (epoch, manifest) in [(0, b"first manifest"), (1, b"other manifest")] {
+            let store = input_path.join(format!("epoch_{epoch}")).join("store");
+            fs::create_dir_all(&store)?;
+            fs::write(store.join("000001.sst"), b"immutable table")?;
+            fs::write(store.join("MANIFEST"), manifest)?;
+        }
+
+        let remote = TempDir::new()?;
+        let input_store = file_store(input_path)?;
+        let remote_store = file_store(remote.path())?;
+        let concurrency = NonZeroUsize::new(2).unwrap();
+        let mut manifests = vec![];
+        for epoch in 0..2 {
+            manifests.push(
+                upload_content_addressed(
+                    &Path::from(format!("epoch_{epoch}")),
+                    &input_store,
+                    &remote_store,
+                    4,
+                    concurrency,
+                )
+                .await?,
+            );
+        }
+        let num_blobs_before = num_blobs(remote.path())?;
+
+        // All blobs are referenced while both uploads are retained
+        assert_eq!(garbage_collect_blobs(&remote_store, concurrency).await?, 0);
+
+        // Only the first two chunks of the manifest files differ between the epochs
+        fs::remove_dir_all(remote.path().join("epoch_0"))?;
+        assert_eq!(garbage_collect_blobs(&remote_store, concurrency).await?, 2);
+        assert_eq!(num_blobs(remote.path())?, num_blobs_before - 2);
+
+        let output = TempDir::new()?;
+        let files = manifests[1].files_with_prefix("store");
+        download_content_addressed(
+            &remote_store,
+            &manifests[1],
+            &files,
+            output.path(),
+            &[],
+            concurrency,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            fs::read(output.path().join("store/MANIFEST"))?,
+            b"other manifest"
+        );
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use object_store::path::Path;
 use object_store::{DynObjectStore, ObjectMeta, ObjectStore};
 use std::sync::Arc;
 
+pub mod content_addressed;
 pub mod http;
 pub mod util;
 
@@ -255,6 +255,8 @@ impl ValidatorConfigBuilder {
             chain_override_for_testing: self.chain_override,
             validator_client_monitor_config: None,
             fork_recovery: None,
+            authority_store_scrubber_config: None,
+            storage_engine_config: None,
         }
     }
 
@@ -564,6 +566,8 @@ impl FullnodeConfigBuilder {
             chain_override_for_testing: self.chain_override,
             validator_client_monitor_config: None,
             fork_recovery: None,
+            authority_store_scrubber_config: None,
+            storage_engine_config: None,
         }
     }
 }
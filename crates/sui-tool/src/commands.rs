@@ -4,9 +4,10 @@
 use crate::{
     check_completed_snapshot,
     db_tool::{execute_db_tool_command, print_db_all_tables, DbToolCommand},
-    download_db_snapshot, download_formal_snapshot, get_latest_available_epoch, get_object,
-    get_transaction_block, make_clients, restore_from_db_checkpoint, ConciseObjectOutput,
-    GroupedObjectOutput, SnapshotVerifyMode, VerboseObjectOutput,
+    download_db_snapshot, download_formal_snapshot, export_formal_snapshot,
+    get_latest_available_epoch, get_object, get_transaction_block, make_clients,
+    restore_from_db_checkpoint, ConciseObjectOutput, GroupedObjectOutput, SnapshotVerifyMode,
+    VerboseObjectOutput,
 };
 use anyhow::Result;
 use consensus_core::storage::{rocksdb_store::RocksDBStore, Store};
@@ -19,6 +20,7 @@ use sui_core::authority_client::AuthorityAPI;
 use sui_protocol_config::Chain;
 use sui_replay::{execute_replay_command, ReplayToolCommand};
 use sui_sdk::{rpc_types::SuiTransactionBlockResponseOptions, SuiClient, SuiClientBuilder};
+use sui_snapshot::filter::LiveObjectFilter;
 use sui_types::messages_consensus::ConsensusTransaction;
 use telemetry_subscribers::TracingHandle;
 
@@ -283,6 +285,21 @@ pub enum ToolCommand {
     DownloadFormalSnapshot {
         #[clap(long = "epoch", conflicts_with = "latest")]
         epoch: Option<u64>,
+        /// Restore the full snapshot of this epoch, then apply the delta snapshots of each
+        /// following epoch up to the target epoch.
+        #[clap(long = "base-epoch")]
+        base_epoch: Option<u64>,
+        /// Only restore objects of this type (repeatable). A type without type parameters
+        /// matches all of its instantiations. Restoring a subset of objects produces a
+        /// partial store that can't be used to run a node.
+        #[clap(long = "type")]
+        types: Vec<String>,
+        /// Only restore packages with this ID and objects of types they define (repeatable).
+        #[clap(long = "package")]
+        packages: Vec<ObjectID>,
+        /// Only restore objects owned by this address or object (repeatable).
+        #[clap(long = "owner")]
+        owners: Vec<SuiAddress>,
         #[clap(long = "genesis")]
         genesis: PathBuf,
         #[clap(long = "path")]
@@ -342,6 +359,65 @@ pub enum ToolCommand {
         all_checkpoints: bool,
     },
 
+    #[clap(
+        name = "export-formal-snapshot",
+        about = "Exports the live objects of a formal snapshot to Parquet files on local disk"
+    )]
+    ExportFormalSnapshot {
+        #[clap(long = "epoch", conflicts_with = "latest")]
+        epoch: Option<u64>,
+        /// Directory to write the Parquet files to, under `epoch_<epoch>`. Files already
+        /// exported there by an interrupted run are skipped.
+        #[clap(long = "path")]
+        path: PathBuf,
+        /// Also decode the contents of Move objects to JSON, in the `object_json` column.
+        #[clap(long = "decode-json")]
+        decode_json: bool,
+        /// Number of parallel downloads to perform. Defaults to a reasonable
+        /// value based on number of available logical cores.
+        #[clap(long = "num-parallel-downloads")]
+        num_parallel_downloads: Option<usize>,
+        /// Network to export snapshot for. Defaults to "mainnet".
+        /// If `--snapshot-bucket` is not specified, the value of this flag
+        /// is used to construct default bucket names.
+        #[clap(long = "network", default_value = "mainnet")]
+        network: Chain,
+        /// Snapshot bucket name. If not specified, defaults are
+        /// based on value of `--network` flag.
+        #[clap(long = "snapshot-bucket", conflicts_with = "no_sign_request")]
+        snapshot_bucket: Option<String>,
+        /// Snapshot bucket type
+        #[clap(
+            long = "snapshot-bucket-type",
+            conflicts_with = "no_sign_request",
+            help = "Required if --no-sign-request is not set"
+        )]
+        snapshot_bucket_type: Option<ObjectStoreType>,
+        /// Path to snapshot directory on local filesystem.
+        /// Only applicable if `--snapshot-bucket-type` is "file".
+        #[clap(long = "snapshot-path")]
+        snapshot_path: Option<PathBuf>,
+        /// If true, no authentication is needed for snapshot exports
+        #[clap(
+            long = "no-sign-request",
+            conflicts_with_all = &["snapshot_bucket", "snapshot_bucket_type"],
+            help = "if set, no authentication is needed for snapshot export"
+        )]
+        no_sign_request: bool,
+        /// Export snapshot of the latest available epoch.
+        /// If `--epoch` is specified, then this flag gets ignored.
+        #[clap(
+            long = "latest",
+            conflicts_with = "epoch",
+            help = "defaults to latest available snapshot in chosen bucket"
+        )]
+        latest: bool,
+        /// If false (default), log level will be overridden to "off",
+        /// and output will be reduced to necessary status information.
+        #[clap(long = "verbose")]
+        verbose: bool,
+    },
+
     #[clap(name = "replay")]
     Replay {
         #[arg(long = "rpc")]
@@ -444,6 +520,96 @@ async fn check_locked_object(
     Ok(())
 }
 
+/// Builds the config of the formal snapshot bucket of `network`, applying the defaults and
+/// environment overrides shared by the formal snapshot commands.
+fn formal_snapshot_store_config(
+    network: Chain,
+    snapshot_bucket: Option<String>,
+    snapshot_bucket_type: Option<ObjectStoreType>,
+    snapshot_path: Option<PathBuf>,
+    no_sign_request: bool,
+) -> ObjectStoreConfig {
+    let snapshot_bucket = snapshot_bucket.or_else(|| match (network, no_sign_request) {
+        (Chain::Mainnet, false) => Some(
+            env::var("MAINNET_FORMAL_SIGNED_BUCKET").unwrap_or("mysten-mainnet-formal".to_string()),
+        ),
+        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, _) => Some(
+            env::var("TESTNET_FORMAL_SIGNED_BUCKET").unwrap_or("mysten-testnet-formal".to_string()),
+        ),
+        (Chain::Unknown, _) => {
+            panic!("Cannot generate default snapshot bucket for unknown network");
+        }
+    });
+
+    let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
+        if no_sign_request {
+            if network == Chain::Mainnet {
+                Some("https://formal-snapshot.mainnet.sui.io".to_string())
+            } else if network == Chain::Testnet {
+                Some("https://formal-snapshot.testnet.sui.io".to_string())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    });
+
+    let snapshot_bucket_type = if no_sign_request {
+        ObjectStoreType::S3
+    } else {
+        snapshot_bucket_type
+            .expect("You must set either --snapshot-bucket-type or --no-sign-request")
+    };
+    match snapshot_bucket_type {
+        ObjectStoreType::S3 => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::S3),
+            bucket: snapshot_bucket.filter(|s| !s.is_empty()),
+            aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
+            aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
+            aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
+            aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
+            aws_virtual_hosted_style_request: env::var("AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS")
+                .ok()
+                .and_then(|b| b.parse().ok())
+                .unwrap_or(no_sign_request),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::GCS => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::GCS),
+            bucket: snapshot_bucket,
+            google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::Azure => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::Azure),
+            bucket: snapshot_bucket,
+            azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
+            azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::File => {
+            if snapshot_path.is_some() {
+                ObjectStoreConfig {
+                    object_store: Some(ObjectStoreType::File),
+                    directory: snapshot_path,
+                    ..Default::default()
+                }
+            } else {
+                panic!("--snapshot-path must be specified for --snapshot-bucket-type=file");
+            }
+        }
+    }
+}
+
 impl ToolCommand {
     #[allow(clippy::format_in_format_args)]
     pub async fn execute(self, tracing_handle: TracingHandle) -> Result<(), anyhow::Error> {
@@ -668,6 +834,10 @@ impl ToolCommand {
             }
             ToolCommand::DownloadFormalSnapshot {
                 epoch,
+                base_epoch,
+                types,
+                packages,
+                owners,
                 genesis,
                 path,
                 num_parallel_downloads,
@@ -691,94 +861,13 @@ impl ToolCommand {
                         .checked_sub(1)
                         .expect("Failed to get number of CPUs")
                 });
-                let snapshot_bucket =
-                    snapshot_bucket.or_else(|| match (network, no_sign_request) {
-                        (Chain::Mainnet, false) => Some(
-                            env::var("MAINNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("mysten-mainnet-formal".to_string()),
-                        ),
-                        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, _) => Some(
-                            env::var("TESTNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("mysten-testnet-formal".to_string()),
-                        ),
-                        (Chain::Unknown, _) => {
-                            panic!("Cannot generate default snapshot bucket for unknown network");
-                        }
-                    });
-
-                let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
-                    if no_sign_request {
-                        if network == Chain::Mainnet {
-                            Some("https://formal-snapshot.mainnet.sui.io".to_string())
-                        } else if network == Chain::Testnet {
-                            Some("https://formal-snapshot.testnet.sui.io".to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                });
-
-                let snapshot_bucket_type = if no_sign_request {
-                    ObjectStoreType::S3
-                } else {
-                    snapshot_bucket_type
-                        .expect("You must set either --snapshot-bucket-type or --no-sign-request")
-                };
-                let snapshot_store_config = match snapshot_bucket_type {
-                    ObjectStoreType::S3 => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::S3),
-                        bucket: snapshot_bucket.filter(|s| !s.is_empty()),
-                        aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
-                        aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
-                        aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
-                        aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
-                        aws_virtual_hosted_style_request: env::var(
-                            "AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS",
-                        )
-                        .ok()
-                        .and_then(|b| b.parse().ok())
-                        .unwrap_or(no_sign_request),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::GCS => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::GCS),
-                        bucket: snapshot_bucket,
-                        google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::Azure => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::Azure),
-                        bucket: snapshot_bucket,
-                        azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
-                        azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::File => {
-                        if snapshot_path.is_some() {
-                            ObjectStoreConfig {
-                                object_store: Some(ObjectStoreType::File),
-                                directory: snapshot_path,
-                                ..Default::default()
-                            }
-                        } else {
-                            panic!(
-                                "--snapshot-path must be specified for --snapshot-bucket-type=file"
-                            );
-                        }
-                    }
-                };
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
+                );
 
                 let ingestion_url = match network {
                     Chain::Mainnet => "https://checkpoints.mainnet.sui.io",
@@ -802,9 +891,12 @@ impl ToolCommand {
                 }
 
                 let verify = verify.unwrap_or_default();
+                let filter = LiveObjectFilter::new(types, packages, owners)?;
                 download_formal_snapshot(
                     &path,
                     epoch_to_download,
+                    base_epoch,
+                    (!filter.is_empty()).then_some(filter),
                     &genesis,
                     snapshot_store_config,
                     ingestion_url,
@@ -815,6 +907,61 @@ impl ToolCommand {
                 )
                 .await?;
             }
+            ToolCommand::ExportFormalSnapshot {
+                epoch,
+                path,
+                decode_json,
+                num_parallel_downloads,
+                network,
+                snapshot_bucket,
+                snapshot_bucket_type,
+                snapshot_path,
+                no_sign_request,
+                latest,
+                verbose,
+            } => {
+                if !verbose {
+                    tracing_handle
+                        .update_log("off")
+                        .expect("Failed to update log level");
+                }
+                let num_parallel_downloads = num_parallel_downloads.unwrap_or_else(|| {
+                    num_cpus::get()
+                        .checked_sub(1)
+                        .expect("Failed to get number of CPUs")
+                });
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
+                );
+
+                let latest_available_epoch =
+                    latest.then_some(get_latest_available_epoch(&snapshot_store_config).await?);
+                let epoch_to_export = epoch.or(latest_available_epoch).expect(
+                    "Either pass epoch with --epoch <epoch_num> or use latest with --latest",
+                );
+
+                if let Err(e) =
+                    check_completed_snapshot(&snapshot_store_config, epoch_to_export).await
+                {
+                    panic!(
+                        "Aborting snapshot export: {}, snapshot may not be uploaded yet",
+                        e
+                    );
+                }
+
+                export_formal_snapshot(
+                    &path,
+                    epoch_to_export,
+                    decode_json,
+                    snapshot_store_config,
+                    num_parallel_downloads,
+                )
+                .await?;
+            }
             ToolCommand::DownloadDBSnapshot {
                 epoch,
                 path,
@@ -306,7 +306,7 @@ mod test {
         let primary_path = tempfile::tempdir()?.keep();
 
         // Open the DB for writing
-        let _: AuthorityEpochTables = AuthorityEpochTables::open(0, &primary_path, None);
+        let _: AuthorityEpochTables = AuthorityEpochTables::open(0, &primary_path, None, None);
         let _: AuthorityPerpetualTables = AuthorityPerpetualTables::open(&primary_path, None);
 
         // Get all the tables for AuthorityEpochTables
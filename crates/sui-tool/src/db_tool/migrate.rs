@@ -0,0 +1,137 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, Context};
+use clap::{Parser, ValueEnum};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sui_config::node::StorageEngine;
+use sui_core::authority::authority_per_epoch_store::AuthorityEpochTables;
+use sui_core::authority::authority_store_tables::{
+    ensure_storage_engine_supported, AuthorityPerpetualTables, AuthorityPerpetualTablesOptions,
+};
+use sui_core::checkpoints::CheckpointStoreTables;
+use sui_types::base_types::EpochId;
+use typed_store::migration::TableMigrationState;
+use typed_store::TypedStoreError;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum MigratedStore {
+    Perpetual,
+    Epoch,
+    Checkpoint,
+}
+
+#[derive(Parser)]
+#[command(rename_all = "kebab-case")]
+pub struct MigrateToTidehunterOptions {
+    /// The store to migrate
+    #[arg(long, value_enum)]
+    store: MigratedStore,
+    /// The epoch of the epoch store to migrate
+    #[arg(long)]
+    epoch: Option<EpochId>,
+    /// The db path to write the migrated store to, with the same layout as the source db path
+    #[arg(long)]
+    target_path: PathBuf,
+    /// Number of entries copied per batch. Progress is saved after every batch
+    #[arg(long, default_value_t = 10_000)]
+    batch_size: usize,
+}
+
+/// Copies a RocksDB-backed store of the node at `db_path` into a TideHunter-backed store at the
+/// target path, table by table. Progress is saved in the target path after every batch, and an
+/// interrupted migration resumes from it when run again. Once every table has been copied, the
+/// target is verified against checksums of the copied entries.
+pub fn migrate_to_tidehunter(
+    db_path: &Path,
+    opt: MigrateToTidehunterOptions,
+) -> anyhow::Result<()> {
+    ensure_storage_engine_supported(StorageEngine::TideHunter)?;
+    fs::create_dir_all(&opt.target_path)?;
+    let store_name = match (opt.store, opt.epoch) {
+        (MigratedStore::Perpetual, _) => "perpetual".to_string(),
+        (MigratedStore::Checkpoint, _) => "checkpoint".to_string(),
+        (MigratedStore::Epoch, Some(epoch)) => format!("epoch_{epoch}"),
+        (MigratedStore::Epoch, None) => bail!("--epoch is required to migrate an epoch store"),
+    };
+    let progress_path = opt
+        .target_path
+        .join(format!("migration-progress-{store_name}.json"));
+    let mut progress = read_progress(&progress_path)?;
+    let on_batch = |progress: &BTreeMap<String, TableMigrationState>| {
+        write_progress(&progress_path, progress)
+            .map_err(|e| TypedStoreError::MigrationError(format!("{e:#}")))
+    };
+
+    match opt.store {
+        MigratedStore::Perpetual => {
+            let open = |path: &Path, engine| {
+                AuthorityPerpetualTables::open(
+                    &path.join("store"),
+                    Some(AuthorityPerpetualTablesOptions {
+                        storage_engine: Some(engine),
+                        ..Default::default()
+                    }),
+                )
+            };
+            let source = open(db_path, StorageEngine::RocksDb);
+            let target = open(&opt.target_path, StorageEngine::TideHunter);
+            source.migrate_tables(&target, &mut progress, opt.batch_size, on_batch)?;
+            target.verify_tables(&progress)?;
+        }
+        MigratedStore::Epoch => {
+            let epoch = opt.epoch.expect("checked above");
+            let open = |path: &Path, engine| {
+                AuthorityEpochTables::open(epoch, &path.join("store"), None, Some(engine))
+            };
+            let source = open(db_path, StorageEngine::RocksDb);
+            let target = open(&opt.target_path, StorageEngine::TideHunter);
+            source.migrate_tables(&target, &mut progress, opt.batch_size, on_batch)?;
+            target.verify_tables(&progress)?;
+        }
+        MigratedStore::Checkpoint => {
+            let open = |path: &Path, engine| {
+                CheckpointStoreTables::new_with_engine(
+                    &path.join("checkpoints"),
+                    "checkpoint",
+                    Some(engine),
+                )
+            };
+            let source = open(db_path, StorageEngine::RocksDb);
+            let target = open(&opt.target_path, StorageEngine::TideHunter);
+            source.migrate_tables(&target, &mut progress, opt.batch_size, on_batch)?;
+            target.verify_tables(&progress)?;
+        }
+    }
+
+    println!("Migrated and verified {store_name} store:");
+    for (table, state) in &progress {
+        println!(
+            "{table}: {} entries, checksum {}",
+            state.num_entries,
+            hex::encode(state.checksum.0)
+        );
+    }
+    Ok(())
+}
+
+fn read_progress(path: &Path) -> anyhow::Result<BTreeMap<String, TableMigrationState>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let bytes = fs::read(path)?;
+    serde_json::from_slice(&bytes)
+        .with_context(|| format!("failed to parse migration progress at {}", path.display()))
+}
+
+fn write_progress(
+    path: &Path,
+    progress: &BTreeMap<String, TableMigrationState>,
+) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(progress)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
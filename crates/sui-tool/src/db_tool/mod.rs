@@ -3,6 +3,7 @@
 
 use self::db_dump::{dump_table, duplicate_objects_summary, list_tables, table_summary, StoreName};
 use self::index_search::{search_index, SearchRange};
+use self::migrate::{migrate_to_tidehunter, MigrateToTidehunterOptions};
 use crate::db_tool::db_dump::{compact, print_table_metadata, prune_checkpoints, prune_objects};
 use anyhow::{anyhow, bail};
 use clap::Parser;
@@ -17,6 +18,7 @@ use sui_types::messages_checkpoint::{CheckpointDigest, CheckpointSequenceNumber}
 use typed_store::rocks::{safe_drop_db, MetricConf};
 pub mod db_dump;
 mod index_search;
+mod migrate;
 
 #[derive(Parser)]
 #[command(rename_all = "kebab-case")]
@@ -40,6 +42,7 @@ pub enum DbToolCommand {
     PruneObjects,
     PruneCheckpoints,
     SetCheckpointWatermark(SetCheckpointWatermarkOptions),
+    MigrateToTidehunter(MigrateToTidehunterOptions),
 }
 
 #[derive(Parser)]
@@ -232,6 +235,7 @@ pub async fn execute_db_tool_command(db_path: PathBuf, cmd: DbToolCommand) -> an
             Ok(())
         }
         DbToolCommand::SetCheckpointWatermark(d) => set_checkpoint_watermark(&db_path, d),
+        DbToolCommand::MigrateToTidehunter(d) => migrate_to_tidehunter(&db_path, d),
     }
 }
 
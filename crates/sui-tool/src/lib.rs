@@ -23,6 +23,9 @@ use sui_network::default_mysten_network_config;
 use sui_protocol_config::Chain;
 use sui_sdk::SuiClient;
 use sui_sdk::SuiClientBuilder;
+use sui_storage::object_store::content_addressed::{
+    download_content_addressed, ContentManifest, CONTENT_MANIFEST_FILENAME,
+};
 use sui_storage::object_store::http::HttpDownloaderBuilder;
 use sui_storage::object_store::util::Manifest;
 use sui_storage::object_store::util::PerEpochManifest;
@@ -51,6 +54,8 @@ use sui_core::authority::AuthorityStore;
 use sui_core::checkpoints::CheckpointStore;
 use sui_core::epoch::committee_store::CommitteeStore;
 use sui_core::storage::RocksDbStore;
+use sui_snapshot::export::export_parquet;
+use sui_snapshot::filter::LiveObjectFilter;
 use sui_snapshot::reader::StateSnapshotReaderV1;
 use sui_snapshot::setup_db_state;
 use sui_storage::object_store::util::{copy_file, exists, get_path};
@@ -70,6 +75,9 @@ pub mod commands;
 pub mod db_tool;
 mod formal_snapshot_util;
 
+/// Name of the file recording the filter of a partial formal snapshot restore.
+pub const RESTORE_FILTER_FILENAME: &str = "RESTORE_FILTER";
+
 #[derive(
     Clone, Serialize, Deserialize, Debug, PartialEq, Copy, PartialOrd, Ord, Eq, ValueEnum, Default,
 )]
@@ -815,9 +823,17 @@ pub async fn check_completed_snapshot(
     }
 }
 
+/// Restores the formal snapshot of the end of `epoch` to `path`. If `base_epoch` is given, the full
+/// snapshot of `base_epoch` is restored and the delta snapshots of each following epoch up to
+/// `epoch` are applied to it.
+///
+/// If `filter` is given, only the live objects matching it are restored, to `path/partial`
+/// rather than `path/live`, with the filter recorded in its `RESTORE_FILTER` file.
 pub async fn download_formal_snapshot(
     path: &Path,
     epoch: EpochId,
+    base_epoch: Option<EpochId>,
+    filter: Option<LiveObjectFilter>,
     genesis: &Path,
     snapshot_store_config: ObjectStoreConfig,
     ingestion_url: &str,
@@ -826,6 +842,17 @@ pub async fn download_formal_snapshot(
     verify: SnapshotVerifyMode,
     all_checkpoints: bool,
 ) -> Result<(), anyhow::Error> {
+    if filter.is_some() && verify == SnapshotVerifyMode::Strict {
+        return Err(anyhow!(
+            "Strict verification of the restored store requires restoring all objects"
+        ));
+    }
+    if base_epoch.is_some_and(|base_epoch| base_epoch > epoch) {
+        return Err(anyhow!(
+            "Base epoch must not be later than the epoch to restore: {}",
+            epoch
+        ));
+    }
     let m = MultiProgress::new();
     m.println(format!(
         "Beginning formal snapshot restore to end of epoch {}, network: {:?}, verification mode: {:?}",
@@ -869,6 +896,7 @@ pub async fn download_formal_snapshot(
     // not pass in a channel to the reader
     let (sender, mut receiver) = mpsc::channel(num_parallel_downloads);
     let m_clone = m.clone();
+    let filter_clone = filter.clone();
 
     let snapshot_handle = tokio::spawn(async move {
         let local_store_config = ObjectStoreConfig {
@@ -876,21 +904,66 @@ pub async fn download_formal_snapshot(
             directory: Some(snapshot_dir_clone.to_path_buf()),
             ..Default::default()
         };
+        let full_epoch = base_epoch.unwrap_or(epoch);
         let mut reader = StateSnapshotReaderV1::new(
-            epoch,
+            full_epoch,
             &snapshot_store_config,
             &local_store_config,
             NonZeroUsize::new(num_parallel_downloads).unwrap(),
-            m_clone,
+            m_clone.clone(),
             false, // skip_reset_local_store
         )
         .await
         .unwrap_or_else(|err| panic!("Failed to create reader: {}", err));
+        if let Some(filter) = &filter_clone {
+            reader.set_filter(filter.clone());
+        }
         reader
-            .read(&perpetual_db_clone, abort_registration, Some(sender))
+            .read(
+                &perpetual_db_clone,
+                abort_registration,
+                Some(sender.clone()),
+            )
             .await
             .unwrap_or_else(|err| panic!("Failed during read: {}", err));
-        Ok::<(), anyhow::Error>(())
+
+        // Apply the delta snapshots on top of the full one. Their removed objects are
+        // subtracted from the accumulated root state hash, so it is only checked against the
+        // commitment of the target epoch once all of them are applied.
+        let mut num_removed_objects = 0;
+        for delta_epoch in full_epoch + 1..=epoch {
+            let mut reader = StateSnapshotReaderV1::new_delta(
+                delta_epoch,
+                &snapshot_store_config,
+                &local_store_config,
+                NonZeroUsize::new(num_parallel_downloads).unwrap(),
+                m_clone.clone(),
+                false, // skip_reset_local_store
+            )
+            .await
+            .unwrap_or_else(|err| panic!("Failed to create delta reader: {}", err));
+            if let Some(filter) = &filter_clone {
+                reader.set_filter(filter.clone());
+            }
+            if reader.base_epoch() != Some(delta_epoch - 1) {
+                panic!(
+                    "Delta snapshot of epoch {} is not against epoch {}",
+                    delta_epoch,
+                    delta_epoch - 1
+                );
+            }
+            let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+            reader
+                .read(
+                    &perpetual_db_clone,
+                    abort_registration,
+                    Some(sender.clone()),
+                )
+                .await
+                .unwrap_or_else(|err| panic!("Failed during delta read: {}", err));
+            num_removed_objects += reader.num_removed_objects();
+        }
+        Ok::<u64, anyhow::Error>(num_removed_objects)
     });
     let mut root_global_state_hash = GlobalStateHash::default();
     let mut num_live_objects = 0;
@@ -957,16 +1030,34 @@ pub async fn download_formal_snapshot(
         )?;
     }
 
-    snapshot_handle
+    let num_removed_objects = snapshot_handle
         .await
         .expect("Task join failed")
         .expect("Snapshot restore task failed");
+    num_live_objects -= num_removed_objects;
 
     // TODO we should ensure this map is being updated for all end of epoch
     // checkpoints during summary sync. This happens in `insert_{verified|certified}_checkpoint`
     // in checkpoint store, but not in the corresponding functions in ObjectStore trait
     checkpoint_store.insert_epoch_last_checkpoint(epoch, &last_checkpoint)?;
 
+    if let Some(filter) = filter {
+        // The partial live object set can't be used to run a node, so it is kept apart from
+        // full restores, along with the filter that selected it.
+        fs::write(path.join(RESTORE_FILTER_FILENAME), filter.to_string())?;
+        let new_path = path.parent().unwrap().join("partial");
+        if new_path.exists() {
+            fs::remove_dir_all(new_path.clone())?;
+        }
+        fs::rename(&path, &new_path)?;
+        fs::remove_dir_all(snapshot_dir.clone())?;
+        println!(
+            "Successfully restored objects matching {} from snapshot at end of epoch {}",
+            filter, epoch
+        );
+        return Ok(());
+    }
+
     setup_db_state(
         epoch,
         root_global_state_hash.clone(),
@@ -994,6 +1085,42 @@ pub async fn download_formal_snapshot(
     Ok(())
 }
 
+/// Exports the live objects of the formal snapshot of the end of `epoch` to Parquet files under
+/// `path/epoch_{epoch}`, one per object file of the snapshot. Rerunning an interrupted export
+/// only exports the files that are missing.
+pub async fn export_formal_snapshot(
+    path: &Path,
+    epoch: EpochId,
+    decode_json: bool,
+    snapshot_store_config: ObjectStoreConfig,
+    num_parallel_downloads: usize,
+) -> Result<(), anyhow::Error> {
+    let snapshot_dir = path.join("snapshot");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(snapshot_dir.clone()),
+        ..Default::default()
+    };
+    let mut reader = StateSnapshotReaderV1::new(
+        epoch,
+        &snapshot_store_config,
+        &local_store_config,
+        NonZeroUsize::new(num_parallel_downloads).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+    )
+    .await?;
+    export_parquet(&mut reader, path, decode_json).await?;
+    fs::remove_dir_all(snapshot_dir)?;
+    println!(
+        "Successfully exported snapshot at end of epoch {} to {}",
+        epoch,
+        path.display()
+    );
+
+    Ok(())
+}
+
 pub async fn download_db_snapshot(
     path: &Path,
     epoch: u64,
@@ -1022,6 +1149,32 @@ pub async fn download_db_snapshot(
     let epoch_path = format!("epoch_{}", epoch);
     let epoch_dir = get_path(&epoch_path);
 
+    // Snapshots uploaded in content addressed form only hold a content manifest, their files are
+    // assembled from chunks shared between epochs
+    if let Ok(bytes) = remote_store
+        .get_bytes(&epoch_dir.child(CONTENT_MANIFEST_FILENAME))
+        .await
+    {
+        let content_manifest: ContentManifest = serde_json::from_slice(&bytes).map_err(|err| {
+            anyhow!(
+                "Error parsing {}/{} from bytes: {}",
+                epoch_path,
+                CONTENT_MANIFEST_FILENAME,
+                err
+            )
+        })?;
+        download_content_addressed_db_snapshot(
+            path,
+            epoch,
+            &remote_store,
+            &content_manifest,
+            skip_indexes,
+            num_parallel_downloads,
+        )
+        .await?;
+        return remove_stale_db_dirs(path);
+    }
+
     let manifest_file = epoch_dir.child(MANIFEST_FILENAME);
     let epoch_manifest_contents =
         String::from_utf8(remote_store.get_bytes(&manifest_file).await?.to_vec())
@@ -1093,6 +1246,61 @@ pub async fn download_db_snapshot(
         .into_iter()
         .for_each(|result| result.expect("Task failed"));
 
+    remove_stale_db_dirs(&path)
+}
+
+/// Downloads a db snapshot uploaded in content addressed form to `path/epoch_<epoch>`. Files
+/// already downloaded are skipped, and files shared with snapshots of other epochs previously
+/// downloaded to `path` are copied from them instead of being downloaded again. Each file is
+/// written to a temporary file and only renamed into place once complete.
+async fn download_content_addressed_db_snapshot(
+    path: &Path,
+    epoch: u64,
+    remote_store: &Arc<dyn ObjectStoreGetExt>,
+    content_manifest: &ContentManifest,
+    skip_indexes: bool,
+    num_parallel_downloads: usize,
+) -> Result<(), anyhow::Error> {
+    let mut files = vec![];
+    files.extend(content_manifest.files_with_prefix("store/perpetual"));
+    files.extend(content_manifest.files_with_prefix("epochs"));
+    files.extend(content_manifest.files_with_prefix("checkpoints"));
+    if !skip_indexes {
+        files.extend(content_manifest.files_with_prefix("indexes"));
+    }
+    let epoch_dir_name = format!("epoch_{}", epoch);
+    let mut reuse_dirs = vec![];
+    if path.exists() {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if entry.file_type()?.is_dir() && name.starts_with("epoch_") && name != epoch_dir_name {
+                reuse_dirs.push(entry.path());
+            }
+        }
+    }
+    let progress_bar = ProgressBar::new(files.len() as u64).with_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] {wide_bar} {pos} out of {len} files done ({msg})",
+        )
+        .unwrap(),
+    );
+    download_content_addressed(
+        remote_store,
+        content_manifest,
+        &files,
+        &path.join(&epoch_dir_name),
+        &reuse_dirs,
+        NonZeroUsize::new(num_parallel_downloads).unwrap(),
+        Some(progress_bar.clone()),
+    )
+    .await?;
+    progress_bar.finish_with_message("Snapshot file download is complete");
+    Ok(())
+}
+
+fn remove_stale_db_dirs(path: &Path) -> Result<(), anyhow::Error> {
     let store_dir = path.join("store");
     if store_dir.exists() {
         fs::remove_dir_all(&store_dir)?;
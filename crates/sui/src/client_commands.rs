@@ -5,7 +5,7 @@ use crate::{
     clever_error_rendering::render_clever_error_opt,
     client_ptb::ptb::PTB,
     displays::Pretty,
-    upgrade_compatibility::check_compatibility,
+    upgrade_compatibility::{check_advisories, check_compatibility},
     verifier_meter::{AccumulatingMeter, Accumulator},
 };
 use std::{
@@ -33,7 +33,13 @@ use move_bytecode_verifier_meter::Scope;
 use move_core_types::{
     account_address::AccountAddress, identifier::Identifier, language_storage::TypeTag,
 };
-use move_package::{source_package::parsed_manifest::Dependencies, BuildConfig as MoveBuildConfig};
+use move_package::{
+    source_package::{
+        layout::SourcePackageLayout, manifest_parser::parse_move_manifest_from_file,
+        parsed_manifest::Dependencies,
+    },
+    BuildConfig as MoveBuildConfig,
+};
 use prometheus::Registry;
 use serde::Serialize;
 use serde_json::{json, Value};
@@ -54,6 +60,7 @@ use sui_json_rpc_types::{
 use sui_keys::key_identity::KeyIdentity;
 use sui_keys::keystore::AccountKeystore;
 use sui_move_build::{
+    attestation::{attestation_path, BuildAttestation, SignedBuildAttestation},
     build_from_resolution_graph, check_conflicting_addresses, check_invalid_dependencies,
     check_unpublished_dependencies, gather_published_ids, implicit_deps, BuildConfig,
     CompiledPackage,
@@ -564,6 +571,15 @@ pub enum SuiClientCommands {
         #[clap(long)]
         verify_compatibility: bool,
 
+        /// Report changes that do not break upgrade compatibility but may break clients of the
+        /// package, e.g. changed abort codes or event types, as warnings before upgrading.
+        #[clap(long)]
+        advisories: bool,
+
+        /// Write the upgrade advisories as JSON to this file. Implies `--advisories`.
+        #[clap(long, value_name = "PATH")]
+        advisories_json: Option<PathBuf>,
+
         /// Upgrade the package without checking whether dependency source code compiles to the on-chain
         /// bytecode
         #[clap(long)]
@@ -636,6 +652,34 @@ pub enum SuiClientCommands {
         address_override: Option<ObjectID>,
     },
 
+    /// Verify a build attestation produced by `sui move build --attest`: check its signature,
+    /// rebuild the package from source to check that the attestation matches the build, and check
+    /// that the attested modules match the package on-chain.
+    #[clap(name = "verify-attestation")]
+    VerifyAttestation {
+        /// Path to directory containing a Move package
+        #[clap(name = "package_path", global = true, default_value = ".")]
+        package_path: PathBuf,
+
+        /// Package build options
+        #[clap(flatten)]
+        build_config: MoveBuildConfig,
+
+        /// The attestation to verify. Defaults to `build/<package>/attestation.json` in the
+        /// package directory.
+        #[clap(long)]
+        attestation: Option<PathBuf>,
+
+        /// Look for the package on-chain at this address, instead of the address it was attested
+        /// to be published at.
+        #[clap(long)]
+        address_override: Option<ObjectID>,
+
+        /// Require the attestation to be signed by this address.
+        #[clap(long)]
+        signer: Option<SuiAddress>,
+    },
+
     /// Remove an existing address by its alias or hexadecimal string.
     #[clap(name = "remove-address")]
     RemoveAddress { alias_or_address: String },
@@ -949,6 +993,8 @@ impl SuiClientCommands {
                 skip_dependency_verification,
                 verify_deps,
                 verify_compatibility,
+                advisories,
+                advisories_json,
                 with_unpublished_dependencies,
                 payment,
                 gas_data,
@@ -1023,6 +1069,17 @@ impl SuiClientCommands {
                     compiled_package.get_package_digest(with_unpublished_dependencies);
                 let dep_ids = compiled_package.get_published_dependencies_ids();
 
+                if advisories || advisories_json.is_some() {
+                    check_advisories(
+                        read_api,
+                        package_id,
+                        &compiled_package,
+                        protocol_config.clone(),
+                        advisories_json.as_deref(),
+                    )
+                    .await?;
+                }
+
                 if verify_compatibility {
                     check_compatibility(
                         read_api,
@@ -1955,6 +2012,71 @@ impl SuiClientCommands {
 
                 SuiClientCommandResult::VerifySource
             }
+            SuiClientCommands::VerifyAttestation {
+                package_path,
+                mut build_config,
+                attestation,
+                address_override,
+                signer,
+            } => {
+                let attestation_file = match attestation {
+                    Some(path) => path,
+                    None => {
+                        let manifest = parse_move_manifest_from_file(
+                            &package_path.join(SourcePackageLayout::Manifest.path()),
+                        )?;
+                        attestation_path(&package_path, manifest.package.name.as_str())
+                    }
+                };
+                let attestation = SignedBuildAttestation::read(&attestation_file)?;
+                let chain_id = &attestation.attestation.chain_id;
+
+                let client = context.get_client().await?;
+                let current_chain_id = client.read_api().get_chain_identifier().await?;
+                if chain_id.as_ref().is_some_and(|id| *id != current_chain_id) {
+                    bail!(
+                        "The attestation is for chain {}, but the active environment is for \
+                         chain {current_chain_id}",
+                        chain_id.as_ref().unwrap()
+                    );
+                }
+
+                // Rebuild the package the way it was built when attesting, resolving the IDs of
+                // its dependencies for the same chain.
+                build_config.implicit_dependencies = implicit_deps(latest_system_packages());
+                let build_config = resolve_lock_file_path(build_config, Some(&package_path))?;
+                let compiled_package = BuildConfig {
+                    config: build_config,
+                    run_bytecode_verifier: true,
+                    print_diags_to_stderr: true,
+                    chain_id: chain_id.clone(),
+                }
+                .build(&package_path)?;
+                // Attest to the rebuilt package at the addresses it was attested to be published
+                // at, so that recording its `published-at` address since does not count as a
+                // difference.
+                let local = BuildAttestation::new_published_at(
+                    &compiled_package,
+                    &package_path,
+                    chain_id.clone(),
+                    attestation.attestation.published_at,
+                    attestation.attestation.original_id,
+                )?;
+
+                let address = BytecodeSourceVerifier::new(client.read_api())
+                    .verify_attestation(
+                        &attestation,
+                        &local,
+                        address_override.map(|id| *id),
+                        signer,
+                    )
+                    .await?;
+
+                SuiClientCommandResult::VerifyAttestation {
+                    package_id: address.into(),
+                    signer: attestation.signer,
+                }
+            }
             SuiClientCommands::PartyTransfer {
                 to,
                 object_id,
@@ -2588,6 +2710,13 @@ impl Display for SuiClientCommandResult {
             SuiClientCommandResult::VerifySource => {
                 writeln!(writer, "Source verification succeeded!")?;
             }
+            SuiClientCommandResult::VerifyAttestation { package_id, signer } => {
+                writeln!(
+                    writer,
+                    "Attestation verification succeeded! Package {package_id} was built from \
+                     these sources, as attested by {signer}."
+                )?;
+            }
             SuiClientCommandResult::VerifyBytecodeMeter {
                 success,
                 max_package_ticks,
@@ -2800,6 +2929,7 @@ impl SuiClientCommandResult {
             | SuiClientCommandResult::SerializedUnsignedTransaction(_)
             | SuiClientCommandResult::Switch(_)
             | SuiClientCommandResult::SyncClientState
+            | SuiClientCommandResult::VerifyAttestation { .. }
             | SuiClientCommandResult::VerifyBytecodeMeter { .. }
             | SuiClientCommandResult::VerifySource => (),
         }
@@ -2959,6 +3089,10 @@ pub enum SuiClientCommandResult {
     Switch(SwitchResponse),
     SyncClientState,
     TransactionBlock(SuiTransactionBlockResponse),
+    VerifyAttestation {
+        package_id: ObjectID,
+        signer: SuiAddress,
+    },
     VerifyBytecodeMeter {
         success: bool,
         max_package_ticks: Option<u128>,
@@ -13,6 +13,7 @@ use clap::*;
 use colored::Colorize;
 use fastcrypto::traits::KeyPair;
 use move_analyzer::analyzer;
+use move_binary_format::CompiledModule;
 use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
 use move_package::BuildConfig;
 use mysten_common::tempdir;
@@ -43,6 +44,8 @@ use sui_indexer::test_utils::{
     start_indexer_jsonrpc_for_testing, start_indexer_writer_for_testing,
 };
 use sui_json_rpc_types::{SuiObjectDataOptions, SuiRawData};
+use sui_move::audit::{AuditMetadata, LinkedPackage};
+use sui_move::inspect::InspectMetadata;
 use sui_move::summary::PackageSummaryMetadata;
 use sui_sdk::apis::ReadApi;
 use sui_sdk::SuiClient;
@@ -558,6 +561,50 @@ impl SuiCommand {
                         )?;
                         return Ok(());
                     }
+                    sui_move::Command::Audit(a) if a.package_id.is_some() => {
+                        let (_, client) = get_chain_id_and_client(
+                            client_config,
+                            "sui move audit --package-id <object_id>",
+                        )
+                        .await?;
+                        let Some(client) = client else {
+                            bail!("`sui move audit --package-id <object_id>` requires a configured network");
+                        };
+                        let root_package_id = a
+                            .package_id
+                            .expect("Safe since we checked in the match statement");
+                        let metadata =
+                            linked_packages_for_audit(client.read_api(), root_package_id).await?;
+                        execute_move_command(
+                            package_path.as_deref(),
+                            build_config,
+                            sui_move::Command::Audit(a),
+                            Some(sui_move::CommandMeta::Audit(metadata)),
+                        )?;
+                        return Ok(());
+                    }
+                    sui_move::Command::Inspect(i) if i.package_id.is_some() => {
+                        let (_, client) = get_chain_id_and_client(
+                            client_config,
+                            "sui move inspect --package-id <object_id>",
+                        )
+                        .await?;
+                        let Some(client) = client else {
+                            bail!("`sui move inspect --package-id <object_id>` requires a configured network");
+                        };
+                        let root_package_id = i
+                            .package_id
+                            .expect("Safe since we checked in the match statement");
+                        let metadata =
+                            linked_modules_for_inspect(client.read_api(), root_package_id).await?;
+                        execute_move_command(
+                            package_path.as_deref(),
+                            build_config,
+                            sui_move::Command::Inspect(i),
+                            Some(sui_move::CommandMeta::Inspect(metadata)),
+                        )?;
+                        return Ok(());
+                    }
                     sui_move::Command::Build(build) if build.dump_bytecode_as_base64 => {
                         // `sui move build` does not ordinarily require a network connection.
                         // The exception is when --dump-bytecode-as-base64 is specified: In this
@@ -648,8 +695,12 @@ impl SuiCommand {
                 };
 
                 // If a specific environment is specified for the build command we set the chain ID
-                // to the one that is specified.
-                if client_config.env.is_some() && matches!(cmd, sui_move::Command::Build(_)) {
+                // to the one that is specified. Attestations always record the chain that the
+                // package's dependencies were resolved for, so use the active environment for them.
+                let attest = matches!(&cmd, sui_move::Command::Build(build) if build.attest);
+                if (client_config.env.is_some() || attest)
+                    && matches!(cmd, sui_move::Command::Build(_))
+                {
                     let (chain_id, _) =
                         get_chain_id_and_client(client_config, "sui move build").await?;
 
@@ -658,6 +709,21 @@ impl SuiCommand {
                     };
 
                     build_config.chain_id = chain_id;
+                } else if let sui_move::Command::Audit(audit) = &mut cmd {
+                    // Local dependencies are audited at the versions they are published at on the
+                    // chain of the active environment, according to their Move.lock.
+                    match get_chain_id_and_client(client_config, "sui move audit").await {
+                        Ok((chain_id, _)) => audit.chain_id = chain_id,
+                        Err(e) => eprintln!(
+                            "{}",
+                            format!(
+                                "[warning] {e}. Auditing dependencies at the versions they are \
+                                 published at on all chains in their Move.lock"
+                            )
+                            .yellow()
+                            .bold()
+                        ),
+                    }
                 }
 
                 execute_move_command(package_path.as_deref(), build_config, cmd, None)
@@ -1056,10 +1122,7 @@ async fn start(
         )
         .await?;
 
-        let app_state = Arc::new(AppState {
-            faucet: local_faucet,
-            config,
-        });
+        let app_state = Arc::new(AppState::new(local_faucet, config)?);
 
         start_faucet(app_state).await?;
     }
@@ -1586,6 +1649,67 @@ async fn download_package_and_deps_under(
     })
 }
 
+/// The package and all the packages in its linkage table, by original package ID, with the
+/// versions that it links against.
+async fn linked_packages_for_audit(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+) -> anyhow::Result<AuditMetadata> {
+    let root_package = resolve_package(read_api, package_id).await?;
+    let mut packages = BTreeMap::new();
+    packages.insert(
+        root_package.original_package_id(),
+        LinkedPackage {
+            name: None,
+            package_id: Some(root_package.id()),
+            version: Some(root_package.version().value()),
+        },
+    );
+    for (original_id, pkg_info) in root_package.linkage_table() {
+        packages.insert(
+            *original_id,
+            LinkedPackage {
+                name: None,
+                package_id: Some(pkg_info.upgraded_id),
+                version: Some(pkg_info.upgraded_version.value()),
+            },
+        );
+    }
+    Ok(AuditMetadata { packages })
+}
+
+/// The modules of the package and of all the packages in its linkage table.
+async fn linked_modules_for_inspect(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+) -> anyhow::Result<InspectMetadata> {
+    let deserialize = |package: &MovePackage| {
+        package
+            .serialized_module_map()
+            .iter()
+            .map(|(name, bytes)| {
+                CompiledModule::deserialize_with_defaults(bytes).with_context(|| {
+                    format!(
+                        "Unable to deserialize module {name} of package {}",
+                        package.id()
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
+
+    let root_package = resolve_package(read_api, package_id).await?;
+    let mut dependency_modules = vec![];
+    for pkg_info in root_package.linkage_table().values() {
+        let package = resolve_package(read_api, pkg_info.upgraded_id).await?;
+        dependency_modules.extend(deserialize(&package)?);
+    }
+    Ok(InspectMetadata {
+        root_modules: deserialize(&root_package)?,
+        dependency_modules,
+    })
+}
+
 /// Parse the input string into a SocketAddr, with a default port if none is provided.
 pub fn parse_host_port(
     input: String,
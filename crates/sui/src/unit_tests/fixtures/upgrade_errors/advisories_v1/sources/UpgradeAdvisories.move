@@ -0,0 +1,38 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// Module: UpgradeAdvisories
+
+module upgrades::upgrades {
+    use sui::event;
+
+    const ENotAllowed: u64 = 1;
+
+    #[error]
+    const EInvalidAmount: vector<u8> = b"Amount must be positive";
+
+    public struct Swapped has copy, drop {
+        amount: u64,
+    }
+
+    public fun swap(amount: u64) {
+        assert!(amount > 0, EInvalidAmount);
+        event::emit(Swapped { amount });
+    }
+
+    public fun check(allowed: bool) {
+        assert!(allowed, ENotAllowed);
+    }
+
+    public fun deposit(_amount: u64) {}
+
+    public fun unchanged(allowed: bool) {
+        assert!(allowed, ENotAllowed);
+    }
+
+    entry fun claim(_amount: u64) {}
+
+    entry fun withdraw<T>(_amount: u64) {}
+
+    entry fun register(_amount: u64) {}
+}
@@ -0,0 +1,47 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/// Module: UpgradeAdvisories
+
+module upgrades::upgrades {
+    use sui::event;
+
+    const ENotAllowed: u64 = 1;
+
+    const ENotAuthorized: u64 = 2;
+
+    #[error]
+    const EInvalidAmount: vector<u8> = b"Amount must be greater than zero";
+
+    public struct Swapped has copy, drop {
+        amount: u64,
+    }
+
+    public struct SwappedV2 has copy, drop {
+        amount: u64,
+        fee: u64,
+    }
+
+    public fun swap(amount: u64) {
+        assert!(amount > 0, EInvalidAmount);
+        event::emit(SwappedV2 { amount, fee: 0 });
+    }
+
+    public fun check(allowed: bool) {
+        assert!(allowed, ENotAuthorized);
+    }
+
+    public fun deposit(amount: u64) {
+        assert!(amount < 1000, ENotAllowed);
+    }
+
+    public fun unchanged(allowed: bool) {
+        assert!(allowed, ENotAllowed);
+    }
+
+    fun claim(_amount: u64) {}
+
+    entry fun withdraw<T: drop>(_amount: u64) {}
+
+    entry fun register(_amount: u64, _name: vector<u8>) {}
+}
@@ -1,12 +1,16 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 use insta::assert_snapshot;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::upgrade_compatibility::{compare_packages, missing_module_diag, FormattedField};
+use crate::upgrade_compatibility::{
+    advisory::AdvisoryKind, compare_packages, missing_module_diag, package_advisories,
+    FormattedField,
+};
 
 use move_binary_format::normalized::{Field, Type};
 use move_binary_format::CompiledModule;
@@ -267,6 +271,49 @@ fn test_address_change() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_advisories() {
+    let (pkg_v1, pkg_v2, _) = get_packages("advisories");
+    let (advisories, diags) = package_advisories(pkg_v1, &pkg_v2).unwrap();
+
+    let found: BTreeSet<_> = advisories
+        .iter()
+        .map(|a| (a.function.as_str(), a.kind))
+        .collect();
+    let expected = BTreeSet::from([
+        ("swap", AdvisoryKind::EventSchemaChange),
+        ("swap", AdvisoryKind::CleverErrorChange),
+        ("check", AdvisoryKind::AbortCodeChange),
+        ("deposit", AdvisoryKind::NewAbort),
+        ("claim", AdvisoryKind::EntryRemoved),
+        ("withdraw", AdvisoryKind::TypeParamConstraintChange),
+        ("register", AdvisoryKind::EntryParametersChange),
+    ]);
+    assert_eq!(found, expected);
+    assert_eq!(diags.len(), advisories.len());
+
+    let advisory = |kind| advisories.iter().find(|a| a.kind == kind).unwrap();
+    let event_change = advisory(AdvisoryKind::EventSchemaChange);
+    assert_eq!(
+        event_change.old.as_deref(),
+        Some("upgrades::Swapped { amount: u64 }")
+    );
+    assert_eq!(
+        event_change.new.as_deref(),
+        Some("upgrades::SwappedV2 { amount: u64, fee: u64 }")
+    );
+    let clever_error_change = advisory(AdvisoryKind::CleverErrorChange);
+    assert_eq!(
+        clever_error_change.new.as_deref(),
+        Some("EInvalidAmount: \"Amount must be greater than zero\"")
+    );
+    let abort_code_change = advisory(AdvisoryKind::AbortCodeChange);
+    assert_eq!(abort_code_change.old.as_deref(), Some("1"));
+    assert_eq!(abort_code_change.new.as_deref(), Some("2"));
+    let params_change = advisory(AdvisoryKind::EntryParametersChange);
+    assert_eq!(params_change.new.as_deref(), Some("(u64, vector<u8>)"));
+}
+
 #[test]
 fn positional_formatting() {
     let name = Identifier::new("pos999").unwrap();
@@ -0,0 +1,504 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Advisory checks for upgrades.
+//! An upgrade that satisfies the compatibility rules can still break the clients of a package,
+//! e.g. an indexer decoding its events or an app matching on its abort codes. These checks diff
+//! the old and new versions of each module at the normalized-module level and report such
+//! changes. They never fail an upgrade, and are reported as warnings and as JSON.
+//!
+//! Types are compared by module and name, ignoring addresses, since the new version of a
+//! package is compiled before its address is known.
+
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Error};
+use move_binary_format::{
+    file_format::{AbilitySet, FunctionDefinitionIndex, Visibility},
+    normalized::{self, Bytecode},
+};
+use move_command_line_common::error_bitset::ErrorBitset;
+use move_compiler::diagnostics::{
+    codes::{custom, DiagnosticInfo, Severity},
+    Diagnostic, Diagnostics,
+};
+use move_ir_types::location::Loc;
+use move_package::compilation::compiled_package::CompiledUnitWithSource;
+use serde::Serialize;
+
+use super::{ability_mismatch_label, Function, IdentifierTableLookup, Module, Type};
+
+const ADVISORY_PREFIX: &str = "Upgrade Advisory ";
+const ADVISORY_CATEGORY: u8 = 1;
+
+const EVENT_MODULE: &str = "event";
+const EMIT_FUNCTION: &str = "emit";
+
+/// Kinds of changes that do not break upgrade compatibility but may break clients.
+// WARNING: add new kinds to the END of the list, the discriminant is the diagnostic code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum AdvisoryKind {
+    /// A function emits events of different types.
+    EventSchemaChange = 1,
+    /// A function aborts with different abort codes.
+    AbortCodeChange,
+    /// The value of a clever error a function aborts with changed.
+    CleverErrorChange,
+    /// A function that did not abort directly now does.
+    NewAbort,
+    /// A non-public function is no longer `entry`.
+    EntryRemoved,
+    /// The parameters of a non-public `entry` function changed.
+    EntryParametersChange,
+    /// The type parameter constraints of a non-public `entry` function changed.
+    TypeParamConstraintChange,
+}
+
+/// A change between two versions of a package that may break its clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Advisory {
+    pub kind: AdvisoryKind,
+    pub module: String,
+    pub function: String,
+    pub message: String,
+    /// The relevant part of the old version, if any.
+    pub old: Option<String>,
+    /// The relevant part of the new version, if any.
+    pub new: Option<String>,
+}
+
+/// How a function directly aborts, as far as can be told from its code.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum AbortInfo {
+    Code(u64),
+    CleverError { name: String, value: String },
+    Unknown,
+}
+
+impl AdvisoryKind {
+    fn diag_info(self) -> DiagnosticInfo {
+        let msg = match self {
+            AdvisoryKind::EventSchemaChange => "event schema change",
+            AdvisoryKind::AbortCodeChange => "abort code change",
+            AdvisoryKind::CleverErrorChange => "clever error change",
+            AdvisoryKind::NewAbort => "new abort",
+            AdvisoryKind::EntryRemoved => "entry removed",
+            AdvisoryKind::EntryParametersChange => "entry parameters change",
+            AdvisoryKind::TypeParamConstraintChange => "type parameter constraint change",
+        };
+        custom(
+            ADVISORY_PREFIX,
+            Severity::Warning,
+            ADVISORY_CATEGORY,
+            self as u8,
+            msg,
+        )
+    }
+
+    fn note(self) -> &'static str {
+        match self {
+            AdvisoryKind::EventSchemaChange => {
+                "Clients decoding the events of the old type will not see the new events."
+            }
+            AdvisoryKind::AbortCodeChange | AdvisoryKind::CleverErrorChange => {
+                "Clients matching on the abort codes or error messages of this function \
+                need to be updated."
+            }
+            AdvisoryKind::NewAbort => {
+                "Transactions calling this function that used to succeed may now abort."
+            }
+            AdvisoryKind::EntryRemoved => {
+                "Transactions calling this function directly will fail on the new version."
+            }
+            AdvisoryKind::EntryParametersChange | AdvisoryKind::TypeParamConstraintChange => {
+                "Transactions built for the old signature will fail on the new version."
+            }
+        }
+    }
+}
+
+/// Returns the advisories for the upgrade of `existing_module` to `new_module`, along with
+/// diagnostics pointing to the new source.
+pub(super) fn module_advisories(
+    existing_module: &Module,
+    new_module: &Module,
+    compiled_unit_with_source: &CompiledUnitWithSource,
+    lookup: &IdentifierTableLookup,
+) -> Result<(Vec<Advisory>, Diagnostics), Error> {
+    let module_name = existing_module.id.name.to_string();
+    let mut advisories = vec![];
+    for (name, old_function) in &existing_module.functions {
+        let new_function = new_module.functions.get(name);
+        let advisory = |kind, message, old, new| Advisory {
+            kind,
+            module: module_name.clone(),
+            function: name.to_string(),
+            message,
+            old,
+            new,
+        };
+
+        let private_entry = old_function.is_entry && old_function.visibility != Visibility::Public;
+        if private_entry && !new_function.is_some_and(|f| f.is_entry) {
+            advisories.push(advisory(
+                AdvisoryKind::EntryRemoved,
+                format!("Function '{name}' is no longer an 'entry' function"),
+                None,
+                None,
+            ));
+        }
+        let Some(new_function) = new_function else {
+            continue;
+        };
+
+        if private_entry && new_function.is_entry {
+            let old_params = format_params(old_function);
+            let new_params = format_params(new_function);
+            if old_params != new_params {
+                advisories.push(advisory(
+                    AdvisoryKind::EntryParametersChange,
+                    format!("The parameters of entry function '{name}' changed"),
+                    Some(old_params),
+                    Some(new_params),
+                ));
+            }
+            if old_function.type_parameters.len() == new_function.type_parameters.len() {
+                for (i, (old_constraints, new_constraints)) in old_function
+                    .type_parameters
+                    .iter()
+                    .zip(&new_function.type_parameters)
+                    .enumerate()
+                {
+                    if old_constraints != new_constraints {
+                        advisories.push(advisory(
+                            AdvisoryKind::TypeParamConstraintChange,
+                            format!(
+                                "Type parameter {i} of entry function '{name}': {}",
+                                ability_mismatch_label(
+                                    *old_constraints,
+                                    *new_constraints,
+                                    "constraint",
+                                    "constraints",
+                                )
+                            ),
+                            Some(format_abilities(*old_constraints)),
+                            Some(format_abilities(*new_constraints)),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let old_events = emitted_events(existing_module, old_function);
+        let new_events = emitted_events(new_module, new_function);
+        if old_events != new_events {
+            let removed = old_events
+                .difference(&new_events)
+                .cloned()
+                .collect::<Vec<_>>();
+            let added = new_events
+                .difference(&old_events)
+                .cloned()
+                .collect::<Vec<_>>();
+            advisories.push(advisory(
+                AdvisoryKind::EventSchemaChange,
+                format!("Function '{name}' emits different events"),
+                (!removed.is_empty()).then(|| removed.join(", ")),
+                (!added.is_empty()).then(|| added.join(", ")),
+            ));
+        }
+
+        let old_aborts = aborts(existing_module, old_function);
+        let new_aborts = aborts(new_module, new_function);
+        if old_aborts.is_empty() && !new_aborts.is_empty() {
+            advisories.push(advisory(
+                AdvisoryKind::NewAbort,
+                format!("Function '{name}' now aborts"),
+                None,
+                Some(format_aborts(&new_aborts)),
+            ));
+            continue;
+        }
+        for (old, new) in clever_error_changes(&old_aborts, &new_aborts) {
+            advisories.push(advisory(
+                AdvisoryKind::CleverErrorChange,
+                format!("Function '{name}' aborts with a different value for a clever error"),
+                Some(old),
+                Some(new),
+            ));
+        }
+        let old_codes = abort_codes(&old_aborts);
+        let new_codes = abort_codes(&new_aborts);
+        if old_codes != new_codes {
+            advisories.push(advisory(
+                AdvisoryKind::AbortCodeChange,
+                format!("Function '{name}' aborts with different abort codes"),
+                Some(format_codes(&old_codes)),
+                Some(format_codes(&new_codes)),
+            ));
+        }
+    }
+
+    let mut diags = Diagnostics::new();
+    for advisory in &advisories {
+        diags.add(advisory_diag(advisory, compiled_unit_with_source, lookup)?);
+    }
+    Ok((advisories, diags))
+}
+
+fn advisory_diag(
+    advisory: &Advisory,
+    compiled_unit_with_source: &CompiledUnitWithSource,
+    lookup: &IdentifierTableLookup,
+) -> Result<Diagnostic, Error> {
+    let source_map = &compiled_unit_with_source.unit.source_map;
+    // removed functions are reported on the module
+    let loc = match lookup
+        .function_identifier_to_index
+        .iter()
+        .find(|(name, _)| name.as_str() == advisory.function)
+    {
+        Some((_, index)) => {
+            source_map
+                .get_function_source_map(FunctionDefinitionIndex::new(*index))
+                .context("Unable to get function source map")?
+                .definition_location
+        }
+        None => source_map.definition_location,
+    };
+
+    let mut notes = vec![];
+    if let Some(old) = &advisory.old {
+        notes.push(format!("Old: {old}"));
+    }
+    if let Some(new) = &advisory.new {
+        notes.push(format!("New: {new}"));
+    }
+    notes.push(advisory.kind.note().to_string());
+
+    Ok(Diagnostic::new(
+        advisory.kind.diag_info(),
+        (loc, advisory.message.clone()),
+        Vec::<(Loc, String)>::new(),
+        notes,
+    ))
+}
+
+/// The types of the events emitted by `function`, with their fields.
+fn emitted_events(module: &Module, function: &Function) -> BTreeSet<String> {
+    function
+        .code()
+        .iter()
+        .filter_map(|instr| match instr {
+            Bytecode::Call(call)
+                if call.module.address == sui_types::SUI_FRAMEWORK_ADDRESS
+                    && call.module.name.as_str() == EVENT_MODULE
+                    && call.function.as_str() == EMIT_FUNCTION =>
+            {
+                call.type_arguments
+                    .first()
+                    .map(|ty| format_event(module, ty))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Formats an event type along with its fields, if it is defined in `module`.
+fn format_event(module: &Module, ty: &Type) -> String {
+    let type_name = format_type(ty);
+    let normalized::Type::Datatype(dt) = ty else {
+        return type_name;
+    };
+    match module.structs.get(dt.name.as_str()) {
+        Some(struct_) if dt.module.name == module.id.name => {
+            let fields = struct_
+                .fields
+                .0
+                .values()
+                .map(|f| format!("{}: {}", f.name, format_type(&f.type_)))
+                .collect::<Vec<_>>();
+            format!("{type_name} {{ {} }}", fields.join(", "))
+        }
+        _ => type_name,
+    }
+}
+
+/// The ways `function` directly aborts.
+fn aborts(module: &Module, function: &Function) -> BTreeSet<AbortInfo> {
+    let code = function.code();
+    code.iter()
+        .enumerate()
+        .filter(|(_, instr)| matches!(instr, Bytecode::Abort))
+        .map(|(i, _)| match i.checked_sub(1).map(|prev| &code[prev]) {
+            Some(Bytecode::LdU64(value)) => abort_info(module, *value),
+            Some(Bytecode::LdConst(constant))
+                if matches!(constant.type_, normalized::Type::U64) =>
+            {
+                bcs::from_bytes::<u64>(&constant.data)
+                    .map_or(AbortInfo::Unknown, |value| abort_info(module, value))
+            }
+            _ => AbortInfo::Unknown,
+        })
+        .collect()
+}
+
+fn abort_info(module: &Module, code: u64) -> AbortInfo {
+    let Some(bitset) = ErrorBitset::from_u64(code) else {
+        return AbortInfo::Code(code);
+    };
+    let constant = |index: Option<u16>| index.and_then(|i| module.constants.get(i as usize));
+    match (
+        constant(bitset.identifier_index()),
+        constant(bitset.constant_index()),
+    ) {
+        (Some(identifier), Some(value)) => AbortInfo::CleverError {
+            name: bcs::from_bytes::<Vec<u8>>(&identifier.data)
+                .map(|name| String::from_utf8_lossy(&name).to_string())
+                .unwrap_or_default(),
+            value: format_constant(value),
+        },
+        // the line number changes whenever the source above the abort changes, so it is not
+        // part of the error that clients can rely on
+        _ => match bitset.error_code() {
+            Some(error_code) => AbortInfo::Code(error_code as u64),
+            None => AbortInfo::Unknown,
+        },
+    }
+}
+
+// Clever errors with the same name but different values, formatted as `(old, new)`.
+fn clever_error_changes(
+    old_aborts: &BTreeSet<AbortInfo>,
+    new_aborts: &BTreeSet<AbortInfo>,
+) -> Vec<(String, String)> {
+    let mut changes = vec![];
+    for old in old_aborts {
+        let AbortInfo::CleverError { name, value } = old else {
+            continue;
+        };
+        for new in new_aborts {
+            match new {
+                AbortInfo::CleverError {
+                    name: new_name,
+                    value: new_value,
+                } if new_name == name && new_value != value => {
+                    changes.push((format!("{name}: {value}"), format!("{name}: {new_value}")));
+                }
+                _ => (),
+            }
+        }
+    }
+    changes
+}
+
+fn abort_codes(aborts: &BTreeSet<AbortInfo>) -> BTreeSet<u64> {
+    aborts
+        .iter()
+        .filter_map(|abort| match abort {
+            AbortInfo::Code(code) => Some(*code),
+            _ => None,
+        })
+        .collect()
+}
+
+fn format_aborts(aborts: &BTreeSet<AbortInfo>) -> String {
+    aborts
+        .iter()
+        .map(|abort| match abort {
+            AbortInfo::Code(code) => code.to_string(),
+            AbortInfo::CleverError { name, value } => format!("{name}: {value}"),
+            AbortInfo::Unknown => "a computed abort code".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_codes(codes: &BTreeSet<u64>) -> String {
+    if codes.is_empty() {
+        return "none".to_string();
+    }
+    codes
+        .iter()
+        .map(|code| code.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_constant(constant: &normalized::Constant<normalized::RcIdentifier>) -> String {
+    match &constant.type_ {
+        normalized::Type::Vector(inner) if matches!(**inner, normalized::Type::U8) => {
+            match bcs::from_bytes::<Vec<u8>>(&constant.data) {
+                Ok(bytes) => match String::from_utf8(bytes) {
+                    Ok(s) => format!("\"{s}\""),
+                    Err(e) => format_bytes(e.as_bytes()),
+                },
+                Err(_) => format_bytes(&constant.data),
+            }
+        }
+        normalized::Type::U64 => bcs::from_bytes::<u64>(&constant.data)
+            .map_or_else(|_| format_bytes(&constant.data), |v| v.to_string()),
+        _ => format_bytes(&constant.data),
+    }
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    format!("0x{hex}")
+}
+
+fn format_params(function: &Function) -> String {
+    let params = function
+        .parameters
+        .iter()
+        .map(|ty| format_type(ty))
+        .collect::<Vec<_>>();
+    format!("({})", params.join(", "))
+}
+
+fn format_abilities(abilities: AbilitySet) -> String {
+    let abilities = abilities
+        .into_iter()
+        .map(|a| format!("{a:?}").to_lowercase())
+        .collect::<Vec<_>>();
+    if abilities.is_empty() {
+        "no constraints".to_string()
+    } else {
+        abilities.join(" + ")
+    }
+}
+
+// Formats a type without addresses, see the module documentation.
+fn format_type(ty: &Type) -> String {
+    use normalized::Type as T;
+    match ty {
+        T::Bool => "bool".to_string(),
+        T::U8 => "u8".to_string(),
+        T::U16 => "u16".to_string(),
+        T::U32 => "u32".to_string(),
+        T::U64 => "u64".to_string(),
+        T::U128 => "u128".to_string(),
+        T::U256 => "u256".to_string(),
+        T::Address => "address".to_string(),
+        T::Signer => "signer".to_string(),
+        T::Vector(inner) => format!("vector<{}>", format_type(inner)),
+        T::Reference(true, inner) => format!("&mut {}", format_type(inner)),
+        T::Reference(false, inner) => format!("&{}", format_type(inner)),
+        T::TypeParameter(i) => format!("T{i}"),
+        T::Datatype(dt) if dt.type_arguments.is_empty() => {
+            format!("{}::{}", dt.module.name, dt.name)
+        }
+        T::Datatype(dt) => format!(
+            "{}::{}<{}>",
+            dt.module.name,
+            dt.name,
+            dt.type_arguments
+                .iter()
+                .map(format_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
@@ -1,17 +1,19 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod advisory;
 mod formatting;
 #[path = "../unit_tests/upgrade_compatibility_tests.rs"]
 #[cfg(test)]
 mod upgrade_compatibility_tests;
 
+pub(crate) use advisory::Advisory;
 use formatting::{format_list, format_param, singular_or_plural, FormattedField};
 
 use anyhow::{anyhow, Context, Error};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -689,6 +691,63 @@ pub(crate) async fn check_compatibility(
     upgrade_policy: u8,
     protocol_config: ProtocolConfig,
 ) -> Result<(), Error> {
+    let (original_id, existing_modules) =
+        existing_package_modules(read_api, package_id, &protocol_config).await?;
+
+    let policy =
+        UpgradePolicy::try_from(upgrade_policy).map_err(|_| anyhow!("Invalid upgrade policy"))?;
+
+    compare_packages(
+        original_id,
+        existing_modules,
+        new_package,
+        package_path,
+        policy,
+    )
+}
+
+/// Report the changes between an existing on-chain package and a new version of it that may
+/// break clients of the package even if the upgrade is compatible, see the `advisory` module. The
+/// advisories are printed as warnings, and written as JSON to `json_path` if set.
+pub(crate) async fn check_advisories(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+    new_package: &CompiledPackage,
+    protocol_config: ProtocolConfig,
+    json_path: Option<&Path>,
+) -> Result<Vec<Advisory>, Error> {
+    let (_, existing_modules) =
+        existing_package_modules(read_api, package_id, &protocol_config).await?;
+
+    let (advisories, diags) = package_advisories(existing_modules, new_package)?;
+    if !diags.is_empty() {
+        eprintln!(
+            "{}",
+            String::from_utf8(report_diagnostics_to_buffer(
+                &new_package.package.file_map,
+                diags,
+                use_colors()
+            ))
+            .context("Unable to convert buffer to string")?
+        );
+    }
+    if let Some(json_path) = json_path {
+        fs::write(json_path, serde_json::to_string_pretty(&advisories)?).with_context(|| {
+            format!(
+                "Unable to write upgrade advisories to {}",
+                json_path.display()
+            )
+        })?;
+    }
+    Ok(advisories)
+}
+
+/// Fetch the modules of an on-chain package, along with its original id.
+async fn existing_package_modules(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+    protocol_config: &ProtocolConfig,
+) -> Result<(AccountAddress, Vec<CompiledModule>), Error> {
     let existing_obj_read = read_api
         .get_object_with_options(package_id, SuiObjectDataOptions::new().with_bcs())
         .await
@@ -708,22 +767,51 @@ pub(crate) async fn check_compatibility(
     let existing_modules = existing_package
         .module_map
         .iter()
-        .map(|m| CompiledModule::deserialize_with_config(m.1, &to_binary_config(&protocol_config)))
+        .map(|m| CompiledModule::deserialize_with_config(m.1, &to_binary_config(protocol_config)))
         .collect::<Result<Vec<_>, _>>()
         .context("Unable to get existing package")?;
 
-    let policy =
-        UpgradePolicy::try_from(upgrade_policy).map_err(|_| anyhow!("Invalid upgrade policy"))?;
+    let original_id = *existing_package
+        .to_move_package(u64::MAX /* safe as this pkg comes from the network */)?
+        .original_package_id();
 
-    compare_packages(
-        *existing_package
-            .to_move_package(u64::MAX /* safe as this pkg comes from the network */)?
-            .original_package_id(),
-        existing_modules,
-        new_package,
-        package_path,
-        policy,
-    )
+    Ok((original_id, existing_modules))
+}
+
+/// Collect the advisories for each module of the existing package that is still in the new one.
+/// Missing modules are reported by the compatibility check.
+fn package_advisories(
+    existing_modules: Vec<CompiledModule>,
+    new_package: &CompiledPackage,
+) -> Result<(Vec<Advisory>, Diagnostics), Error> {
+    let mut advisories = vec![];
+    let mut diags = Diagnostics::new();
+
+    for existing_module in existing_modules {
+        let name = existing_module.self_id().name().to_owned();
+        let Some(new_module) = new_package
+            .get_modules()
+            .find(|m| m.self_id().name() == name.as_ident_str())
+        else {
+            continue;
+        };
+        let compiled_unit_with_source = new_package
+            .package
+            .get_module_by_name_from_root(name.as_str())
+            .context("Unable to get module")?;
+
+        let pool = &mut normalized::RcPool::new();
+        let (module_advisories, module_diags) = advisory::module_advisories(
+            &Module::new(pool, &existing_module, /* include code */ true),
+            &Module::new(pool, new_module, /* include code */ true),
+            compiled_unit_with_source,
+            &table_index(new_module),
+        )?;
+        advisories.extend(module_advisories);
+        diags.extend(module_diags);
+    }
+
+    Ok((advisories, diags))
 }
 
 /// Collect all the errors into a single error message.
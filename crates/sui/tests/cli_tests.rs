@@ -167,6 +167,8 @@ impl TreeShakingTest {
             skip_dependency_verification: false,
             verify_deps: false,
             verify_compatibility: true,
+            advisories: false,
+            advisories_json: None,
             with_unpublished_dependencies: false,
             payment: PaymentArgs {
                 gas: vec![self.gas_obj_id],
@@ -2282,6 +2284,8 @@ async fn test_package_upgrade_command() -> Result<(), anyhow::Error> {
         upgrade_capability: cap.reference.object_id,
         build_config,
         verify_compatibility: true,
+        advisories: false,
+        advisories_json: None,
         skip_dependency_verification: false,
         verify_deps: true,
         with_unpublished_dependencies: false,
@@ -2417,6 +2421,8 @@ async fn test_package_management_on_upgrade_command() -> Result<(), anyhow::Erro
         upgrade_capability: cap.reference.object_id,
         build_config: build_config.clone(),
         verify_compatibility: true,
+        advisories: false,
+        advisories_json: None,
         skip_dependency_verification: false,
         verify_deps: true,
         with_unpublished_dependencies: false,
@@ -2583,6 +2589,8 @@ async fn test_package_management_on_upgrade_command_conflict() -> Result<(), any
         upgrade_capability: cap.reference.object_id,
         build_config: build_config_upgrade.clone(),
         verify_compatibility: true,
+        advisories: false,
+        advisories_json: None,
         skip_dependency_verification: false,
         verify_deps: true,
         with_unpublished_dependencies: false,
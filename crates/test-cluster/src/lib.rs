@@ -971,6 +971,7 @@ impl TestClusterBuilder {
             object_store_config: None,
             perform_index_db_checkpoints_at_epoch_end: None,
             prune_and_compact_before_upload: None,
+            content_addressed_upload: None,
         };
         self
     }
@@ -982,6 +983,7 @@ impl TestClusterBuilder {
             object_store_config: None,
             perform_index_db_checkpoints_at_epoch_end: None,
             prune_and_compact_before_upload: Some(true),
+            content_addressed_upload: None,
         };
         self
     }
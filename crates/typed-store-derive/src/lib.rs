@@ -240,6 +240,43 @@ pub fn derive_dbmap_utils_general(input: TokenStream) -> TokenStream {
                     (stringify!(#cf_names).to_owned(), (stringify!(#key_names).to_owned(), stringify!(#value_names).to_owned())),
                 )*].into_iter().collect()
             }
+
+            /// Copies every table into the matching table of `target`, which may use a different storage engine.
+            /// `progress` holds the per-table state to resume from, and is passed to `on_batch` after every batch
+            pub fn migrate_tables(
+                &self,
+                target: &Self,
+                progress: &mut std::collections::BTreeMap<String, typed_store::migration::TableMigrationState>,
+                batch_size: usize,
+                mut on_batch: impl FnMut(&std::collections::BTreeMap<String, typed_store::migration::TableMigrationState>) -> Result<(), typed_store::TypedStoreError>,
+            ) -> Result<(), typed_store::TypedStoreError> {
+                #(
+                    let mut state = progress.remove(stringify!(#cf_names)).unwrap_or_default();
+                    let result = typed_store::migration::migrate_table(&self.#field_names, &target.#field_names, &mut state, batch_size, |state| {
+                        progress.insert(stringify!(#cf_names).to_owned(), state.clone());
+                        on_batch(progress)
+                    });
+                    progress.insert(stringify!(#cf_names).to_owned(), state);
+                    result?;
+                )*
+                Ok(())
+            }
+
+            /// Checks every table against the state recorded by a completed `migrate_tables`
+            pub fn verify_tables(
+                &self,
+                progress: &std::collections::BTreeMap<String, typed_store::migration::TableMigrationState>,
+            ) -> Result<(), typed_store::TypedStoreError> {
+                #(
+                    let state = progress
+                        .get(stringify!(#cf_names))
+                        .filter(|state| state.complete)
+                        .ok_or_else(|| typed_store::TypedStoreError::MigrationError(format!("table {} has not been migrated", stringify!(#cf_names))))?;
+                    typed_store::migration::verify_table(&self.#field_names, state)
+                        .map_err(|e| typed_store::TypedStoreError::MigrationError(format!("table {}: {}", stringify!(#cf_names), e)))?;
+                )*
+                Ok(())
+            }
         }
     };
 
@@ -343,17 +380,139 @@ pub fn derive_dbmap_utils_general(input: TokenStream) -> TokenStream {
         }
     };
 
-    if is_tidehunter {
-        TokenStream::from(quote! {
-            #base_code
+    let rocks_code = quote! {
+        impl <
+                #(
+                    #generics_names: #generics_bounds_token,
+                )*
+            > #intermediate_db_map_struct_name #generics {
+            /// Opens a set of tables in read-write mode
+            /// If as_secondary_with_path is set, the DB is opened in read only mode with the path specified
+            pub fn open_tables_impl(
+                path: std::path::PathBuf,
+                as_secondary_with_path: Option<std::path::PathBuf>,
+                metric_conf: typed_store::rocks::MetricConf,
+                global_db_options_override: Option<typed_store::rocksdb::Options>,
+                tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>,
+                remove_deprecated_tables: bool,
+            ) -> Self {
+                let path = &path;
+                let default_cf_opt = if let Some(opt) = global_db_options_override.as_ref() {
+                    typed_store::rocks::DBOptions {
+                        options: opt.clone(),
+                        rw_options: typed_store::rocks::default_db_options().rw_options,
+                    }
+                } else {
+                    typed_store::rocks::default_db_options()
+                };
+                let (db, rwopt_cfs) = {
+                    let opt_cfs = match tables_db_options_override {
+                        None => [
+                            #(
+                                (stringify!(#cf_names).to_owned(), #default_options_override_fn_names()),
+                            )*
+                        ],
+                        Some(o) => [
+                            #(
+                                (stringify!(#cf_names).to_owned(), o.to_map().get(stringify!(#cf_names)).unwrap_or(&default_cf_opt).clone()),
+                            )*
+                        ]
+                    };
+                    // Safe to call unwrap because we will have at least one field_name entry in the struct
+                    let rwopt_cfs: std::collections::HashMap<String, typed_store::rocks::ReadWriteOptions> = opt_cfs.iter().map(|q| (q.0.as_str().to_string(), q.1.rw_options.clone())).collect();
+                    let opt_cfs: Vec<_> = opt_cfs.iter().map(|q| (q.0.as_str(), q.1.options.clone())).collect();
+                    let db = match as_secondary_with_path.clone() {
+                        Some(p) => typed_store::rocks::open_cf_opts_secondary(path, Some(&p), global_db_options_override, metric_conf, &opt_cfs),
+                        _ => typed_store::rocks::open_cf_opts(path, global_db_options_override, metric_conf, &opt_cfs)
+                    };
+                    db.map(|d| (d, rwopt_cfs))
+                }.expect(&format!("Cannot open DB at {:?}", path));
+                let deprecated_tables = vec![#(stringify!(#deprecated_cfs),)*];
+                let (
+                        #(
+                            #field_names
+                        ),*
+                ) = (#(
+                        DBMap::#inner_types::reopen(&db, Some(stringify!(#cf_names)), rwopt_cfs.get(stringify!(#cf_names)).unwrap_or(&typed_store::rocks::ReadWriteOptions::default()), remove_deprecated_tables && deprecated_tables.contains(&stringify!(#cf_names))).expect(&format!("Cannot open {} CF.", stringify!(#cf_names))[..])
+                    ),*);
+
+                if as_secondary_with_path.is_none() && remove_deprecated_tables {
+                    #(
+                        db.drop_cf(stringify!(#deprecated_cfs)).expect("failed to drop a deprecated cf");
+                    )*
+                }
+                Self {
+                    #(
+                        #field_names,
+                    )*
+                }
+            }
+        }
+
+        // <----------- This section generates the read-write open logic and other common utils -------------->
+        impl <
+                #(
+                    #generics_names: #generics_bounds_token,
+                )*
+            > #name #generics {
+            /// Opens a set of tables in read-write mode
+            /// Only one process is allowed to do this at a time
+            /// `global_db_options_override` apply to the whole DB
+            /// `tables_db_options_override` apply to each table. If `None`, the attributes from `default_options_override_fn` are used if any
+            #[allow(unused_parens)]
+            pub fn open_tables_read_write(
+                path: std::path::PathBuf,
+                metric_conf: typed_store::rocks::MetricConf,
+                global_db_options_override: Option<typed_store::rocksdb::Options>,
+                tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>
+            ) -> Self {
+                let inner = #intermediate_db_map_struct_name::open_tables_impl(path, None, metric_conf, global_db_options_override, tables_db_options_override, false);
+                Self {
+                    #(
+                        #field_names: inner.#field_names,
+                    )*
+                }
+            }
+
+            #[allow(unused_parens)]
+            pub fn open_tables_read_write_with_deprecation_option(
+                path: std::path::PathBuf,
+                metric_conf: typed_store::rocks::MetricConf,
+                global_db_options_override: Option<typed_store::rocksdb::Options>,
+                tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>,
+                remove_deprecated_tables: bool,
+            ) -> Self {
+                let inner = #intermediate_db_map_struct_name::open_tables_impl(path, None, metric_conf, global_db_options_override, tables_db_options_override, remove_deprecated_tables);
+                Self {
+                    #(
+                        #field_names: inner.#field_names,
+                    )*
+                }
+            }
+
+            /// This opens the DB in read only mode and returns a struct which exposes debug features
+            pub fn get_read_only_handle (
+                primary_path: std::path::PathBuf,
+                with_secondary_path: Option<std::path::PathBuf>,
+                global_db_options_override: Option<typed_store::rocksdb::Options>,
+                metric_conf: typed_store::rocks::MetricConf,
+                ) -> #secondary_db_map_struct_name #generics {
+                #secondary_db_map_struct_name::open_tables_read_only(primary_path, with_secondary_path, metric_conf, global_db_options_override)
+            }
+        }
+        #secondary_code
+    };
+
+    // Tables that may be backed by TideHunter additionally get an opener for it
+    let tidehunter_code = if is_tidehunter {
+        quote! {
             impl <
                     #(
                         #generics_names: #generics_bounds_token,
                     )*
                 > #intermediate_db_map_struct_name #generics {
-                /// Opens a set of tables in read-write mode
-                /// If as_secondary_with_path is set, the DB is opened in read only mode with the path specified
-                pub fn open_tables_impl(
+                /// Opens a set of tables backed by TideHunter, with a key space per table
+                pub fn open_tables_impl_tidehunter(
                     path: std::path::PathBuf,
                     metric_conf: typed_store::rocks::MetricConf,
                     cf_configs: std::collections::BTreeMap<String, typed_store::tidehunter_util::ThConfig>,
@@ -400,155 +559,29 @@ pub fn derive_dbmap_utils_general(input: TokenStream) -> TokenStream {
                     #generics_names: #generics_bounds_token,
                 )*
             > #name #generics {
-                pub fn open_tables_read_write(
+                /// Opens a set of tables in read-write mode, backed by TideHunter instead of RocksDB
+                /// `cf_configs` must contain the key space config of every table
+                pub fn open_tables_tidehunter(
                     path: std::path::PathBuf,
                     metric_conf: typed_store::rocks::MetricConf,
                     cf_configs: std::collections::BTreeMap<String, typed_store::tidehunter_util::ThConfig>,
                 ) -> Self {
-                    let inner = #intermediate_db_map_struct_name::open_tables_impl(path, metric_conf, cf_configs);
+                    let inner = #intermediate_db_map_struct_name::open_tables_impl_tidehunter(path, metric_conf, cf_configs);
                     Self {
                         #(
                             #field_names: inner.#field_names,
                         )*
                     }
                 }
-
-                pub fn get_read_only_handle (
-                    _: std::path::PathBuf,
-                    _: Option<std::path::PathBuf>,
-                    _: Option<typed_store::rocksdb::Options>,
-                    _: typed_store::rocks::MetricConf,
-                ) -> #secondary_db_map_struct_name #generics {
-                    unimplemented!("read only mode is not supported for TideHunter");
-                }
             }
-
-            pub struct #secondary_db_map_struct_name;
-        })
+        }
     } else {
-        TokenStream::from(quote! {
-            #base_code
-
-            impl <
-                    #(
-                        #generics_names: #generics_bounds_token,
-                    )*
-                > #intermediate_db_map_struct_name #generics {
-                /// Opens a set of tables in read-write mode
-                /// If as_secondary_with_path is set, the DB is opened in read only mode with the path specified
-                pub fn open_tables_impl(
-                    path: std::path::PathBuf,
-                    as_secondary_with_path: Option<std::path::PathBuf>,
-                    metric_conf: typed_store::rocks::MetricConf,
-                    global_db_options_override: Option<typed_store::rocksdb::Options>,
-                    tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>,
-                    remove_deprecated_tables: bool,
-                ) -> Self {
-                    let path = &path;
-                    let default_cf_opt = if let Some(opt) = global_db_options_override.as_ref() {
-                        typed_store::rocks::DBOptions {
-                            options: opt.clone(),
-                            rw_options: typed_store::rocks::default_db_options().rw_options,
-                        }
-                    } else {
-                        typed_store::rocks::default_db_options()
-                    };
-                    let (db, rwopt_cfs) = {
-                        let opt_cfs = match tables_db_options_override {
-                            None => [
-                                #(
-                                    (stringify!(#cf_names).to_owned(), #default_options_override_fn_names()),
-                                )*
-                            ],
-                            Some(o) => [
-                                #(
-                                    (stringify!(#cf_names).to_owned(), o.to_map().get(stringify!(#cf_names)).unwrap_or(&default_cf_opt).clone()),
-                                )*
-                            ]
-                        };
-                        // Safe to call unwrap because we will have at least one field_name entry in the struct
-                        let rwopt_cfs: std::collections::HashMap<String, typed_store::rocks::ReadWriteOptions> = opt_cfs.iter().map(|q| (q.0.as_str().to_string(), q.1.rw_options.clone())).collect();
-                        let opt_cfs: Vec<_> = opt_cfs.iter().map(|q| (q.0.as_str(), q.1.options.clone())).collect();
-                        let db = match as_secondary_with_path.clone() {
-                            Some(p) => typed_store::rocks::open_cf_opts_secondary(path, Some(&p), global_db_options_override, metric_conf, &opt_cfs),
-                            _ => typed_store::rocks::open_cf_opts(path, global_db_options_override, metric_conf, &opt_cfs)
-                        };
-                        db.map(|d| (d, rwopt_cfs))
-                    }.expect(&format!("Cannot open DB at {:?}", path));
-                    let deprecated_tables = vec![#(stringify!(#deprecated_cfs),)*];
-                    let (
-                            #(
-                                #field_names
-                            ),*
-                    ) = (#(
-                            DBMap::#inner_types::reopen(&db, Some(stringify!(#cf_names)), rwopt_cfs.get(stringify!(#cf_names)).unwrap_or(&typed_store::rocks::ReadWriteOptions::default()), remove_deprecated_tables && deprecated_tables.contains(&stringify!(#cf_names))).expect(&format!("Cannot open {} CF.", stringify!(#cf_names))[..])
-                        ),*);
-
-                    if as_secondary_with_path.is_none() && remove_deprecated_tables {
-                        #(
-                            db.drop_cf(stringify!(#deprecated_cfs)).expect("failed to drop a deprecated cf");
-                        )*
-                    }
-                    Self {
-                        #(
-                            #field_names,
-                        )*
-                    }
-                }
-            }
-
-            // <----------- This section generates the read-write open logic and other common utils -------------->
-            impl <
-                    #(
-                        #generics_names: #generics_bounds_token,
-                    )*
-                > #name #generics {
-                /// Opens a set of tables in read-write mode
-                /// Only one process is allowed to do this at a time
-                /// `global_db_options_override` apply to the whole DB
-                /// `tables_db_options_override` apply to each table. If `None`, the attributes from `default_options_override_fn` are used if any
-                #[allow(unused_parens)]
-                pub fn open_tables_read_write(
-                    path: std::path::PathBuf,
-                    metric_conf: typed_store::rocks::MetricConf,
-                    global_db_options_override: Option<typed_store::rocksdb::Options>,
-                    tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>
-                ) -> Self {
-                    let inner = #intermediate_db_map_struct_name::open_tables_impl(path, None, metric_conf, global_db_options_override, tables_db_options_override, false);
-                    Self {
-                        #(
-                            #field_names: inner.#field_names,
-                        )*
-                    }
-                }
-
-                #[allow(unused_parens)]
-                pub fn open_tables_read_write_with_deprecation_option(
-                    path: std::path::PathBuf,
-                    metric_conf: typed_store::rocks::MetricConf,
-                    global_db_options_override: Option<typed_store::rocksdb::Options>,
-                    tables_db_options_override: Option<typed_store::rocks::DBMapTableConfigMap>,
-                    remove_deprecated_tables: bool,
-                ) -> Self {
-                    let inner = #intermediate_db_map_struct_name::open_tables_impl(path, None, metric_conf, global_db_options_override, tables_db_options_override, remove_deprecated_tables);
-                    Self {
-                        #(
-                            #field_names: inner.#field_names,
-                        )*
-                    }
-                }
+        quote! {}
+    };
 
-                /// This opens the DB in read only mode and returns a struct which exposes debug features
-                pub fn get_read_only_handle (
-                    primary_path: std::path::PathBuf,
-                    with_secondary_path: Option<std::path::PathBuf>,
-                    global_db_options_override: Option<typed_store::rocksdb::Options>,
-                    metric_conf: typed_store::rocks::MetricConf,
-                    ) -> #secondary_db_map_struct_name #generics {
-                    #secondary_db_map_struct_name::open_tables_read_only(primary_path, with_secondary_path, metric_conf, global_db_options_override)
-                }
-            }
-            #secondary_code
-        })
-    }
+    TokenStream::from(quote! {
+        #base_code
+        #rocks_code
+        #tidehunter_code
+    })
 }
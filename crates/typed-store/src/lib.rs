@@ -15,6 +15,7 @@ pub mod traits;
 pub use traits::{DbIterator, Map};
 pub mod memstore;
 pub mod metrics;
+pub mod migration;
 pub mod rocks;
 #[cfg(tidehunter)]
 pub mod tidehunter_util;
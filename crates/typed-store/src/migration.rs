@@ -0,0 +1,188 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Copies tables between databases that may be backed by different storage engines, e.g. from
+//! RocksDB to TideHunter.
+//!
+//! Tables are copied in key order, a batch at a time. After each batch the caller is handed a
+//! [`TableMigrationState`] to persist, from which an interrupted copy resumes. The state also
+//! accumulates a checksum of the copied entries, which [`verify_table`] checks the target
+//! against once the copy is complete.
+
+use crate::rocks::errors::typed_store_err_from_bcs_err;
+use crate::rocks::DBMap;
+use crate::util::be_fix_int_ser;
+use crate::{Map, TypedStoreError};
+use bincode::Options;
+use fastcrypto::hash::{HashFunction, Sha3_256};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Progress of copying a table.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableMigrationState {
+    /// Serialized key of the last copied entry, that copying resumes after.
+    pub last_key: Option<Vec<u8>>,
+    /// Number of copied entries.
+    pub num_entries: u64,
+    /// Order-independent checksum of the copied entries, see [`TableChecksum`].
+    pub checksum: TableChecksum,
+    /// Whether all entries have been copied.
+    pub complete: bool,
+}
+
+/// XOR of the SHA3-256 digests of the serialized key and value of every entry of a table. As keys
+/// are unique, this doesn't depend on the order entries are iterated in, which differs between
+/// storage engines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableChecksum(pub [u8; 32]);
+
+impl TableChecksum {
+    fn add(&mut self, key: &[u8], value: &[u8]) {
+        let mut hasher = Sha3_256::default();
+        hasher.update((key.len() as u64).to_be_bytes());
+        hasher.update(key);
+        hasher.update(value);
+        for (acc, byte) in self.0.iter_mut().zip(hasher.finalize().digest) {
+            *acc ^= byte;
+        }
+    }
+}
+
+/// Copies the entries of `source` into `target`, starting after `state.last_key`, in batches of
+/// `batch_size` entries. `on_batch` is called with the updated state after each batch has been
+/// written to `target`.
+pub fn migrate_table<K, V>(
+    source: &DBMap<K, V>,
+    target: &DBMap<K, V>,
+    state: &mut TableMigrationState,
+    batch_size: usize,
+    mut on_batch: impl FnMut(&TableMigrationState) -> Result<(), TypedStoreError>,
+) -> Result<(), TypedStoreError>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    if state.complete {
+        return Ok(());
+    }
+    let lower_bound = state
+        .last_key
+        .as_ref()
+        .map(|key| deserialize_key::<K>(key))
+        .transpose()?;
+
+    let mut batch = target.batch();
+    let mut batch_len = 0;
+    for entry in source.safe_iter_with_bounds(lower_bound, None) {
+        let (key, value) = entry?;
+        let key_bytes = be_fix_int_ser(&key);
+        // The lower bound is inclusive, and the entry at it has already been copied.
+        if state.last_key.as_ref() == Some(&key_bytes) {
+            continue;
+        }
+        let value_bytes = bcs::to_bytes(&value).map_err(typed_store_err_from_bcs_err)?;
+        batch.insert_batch(target, [(&key, &value)])?;
+        state.checksum.add(&key_bytes, &value_bytes);
+        state.num_entries += 1;
+        state.last_key = Some(key_bytes);
+        batch_len += 1;
+
+        if batch_len == batch_size {
+            batch.write()?;
+            on_batch(state)?;
+            batch = target.batch();
+            batch_len = 0;
+        }
+    }
+    batch.write()?;
+    state.complete = true;
+    on_batch(state)
+}
+
+/// Checks that `target` holds exactly the entries recorded in the completed `state`.
+pub fn verify_table<K, V>(
+    target: &DBMap<K, V>,
+    state: &TableMigrationState,
+) -> Result<(), TypedStoreError>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    let mut num_entries = 0;
+    let mut checksum = TableChecksum::default();
+    for entry in target.safe_iter() {
+        let (key, value) = entry?;
+        let value_bytes = bcs::to_bytes(&value).map_err(typed_store_err_from_bcs_err)?;
+        checksum.add(&be_fix_int_ser(&key), &value_bytes);
+        num_entries += 1;
+    }
+    if num_entries != state.num_entries || checksum != state.checksum {
+        return Err(TypedStoreError::MigrationError(format!(
+            "migrated table has {num_entries} entries with checksum {:?}, expected {} entries with checksum {:?}",
+            checksum, state.num_entries, state.checksum
+        )));
+    }
+    Ok(())
+}
+
+fn deserialize_key<K: DeserializeOwned>(key: &[u8]) -> Result<K, TypedStoreError> {
+    bincode::DefaultOptions::new()
+        .with_big_endian()
+        .with_fixint_encoding()
+        .deserialize(key)
+        .map_err(|e| TypedStoreError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate_table, verify_table, TableMigrationState};
+    use crate::rocks::{open_cf_opts, DBMap, MetricConf, ReadWriteOptions};
+    use crate::Map;
+
+    fn open_map(path: &std::path::Path) -> DBMap<u64, String> {
+        let db = open_cf_opts(
+            path,
+            None,
+            MetricConf::default(),
+            &[("table", Default::default())],
+        )
+        .unwrap();
+        DBMap::reopen(&db, Some("table"), &ReadWriteOptions::default(), false).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_table_resumes() {
+        let (source_dir, target_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let source = open_map(source_dir.path());
+        let target = open_map(target_dir.path());
+        source
+            .multi_insert((0..25u64).map(|i| (i, i.to_string())))
+            .unwrap();
+
+        // Interrupt the copy after its second batch.
+        let mut state = TableMigrationState::default();
+        let mut saved = None;
+        let mut batches = 0;
+        let result = migrate_table(&source, &target, &mut state, 10, |state| {
+            batches += 1;
+            saved = Some(state.clone());
+            if batches == 2 {
+                return Err(crate::TypedStoreError::MigrationError("interrupted".into()));
+            }
+            Ok(())
+        });
+        assert!(result.is_err());
+
+        let mut state = saved.unwrap();
+        assert_eq!(state.num_entries, 20);
+        assert!(!state.complete);
+        migrate_table(&source, &target, &mut state, 10, |_| Ok(())).unwrap();
+        assert_eq!(state.num_entries, 25);
+        assert!(state.complete);
+        verify_table(&target, &state).unwrap();
+
+        target.insert(&100, &"extra".to_string()).unwrap();
+        assert!(verify_table(&target, &state).is_err());
+    }
+}
@@ -157,6 +157,15 @@ impl Database {
         }
     }
 
+    /// Whether the database is backed by TideHunter rather than RocksDB or memory.
+    pub fn is_tidehunter(&self) -> bool {
+        match &self.storage {
+            #[cfg(tidehunter)]
+            Storage::TideHunter(_) => true,
+            _ => false,
+        }
+    }
+
     /// Flush all memtables to SST files on disk.
     pub fn flush(&self) -> Result<(), TypedStoreError> {
         match &self.storage {
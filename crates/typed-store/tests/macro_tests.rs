@@ -300,7 +300,7 @@ mod tidehunter_tests {
             ("table1".to_string(), ThConfig::new(11, 1, 1)),
             ("table2".to_string(), ThConfig::new(11, 1, 1)),
         ];
-        let db = ThTable::open_tables_read_write(
+        let db = ThTable::open_tables_tidehunter(
             primary_path.clone(),
             MetricConf::new("test_th"),
             BTreeMap::from_iter(configs),
@@ -310,4 +310,36 @@ mod tidehunter_tests {
         let result = db.table1.get(&key).unwrap();
         assert_eq!(result, Some(value));
     }
+
+    #[tokio::test]
+    async fn test_migrate_rocksdb_to_tidehunter() {
+        let source =
+            ThTable::open_tables_read_write(temp_dir(), MetricConf::new("test_rocks"), None, None);
+        // Keys are 3 characters long, to fit the 11 byte key size of the configs below.
+        source
+            .table1
+            .multi_insert((0..10).map(|i| (format!("k{i:02}"), i.to_string())))
+            .unwrap();
+        let configs = vec![
+            ("table1".to_string(), ThConfig::new(11, 1, 1)),
+            ("table2".to_string(), ThConfig::new(11, 1, 1)),
+        ];
+        let target = ThTable::open_tables_tidehunter(
+            temp_dir(),
+            MetricConf::new("test_th"),
+            BTreeMap::from_iter(configs),
+        );
+
+        let mut progress = BTreeMap::new();
+        source
+            .migrate_tables(&target, &mut progress, 3, |_| Ok(()))
+            .unwrap();
+        assert_eq!(progress["table1"].num_entries, 10);
+        assert_eq!(progress["table2"].num_entries, 0);
+        target.verify_tables(&progress).unwrap();
+        assert_eq!(
+            target.table1.get(&"k07".to_string()).unwrap(),
+            Some("7".to_string())
+        );
+    }
 }
@@ -15,9 +15,13 @@ use move_compiler::{
 };
 use move_coverage::coverage_map::{CoverageMap, output_map_to_file};
 use move_package::{BuildConfig, compilation::build_plan::BuildPlan};
-use move_unit_test::UnitTestingConfig;
+use move_unit_test::{UnitTestingConfig, test_reporter::TestReport, test_runner::Shard};
 use move_vm_test_utils::gas_schedule::CostTable;
-use std::{io::Write, path::Path, process::ExitStatus};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
 // if windows
 #[cfg(target_family = "windows")]
 use std::os::windows::process::ExitStatusExt;
@@ -28,6 +32,9 @@ use std::os::unix::prelude::ExitStatusExt;
 #[cfg(not(any(target_family = "windows", target_family = "unix")))]
 compile_error!("Unsupported OS, currently we only support windows and unix family");
 
+/// Where test results are cached with `--cache`, relative to the package root.
+pub const TEST_CACHE_PATH: &str = "build/test_cache.json";
+
 /// Run Move unit tests in this package.
 #[derive(Parser)]
 #[clap(name = "test")]
@@ -50,9 +57,26 @@ pub struct Test {
         long = "threads"
     )]
     pub num_threads: usize,
+    /// Only run the tests in shard `<index>/<count>` (e.g. `1/4`), to distribute tests across
+    /// machines. Tests are assigned to shards deterministically, after filtering.
+    #[clap(name = "shard", long = "shard", value_name = "INDEX/COUNT")]
+    pub shard: Option<Shard>,
+    /// Skip tests that passed in a previous run and whose bytecode, and that of their
+    /// dependencies, has not changed since. Results are cached under the package's `build`
+    /// directory.
+    #[clap(
+        name = "cache",
+        long = "cache",
+        conflicts_with_all = ["compute_coverage", "trace_execution"]
+    )]
+    pub cache: bool,
     /// Report test statistics at the end of testing. CSV report generated if 'csv' passed
     #[clap(name = "report-statistics", short = 's', long = "statistics")]
     pub report_statistics: Option<Option<String>>,
+    /// Write a machine-readable report of the test results to a file: `junit=<path>` for a
+    /// JUnit XML report or `json=<path>` for a JSON report. Can be repeated.
+    #[clap(name = "report", long = "report", value_name = "FORMAT=PATH")]
+    pub reports: Vec<TestReport>,
 
     /// Verbose mode
     #[clap(long = "verbose")]
@@ -110,7 +134,10 @@ impl Test {
             filter,
             list,
             num_threads,
+            shard,
+            cache,
             report_statistics,
+            reports,
             verbose_mode,
             compute_coverage: _,
             seed,
@@ -122,7 +149,11 @@ impl Test {
             filter,
             list,
             num_threads,
+            shard,
+            // relative to the package, see `run_move_unit_tests`
+            cache_path: cache.then(|| PathBuf::from(TEST_CACHE_PATH)),
             report_statistics,
+            reports,
             verbose: verbose_mode,
             seed,
             rand_num_iters,
@@ -153,6 +184,9 @@ pub fn run_move_unit_tests<W: Write + Send>(
     build_config.test_mode = true;
     build_config.dev_mode = true;
     build_config.save_disassembly = save_disassembly;
+    unit_test_config.cache_path = unit_test_config
+        .cache_path
+        .map(|cache_path| pkg_path.join(cache_path));
 
     // Build the resolution graph (resolution graph diagnostics are only needed for CLI commands so
     // ignore them by passing a vector as the writer)
@@ -23,6 +23,7 @@ pub mod public_mut_tx_context;
 pub mod public_random;
 pub mod self_transfer;
 pub mod share_owned;
+pub mod shared_object_contention;
 pub mod unnecessary_public_entry;
 
 pub const TRANSFER_MOD_NAME: &str = "transfer";
@@ -62,6 +63,9 @@ pub const VEC_MAP_STRUCT_NAME: &str = "VecMap";
 pub const VEC_SET_MOD_NAME: &str = "vec_set";
 pub const VEC_SET_STRUCT_NAME: &str = "VecSet";
 
+pub const DYNAMIC_FIELD_MOD_NAME: &str = "dynamic_field";
+pub const DYNAMIC_OBJECT_FIELD_MOD_NAME: &str = "dynamic_object_field";
+
 pub const SHARE_OWNED_FILTER_NAME: &str = "share_owned";
 pub const SELF_TRANSFER_FILTER_NAME: &str = "self_transfer";
 pub const CUSTOM_STATE_CHANGE_FILTER_NAME: &str = "custom_state_change";
@@ -73,6 +77,7 @@ pub const MISSING_KEY_FILTER_NAME: &str = "missing_key";
 pub const FREEZING_CAPABILITY_FILTER_NAME: &str = "freezing_capability";
 pub const PREFER_MUTABLE_TX_CONTEXT_FILTER_NAME: &str = "prefer_mut_tx_context";
 pub const UNNECESSARY_PUBLIC_ENTRY_FILTER_NAME: &str = "public_entry";
+pub const SHARED_OBJECT_CONTENTION_FILTER_NAME: &str = "shared_object_contention";
 
 pub const RANDOM_MOD_NAME: &str = "random";
 pub const RANDOM_STRUCT_NAME: &str = "Random";
@@ -93,6 +98,7 @@ pub enum LinterDiagnosticCode {
     FreezingCapability,
     PreferMutableTxContext,
     UnnecessaryPublicEntry,
+    SharedObjectContention,
 }
 
 pub fn known_filters() -> (Option<Symbol>, Vec<WarningFilter>) {
@@ -164,6 +170,12 @@ pub fn known_filters() -> (Option<Symbol>, Vec<WarningFilter>) {
             LinterDiagnosticCode::UnnecessaryPublicEntry as u8,
             Some(UNNECESSARY_PUBLIC_ENTRY_FILTER_NAME),
         ),
+        WarningFilter::code(
+            Some(LINT_WARNING_PREFIX),
+            LinterDiagnosticCategory::Sui as u8,
+            LinterDiagnosticCode::SharedObjectContention as u8,
+            Some(SHARED_OBJECT_CONTENTION_FILTER_NAME),
+        ),
     ];
 
     (Some(ALLOW_ATTR_CATEGORY.into()), filters)
@@ -188,6 +200,7 @@ pub fn linter_visitors(level: LintLevel) -> Vec<Visitor> {
             visitors.extend([
                 freezing_capability::WarnFreezeCapability.visitor(),
                 public_mut_tx_context::PreferMutableTxContext.visitor(),
+                shared_object_contention::SharedObjectContention.visitor(),
             ]);
             visitors
         }
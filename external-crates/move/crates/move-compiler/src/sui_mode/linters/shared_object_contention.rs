@@ -0,0 +1,439 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This analysis flags function designs that make transactions contend on shared objects, which
+//! is what triggers congestion control on those objects under load. Only types shared in the
+//! program with `sui::transfer::share_object` or `sui::transfer::public_share_object` are
+//! considered shared. The analysis flags `public` and `entry` functions that:
+//! - take a shared object by mutable reference but only read it, as transactions writing to a
+//!   shared object are sequenced against all other transactions using it;
+//! - unconditionally increment or decrement a field of a shared object (a global counter), so
+//!   that every call writes to the same object;
+//! - access dynamic fields (or collections built on them) of a shared object in a loop, making
+//!   transactions on the object long-running.
+
+use crate::{
+    diag,
+    diagnostics::{
+        Diagnostic, DiagnosticReporter, Diagnostics,
+        codes::{DiagnosticInfo, Severity, custom},
+        warning_filters::WarningFilters,
+    },
+    expansion::ast::{ModuleIdent, Visibility},
+    naming::ast::{self as N, Var},
+    parser::ast::{self as P, DatatypeName, FunctionName},
+    shared::{CompilationEnv, Identifier},
+    sui_mode::{
+        SUI_ADDR_VALUE,
+        linters::{
+            BAG_MOD_NAME, DYNAMIC_FIELD_MOD_NAME, DYNAMIC_OBJECT_FIELD_MOD_NAME,
+            LINKED_TABLE_MOD_NAME, LINT_WARNING_PREFIX, LinterDiagnosticCategory,
+            LinterDiagnosticCode, OBJECT_BAG_MOD_NAME, OBJECT_TABLE_MOD_NAME, PUBLIC_SHARE_FUN,
+            SHARE_FUN, TABLE_MOD_NAME, TABLE_VEC_MOD_NAME, TRANSFER_MOD_NAME,
+        },
+    },
+    typing::{
+        ast as T, core,
+        visitor::{TypingVisitorConstructor, TypingVisitorContext},
+    },
+};
+use move_core_types::account_address::AccountAddress;
+use move_ir_types::location::*;
+use move_symbol_pool::Symbol;
+use std::collections::BTreeSet;
+
+const SHARED_OBJECT_CONTENTION_DIAG: DiagnosticInfo = custom(
+    LINT_WARNING_PREFIX,
+    Severity::Warning,
+    LinterDiagnosticCategory::Sui as u8,
+    LinterDiagnosticCode::SharedObjectContention as u8,
+    "possible shared object contention",
+);
+
+const SHARE_FUNCTIONS: &[(AccountAddress, &str, &str)] = &[
+    (SUI_ADDR_VALUE, TRANSFER_MOD_NAME, SHARE_FUN),
+    (SUI_ADDR_VALUE, TRANSFER_MOD_NAME, PUBLIC_SHARE_FUN),
+];
+
+/// Modules whose functions access dynamic fields of the object passed to them.
+const DYNAMIC_FIELD_MODULES: &[(AccountAddress, &str)] = &[
+    (SUI_ADDR_VALUE, DYNAMIC_FIELD_MOD_NAME),
+    (SUI_ADDR_VALUE, DYNAMIC_OBJECT_FIELD_MOD_NAME),
+    (SUI_ADDR_VALUE, TABLE_MOD_NAME),
+    (SUI_ADDR_VALUE, OBJECT_TABLE_MOD_NAME),
+    (SUI_ADDR_VALUE, LINKED_TABLE_MOD_NAME),
+    (SUI_ADDR_VALUE, TABLE_VEC_MOD_NAME),
+    (SUI_ADDR_VALUE, BAG_MOD_NAME),
+    (SUI_ADDR_VALUE, OBJECT_BAG_MOD_NAME),
+];
+
+/// Types shared somewhere in the program.
+type SharedTypes = BTreeSet<(ModuleIdent, DatatypeName)>;
+
+pub struct SharedObjectContention;
+
+pub struct Context<'a> {
+    #[allow(unused)]
+    env: &'a CompilationEnv,
+    reporter: DiagnosticReporter<'a>,
+    shared_types: SharedTypes,
+}
+
+/// A parameter of a function taking a shared object by mutable reference.
+struct SharedParam {
+    var: Var,
+    /// Location of the parameter type
+    ty_loc: Loc,
+    /// Name of the shared type, for diagnostics
+    ty_name: String,
+}
+
+/// Collects the types passed to the share functions.
+struct SharedTypesCollector(SharedTypes);
+
+/// Tracks how the shared parameters of a function are used in its body.
+struct ParamUses<'p> {
+    params: &'p [SharedParam],
+    /// Whether each parameter is used mutably
+    mutated: Vec<bool>,
+    /// Location of the outermost loop being visited, if any
+    current_loop: Option<Loc>,
+    /// Loops accessing dynamic fields of a parameter: loop location, call location, parameter
+    dynamic_field_loops: Vec<(Loc, Loc, usize)>,
+}
+
+impl TypingVisitorConstructor for SharedObjectContention {
+    type Context<'a> = Context<'a>;
+
+    fn context<'a>(env: &'a CompilationEnv, program: &T::Program) -> Self::Context<'a> {
+        let reporter = env.diagnostic_reporter_at_top_level();
+        let mut collector = SharedTypesCollector(SharedTypes::new());
+        collector.visit(program);
+        Context {
+            env,
+            reporter,
+            shared_types: collector.0,
+        }
+    }
+}
+
+impl Context<'_> {
+    fn add_diag(&self, diag: Diagnostic) {
+        self.reporter.add_diag(diag);
+    }
+
+    #[allow(unused)]
+    fn add_diags(&self, diags: Diagnostics) {
+        self.reporter.add_diags(diags);
+    }
+
+    /// Returns the parameter if it takes a shared object by mutable reference.
+    fn shared_param(&self, var: Var, ty: &N::Type) -> Option<SharedParam> {
+        let N::Type_::Ref(true, inner) = &ty.value else {
+            return None;
+        };
+        let sp!(_, N::TypeName_::ModuleType(mident, sname)) = inner.value.type_name()? else {
+            return None;
+        };
+        self.shared_types
+            .contains(&(*mident, *sname))
+            .then(|| SharedParam {
+                var,
+                ty_loc: ty.loc,
+                ty_name: core::error_format_(&inner.value, &core::Subst::empty()),
+            })
+    }
+}
+
+impl TypingVisitorContext for Context<'_> {
+    fn visit_module_custom(&mut self, ident: ModuleIdent, mdef: &T::ModuleDefinition) -> bool {
+        // skips if true
+        mdef.attributes.is_test_or_test_only() || ident.value.address.is(&SUI_ADDR_VALUE)
+    }
+
+    fn visit_function_custom(
+        &mut self,
+        _module: ModuleIdent,
+        fname: FunctionName,
+        fdef: &T::Function,
+    ) -> bool {
+        if fdef.attributes.is_test_or_test_only()
+            || (fdef.entry.is_none() && !matches!(fdef.visibility, Visibility::Public(_)))
+        {
+            return true;
+        }
+        let T::FunctionBody_::Defined(seq) = &fdef.body.value else {
+            return true;
+        };
+        let params = fdef
+            .signature
+            .parameters
+            .iter()
+            .filter_map(|(_, var, ty)| self.shared_param(*var, ty))
+            .collect::<Vec<_>>();
+        if params.is_empty() {
+            return true;
+        }
+
+        let mut uses = ParamUses::new(&params);
+        uses.visit_seq(fdef.body.loc, seq);
+        for (param, mutated) in params.iter().zip(&uses.mutated) {
+            if !mutated {
+                let msg = format!(
+                    "Function '{fname}' takes shared object {} by mutable reference \
+                    but only reads it",
+                    param.ty_name
+                );
+                let mut d = diag!(SHARED_OBJECT_CONTENTION_DIAG, (param.ty_loc, msg));
+                d.add_note(
+                    "Transactions taking a shared object by mutable reference are ordered \
+                    against every other transaction writing to it, even if they do not modify it",
+                );
+                d.add_note(
+                    "Suggested fix: take the object by immutable reference ('&') so that \
+                    transactions only reading it do not contend with each other",
+                );
+                self.add_diag(d);
+            }
+        }
+        check_counters(self, fname, &params, seq);
+        for (_, call_loc, idx) in &uses.dynamic_field_loops {
+            let msg = format!(
+                "Dynamic fields of shared object {} are accessed in a loop",
+                params[*idx].ty_name
+            );
+            let mut d = diag!(SHARED_OBJECT_CONTENTION_DIAG, (*call_loc, msg));
+            d.add_note(
+                "Each dynamic field accessed adds an object to the transaction, making \
+                transactions on the shared object long-running and more likely to be \
+                deferred by congestion control",
+            );
+            d.add_note(
+                "Suggested fix: bound the number of iterations, or split the work across \
+                transactions, e.g. by keeping a cursor in the object",
+            );
+            self.add_diag(d);
+        }
+        true
+    }
+
+    fn push_warning_filter_scope(&mut self, filters: WarningFilters) {
+        self.reporter.push_warning_filter_scope(filters)
+    }
+
+    fn pop_warning_filter_scope(&mut self) {
+        self.reporter.pop_warning_filter_scope()
+    }
+}
+
+impl TypingVisitorContext for SharedTypesCollector {
+    fn visit_exp_custom(&mut self, exp: &T::Exp) -> bool {
+        if let T::UnannotatedExp_::ModuleCall(fun) = &exp.exp.value {
+            if SHARE_FUNCTIONS.iter().any(|(addr, module, fname)| {
+                fun.module.value.is(addr, *module) && &fun.name.value().as_str() == fname
+            }) {
+                if let Some(sp!(_, N::TypeName_::ModuleType(mident, sname))) = fun
+                    .type_arguments
+                    .first()
+                    .and_then(|ty| ty.value.type_name())
+                {
+                    self.0.insert((*mident, *sname));
+                }
+            }
+        }
+        // always return false to process arguments of the call
+        false
+    }
+
+    fn push_warning_filter_scope(&mut self, _filters: WarningFilters) {}
+
+    fn pop_warning_filter_scope(&mut self) {}
+}
+
+impl<'p> ParamUses<'p> {
+    fn new(params: &'p [SharedParam]) -> Self {
+        Self {
+            params,
+            mutated: vec![false; params.len()],
+            current_loop: None,
+            dynamic_field_loops: vec![],
+        }
+    }
+
+    /// Returns the index of the parameter the expression is a (field of a) reference to.
+    fn param_index(&self, exp: &T::Exp) -> Option<usize> {
+        let (var, _) = field_path(exp)?;
+        self.params.iter().position(|p| p.var.value == var.value)
+    }
+
+    fn visit_loop(&mut self, loop_loc: Loc, body: &T::Exp) {
+        let outer = self.current_loop;
+        if outer.is_none() {
+            self.current_loop = Some(loop_loc);
+        }
+        self.visit_exp(body);
+        self.current_loop = outer;
+    }
+}
+
+impl TypingVisitorContext for ParamUses<'_> {
+    fn visit_exp_custom(&mut self, exp: &T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        match &exp.exp.value {
+            // a use of the reference other than the ones below, e.g. passed to a function
+            // taking a mutable reference or bound to a local
+            E::Copy { var, .. } | E::Move { var, .. } | E::Use(var) => {
+                if let Some(idx) = self.params.iter().position(|p| p.var.value == var.value) {
+                    self.mutated[idx] = true;
+                }
+                true
+            }
+            E::Borrow(false, base, _) | E::Dereference(base)
+                if self.param_index(base).is_some() =>
+            {
+                true
+            }
+            E::Builtin(b, base)
+                if matches!(b.value, T::BuiltinFunction_::Freeze(_))
+                    && self.param_index(base).is_some() =>
+            {
+                true
+            }
+            E::ModuleCall(fun) => {
+                let args = call_arguments(&fun.arguments);
+                if let Some(loop_loc) = self.current_loop {
+                    let accesses_dynamic_fields = DYNAMIC_FIELD_MODULES
+                        .iter()
+                        .any(|(addr, module)| fun.module.value.is(addr, *module));
+                    let reported = self
+                        .dynamic_field_loops
+                        .iter()
+                        .any(|(loc, _, _)| *loc == loop_loc);
+                    if accesses_dynamic_fields && !reported {
+                        if let Some(idx) = args.iter().find_map(|arg| self.param_index(arg)) {
+                            self.dynamic_field_loops.push((loop_loc, exp.exp.loc, idx));
+                        }
+                    }
+                }
+                for (i, arg) in args.into_iter().enumerate() {
+                    let immutable_param = fun
+                        .parameter_types
+                        .get(i)
+                        .is_some_and(|ty| matches!(ty.value, N::Type_::Ref(false, _)));
+                    if immutable_param && self.param_index(arg).is_some() {
+                        continue;
+                    }
+                    self.visit_exp(arg);
+                }
+                true
+            }
+            E::While(_, cond, body) => {
+                self.visit_exp(cond);
+                self.visit_loop(exp.exp.loc, body);
+                true
+            }
+            E::Loop { body, .. } => {
+                self.visit_loop(exp.exp.loc, body);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn push_warning_filter_scope(&mut self, _filters: WarningFilters) {}
+
+    fn pop_warning_filter_scope(&mut self) {}
+}
+
+/// Flags fields of shared parameters incremented or decremented by a constant in the top-level
+/// sequence of the function body, i.e. on every call.
+fn check_counters(
+    context: &mut Context,
+    fname: FunctionName,
+    params: &[SharedParam],
+    (_, seq): &T::Sequence,
+) {
+    use T::UnannotatedExp_ as E;
+    for item in seq {
+        let T::SequenceItem_::Seq(e) = &item.value else {
+            continue;
+        };
+        let E::Mutate(lhs, rhs) = &e.exp.value else {
+            continue;
+        };
+        let Some((var, path)) = field_path(lhs) else {
+            continue;
+        };
+        let Some(param) = params.iter().find(|p| p.var.value == var.value) else {
+            continue;
+        };
+        if path.is_empty() || !is_counter_update(rhs, var, &path) {
+            continue;
+        }
+        let field = path
+            .iter()
+            .map(|f| f.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        let msg = format!(
+            "Field '{field}' of shared object {} is updated on every call to '{fname}'",
+            param.ty_name
+        );
+        let mut d = diag!(SHARED_OBJECT_CONTENTION_DIAG, (e.exp.loc, msg));
+        d.add_note(
+            "A counter in a shared object makes every transaction calling this function \
+            write to the same object, so that they cannot execute in parallel",
+        );
+        d.add_note(
+            "Suggested fix: shard the counter across several objects, keep it in objects \
+            owned by each user, or compute it off-chain from events",
+        );
+        context.add_diag(d);
+    }
+}
+
+/// Checks if `rhs` is the field at `path` of `var` plus or minus a constant.
+fn is_counter_update(rhs: &T::Exp, var: Var, path: &[Symbol]) -> bool {
+    use T::UnannotatedExp_ as E;
+    let E::BinopExp(lhs_op, op, _, rhs_op) = &rhs.exp.value else {
+        return false;
+    };
+    if !matches!(op.value, P::BinOp_::Add | P::BinOp_::Sub) {
+        return false;
+    }
+    let is_field = |e: &T::Exp| {
+        matches!(&e.exp.value, E::Dereference(base)
+            if field_path(base).is_some_and(|(v, p)| v.value == var.value && p == path))
+    };
+    let is_constant = |e: &T::Exp| matches!(&e.exp.value, E::Value(_) | E::Constant(_, _));
+    (is_field(lhs_op) && is_constant(rhs_op)) || (is_constant(lhs_op) && is_field(rhs_op))
+}
+
+/// Returns the local and the fields borrowed from it, if the expression is a (field) borrow of a
+/// local.
+fn field_path(exp: &T::Exp) -> Option<(Var, Vec<Symbol>)> {
+    use T::UnannotatedExp_ as E;
+    match &exp.exp.value {
+        E::Copy { var, .. } | E::Move { var, .. } | E::Use(var) => Some((*var, vec![])),
+        E::Borrow(_, base, field) => {
+            let (var, mut path) = field_path(base)?;
+            path.push(field.value());
+            Some((var, path))
+        }
+        _ => None,
+    }
+}
+
+fn call_arguments(arguments: &T::Exp) -> Vec<&T::Exp> {
+    use T::UnannotatedExp_ as E;
+    match &arguments.exp.value {
+        E::Unit { .. } => vec![],
+        E::ExpList(items) => items
+            .iter()
+            .map(|item| match item {
+                T::ExpListItem::Single(e, _) | T::ExpListItem::Splat(_, e, _) => e,
+            })
+            .collect(),
+        _ => vec![arguments],
+    }
+}
@@ -0,0 +1,119 @@
+// tests the lint for function designs causing contention on shared objects
+module a::pool {
+    use sui::dynamic_field;
+    use sui::object::{Self, UID};
+    use sui::transfer;
+    use sui::tx_context::TxContext;
+
+    struct Pool has key {
+        id: UID,
+        balance: u64,
+        swaps: u64,
+    }
+
+    struct Config has key {
+        id: UID,
+        fee: u64,
+    }
+
+    public fun create(ctx: &mut TxContext) {
+        transfer::share_object(Pool { id: object::new(ctx), balance: 0, swaps: 0 });
+    }
+
+    public fun new_config(ctx: &mut TxContext): Config {
+        Config { id: object::new(ctx), fee: 0 }
+    }
+
+    // should warn: only reads the shared object
+    public fun balance(pool: &mut Pool): u64 {
+        pool.balance
+    }
+
+    public fun balance_imm(pool: &Pool): u64 {
+        pool.balance
+    }
+
+    // should warn: only passes the shared object as an immutable reference
+    public fun balance_through_call(pool: &mut Pool): u64 {
+        balance_imm(pool)
+    }
+
+    public fun deposit(pool: &mut Pool, amount: u64) {
+        pool.balance = pool.balance + amount;
+    }
+
+    public fun deposit_through_call(pool: &mut Pool, amount: u64) {
+        deposit(pool, amount)
+    }
+
+    // should warn: counter updated on every call
+    public fun swap(pool: &mut Pool, amount: u64) {
+        pool.balance = pool.balance - amount;
+        pool.swaps = pool.swaps + 1;
+    }
+
+    public fun maybe_count(pool: &mut Pool, count: bool) {
+        if (count) pool.swaps = pool.swaps + 1
+    }
+
+    // should warn: dynamic fields accessed in a loop
+    public fun reset(pool: &mut Pool, n: u64) {
+        let i = 0;
+        while (i < n) {
+            *dynamic_field::borrow_mut<u64, u64>(&mut pool.id, i) = 0;
+            i = i + 1;
+        }
+    }
+
+    // not shared
+    public fun fee(config: &mut Config): u64 {
+        config.fee
+    }
+
+    fun private_balance(pool: &mut Pool): u64 {
+        pool.balance
+    }
+
+    public fun total(pool: &mut Pool): u64 {
+        pool.swaps = 0;
+        private_balance(pool)
+    }
+
+    #[allow(lint(shared_object_contention))]
+    public fun suppressed(pool: &mut Pool): u64 {
+        pool.balance
+    }
+}
+
+module sui::object {
+    use sui::tx_context::TxContext;
+
+    struct UID has store {
+        id: address,
+    }
+
+    public fun new(_: &mut TxContext): UID {
+        abort 0
+    }
+}
+
+module sui::tx_context {
+    struct TxContext has drop {}
+}
+
+module sui::transfer {
+    public fun share_object<T: key>(_: T) {
+        abort 0
+    }
+}
+
+module sui::dynamic_field {
+    use sui::object::UID;
+
+    public fun borrow_mut<Name: copy + drop + store, Value: store>(
+        _: &mut UID,
+        _: Name,
+    ): &mut Value {
+        abort 0
+    }
+}
@@ -4,8 +4,10 @@
 
 //! This module manages native extensions supported by the unit testing framework.
 //! Such extensions are enabled by cfg features and must be compiled into the test
-//! to be usable.
+//! to be usable. It also manages the hook used to load modules that tests need but that are
+//! not part of the package under test or its dependencies.
 
+use move_core_types::language_storage::ModuleId;
 use move_vm_runtime::native_extensions::NativeContextExtensions;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
@@ -30,6 +32,27 @@ pub fn set_extension_hook(p: Box<dyn Fn(&mut NativeContextExtensions<'_>) + Send
     *EXTENSION_HOOK.lock().unwrap() = Some(p)
 }
 
+static MODULE_FETCH_HOOK: Lazy<
+    Mutex<Option<Box<dyn Fn(&ModuleId) -> Result<Option<Vec<u8>>, String> + Send + Sync>>>,
+> = Lazy::new(|| Mutex::new(None));
+
+/// Sets a hook which is called to load modules that are not part of the package under test or
+/// its dependencies, e.g. to fetch them from a network that tests run against. The hook is
+/// called on the thread running the test that needs the module.
+pub fn set_module_fetch_hook(
+    p: Box<dyn Fn(&ModuleId) -> Result<Option<Vec<u8>>, String> + Send + Sync>,
+) {
+    *MODULE_FETCH_HOOK.lock().unwrap() = Some(p)
+}
+
+/// Load module `id` with the module fetch hook, if one is set.
+pub(crate) fn fetch_module(id: &ModuleId) -> Result<Option<Vec<u8>>, String> {
+    match &*MODULE_FETCH_HOOK.lock().unwrap() {
+        Some(h) => (*h)(id),
+        None => Ok(None),
+    }
+}
+
 /// Create all available native context extensions.
 #[allow(unused_mut, clippy::let_and_return)]
 pub(crate) fn new_extensions<'a>() -> NativeContextExtensions<'a> {
@@ -42,8 +65,11 @@ pub(crate) fn new_extensions<'a>() -> NativeContextExtensions<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::extensions::{new_extensions, set_extension_hook};
+    use crate::extensions::{
+        fetch_module, new_extensions, set_extension_hook, set_module_fetch_hook,
+    };
     use better_any::{Tid, TidAble};
+    use move_core_types::{account_address::AccountAddress, ident_str, language_storage::ModuleId};
     use move_vm_runtime::native_extensions::{NativeContextExtensions, NativeExtensionMarker};
 
     /// A test that extension hooks work as expected.
@@ -54,6 +80,19 @@ mod tests {
         let _e = ext.get::<TestExtension>();
     }
 
+    /// A test that module fetch hooks work as expected.
+    #[test]
+    fn test_module_fetch_hook() {
+        let fetched = ModuleId::new(AccountAddress::ONE, ident_str!("fetched").to_owned());
+        let missing = ModuleId::new(AccountAddress::ONE, ident_str!("missing").to_owned());
+        assert_eq!(fetch_module(&fetched), Ok(None));
+        set_module_fetch_hook(Box::new(|id| {
+            Ok((id.name().as_str() == "fetched").then(|| vec![1, 2, 3]))
+        }));
+        assert_eq!(fetch_module(&fetched), Ok(Some(vec![1, 2, 3])));
+        assert_eq!(fetch_module(&missing), Ok(None));
+    }
+
     #[derive(Tid)]
     struct TestExtension();
     impl NativeExtensionMarker<'_> for TestExtension {}
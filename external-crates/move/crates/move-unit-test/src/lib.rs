@@ -4,10 +4,15 @@
 
 pub mod cargo_runner;
 pub mod extensions;
+pub mod test_cache;
 pub mod test_reporter;
 pub mod test_runner;
 
-use crate::test_runner::TestRunner;
+use crate::{
+    test_cache::TestCache,
+    test_reporter::TestReport,
+    test_runner::{Shard, TestRunner},
+};
 use anyhow::{Result, bail};
 use clap::*;
 use move_binary_format::CompiledModule;
@@ -22,7 +27,7 @@ use move_compiler::{
 use move_core_types::language_storage::ModuleId;
 use move_vm_runtime::native_functions::NativeFunctionTable;
 use move_vm_test_utils::gas_schedule::CostTable;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex};
+use std::{collections::BTreeMap, io::Write, marker::Send, path::PathBuf, sync::Mutex};
 
 /// The default value bounding the amount of gas consumed in a test.
 const DEFAULT_EXECUTION_BOUND: u64 = 1_000_000;
@@ -58,6 +63,25 @@ pub struct UnitTestingConfig {
     )]
     pub num_threads: usize,
 
+    /// Only run the tests in shard `<index>/<count>` (e.g. `1/4`), to distribute tests across
+    /// machines. Tests are assigned to shards deterministically, after filtering.
+    #[clap(long = "shard", value_name = "INDEX/COUNT")]
+    pub shard: Option<Shard>,
+
+    /// Skip tests that passed in a previous run and whose bytecode, and that of their
+    /// dependencies, has not changed since, keeping track of results in the file at this path.
+    #[clap(
+        long = "cache",
+        value_name = "PATH",
+        conflicts_with = "trace_execution"
+    )]
+    pub cache_path: Option<PathBuf>,
+
+    /// State that the results of tests depend on besides their bytecode (e.g. the networks that
+    /// tests fork), included in the cache key of every test.
+    #[clap(skip)]
+    pub cache_context: Option<String>,
+
     /// Dependency files
     #[clap(
         name = "dependencies",
@@ -81,6 +105,11 @@ pub struct UnitTestingConfig {
     #[clap(name = "report-statistics", short = 's', long = "statistics")]
     pub report_statistics: Option<Option<String>>,
 
+    /// Write a machine-readable report of the test results to a file: `junit=<path>` for a
+    /// JUnit XML report or `json=<path>` for a JSON report. Can be repeated.
+    #[clap(name = "report", long = "report", value_name = "FORMAT=PATH")]
+    pub reports: Vec<TestReport>,
+
     #[clap(
         name = "report_stacktrace_on_abort",
         short = 'r',
@@ -145,7 +174,11 @@ impl UnitTestingConfig {
             gas_limit: bound.or(Some(DEFAULT_EXECUTION_BOUND)),
             filter: None,
             num_threads: 8,
+            shard: None,
+            cache_path: None,
+            cache_context: None,
             report_statistics: None,
+            reports: vec![],
             report_stacktrace_on_abort: false,
             source_files: vec![],
             dep_files: vec![],
@@ -290,11 +323,24 @@ impl UnitTestingConfig {
         if let Some(filter_str) = &self.filter {
             test_runner.filter(filter_str)?;
         }
+        if let Some(shard) = self.shard {
+            test_runner.shard(shard);
+        }
+        let mut cache = self.cache_path.as_deref().map(TestCache::load);
+        let cache_keys = cache
+            .as_ref()
+            .map(|cache| test_runner.skip_cached(cache, self.cache_context.as_deref()))
+            .unwrap_or_default();
 
         let test_results = test_runner.run(&shared_writer).unwrap();
+        if let Some(cache) = &mut cache {
+            test_results.update_cache(cache, &cache_keys);
+            cache.save()?;
+        }
         if let Some(report_type) = &self.report_statistics {
             test_results.report_statistics(&shared_writer, report_type)?;
         }
+        test_results.write_reports(&self.reports)?;
 
         let ok = test_results.summarize(&shared_writer)?;
 
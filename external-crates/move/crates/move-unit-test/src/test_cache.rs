@@ -0,0 +1,395 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Caching of test results across runs. A test that passed is skipped by later runs for as long
+//! as its cache key is unchanged. The key covers the test's attributes, the gas limit, the
+//! compiled bytecode of the test's module and of every module it transitively depends on, and any
+//! other state the results of tests depend on that the client provides as a context (e.g. the
+//! networks that tests fork).
+//!
+//! Tests with generated arguments (`#[random_test]`) are never cached, since a pass with some
+//! arguments says nothing about others. The key does not cover the implementation of native
+//! functions, so the cache should be removed when those change (e.g. on a toolchain upgrade).
+
+use crate::format_module_id;
+use anyhow::{Context, Result};
+use move_binary_format::CompiledModule;
+use move_compiler::unit_test::{TestArgument, TestPlan};
+use move_core_types::language_storage::ModuleId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Version of the cache format, also part of every cache key: bumping it invalidates all
+/// existing caches.
+const CACHE_VERSION: u64 = 1;
+
+/// The tests that passed in previous runs, stored as JSON at `path`.
+#[derive(Debug)]
+pub struct TestCache {
+    path: PathBuf,
+    /// Cache keys of the tests that passed, by fully qualified test name.
+    passed: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u64,
+    passed: BTreeMap<String, String>,
+}
+
+impl TestCache {
+    /// Load the cache at `path`. A cache that is missing, unreadable, or in an older format is
+    /// treated as empty.
+    pub fn load(path: &Path) -> Self {
+        let passed = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| file.passed)
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            passed,
+        }
+    }
+
+    /// Write the cache back to where it was loaded from.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            passed: self.passed.clone(),
+        };
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&file)?)
+            .with_context(|| format!("Failed to write test cache to {}", self.path.display()))
+    }
+
+    pub(crate) fn is_cached_pass(&self, test_name: &str, key: &str) -> bool {
+        self.passed
+            .get(test_name)
+            .is_some_and(|cached| cached == key)
+    }
+
+    pub(crate) fn record(&mut self, test_name: String, key: String, passed: bool) {
+        if passed {
+            self.passed.insert(test_name, key);
+        } else {
+            self.passed.remove(&test_name);
+        }
+    }
+}
+
+/// The cache keys of the tests in `test_plan` that can be cached, by fully qualified test name,
+/// when run in `context`.
+pub(crate) fn cache_keys(
+    test_plan: &TestPlan,
+    execution_bound: u64,
+    context: Option<&str>,
+) -> BTreeMap<String, String> {
+    let modules: BTreeMap<ModuleId, &CompiledModule> = test_plan
+        .module_info
+        .values()
+        .map(|info| &info.module)
+        .chain(&test_plan.bytecode_deps_modules)
+        .map(|module| (module.self_id(), module))
+        .collect();
+    let mut module_digests = BTreeMap::new();
+
+    let mut keys = BTreeMap::new();
+    for (module_id, module_tests) in &test_plan.module_tests {
+        // The digests of the test module and its transitive dependencies are the same for all of
+        // its tests.
+        let mut dependencies = BTreeSet::new();
+        let mut stack = vec![module_id.clone()];
+        while let Some(id) = stack.pop() {
+            if !dependencies.insert(id.clone()) {
+                continue;
+            }
+            if let Some(module) = modules.get(&id) {
+                stack.extend(module.immediate_dependencies());
+            }
+        }
+        let mut module_hasher = Sha256::new();
+        for id in &dependencies {
+            let Some(module) = modules.get(id) else {
+                continue;
+            };
+            let digest = module_digests
+                .entry(id.clone())
+                .or_insert_with(|| module_digest(module));
+            module_hasher.update(id.to_canonical_string(/* with_prefix */ true));
+            module_hasher.update(*digest);
+        }
+        let modules_digest = module_hasher.finalize();
+
+        let module_name = format_module_id(&test_plan.module_info, module_id);
+        for (test_name, test_case) in &module_tests.tests {
+            if test_case
+                .arguments
+                .iter()
+                .any(|arg| matches!(arg, TestArgument::Generate { .. }))
+            {
+                continue;
+            }
+            let mut hasher = Sha256::new();
+            hasher.update(CACHE_VERSION.to_le_bytes());
+            hasher.update(execution_bound.to_le_bytes());
+            if let Some(context) = context {
+                hasher.update((context.len() as u64).to_le_bytes());
+                hasher.update(context);
+            }
+            // The test's attributes (e.g. `#[expected_failure]`) are not part of the bytecode.
+            hasher.update(format!("{test_case:?}"));
+            hasher.update(modules_digest);
+            keys.insert(
+                format!("{module_name}::{test_name}"),
+                hex::encode(hasher.finalize()),
+            );
+        }
+    }
+    keys
+}
+
+fn module_digest(module: &CompiledModule) -> [u8; 32] {
+    let mut bytes = vec![];
+    module
+        .serialize_with_version(module.version, &mut bytes)
+        .expect("Compiled modules can be serialized");
+    Sha256::digest(&bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_runner::{Shard, TestRunner};
+    use move_binary_format::file_format::{
+        Constant, IdentifierIndex, ModuleHandle, SignatureToken, empty_module,
+    };
+    use move_bytecode_source_map::source_map::SourceMap;
+    use move_command_line_common::files::FileHash;
+    use move_compiler::{
+        compiled_unit::NamedCompiledModule,
+        shared::{NumberFormat, NumericalAddress, files::MappedFiles},
+        unit_test::{ModuleTestPlan, TestCase},
+    };
+    use move_core_types::{
+        account_address::AccountAddress, identifier::Identifier, language_storage::TypeTag,
+    };
+    use move_ir_types::{
+        ast::{ModuleIdent, ModuleName},
+        location::Loc,
+    };
+    use move_symbol_pool::Symbol;
+    use std::str::FromStr;
+
+    const BOUND: u64 = 1000;
+
+    // Module `0x0::<name>`, depending on the modules `deps` at the same address.
+    fn module(name: &str, deps: &[&str]) -> CompiledModule {
+        let mut module = empty_module();
+        module.identifiers[0] = Identifier::new(name).unwrap();
+        for dep in deps {
+            module.module_handles.push(ModuleHandle {
+                address: module.module_handles[0].address,
+                name: IdentifierIndex(module.identifiers.len() as u16),
+            });
+            module.identifiers.push(Identifier::new(*dep).unwrap());
+        }
+        module
+    }
+
+    fn named(module: CompiledModule) -> NamedCompiledModule {
+        let name = Symbol::from(module.self_id().name().as_str());
+        NamedCompiledModule {
+            package_name: None,
+            address: NumericalAddress::new(AccountAddress::ZERO.into_bytes(), NumberFormat::Hex),
+            address_name: None,
+            name,
+            module,
+            source_map: SourceMap::new(
+                Loc::new(FileHash::empty(), 0, 0),
+                ModuleIdent::new(ModuleName(name), AccountAddress::ZERO),
+            ),
+        }
+    }
+
+    fn test_case(name: &str, arguments: Vec<TestArgument>) -> (String, TestCase) {
+        let test_case = TestCase {
+            test_name: name.to_string(),
+            arguments,
+            expected_failure: None,
+        };
+        (name.to_string(), test_case)
+    }
+
+    // Tests in module `A`, which depends on `B`, and in module `C`, which depends on nothing.
+    // `changed_dependency` changes the bytecode of `B`.
+    fn test_plan(changed_dependency: bool) -> TestPlan {
+        let mut b = module("B", &[]);
+        if changed_dependency {
+            b.constant_pool.push(Constant {
+                type_: SignatureToken::U64,
+                data: bcs::to_bytes(&1u64).unwrap(),
+            });
+        }
+        let modules = [module("A", &["B"]), b, module("C", &[])];
+        let module_info: BTreeMap<_, _> = modules
+            .into_iter()
+            .map(|module| (module.self_id(), named(module)))
+            .collect();
+        let id = |name: &str| ModuleId::new(AccountAddress::ZERO, Identifier::new(name).unwrap());
+        let module_tests = BTreeMap::from([
+            (
+                id("A"),
+                ModuleTestPlan {
+                    module_id: id("A"),
+                    tests: BTreeMap::from([
+                        test_case(
+                            "random",
+                            vec![TestArgument::Generate {
+                                generated_type: TypeTag::U64,
+                            }],
+                        ),
+                        test_case("t1", vec![]),
+                        test_case("t2", vec![]),
+                        test_case("t3", vec![]),
+                    ]),
+                },
+            ),
+            (
+                id("C"),
+                ModuleTestPlan {
+                    module_id: id("C"),
+                    tests: BTreeMap::from([test_case("t4", vec![])]),
+                },
+            ),
+        ]);
+        TestPlan {
+            mapped_files: MappedFiles::empty(),
+            module_tests,
+            module_info,
+            bytecode_deps_modules: vec![],
+        }
+    }
+
+    fn test_runner(test_plan: TestPlan) -> TestRunner {
+        TestRunner::new(BOUND, 1, false, None, 1, false, None, test_plan, None, None).unwrap()
+    }
+
+    #[test]
+    fn parse_shards() {
+        assert_eq!(
+            Shard::from_str("1/4").unwrap(),
+            Shard { index: 1, count: 4 }
+        );
+        assert_eq!(
+            Shard::from_str(" 4 / 4 ").unwrap(),
+            Shard { index: 4, count: 4 }
+        );
+        for invalid in [
+            "", "1", "1/", "/4", "a/4", "0/4", "5/4", "1/0", "-1/4", "1/4/2",
+        ] {
+            assert!(Shard::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn shards_partition_tests() {
+        let all: BTreeSet<_> = cache_keys(&test_plan(false), BOUND, None)
+            .into_keys()
+            .collect();
+        let mut sharded = BTreeSet::new();
+        for index in 1..=2 {
+            let mut runner = test_runner(test_plan(false));
+            runner.shard(Shard { index, count: 2 });
+            // with an empty cache, the keys are those of the remaining tests
+            let keys = runner.skip_cached(&TestCache::load(Path::new("missing")), None);
+            for name in keys.into_keys() {
+                assert!(sharded.insert(name), "tests are in exactly one shard");
+            }
+        }
+        assert_eq!(sharded, all);
+
+        // shards are assigned round-robin, in order of fully qualified names
+        let mut runner = test_runner(test_plan(false));
+        runner.shard(Shard { index: 2, count: 2 });
+        let keys = runner.skip_cached(&TestCache::load(Path::new("missing")), None);
+        assert_eq!(
+            keys.into_keys().collect::<Vec<_>>(),
+            ["0x0::A::t1", "0x0::A::t3"]
+        );
+    }
+
+    #[test]
+    fn cache_hits_and_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let keys = cache_keys(&test_plan(false), BOUND, None);
+        // tests with generated arguments are never cached
+        assert_eq!(
+            keys.keys().collect::<Vec<_>>(),
+            ["0x0::A::t1", "0x0::A::t2", "0x0::A::t3", "0x0::C::t4"]
+        );
+
+        let mut cache = TestCache::load(&path);
+        cache.record("0x0::A::t1".to_string(), keys["0x0::A::t1"].clone(), true);
+        cache.record("0x0::A::t2".to_string(), keys["0x0::A::t2"].clone(), false);
+        cache.record("0x0::C::t4".to_string(), keys["0x0::C::t4"].clone(), true);
+        cache.save().unwrap();
+
+        let cache = TestCache::load(&path);
+        assert!(cache.is_cached_pass("0x0::A::t1", &keys["0x0::A::t1"]));
+        assert!(!cache.is_cached_pass("0x0::A::t2", &keys["0x0::A::t2"]));
+        assert!(!cache.is_cached_pass("0x0::A::t3", &keys["0x0::A::t3"]));
+        assert!(!cache.is_cached_pass("0x0::A::t1", &keys["0x0::A::t3"]));
+
+        // cached passes are skipped
+        let mut runner = test_runner(test_plan(false));
+        assert_eq!(runner.skip_cached(&cache, None), keys);
+        let remaining = runner.skip_cached(&TestCache::load(Path::new("missing")), None);
+        assert_eq!(
+            remaining.keys().collect::<Vec<_>>(),
+            ["0x0::A::t2", "0x0::A::t3"]
+        );
+
+        // a failure evicts a cached pass
+        let mut cache = TestCache::load(&path);
+        cache.record("0x0::A::t1".to_string(), keys["0x0::A::t1"].clone(), false);
+        assert!(!cache.is_cached_pass("0x0::A::t1", &keys["0x0::A::t1"]));
+
+        // caches in another format are ignored
+        std::fs::write(&path, r#"{"version": 0, "passed": {}}"#).unwrap();
+        assert!(!TestCache::load(&path).is_cached_pass("0x0::C::t4", &keys["0x0::C::t4"]));
+    }
+
+    #[test]
+    fn cache_keys_are_invalidated() {
+        let keys = cache_keys(&test_plan(false), BOUND, None);
+        assert_eq!(keys, cache_keys(&test_plan(false), BOUND, None));
+
+        // changing a dependency invalidates the tests that depend on it, and only those
+        let changed = cache_keys(&test_plan(true), BOUND, None);
+        for test in ["0x0::A::t1", "0x0::A::t2", "0x0::A::t3"] {
+            assert_ne!(keys[test], changed[test], "{test}");
+        }
+        assert_eq!(keys["0x0::C::t4"], changed["0x0::C::t4"]);
+
+        // as does changing the gas limit or the context the tests run in
+        let bound = cache_keys(&test_plan(false), BOUND + 1, None);
+        let context = cache_keys(&test_plan(false), BOUND, Some("fork:mainnet=a"));
+        let other_context = cache_keys(&test_plan(false), BOUND, Some("fork:mainnet=b"));
+        let empty_context = cache_keys(&test_plan(false), BOUND, Some(""));
+        for (test, key) in &keys {
+            assert_ne!(*key, bound[test]);
+            assert_ne!(*key, context[test]);
+            assert_ne!(context[test], other_context[test]);
+            assert_ne!(*key, empty_context[test]);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::format_module_id;
+use crate::{format_module_id, test_cache::TestCache};
 use colored::{Colorize, control};
 use move_binary_format::errors::{ExecutionState, Location, VMError};
 use move_command_line_common::error_bitset::ErrorBitset;
@@ -12,14 +12,17 @@ use move_compiler::{
 };
 use move_core_types::{
     language_storage::ModuleId,
+    runtime_value::MoveValue,
     vm_status::{StatusCode, StatusType},
 };
 use move_ir_types::location::Loc;
 use move_trace_format::format::MoveTrace;
+use serde::Serialize;
 use std::{
     collections::{BTreeMap, BTreeSet},
     io::{Result, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::Mutex,
     time::Duration,
 };
@@ -63,6 +66,8 @@ type TestRuns<T> = BTreeMap<String, Vec<T>>;
 pub struct TestStatistics {
     passed: BTreeMap<ModuleId, TestRuns<TestRunInfo>>,
     failed: BTreeMap<ModuleId, TestRuns<TestFailure>>,
+    // Tests that were skipped because they passed in a previous run and have not changed since.
+    cached: BTreeMap<ModuleId, BTreeSet<String>>,
 }
 
 // #[derive(Debug, Clone)]
@@ -71,6 +76,54 @@ pub struct TestResults {
     test_plan: TestPlan,
 }
 
+/// Format of a machine-readable report of the test results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestReportFormat {
+    Junit,
+    Json,
+}
+
+/// A machine-readable report of the test results to write to `path`, as provided on the command
+/// line: `<format>=<path>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+    pub format: TestReportFormat,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TestCaseStatus {
+    Passed,
+    // Skipped, because the test passed in a previous run and has not changed since
+    Cached,
+    Failed,
+    Timeout,
+}
+
+// A single run of a test in a machine-readable report. A `#[random_test]` has one entry per run.
+#[derive(Debug, Clone, Serialize)]
+struct TestCaseReport {
+    module: String,
+    name: String,
+    status: TestCaseStatus,
+    gas_used: u64,
+    wall_time_secs: f64,
+    seed: Option<u64>,
+    abort_location: Option<String>,
+    abort_code: Option<u64>,
+    clever_error: Option<String>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JsonTestReport<'a> {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    tests: &'a [TestCaseReport],
+}
+
 fn write_bytes_to_file(filepath: &str, content: &[u8]) -> std::io::Result<()> {
     let path = Path::new(filepath);
     if let Some(parent) = path.parent() {
@@ -137,6 +190,31 @@ impl FailureReason {
     }
 }
 
+impl FromStr for TestReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let Some((format, path)) = s.split_once('=') else {
+            return Err(format!(
+                "Invalid report '{s}', expected 'junit=<path>' or 'json=<path>'"
+            ));
+        };
+        let format = match format {
+            "junit" => TestReportFormat::Junit,
+            "json" => TestReportFormat::Json,
+            _ => {
+                return Err(format!(
+                    "Unknown report format '{format}', expected 'junit' or 'json'"
+                ));
+            }
+        };
+        Ok(TestReport {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
 fn clever_error_line_number_to_loc(test_plan: &TestPlan, vm_error: &VMError) -> Option<Loc> {
     let abort_code = match (vm_error.major_status(), vm_error.sub_status()) {
         (StatusCode::ABORTED, Some(abort_code)) => abort_code,
@@ -363,6 +441,7 @@ impl TestStatistics {
         Self {
             passed: BTreeMap::new(),
             failed: BTreeMap::new(),
+            cached: BTreeMap::new(),
         }
     }
 
@@ -396,6 +475,17 @@ impl TestStatistics {
         true
     }
 
+    pub fn test_cached(&mut self, test_name: String, module_id: &ModuleId) {
+        self.cached
+            .entry(module_id.clone())
+            .or_default()
+            .insert(test_name);
+    }
+
+    pub fn cached_tests(&self) -> &BTreeMap<ModuleId, BTreeSet<String>> {
+        &self.cached
+    }
+
     pub fn combine(mut self, other: Self) -> Self {
         for (module_id, test_result) in other.passed {
             let entry = self.passed.entry(module_id).or_default();
@@ -410,6 +500,9 @@ impl TestStatistics {
             let entry = self.failed.entry(module_id).or_default();
             entry.extend(test_result.into_iter());
         }
+        for (module_id, test_names) in other.cached {
+            self.cached.entry(module_id).or_default().extend(test_names);
+        }
         self
     }
 }
@@ -427,6 +520,12 @@ fn calculate_run_statistics<'a, I: IntoIterator<Item = &'a TestRunInfo>>(
     )
 }
 
+impl TestCaseStatus {
+    fn is_failure(self) -> bool {
+        matches!(self, TestCaseStatus::Failed | TestCaseStatus::Timeout)
+    }
+}
+
 impl TestResults {
     pub fn new(final_statistics: TestStatistics, test_plan: TestPlan) -> Self {
         Self {
@@ -567,6 +666,122 @@ impl TestResults {
         writeln!(writer.lock().unwrap())
     }
 
+    /// Write the machine-readable `reports` of the test results.
+    pub fn write_reports(&self, reports: &[TestReport]) -> Result<()> {
+        if reports.is_empty() {
+            return Ok(());
+        }
+        let test_cases = self.test_case_reports();
+        for report in reports {
+            let content = match report.format {
+                TestReportFormat::Junit => junit_report(&test_cases).into_bytes(),
+                TestReportFormat::Json => {
+                    let failed = test_cases
+                        .iter()
+                        .filter(|test| test.status.is_failure())
+                        .count();
+                    serde_json::to_vec_pretty(&JsonTestReport {
+                        total: test_cases.len(),
+                        passed: test_cases.len() - failed,
+                        failed,
+                        tests: &test_cases,
+                    })
+                    .map_err(std::io::Error::other)?
+                }
+            };
+            write_bytes_to_file(&report.path.to_string_lossy(), &content)?;
+        }
+        Ok(())
+    }
+
+    fn test_case_reports(&self) -> Vec<TestCaseReport> {
+        let mut test_cases = vec![];
+        for (module_id, test_results) in &self.final_statistics.passed {
+            let module = format_module_id(&self.test_plan.module_info, module_id);
+            for (function_name, test_runs) in test_results {
+                for test_run in test_runs {
+                    test_cases.push(TestCaseReport {
+                        module: module.clone(),
+                        name: function_name.clone(),
+                        status: TestCaseStatus::Passed,
+                        gas_used: test_run.instructions_executed,
+                        wall_time_secs: test_run.elapsed_time.as_secs_f64(),
+                        seed: None,
+                        abort_location: None,
+                        abort_code: None,
+                        clever_error: None,
+                        message: None,
+                    });
+                }
+            }
+        }
+
+        for (module_id, test_names) in &self.final_statistics.cached {
+            let module = format_module_id(&self.test_plan.module_info, module_id);
+            for test_name in test_names {
+                test_cases.push(TestCaseReport {
+                    module: module.clone(),
+                    name: test_name.clone(),
+                    status: TestCaseStatus::Cached,
+                    gas_used: 0,
+                    wall_time_secs: 0.0,
+                    seed: None,
+                    abort_location: None,
+                    abort_code: None,
+                    clever_error: None,
+                    message: None,
+                });
+            }
+        }
+
+        // render failures without colors, as they are not displayed in a terminal
+        control::set_override(false);
+        for (module_id, test_failures) in &self.final_statistics.failed {
+            let module = format_module_id(&self.test_plan.module_info, module_id);
+            for (function_name, test_failures) in test_failures {
+                for test_failure in test_failures {
+                    let vm_error = test_failure.vm_error.as_ref();
+                    test_cases.push(TestCaseReport {
+                        module: module.clone(),
+                        name: function_name.clone(),
+                        status: match test_failure.failure_reason {
+                            FailureReason::Timeout(_) => TestCaseStatus::Timeout,
+                            _ => TestCaseStatus::Failed,
+                        },
+                        gas_used: test_failure.test_run_info.instructions_executed,
+                        wall_time_secs: test_failure.test_run_info.elapsed_time.as_secs_f64(),
+                        seed: test_failure.prng_seed,
+                        abort_location: vm_error.and_then(|e| abort_location(&self.test_plan, e)),
+                        abort_code: vm_error.and_then(|e| match e.major_status() {
+                            StatusCode::ABORTED => e.sub_status(),
+                            _ => None,
+                        }),
+                        clever_error: vm_error
+                            .and_then(|e| clever_error_message(&self.test_plan, e)),
+                        message: Some(test_failure.render_error(&self.test_plan)),
+                    });
+                }
+            }
+        }
+        control::unset_override();
+        test_cases
+    }
+
+    /// Record the results of the tests that can be cached (those in `cache_keys`) in `cache`.
+    pub(crate) fn update_cache(
+        &self,
+        cache: &mut TestCache,
+        cache_keys: &BTreeMap<String, String>,
+    ) {
+        let passed = qualified_test_names(&self.test_plan, &self.final_statistics.passed);
+        let failed = qualified_test_names(&self.test_plan, &self.final_statistics.failed);
+        for test_name in passed.iter().chain(&failed) {
+            if let Some(key) = cache_keys.get(test_name) {
+                cache.record(test_name.clone(), key.clone(), !failed.contains(test_name));
+            }
+        }
+    }
+
     /// Returns `true` if all tests passed, `false` if there was a test failure/timeout
     pub fn summarize<W: Write>(self, writer: &Mutex<W>) -> Result<bool> {
         let num_failed_tests = self
@@ -579,6 +794,11 @@ impl TestResults {
             .passed
             .iter()
             .fold(0, |acc, (_, fns)| acc + fns.len()) as u64;
+        let num_cached_tests = self
+            .final_statistics
+            .cached
+            .iter()
+            .fold(0, |acc, (_, fns)| acc + fns.len()) as u64;
         if !self.final_statistics.failed.is_empty() {
             writeln!(writer.lock().unwrap(), "\nTest failures:\n")?;
             for (module_id, test_failures) in &self.final_statistics.failed {
@@ -624,9 +844,10 @@ impl TestResults {
             }
         }
 
+        let num_passed_tests = num_passed_tests + num_cached_tests;
         writeln!(
             writer.lock().unwrap(),
-            "Test result: {}. Total tests: {}; passed: {}; failed: {}",
+            "Test result: {}. Total tests: {}; passed: {}{}; failed: {}",
             if num_failed_tests == 0 {
                 "OK".bold().bright_green()
             } else {
@@ -634,8 +855,468 @@ impl TestResults {
             },
             num_passed_tests + num_failed_tests,
             num_passed_tests,
+            if num_cached_tests > 0 {
+                format!(" ({num_cached_tests} cached)")
+            } else {
+                "".to_string()
+            },
             num_failed_tests
         )?;
         Ok(num_failed_tests == 0)
     }
 }
+
+// The fully qualified names of the tests in `results`.
+fn qualified_test_names<T>(
+    test_plan: &TestPlan,
+    results: &BTreeMap<ModuleId, TestRuns<T>>,
+) -> BTreeSet<String> {
+    results
+        .iter()
+        .flat_map(|(module_id, tests)| {
+            let module = format_module_id(&test_plan.module_info, module_id);
+            tests
+                .keys()
+                .map(move |test_name| format!("{module}::{test_name}"))
+        })
+        .collect()
+}
+
+// The function an error originated from, as `<module>::<function>`.
+fn abort_location(test_plan: &TestPlan, vm_error: &VMError) -> Option<String> {
+    let Location::Module(module_id) = vm_error.location() else {
+        return None;
+    };
+    let module_name = format_module_id(&test_plan.module_info, module_id);
+    let Some(((fdef_idx, _), named_module)) = vm_error
+        .offsets()
+        .first()
+        .zip(test_plan.module_info.get(module_id))
+    else {
+        return Some(module_name);
+    };
+    let module = &named_module.module;
+    let fn_handle = module.function_handle_at(module.function_def_at(*fdef_idx).function);
+    Some(format!(
+        "{module_name}::{}",
+        module.identifier_at(fn_handle.name)
+    ))
+}
+
+// Render the abort code of a clever error as `<constant name>: <constant value>`.
+fn clever_error_message(test_plan: &TestPlan, vm_error: &VMError) -> Option<String> {
+    let (StatusCode::ABORTED, Some(abort_code), Location::Module(module_id)) = (
+        vm_error.major_status(),
+        vm_error.sub_status(),
+        vm_error.location(),
+    ) else {
+        return None;
+    };
+    let bitset = ErrorBitset::from_u64(abort_code)?;
+    let module = &test_plan.module_info.get(module_id)?.module;
+    let constant = |index: u16| {
+        module
+            .constant_pool
+            .get(index as usize)?
+            .deserialize_constant()
+    };
+    let name = match constant(bitset.identifier_index()?)? {
+        MoveValue::Vector(bytes) => render_bytes(&bytes)?,
+        _ => return None,
+    };
+    let value = match bitset.constant_index().and_then(constant) {
+        Some(MoveValue::Vector(bytes)) => {
+            render_bytes(&bytes).unwrap_or_else(|| format!("{:?}", MoveValue::Vector(bytes)))
+        }
+        Some(value) => value.to_string(),
+        None => return Some(name),
+    };
+    Some(format!("{name}: {value}"))
+}
+
+// Render a vector of bytes as a UTF-8 string, if it is one.
+fn render_bytes(bytes: &[MoveValue]) -> Option<String> {
+    let bytes = bytes
+        .iter()
+        .map(|b| match b {
+            MoveValue::U8(b) => Some(*b),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+// Render the test runs as a JUnit XML report, with a test suite per module.
+fn junit_report(test_cases: &[TestCaseReport]) -> String {
+    let mut suites: BTreeMap<&str, Vec<&TestCaseReport>> = BTreeMap::new();
+    for test_case in test_cases {
+        suites
+            .entry(test_case.module.as_str())
+            .or_default()
+            .push(test_case);
+    }
+    let failures = |cases: &mut dyn Iterator<Item = &TestCaseReport>| {
+        cases.filter(|test| test.status.is_failure()).count()
+    };
+    let time = |cases: &mut dyn Iterator<Item = &TestCaseReport>| {
+        cases.map(|test| test.wall_time_secs).sum::<f64>()
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"move-unit-tests\" tests=\"{}\" failures=\"{}\" time=\"{:.6}\">\n",
+        test_cases.len(),
+        failures(&mut test_cases.iter()),
+        time(&mut test_cases.iter()),
+    ));
+    for (module, cases) in suites {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.6}\">\n",
+            xml_escape(module),
+            cases.len(),
+            failures(&mut cases.iter().copied()),
+            time(&mut cases.iter().copied()),
+        ));
+        for case in cases {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.6}\">\n",
+                xml_escape(&case.name),
+                xml_escape(module),
+                case.wall_time_secs,
+            ));
+            xml.push_str("      <properties>\n");
+            let mut properties = vec![("gas_used", case.gas_used.to_string())];
+            if case.status == TestCaseStatus::Cached {
+                properties.push(("cached", "true".to_string()));
+            }
+            properties.extend(case.seed.map(|seed| ("seed", seed.to_string())));
+            properties.extend(
+                case.abort_location
+                    .clone()
+                    .map(|loc| ("abort_location", loc)),
+            );
+            properties.extend(case.abort_code.map(|code| ("abort_code", code.to_string())));
+            properties.extend(case.clever_error.clone().map(|err| ("clever_error", err)));
+            for (name, value) in properties {
+                xml.push_str(&format!(
+                    "        <property name=\"{name}\" value=\"{}\"/>\n",
+                    xml_escape(&value)
+                ));
+            }
+            xml.push_str("      </properties>\n");
+            if case.status.is_failure() {
+                let message = case.message.as_deref().unwrap_or_default();
+                let summary = case
+                    .clever_error
+                    .as_deref()
+                    .unwrap_or_else(|| message.lines().next().unwrap_or_default());
+                xml.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    match case.status {
+                        TestCaseStatus::Timeout => "timeout",
+                        _ => "failure",
+                    },
+                    xml_escape(summary),
+                    xml_escape(message),
+                ));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // characters that are not allowed in XML documents
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::{
+        errors::PartialVMError,
+        file_format::{Constant, FunctionDefinitionIndex, SignatureToken, basic_test_module},
+    };
+    use move_bytecode_source_map::source_map::SourceMap;
+    use move_command_line_common::{error_bitset::ErrorBitsetBuilder, files::FileHash};
+    use move_compiler::{
+        compiled_unit::NamedCompiledModule,
+        shared::{NumberFormat, NumericalAddress, files::MappedFiles},
+    };
+    use move_core_types::account_address::AccountAddress;
+    use move_ir_types::ast::{ModuleIdent, ModuleName};
+    use move_symbol_pool::Symbol;
+
+    const JUNIT_REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="move-unit-tests" tests="4" failures="2" time="3.750000">
+  <testsuite name="0x0::DUMMY" tests="4" failures="2" time="3.750000">
+    <testcase name="passes" classname="0x0::DUMMY" time="1.500000">
+      <properties>
+        <property name="gas_used" value="10"/>
+      </properties>
+    </testcase>
+    <testcase name="was_cached" classname="0x0::DUMMY" time="0.000000">
+      <properties>
+        <property name="gas_used" value="0"/>
+        <property name="cached" value="true"/>
+      </properties>
+    </testcase>
+    <testcase name="aborts" classname="0x0::DUMMY" time="0.250000">
+      <properties>
+        <property name="gas_used" value="20"/>
+        <property name="seed" value="42"/>
+        <property name="abort_location" value="0x0::DUMMY::foo"/>
+        <property name="abort_code" value="13906834225882988545"/>
+        <property name="clever_error" value="EBadValue: value must be positive"/>
+      </properties>
+      <failure type="failure" message="EBadValue: value must be positive">assertion &lt;a&gt; &amp; &quot;b&quot; failed
+second line</failure>
+    </testcase>
+    <testcase name="times_out&lt;&amp;&gt;&quot;&apos;" classname="0x0::DUMMY" time="2.000000">
+      <properties>
+        <property name="gas_used" value="5"/>
+      </properties>
+      <failure type="timeout" message="Test timed out">Test timed out</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+
+    const JSON_REPORT: &str = r#"{
+  "total": 4,
+  "passed": 2,
+  "failed": 2,
+  "tests": [
+    {
+      "module": "0x0::DUMMY",
+      "name": "passes",
+      "status": "passed",
+      "gas_used": 10,
+      "wall_time_secs": 1.5,
+      "seed": null,
+      "abort_location": null,
+      "abort_code": null,
+      "clever_error": null,
+      "message": null
+    },
+    {
+      "module": "0x0::DUMMY",
+      "name": "was_cached",
+      "status": "cached",
+      "gas_used": 0,
+      "wall_time_secs": 0.0,
+      "seed": null,
+      "abort_location": null,
+      "abort_code": null,
+      "clever_error": null,
+      "message": null
+    },
+    {
+      "module": "0x0::DUMMY",
+      "name": "aborts",
+      "status": "failed",
+      "gas_used": 20,
+      "wall_time_secs": 0.25,
+      "seed": 42,
+      "abort_location": "0x0::DUMMY::foo",
+      "abort_code": 13906834225882988545,
+      "clever_error": "EBadValue: value must be positive",
+      "message": "assertion <a> & \"b\" failed\u001b\nsecond line"
+    },
+    {
+      "module": "0x0::DUMMY",
+      "name": "times_out<&>\"'",
+      "status": "timeout",
+      "gas_used": 5,
+      "wall_time_secs": 2.0,
+      "seed": null,
+      "abort_location": null,
+      "abort_code": null,
+      "clever_error": null,
+      "message": "Test timed out"
+    }
+  ]
+}"#;
+
+    // A test plan with module `0x0::DUMMY`, whose constants are the name of a clever error
+    // followed by its value.
+    fn test_plan() -> (TestPlan, ModuleId) {
+        let mut module = basic_test_module();
+        for constant in [&b"EBadValue"[..], b"value must be positive"] {
+            module.constant_pool.push(Constant {
+                type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+                data: bcs::to_bytes(constant).unwrap(),
+            });
+        }
+        module.constant_pool.push(Constant {
+            type_: SignatureToken::U64,
+            data: bcs::to_bytes(&7u64).unwrap(),
+        });
+        let module_id = module.self_id();
+        let named_module = NamedCompiledModule {
+            package_name: None,
+            address: NumericalAddress::new(AccountAddress::ZERO.into_bytes(), NumberFormat::Hex),
+            address_name: None,
+            name: Symbol::from("DUMMY"),
+            module,
+            source_map: SourceMap::new(
+                Loc::new(FileHash::empty(), 0, 0),
+                ModuleIdent::new(ModuleName(Symbol::from("DUMMY")), AccountAddress::ZERO),
+            ),
+        };
+        let test_plan = TestPlan {
+            mapped_files: MappedFiles::empty(),
+            module_tests: BTreeMap::new(),
+            module_info: BTreeMap::from([(module_id.clone(), named_module)]),
+            bytecode_deps_modules: vec![],
+        };
+        (test_plan, module_id)
+    }
+
+    // A clever abort in function `foo` of `module_id`, with the given constants.
+    fn clever_abort(
+        module_id: &ModuleId,
+        identifier_index: Option<u16>,
+        constant_index: Option<u16>,
+    ) -> VMError {
+        let mut bitset = ErrorBitsetBuilder::new(12);
+        if let Some(index) = identifier_index {
+            bitset.with_identifier_index(index);
+        }
+        if let Some(index) = constant_index {
+            bitset.with_constant_index(index);
+        }
+        PartialVMError::new(StatusCode::ABORTED)
+            .with_sub_status(bitset.build().bits())
+            .at_code_offset(FunctionDefinitionIndex(0), 0)
+            .finish(Location::Module(module_id.clone()))
+    }
+
+    fn run_info(elapsed_time: Duration, instructions_executed: u64) -> TestRunInfo {
+        TestRunInfo::new(elapsed_time, instructions_executed, None)
+    }
+
+    #[test]
+    fn reports_match_golden_outputs() {
+        let (test_plan, module_id) = test_plan();
+        let mut statistics = TestStatistics::new();
+        statistics
+            .passed
+            .entry(module_id.clone())
+            .or_default()
+            .insert(
+                "passes".to_string(),
+                vec![run_info(Duration::from_millis(1500), 10)],
+            );
+        statistics.test_cached("was_cached".to_string(), &module_id);
+        let failures = statistics.failed.entry(module_id.clone()).or_default();
+        failures.insert(
+            "aborts".to_string(),
+            vec![TestFailure::new(
+                FailureReason::property(
+                    "assertion <a> & \"b\" failed\u{1b}\nsecond line".to_string(),
+                ),
+                run_info(Duration::from_millis(250), 20),
+                Some(clever_abort(&module_id, Some(0), Some(1))),
+                Some(42),
+            )],
+        );
+        failures.insert(
+            "times_out<&>\"'".to_string(),
+            vec![TestFailure::new(
+                FailureReason::timeout(),
+                run_info(Duration::from_secs(2), 5),
+                None,
+                None,
+            )],
+        );
+        let results = TestResults::new(statistics, test_plan);
+
+        let dir = tempfile::tempdir().unwrap();
+        let junit = dir.path().join("reports/junit.xml");
+        let json = dir.path().join("reports/report.json");
+        results
+            .write_reports(&[
+                TestReport {
+                    format: TestReportFormat::Junit,
+                    path: junit.clone(),
+                },
+                TestReport {
+                    format: TestReportFormat::Json,
+                    path: json.clone(),
+                },
+            ])
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(junit).unwrap(), JUNIT_REPORT);
+        assert_eq!(std::fs::read_to_string(json).unwrap(), JSON_REPORT);
+    }
+
+    #[test]
+    fn clever_error_messages() {
+        let (test_plan, module_id) = test_plan();
+        let message = |error| clever_error_message(&test_plan, &error);
+        assert_eq!(
+            message(clever_abort(&module_id, Some(0), Some(1))).as_deref(),
+            Some("EBadValue: value must be positive")
+        );
+        assert_eq!(
+            message(clever_abort(&module_id, Some(0), Some(2))).as_deref(),
+            Some("EBadValue: 7u64")
+        );
+        assert_eq!(
+            message(clever_abort(&module_id, Some(0), None)).as_deref(),
+            Some("EBadValue")
+        );
+        // only the line number is known
+        assert_eq!(message(clever_abort(&module_id, None, None)), None);
+        // plain abort codes are not clever errors
+        let plain_abort = PartialVMError::new(StatusCode::ABORTED)
+            .with_sub_status(7)
+            .finish(Location::Module(module_id.clone()));
+        assert_eq!(message(plain_abort), None);
+        // neither are other errors
+        let arithmetic_error = PartialVMError::new(StatusCode::ARITHMETIC_ERROR)
+            .finish(Location::Module(module_id.clone()));
+        assert_eq!(message(arithmetic_error), None);
+    }
+
+    #[test]
+    fn xml_escaping() {
+        assert_eq!(
+            xml_escape("<a b=\"c\" d='e'>&\u{0}\u{1b}\n\t</a>"),
+            "&lt;a b=&quot;c&quot; d=&apos;e&apos;&gt;&amp;\n\t&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn parse_test_reports() {
+        assert_eq!(
+            TestReport::from_str("junit=out/junit.xml").unwrap(),
+            TestReport {
+                format: TestReportFormat::Junit,
+                path: PathBuf::from("out/junit.xml"),
+            }
+        );
+        assert_eq!(
+            TestReport::from_str("json=report.json").unwrap().format,
+            TestReportFormat::Json
+        );
+        assert!(TestReport::from_str("junit").is_err());
+        assert!(TestReport::from_str("xml=report.xml").is_err());
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::{
     extensions, format_module_id,
+    test_cache::{self, TestCache},
     test_reporter::{
         FailureReason, MoveError, TestFailure, TestResults, TestRunInfo, TestStatistics,
     },
@@ -27,6 +28,7 @@ use move_core_types::{
     effects::ChangeSet,
     identifier::IdentStr,
     language_storage::{ModuleId, TypeTag},
+    resolver::{LinkageResolver, ModuleResolver},
     runtime_value::{MoveValue, serialize_values},
     u256::U256,
     vm_status::StatusCode,
@@ -40,7 +42,9 @@ use move_vm_test_utils::{
 use rand::{Rng, SeedableRng, rngs::StdRng};
 use rayon::prelude::*;
 use regex::Regex;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex, time::Instant};
+use std::{
+    collections::BTreeMap, io::Write, marker::Send, str::FromStr, sync::Mutex, time::Instant,
+};
 
 use move_vm_runtime::native_extensions::NativeContextExtensions;
 
@@ -61,6 +65,37 @@ pub struct TestRunner {
     num_threads: usize,
     testing_config: SharedTestingConfig,
     tests: TestPlan,
+    // Tests skipped because they passed in a previous run
+    cached: TestStatistics,
+}
+
+/// One of `count` disjoint subsets of the tests, for distributing the tests of a package across
+/// machines, as provided on the command line: `<index>/<count>`, with `index` starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Storage for a test run: the modules needed for all tests, falling back to the module fetch
+/// hook (see `extensions::set_module_fetch_hook`) for modules that are not part of the package
+/// under test or its dependencies.
+struct TestStorage<'a>(&'a InMemoryStorage);
+
+impl LinkageResolver for TestStorage<'_> {
+    type Error = String;
+}
+
+impl ModuleResolver for TestStorage<'_> {
+    type Error = String;
+
+    fn get_module(&self, id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.0.get_module(id) {
+            Ok(Some(module)) => Ok(Some(module)),
+            Ok(None) => extensions::fetch_module(id),
+            Err(()) => Err(format!("Failed to load module {id}")),
+        }
+    }
 }
 
 /// Setup storage state with the set of modules that will be needed for all tests
@@ -163,10 +198,23 @@ impl TestRunner {
             },
             num_threads,
             tests,
+            cached: TestStatistics::new(),
         })
     }
 
     pub fn run<W: Write + Send>(self, writer: &Mutex<W>) -> Result<TestResults> {
+        for (module_id, test_names) in self.cached.cached_tests() {
+            for test_name in test_names {
+                writeln!(
+                    writer.lock().unwrap(),
+                    "[ {}  ] {}::{}",
+                    "CACHED".bold().bright_cyan(),
+                    format_module_id(&self.tests.module_info, module_id),
+                    test_name
+                )?;
+            }
+        }
+
         rayon::ThreadPoolBuilder::new()
             .num_threads(self.num_threads)
             .build()
@@ -183,7 +231,8 @@ impl TestRunner {
                             writer,
                         )
                     })
-                    .reduce(TestStatistics::new, |acc, stats| acc.combine(stats));
+                    .reduce(TestStatistics::new, |acc, stats| acc.combine(stats))
+                    .combine(self.cached);
 
                 Ok(TestResults::new(final_statistics, self.tests))
             })
@@ -211,6 +260,62 @@ impl TestRunner {
         }
         Ok(())
     }
+
+    /// Only keep the tests in `shard`. Tests are assigned to shards round-robin, in order of
+    /// their fully qualified names, so every test is in exactly one shard.
+    pub fn shard(&mut self, shard: Shard) {
+        let mut position = 0;
+        for module_test in self.tests.module_tests.values_mut() {
+            module_test.tests.retain(|_, _| {
+                let in_shard = position % shard.count == shard.index - 1;
+                position += 1;
+                in_shard
+            });
+        }
+    }
+
+    /// Skip the tests that passed in a previous run recorded in `cache` and have not changed
+    /// since, in `context` (see `test_cache::cache_keys`). Returns the cache keys of the tests
+    /// that can be cached, by fully qualified name.
+    pub fn skip_cached(
+        &mut self,
+        cache: &TestCache,
+        context: Option<&str>,
+    ) -> BTreeMap<String, String> {
+        let keys =
+            test_cache::cache_keys(&self.tests, self.testing_config.execution_bound, context);
+        for (module_id, module_test) in self.tests.module_tests.iter_mut() {
+            let module_name = format_module_id(&self.tests.module_info, module_id);
+            module_test.tests.retain(|test_name, _| {
+                let qualified_name = format!("{module_name}::{test_name}");
+                let is_cached = keys
+                    .get(&qualified_name)
+                    .is_some_and(|key| cache.is_cached_pass(&qualified_name, key));
+                if is_cached {
+                    self.cached.test_cached(test_name.clone(), module_id);
+                }
+                !is_cached
+            });
+        }
+        keys
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("Invalid shard '{s}', expected '<index>/<count>', e.g. '1/4'");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+        if count == 0 || index == 0 || index > count {
+            return Err(format!(
+                "Invalid shard '{s}', the index must be between 1 and the number of shards"
+            ));
+        }
+        Ok(Shard { index, count })
+    }
 }
 
 // TODO: do not expose this to backend implementations
@@ -283,8 +388,8 @@ impl SharedTestingConfig {
             None
         };
 
-        let mut session =
-            move_vm.new_session_with_extensions(&self.starting_storage_state, extensions);
+        let mut session = move_vm
+            .new_session_with_extensions(TestStorage(&self.starting_storage_state), extensions);
         let mut gas_meter = GasStatus::new(&self.cost_table, Gas::new(self.execution_bound));
         move_vm_profiler::tracing_feature_enabled! {
             use move_vm_profiler::GasProfiler;
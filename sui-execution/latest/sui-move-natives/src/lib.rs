@@ -1114,6 +1114,11 @@ pub fn all_natives(silent: bool, protocol_config: &ProtocolConfig) -> NativeFunc
             "deallocate_receiving_ticket_for_object",
             make_native!(test_scenario::deallocate_receiving_ticket_for_object),
         ),
+        (
+            "test_scenario",
+            "set_fork",
+            make_native!(test_scenario::set_fork),
+        ),
         (
             "transfer",
             "transfer_impl",
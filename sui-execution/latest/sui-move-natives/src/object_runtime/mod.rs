@@ -491,6 +491,18 @@ impl<'a> ObjectRuntime<'a> {
         )
     }
 
+    /// Register an object that is loaded into a test from the state of a forked network, so
+    /// that its child objects can be read from the test store as for a transaction input.
+    pub(crate) fn add_forked_object(
+        &mut self,
+        id: ObjectID,
+        version: SequenceNumber,
+        contained_uids: BTreeSet<ObjectID>,
+    ) {
+        self.child_object_store
+            .add_root_object(id, version, contained_uids);
+    }
+
     // returns None if a child object is still borrowed
     pub(crate) fn take_state(&mut self) -> ObjectRuntimeState {
         std::mem::take(&mut self.state)
@@ -8,7 +8,7 @@ use move_core_types::{
 };
 use move_vm_types::values::{GlobalValue, StructRef, Value};
 use std::{
-    collections::{btree_map, BTreeMap},
+    collections::{btree_map, BTreeMap, BTreeSet},
     sync::Arc,
 };
 use sui_protocol_config::{check_limit_by_meter, LimitThresholdCrossed, ProtocolConfig};
@@ -730,6 +730,24 @@ impl<'a> ChildObjectStore<'a> {
         &self.inner.wrapped_object_containers
     }
 
+    // Record the root version of an object that becomes an input after the store was created,
+    // along with the UIDs it contains, so that its child objects can be fetched from storage.
+    pub(super) fn add_root_object(
+        &mut self,
+        id: ObjectID,
+        version: SequenceNumber,
+        contained_uids: BTreeSet<ObjectID>,
+    ) {
+        for contained_uid in contained_uids {
+            self.inner.root_version.insert(contained_uid, version);
+            if contained_uid != id {
+                self.inner
+                    .wrapped_object_containers
+                    .insert(contained_uid, id);
+            }
+        }
+    }
+
     // retrieve the `Op` effects for the child objects
     pub(super) fn take_effects(&mut self) -> PartialVMResult<ChildObjectEffects> {
         if self.inner.protocol_config.minimize_child_object_mutations() {
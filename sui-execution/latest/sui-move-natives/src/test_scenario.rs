@@ -3,7 +3,9 @@
 
 use crate::{
     get_nth_struct_field, get_tag_and_layouts, legacy_test_cost,
-    object_runtime::{object_store::ChildObjectEffects, ObjectRuntime, RuntimeResults},
+    object_runtime::{
+        get_all_uids, object_store::ChildObjectEffects, ObjectRuntime, RuntimeResults,
+    },
 };
 use better_any::{Tid, TidAble};
 use indexmap::{IndexMap, IndexSet};
@@ -12,7 +14,7 @@ use move_core_types::{
     account_address::AccountAddress,
     annotated_value::{MoveFieldLayout, MoveStructLayout, MoveTypeLayout, MoveValue},
     annotated_visitor as AV,
-    language_storage::StructTag,
+    language_storage::{ModuleId, StructTag},
     vm_status::StatusCode,
 };
 use move_vm_runtime::{native_extensions::NativeExtensionMarker, native_functions::NativeContext};
@@ -27,6 +29,7 @@ use std::{
     borrow::Borrow,
     cell::RefCell,
     collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Arc,
     thread::LocalKey,
 };
 use sui_types::{
@@ -34,10 +37,11 @@ use sui_types::{
     config,
     digests::{ObjectDigest, TransactionDigest},
     dynamic_field::DynamicFieldInfo,
+    error::{SuiError, SuiResult},
     execution::DynamicallyLoadedObjectMetadata,
     id::UID,
     in_memory_storage::InMemoryStorage,
-    object::{MoveObject, Object, Owner},
+    object::{Data, MoveObject, Object, Owner},
     storage::ChildObjectResolver,
     TypeTag,
 };
@@ -54,10 +58,120 @@ type Set<K> = IndexSet<K>;
 /// An in-memory test store is a thin wrapper around the in-memory storage in a mutex. The mutex
 /// allows this to be used by both the object runtime (for reading) and the test scenario (for
 /// writing) while hiding mutability.
+/// When a test runs against a fork of a network (see `test_scenario::begin_fork`), objects
+/// missing from the in-memory storage are fetched from the forked state and cached in the
+/// in-memory storage until the next test starts.
 #[derive(Tid)]
-pub struct InMemoryTestStore(pub &'static LocalKey<RefCell<InMemoryStorage>>);
+pub struct InMemoryTestStore(
+    pub &'static LocalKey<RefCell<InMemoryStorage>>,
+    pub Option<&'static dyn ForkProvider>,
+);
 impl<'a> NativeExtensionMarker<'a> for &'a InMemoryTestStore {}
 
+/// The state of a network, at a given checkpoint, that tests run against when testing against
+/// a fork of that network.
+pub trait ForkedState: Send + Sync {
+    /// Return the latest version of object `id` in the forked state, if it exists.
+    fn get_object(&self, id: &ObjectID) -> SuiResult<Option<Object>>;
+}
+
+/// Provides the forked states that tests declare they run against.
+pub trait ForkProvider: Send + Sync {
+    /// Return the state of network `chain` at `checkpoint`.
+    fn fork(&self, chain: &str, checkpoint: u64) -> SuiResult<Arc<dyn ForkedState>>;
+}
+
+// The fork of a network the test running on this thread runs against, if any.
+struct CurrentFork {
+    state: Arc<dyn ForkedState>,
+    // objects of the forked state added to the in-memory storage by this test
+    loaded: BTreeSet<ObjectID>,
+}
+
+thread_local! {
+    static CURRENT_FORK: RefCell<Option<CurrentFork>> = const { RefCell::new(None) };
+}
+
+impl InMemoryTestStore {
+    /// Reset the state left by the previous test run on this thread: the test no longer runs
+    /// against the fork the previous test declared, and objects loaded from it are removed from
+    /// the in-memory storage.
+    pub fn start_test(&self) {
+        let Some(fork) = CURRENT_FORK.take() else {
+            return;
+        };
+        self.0.with_borrow_mut(|store| {
+            for id in fork.loaded {
+                store.remove_object(id);
+            }
+        });
+    }
+
+    /// Run the current test against the state of network `chain` at `checkpoint`.
+    pub fn set_fork(&self, chain: &str, checkpoint: u64) -> SuiResult<()> {
+        let Some(provider) = self.1 else {
+            return Err(SuiError::UnsupportedFeatureError {
+                error: "Forking is not supported by this test runner".to_string(),
+            });
+        };
+        if CURRENT_FORK.with_borrow(|fork| fork.is_some()) {
+            return Err(SuiError::Storage(
+                "The test already runs against a fork".to_string(),
+            ));
+        }
+        let state = provider.fork(chain, checkpoint)?;
+        CURRENT_FORK.set(Some(CurrentFork {
+            state,
+            loaded: BTreeSet::new(),
+        }));
+        Ok(())
+    }
+
+    /// Return the bytes of module `id` from the forked state the current test runs against, if
+    /// any. Modules are loaded from the package at the module's address, so for an upgraded
+    /// package this is the original version of the package.
+    pub fn forked_module(&self, id: &ModuleId) -> SuiResult<Option<Vec<u8>>> {
+        let Some(state) = CURRENT_FORK.with_borrow(|fork| fork.as_ref().map(|f| f.state.clone()))
+        else {
+            return Ok(None);
+        };
+        let Some(object) = state.get_object(&ObjectID::from(*id.address()))? else {
+            return Ok(None);
+        };
+        let Data::Package(package) = &object.data else {
+            return Ok(None);
+        };
+        Ok(package
+            .serialized_module_map()
+            .get(id.name().as_str())
+            .cloned())
+    }
+
+    // Return object `id` from the in-memory storage, fetching it from the forked state first
+    // if the current test runs against a fork and the object is not in the in-memory storage
+    // yet.
+    fn fetch_forked_object(&self, id: &ObjectID) -> SuiResult<Option<Object>> {
+        let Some(state) = CURRENT_FORK.with_borrow(|fork| fork.as_ref().map(|f| f.state.clone()))
+        else {
+            return Ok(None);
+        };
+        if let Some(object) = self.0.with_borrow(|store| store.get_object(id).cloned()) {
+            return Ok(Some(object));
+        }
+        let object = state.get_object(id)?;
+        if let Some(object) = &object {
+            self.0
+                .with_borrow_mut(|store| store.insert_object(object.clone()));
+            CURRENT_FORK.with_borrow_mut(|fork| {
+                if let Some(fork) = fork {
+                    fork.loaded.insert(*id);
+                }
+            });
+        }
+        Ok(object)
+    }
+}
+
 impl ChildObjectResolver for InMemoryTestStore {
     fn read_child_object(
         &self,
@@ -65,6 +179,11 @@ impl ChildObjectResolver for InMemoryTestStore {
         child: &ObjectID,
         child_version_upper_bound: SequenceNumber,
     ) -> sui_types::error::SuiResult<Option<Object>> {
+        // only objects loaded from the forked state have a root version, other parents are
+        // created by the test and cannot have children in the forked state
+        if child_version_upper_bound > SequenceNumber::new() {
+            self.fetch_forked_object(child)?;
+        }
         let l: &'static LocalKey<RefCell<InMemoryStorage>> = self.0;
         l.with_borrow(|store| store.read_child_object(parent, child, child_version_upper_bound))
     }
@@ -381,6 +500,7 @@ pub fn take_from_address_by_id(
     pop_arg!(args, StructRef);
     assert!(args.is_empty());
     let specified_obj_ty = object_type_of_type(context, &specified_ty)?;
+    load_forked_object(context, &specified_ty, &specified_obj_ty, id)?;
     let object_runtime: &mut ObjectRuntime = context.extensions_mut().get_mut()?;
     let inventories = &mut object_runtime.test_inventories;
     let res = take_from_inventory(
@@ -482,6 +602,7 @@ pub fn take_immutable_by_id(
     pop_arg!(args, StructRef);
     assert!(args.is_empty());
     let specified_obj_ty = object_type_of_type(context, &specified_ty)?;
+    load_forked_object(context, &specified_ty, &specified_obj_ty, id)?;
     let object_runtime: &mut ObjectRuntime = context.extensions_mut().get_mut()?;
     let inventories = &mut object_runtime.test_inventories;
     let res = take_from_inventory(
@@ -567,6 +688,7 @@ pub fn take_shared_by_id(
     pop_arg!(args, StructRef);
     assert!(args.is_empty());
     let specified_obj_ty = object_type_of_type(context, &specified_ty)?;
+    load_forked_object(context, &specified_ty, &specified_obj_ty, id)?;
     let object_runtime: &mut ObjectRuntime = context.extensions_mut().get_mut()?;
     let inventories = &mut object_runtime.test_inventories;
     let res = take_from_inventory(
@@ -755,8 +877,96 @@ pub fn deallocate_receiving_ticket_for_object(
     Ok(NativeResult::ok(legacy_test_cost(), smallvec![]))
 }
 
+// native fun set_fork(chain: vector<u8>, checkpoint: u64);
+pub fn set_fork(
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    assert!(ty_args.is_empty());
+    let checkpoint = pop_arg!(args, u64);
+    let chain = pop_arg!(args, Vec<u8>);
+    assert!(args.is_empty());
+    let chain = String::from_utf8(chain).map_err(|_| {
+        PartialVMError::new(StatusCode::VALUE_DESERIALIZATION_ERROR)
+            .with_message("Chain name is not valid UTF-8".to_string())
+    })?;
+    let store: &&InMemoryTestStore = context.extensions().get()?;
+    store.set_fork(&chain, checkpoint).map_err(|e| {
+        PartialVMError::new(StatusCode::STORAGE_ERROR).with_message(format!(
+            "Failed to fork {chain} at checkpoint {checkpoint}: {e}"
+        ))
+    })?;
+    Ok(NativeResult::ok(legacy_test_cost(), smallvec![]))
+}
+
 // impls
 
+// When testing against a fork, make object `id` of the forked state available to the test the
+// first time it is taken, by adding it to the inventory matching its owner. Objects that are
+// not of the specified type are left out, so that taking them aborts as for any object that
+// cannot be found.
+fn load_forked_object(
+    context: &mut NativeContext,
+    specified_ty: &Type,
+    specified_obj_ty: &MoveObjectType,
+    id: ObjectID,
+) -> PartialVMResult<()> {
+    let object = {
+        let object_runtime: &ObjectRuntime = context.extensions().get()?;
+        let inventories = &object_runtime.test_inventories;
+        if inventories.objects.contains_key(&id) || inventories.taken.contains_key(&id) {
+            return Ok(());
+        }
+        let store: &&InMemoryTestStore = context.extensions().get()?;
+        store.fetch_forked_object(&id).map_err(|e| {
+            PartialVMError::new(StatusCode::STORAGE_ERROR).with_message(format!(
+                "Failed to load object {id} from the forked state: {e}"
+            ))
+        })?
+    };
+    let Some(object) = object else {
+        return Ok(());
+    };
+    let Data::Move(move_obj) = &object.data else {
+        return Ok(());
+    };
+    if move_obj.type_() != specified_obj_ty {
+        return Ok(());
+    }
+    let Some((_, layout, annotated_layout)) = get_tag_and_layouts(context, specified_ty)? else {
+        return Ok(());
+    };
+    let Some(value) = Value::simple_deserialize(move_obj.contents(), &layout) else {
+        return Err(PartialVMError::new(StatusCode::STORAGE_ERROR)
+            .with_message(format!("Failed to deserialize forked object {id}")));
+    };
+    let contained_uids = get_all_uids(&annotated_layout, move_obj.contents()).map_err(|e| {
+        PartialVMError::new(StatusCode::STORAGE_ERROR)
+            .with_message(format!("Failed to get UIDs of forked object {id}: {e}"))
+    })?;
+
+    let object_runtime: &mut ObjectRuntime = context.extensions_mut().get_mut()?;
+    object_runtime.add_forked_object(id, object.version(), contained_uids);
+    let inventories = &mut object_runtime.test_inventories;
+    let inventory = match &object.owner {
+        Owner::AddressOwner(a) | Owner::ConsensusAddressOwner { owner: a, .. } => inventories
+            .address_inventories
+            .entry(*a)
+            .or_default()
+            .entry(specified_obj_ty.clone()),
+        Owner::Shared { .. } => inventories.shared_inventory.entry(specified_obj_ty.clone()),
+        Owner::Immutable => inventories
+            .immutable_inventory
+            .entry(specified_obj_ty.clone()),
+        // child objects are read through the object runtime
+        Owner::ObjectOwner(_) => return Ok(()),
+    };
+    inventory.or_default().insert(id);
+    inventories.objects.insert(id, value);
+    Ok(())
+}
+
 fn take_from_inventory(
     is_in_inventory: impl FnOnce(&ObjectID) -> bool,
     objects: &BTreeMap<ObjectID, Value>,